@@ -0,0 +1,77 @@
+//! Per-article content extraction: `content::extract_all` against running each
+//! extractor over the text on its own, and the one-pass `scan_brackets` against
+//! the bracket regexes it replaces.
+//!
+//! Run with `cargo bench --bench content`.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use dedalus::content;
+
+/// A long synthetic article: an infobox, many sections of linked prose, file
+/// links, references, external links, categories and interlanguage links.
+fn article() -> String {
+    let mut text = String::from(
+        "{{Infobox programming language\n| name = Rust\n| logo = Rust logo.svg\n\
+         | paradigm = [[Multi-paradigm programming language|Multi-paradigm]]\n}}\n\
+         '''Rust''' is a [[general-purpose programming language]].{{coord|40.7|-74.0}}\n",
+    );
+    for section in 0..60 {
+        text.push_str(&format!("\n== Section {section} ==\n"));
+        for para in 0..8 {
+            text.push_str(&format!(
+                "Text about [[Topic {section}]] and [[Other topic {para}|others]], with \
+                 [[File:Figure {section} {para}.png|thumb|A [[figure]]]] and a source.\
+                 <ref>{{{{cite web|url=https://example.org/{section}/{para}}}}}</ref> \
+                 See [https://example.com/{section} the site].\n"
+            ));
+        }
+    }
+    text.push_str("\n== See also ==\n* [[C++]]\n* [[Go (programming language)]]\n");
+    for category in 0..20 {
+        text.push_str(&format!("[[Category:Category {category}]]\n"));
+    }
+    text.push_str("[[de:Rust (Programmiersprache)]]\n[[fr:Rust (langage)]]\n");
+    text
+}
+
+fn bench_content(c: &mut Criterion) {
+    let text = article();
+    let mut group = c.benchmark_group("content");
+    group.bench_function("extract_all", |b| {
+        b.iter(|| content::extract_all(black_box(&text)))
+    });
+    group.bench_function("individual_extractors", |b| {
+        b.iter(|| {
+            let text = black_box(&text);
+            let live = content::mask_non_content(text);
+            black_box(content::extract_abstract(text));
+            black_box(content::extract_sections(text));
+            black_box(content::extract_categories(&live));
+            black_box(content::extract_images(&live));
+            black_box(content::extract_external_links(&live));
+            black_box(content::extract_see_also_links(&live));
+            black_box(content::extract_interlanguage_links(&live));
+            black_box(content::LINK_REGEX.captures_iter(&live).count());
+            black_box(content::extract_infoboxes(text));
+            black_box(content::extract_coordinates(text));
+            black_box(content::is_disambiguation(text));
+        })
+    });
+    group.bench_function("scan_brackets", |b| {
+        b.iter(|| content::scan_brackets(black_box(&text)))
+    });
+    group.bench_function("bracket_regexes", |b| {
+        b.iter(|| {
+            let text = black_box(&text);
+            black_box(content::LINK_REGEX.captures_iter(text).count());
+            black_box(content::extract_categories(text));
+            black_box(content::extract_images(text));
+            black_box(content::extract_external_links(text));
+            black_box(content::extract_interlanguage_links(text));
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_content);
+criterion_main!(benches);
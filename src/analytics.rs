@@ -5,6 +5,7 @@
 //! Uses rayon for parallel PageRank iterations.
 
 use crate::config;
+use crate::csv_util;
 use anyhow::{Context, Result};
 use rayon::prelude::*;
 use rustc_hash::FxHashMap;
@@ -18,6 +19,9 @@ use tracing::info;
 #[derive(Debug, Clone)]
 pub struct AnalyticsConfig {
     pub db_path: String,
+    /// SurrealDB namespace and database holding the loaded articles.
+    pub namespace: String,
+    pub database: String,
     pub output_dir: String,
     pub pagerank_iterations: u32,
     pub pagerank_damping: f64,
@@ -29,6 +33,8 @@ impl Default for AnalyticsConfig {
     fn default() -> Self {
         Self {
             db_path: config::DEFAULT_DB_PATH.to_string(),
+            namespace: config::SURREAL_NAMESPACE.to_string(),
+            database: config::SURREAL_DATABASE.to_string(),
             output_dir: String::new(),
             pagerank_iterations: config::PAGERANK_ITERATIONS,
             pagerank_damping: config::PAGERANK_DAMPING,
@@ -62,7 +68,7 @@ struct CsrGraph {
     /// Source nodes for incoming edges.
     in_col_idx: Vec<u32>,
     /// Mapping from dense index back to Wikipedia page ID.
-    dense_to_wiki: Vec<u32>,
+    dense_to_wiki: Vec<u64>,
 }
 
 /// Runs all analytics (PageRank, Louvain, degree) and writes results to SurrealDB.
@@ -111,8 +117,8 @@ pub async fn run_analytics(config: AnalyticsConfig) -> Result<AnalyticsStats> {
         .await
         .with_context(|| format!("Failed to open SurrealDB at {}", db_path))?;
 
-    db.use_ns(config::SURREAL_NAMESPACE)
-        .use_db(config::SURREAL_DATABASE)
+    db.use_ns(&config.namespace)
+        .use_db(&config.database)
         .await
         .context("Failed to select namespace/database")?;
 
@@ -130,32 +136,34 @@ pub async fn run_analytics(config: AnalyticsConfig) -> Result<AnalyticsStats> {
     })
 }
 
-/// Builds a CSR graph from nodes.csv and edges.csv.
+/// Builds a CSR graph from nodes.csv and edges.csv (including rollover parts).
 ///
 /// Returns the graph and the sparse-to-dense ID mapping.
-fn build_graph(output_dir: &str) -> Result<(CsrGraph, FxHashMap<u32, u32>)> {
+fn build_graph(output_dir: &str) -> Result<(CsrGraph, FxHashMap<u64, u32>)> {
     info!("Building graph from CSVs");
 
     // Read node IDs
-    let nodes_path = Path::new(output_dir).join("nodes.csv");
-    let mut node_reader = csv::Reader::from_path(&nodes_path)
-        .with_context(|| format!("Failed to open {:?}", nodes_path))?;
-
-    let mut wiki_ids: Vec<u32> = Vec::new();
-    for result in node_reader.records() {
-        let record = result.context("Failed to read node record")?;
-        let id: u32 = record
-            .get(0)
-            .unwrap_or("0")
-            .parse()
-            .context("Invalid node ID")?;
-        wiki_ids.push(id);
+    let mut wiki_ids: Vec<u64> = Vec::new();
+    for part in csv_util::single_file_parts(output_dir, "nodes") {
+        let nodes_path = Path::new(output_dir).join(part);
+        let mut node_reader = csv::Reader::from_path(&nodes_path)
+            .with_context(|| format!("Failed to open {:?}", nodes_path))?;
+
+        for result in node_reader.records() {
+            let record = result.context("Failed to read node record")?;
+            let id: u64 = record
+                .get(0)
+                .unwrap_or("0")
+                .parse()
+                .context("Invalid node ID")?;
+            wiki_ids.push(id);
+        }
     }
 
     // Build sparse → dense mapping
-    let mut id_map: FxHashMap<u32, u32> =
+    let mut id_map: FxHashMap<u64, u32> =
         FxHashMap::with_capacity_and_hasher(wiki_ids.len(), Default::default());
-    let mut dense_to_wiki: Vec<u32> = Vec::with_capacity(wiki_ids.len());
+    let mut dense_to_wiki: Vec<u64> = Vec::with_capacity(wiki_ids.len());
     for (dense_idx, &wiki_id) in wiki_ids.iter().enumerate() {
         id_map.insert(wiki_id, dense_idx as u32);
         dense_to_wiki.push(wiki_id);
@@ -163,31 +171,33 @@ fn build_graph(output_dir: &str) -> Result<(CsrGraph, FxHashMap<u32, u32>)> {
     let n = wiki_ids.len();
     drop(wiki_ids);
 
-    // Read edges and build adjacency lists
-    let edges_path = Path::new(output_dir).join("edges.csv");
-    let mut edge_reader = csv::Reader::from_path(&edges_path)
-        .with_context(|| format!("Failed to open {:?}", edges_path))?;
-
     // Collect outgoing and incoming edges per node
     let mut out_edges: Vec<Vec<u32>> = vec![Vec::new(); n];
     let mut in_edges: Vec<Vec<u32>> = vec![Vec::new(); n];
 
-    for result in edge_reader.records() {
-        let record = result.context("Failed to read edge record")?;
-        let src_wiki: u32 = record
-            .get(0)
-            .unwrap_or("0")
-            .parse()
-            .context("Invalid source ID")?;
-        let dst_wiki: u32 = record
-            .get(1)
-            .unwrap_or("0")
-            .parse()
-            .context("Invalid target ID")?;
-
-        if let (Some(&src), Some(&dst)) = (id_map.get(&src_wiki), id_map.get(&dst_wiki)) {
-            out_edges[src as usize].push(dst);
-            in_edges[dst as usize].push(src);
+    // Read edges and build adjacency lists
+    for part in csv_util::single_file_parts(output_dir, "edges") {
+        let edges_path = Path::new(output_dir).join(part);
+        let mut edge_reader = csv::Reader::from_path(&edges_path)
+            .with_context(|| format!("Failed to open {:?}", edges_path))?;
+
+        for result in edge_reader.records() {
+            let record = result.context("Failed to read edge record")?;
+            let src_wiki: u64 = record
+                .get(0)
+                .unwrap_or("0")
+                .parse()
+                .context("Invalid source ID")?;
+            let dst_wiki: u64 = record
+                .get(1)
+                .unwrap_or("0")
+                .parse()
+                .context("Invalid target ID")?;
+
+            if let (Some(&src), Some(&dst)) = (id_map.get(&src_wiki), id_map.get(&dst_wiki)) {
+                out_edges[src as usize].push(dst);
+                in_edges[dst as usize].push(src);
+            }
         }
     }
 
@@ -425,6 +435,27 @@ mod tests {
         assert_eq!(graph.col_idx.len(), 4); // 4 edges
     }
 
+    #[test]
+    fn test_build_graph_reads_rollover_parts() {
+        let dir = TempDir::new().unwrap();
+        write_test_graph(dir.path());
+        std::fs::write(
+            dir.path().join("nodes_part001.csv"),
+            "id:ID,title,:LABEL\n50,E,Page\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("edges_part001.csv"),
+            ":START_ID,:END_ID,:TYPE\n40,50,LINKS_TO\n",
+        )
+        .unwrap();
+
+        let (graph, id_map) = build_graph(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(graph.n, 5);
+        assert_eq!(graph.col_idx.len(), 5);
+        assert!(id_map.contains_key(&50));
+    }
+
     #[test]
     fn test_pagerank_basic() {
         let dir = TempDir::new().unwrap();
@@ -1,17 +1,24 @@
 //! Index persistence using `bincode` serialization.
 //!
 //! Saves and loads `WikiIndex` as `index.cache`, validating against input file
-//! mtime and size. Uses zero-copy serialization via `IndexCacheSer` to avoid
-//! cloning ~17M strings during writes.
+//! mtime and size. A cache built from a base dump plus delta dumps records the
+//! delta lineage, so a later run with more deltas only applies the new ones.
+//! Uses zero-copy serialization via `IndexCacheSer` to avoid cloning ~17M
+//! strings during writes.
+//!
+//! `export_jsonl()` writes the same maps as gzipped JSON Lines for tools that
+//! cannot read bincode; the bincode cache stays the format dedalus loads.
 
 use crate::config::CACHE_VERSION;
-use crate::index::WikiIndex;
+use crate::index::{Redirect, WikiIndex};
 use anyhow::{Context, Result, bail};
 use bincode::Options;
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use tracing::{info, warn};
@@ -25,21 +32,58 @@ pub struct CacheMetadata {
     pub input_size: u64,
     pub article_count: usize,
     pub redirect_count: usize,
+    /// Pages seen by the indexing pass (`WikiIndex::page_count`).
+    pub page_count: u64,
+    /// Pages with a repeated ID (`WikiIndex::duplicate_ids`).
+    pub duplicate_ids: u64,
+    /// Delta dumps applied on top of the base input, in order.
+    pub deltas: Vec<DeltaSource>,
+}
+
+/// A delta dump recorded in the cache lineage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaSource {
+    pub path: String,
+    pub mtime: u64,
+    pub size: u64,
+}
+
+impl DeltaSource {
+    fn from_path(path: &str) -> Result<Self> {
+        let (mtime, size) = get_input_metadata(path)?;
+        Ok(Self {
+            path: path.to_string(),
+            mtime,
+            size,
+        })
+    }
 }
 
 #[derive(Deserialize)]
 struct IndexCacheDe {
     metadata: CacheMetadata,
-    articles: FxHashMap<String, u32>,
-    redirects: FxHashMap<String, String>,
+    articles: FxHashMap<String, u64>,
+    redirects: FxHashMap<String, Redirect>,
+    namespaces: Vec<String>,
+    content_namespaces: Vec<i32>,
 }
 
 /// Borrows the index data to avoid cloning ~17M strings during serialization.
 #[derive(Serialize)]
 struct IndexCacheSer<'a> {
     metadata: CacheMetadata,
-    articles: &'a FxHashMap<String, u32>,
-    redirects: &'a FxHashMap<String, String>,
+    articles: &'a FxHashMap<String, u64>,
+    redirects: &'a FxHashMap<String, Redirect>,
+    namespaces: &'a [String],
+    content_namespaces: &'a [i32],
+}
+
+/// One line of [`export_jsonl`] output.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum IndexLine<'a> {
+    Article { title: &'a str, id: u64 },
+    Redirect { redirect: &'a str, to: &'a str },
 }
 
 /// Returns the path to the index cache file for a given output directory.
@@ -62,7 +106,23 @@ fn get_input_metadata(input_path: &str) -> Result<(u64, u64)> {
 }
 
 /// Returns `Ok(Some(index))` if the cache is valid, `Ok(None)` if missing or stale.
+///
+/// A cache that has deltas applied is not valid for the bare base input.
 pub fn try_load_index(cache_path: &Path, input_path: &str) -> Result<Option<WikiIndex>> {
+    Ok(try_load_index_with_deltas(cache_path, input_path, &[])?.map(|(index, _)| index))
+}
+
+/// Loads a cache built from `input_path` plus a prefix of `deltas`.
+///
+/// Returns the index and the number of `deltas` already applied to it; the
+/// caller applies `deltas[applied..]`. Returns `Ok(None)` if the cache is
+/// missing or stale, or if its lineage includes a delta that is not the
+/// corresponding entry of `deltas` (or that has changed on disk).
+pub fn try_load_index_with_deltas(
+    cache_path: &Path,
+    input_path: &str,
+    deltas: &[String],
+) -> Result<Option<(WikiIndex, usize)>> {
     if !cache_path.exists() {
         return Ok(None);
     }
@@ -112,17 +172,57 @@ pub fn try_load_index(cache_path: &Path, input_path: &str) -> Result<Option<Wiki
         return Ok(None);
     }
 
+    let cached_deltas = &cache.metadata.deltas;
+    if cached_deltas.len() > deltas.len() {
+        info!(
+            cached = cached_deltas.len(),
+            requested = deltas.len(),
+            "Cache has deltas applied that were not requested"
+        );
+        return Ok(None);
+    }
+    for (cached, path) in cached_deltas.iter().zip(deltas) {
+        if DeltaSource::from_path(path)? != *cached {
+            info!(
+                cached = cached.path,
+                current = path,
+                "Cache delta lineage mismatch"
+            );
+            return Ok(None);
+        }
+    }
+
     info!(
         articles = cache.metadata.article_count,
         redirects = cache.metadata.redirect_count,
+        deltas = cached_deltas.len(),
         "Index loaded from cache"
     );
 
-    Ok(Some(WikiIndex::from_maps(cache.articles, cache.redirects)))
+    let applied = cached_deltas.len();
+    Ok(Some((
+        WikiIndex::from_maps(cache.articles, cache.redirects)
+            .with_namespaces(&cache.namespaces)
+            .with_content_namespaces(&cache.content_namespaces)
+            .with_page_count(cache.metadata.page_count)
+            .with_duplicate_ids(cache.metadata.duplicate_ids),
+        applied,
+    )))
 }
 
 /// Serializes the index by reference (no cloning) and writes atomically via rename.
 pub fn save_index(index: &WikiIndex, input_path: &str, output_dir: &str) -> Result<()> {
+    save_index_with_deltas(index, input_path, &[], output_dir)
+}
+
+/// Like [`save_index`], recording `deltas` (in application order) as the
+/// cache lineage.
+pub fn save_index_with_deltas(
+    index: &WikiIndex,
+    input_path: &str,
+    deltas: &[String],
+    output_dir: &str,
+) -> Result<()> {
     let path = cache_path(output_dir);
 
     if let Some(parent) = path.parent() {
@@ -131,6 +231,10 @@ pub fn save_index(index: &WikiIndex, input_path: &str, output_dir: &str) -> Resu
     }
 
     let (mtime, size) = get_input_metadata(input_path)?;
+    let deltas = deltas
+        .iter()
+        .map(|d| DeltaSource::from_path(d))
+        .collect::<Result<Vec<_>>>()?;
     let (articles, redirects) = index.maps();
     let (article_count, redirect_count) = index.stats();
 
@@ -142,9 +246,14 @@ pub fn save_index(index: &WikiIndex, input_path: &str, output_dir: &str) -> Resu
             input_size: size,
             article_count,
             redirect_count,
+            page_count: index.page_count(),
+            duplicate_ids: index.duplicate_ids(),
+            deltas,
         },
         articles,
         redirects,
+        namespaces: index.namespaces(),
+        content_namespaces: index.content_namespaces(),
     };
 
     let tmp_path = path.with_extension("cache.tmp");
@@ -169,6 +278,45 @@ pub fn save_index(index: &WikiIndex, input_path: &str, output_dir: &str) -> Resu
     Ok(())
 }
 
+/// Writes `index` to `writer` as gzipped JSON Lines: one
+/// `{"title":..,"id":..}` object per article, then one `{"redirect":..,"to":..}`
+/// object per redirect, each group sorted by title so exports of the same
+/// index are identical. Returns the `(articles, redirects)` written.
+pub fn export_jsonl(index: &WikiIndex, writer: impl Write) -> Result<(usize, usize)> {
+    let (articles, redirects) = index.maps();
+    let mut articles: Vec<(&str, u64)> = articles.iter().map(|(t, id)| (t.as_str(), *id)).collect();
+    articles.sort_unstable();
+    let mut redirects: Vec<(&str, &str)> = redirects
+        .iter()
+        .map(|(from, redirect)| (from.as_str(), redirect.target.as_str()))
+        .collect();
+    redirects.sort_unstable();
+
+    let mut out = GzEncoder::new(BufWriter::new(writer), Compression::default());
+    let lines = articles
+        .iter()
+        .map(|&(title, id)| IndexLine::Article { title, id })
+        .chain(
+            redirects
+                .iter()
+                .map(|&(redirect, to)| IndexLine::Redirect { redirect, to }),
+        );
+    for line in lines {
+        serde_json::to_writer(&mut out, &line).context("Failed to write index JSON Lines")?;
+        out.write_all(b"\n")?;
+    }
+    out.finish()
+        .and_then(|mut writer| writer.flush())
+        .context("Failed to write index JSON Lines")?;
+
+    info!(
+        articles = articles.len(),
+        redirects = redirects.len(),
+        "Index exported as JSON Lines"
+    );
+    Ok((articles.len(), redirects.len()))
+}
+
 /// Returns `true` if the cache exists and matches the current input file.
 pub fn is_cache_valid(cache_path: &Path, input_path: &str) -> Result<bool> {
     Ok(try_load_index(cache_path, input_path)?.is_some())
@@ -192,7 +340,11 @@ pub fn load_index(cache_path: &Path) -> Result<WikiIndex> {
         .deserialize_from(reader)
         .context("Failed to deserialize index cache")?;
 
-    let index = WikiIndex::from_maps(cache.articles, cache.redirects);
+    let index = WikiIndex::from_maps(cache.articles, cache.redirects)
+        .with_namespaces(&cache.namespaces)
+        .with_content_namespaces(&cache.content_namespaces)
+        .with_page_count(cache.metadata.page_count)
+        .with_duplicate_ids(cache.metadata.duplicate_ids);
 
     info!(
         articles = cache.metadata.article_count,
@@ -244,7 +396,10 @@ mod tests {
         let input_str = input_path.to_str().unwrap();
         let output_dir = dir.path().to_str().unwrap();
 
-        let original = create_test_index();
+        let original = create_test_index()
+            .with_namespaces(&["Kategorie".to_string()])
+            .with_content_namespaces(&[100, 0])
+            .with_page_count(42);
         save_index(&original, input_str, output_dir).unwrap();
 
         let cache_file = cache_path(output_dir);
@@ -253,6 +408,9 @@ mod tests {
         assert_eq!(loaded.resolve_id("Article1"), Some(1));
         assert_eq!(loaded.resolve_id("Article2"), Some(2));
         assert_eq!(loaded.resolve_id("Redirect1"), Some(1));
+        assert_eq!(loaded.namespaces(), original.namespaces());
+        assert_eq!(loaded.content_namespaces(), [0, 100]);
+        assert_eq!(loaded.page_count(), 42);
     }
 
     #[test]
@@ -337,6 +495,65 @@ mod tests {
         assert!(!result);
     }
 
+    #[test]
+    fn delta_lineage_prefix_is_reused() {
+        let dir = TempDir::new().unwrap();
+        let input_path = create_test_input(&dir);
+        let input_str = input_path.to_str().unwrap();
+        let output_dir = dir.path().to_str().unwrap();
+        let d1 = dir.path().join("delta1.xml");
+        let d2 = dir.path().join("delta2.xml");
+        fs::write(&d1, "delta one").unwrap();
+        fs::write(&d2, "delta two").unwrap();
+        let deltas = vec![
+            d1.to_str().unwrap().to_string(),
+            d2.to_str().unwrap().to_string(),
+        ];
+
+        let index = create_test_index();
+        save_index_with_deltas(&index, input_str, &deltas[..1], output_dir).unwrap();
+        let cache_file = cache_path(output_dir);
+
+        let (_, applied) = try_load_index_with_deltas(&cache_file, input_str, &deltas[..1])
+            .unwrap()
+            .unwrap();
+        assert_eq!(applied, 1);
+
+        let (_, applied) = try_load_index_with_deltas(&cache_file, input_str, &deltas)
+            .unwrap()
+            .unwrap();
+        assert_eq!(applied, 1);
+
+        // Base-only and mismatched lineages are cache misses
+        assert!(try_load_index(&cache_file, input_str).unwrap().is_none());
+        assert!(
+            try_load_index_with_deltas(&cache_file, input_str, &deltas[1..])
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn delta_lineage_invalidated_when_delta_modified() {
+        let dir = TempDir::new().unwrap();
+        let input_path = create_test_input(&dir);
+        let input_str = input_path.to_str().unwrap();
+        let output_dir = dir.path().to_str().unwrap();
+        let delta = dir.path().join("delta.xml");
+        fs::write(&delta, "delta").unwrap();
+        let delta = vec![delta.to_str().unwrap().to_string()];
+
+        save_index_with_deltas(&create_test_index(), input_str, &delta, output_dir).unwrap();
+        fs::write(&delta[0], "a longer delta").unwrap();
+
+        let cache_file = cache_path(output_dir);
+        assert!(
+            try_load_index_with_deltas(&cache_file, input_str, &delta)
+                .unwrap()
+                .is_none()
+        );
+    }
+
     #[test]
     fn save_index_creates_parent_directories() {
         let dir = TempDir::new().unwrap();
@@ -351,4 +568,41 @@ mod tests {
         let cache_file = cache_path(output_dir);
         assert!(cache_file.exists());
     }
+
+    #[test]
+    fn export_jsonl_roundtrips_through_a_jsonl_reader() {
+        use std::io::BufRead;
+
+        let index = create_test_index();
+        let mut gz = Vec::new();
+        assert_eq!(export_jsonl(&index, &mut gz).unwrap(), (2, 1));
+
+        let reader = std::io::BufReader::new(flate2::read::GzDecoder::new(gz.as_slice()));
+        let lines: Vec<String> = reader.lines().map(Result::unwrap).collect();
+        assert_eq!(lines[0], r#"{"title":"Article1","id":1}"#);
+        assert_eq!(lines[2], r#"{"redirect":"Redirect1","to":"Article1"}"#);
+
+        let mut articles = FxHashMap::default();
+        let mut redirects = FxHashMap::default();
+        let (expected_articles, expected_redirects) = index.maps();
+        for line in &lines {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            if let Some(title) = value["title"].as_str() {
+                articles.insert(title.to_string(), value["id"].as_u64().unwrap());
+            } else {
+                redirects.insert(
+                    value["redirect"].as_str().unwrap().to_string(),
+                    value["to"].as_str().unwrap().to_string(),
+                );
+            }
+        }
+        let expected_redirects: FxHashMap<String, String> = expected_redirects
+            .iter()
+            .map(|(from, redirect)| (from.clone(), redirect.target.clone()))
+            .collect();
+        assert_eq!(
+            (&articles, &redirects),
+            (expected_articles, &expected_redirects)
+        );
+    }
 }
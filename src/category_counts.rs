@@ -0,0 +1,96 @@
+//! Per-category article counts for `--category-counts`.
+//!
+//! `CategoryCounts` wraps a `DashMap<String, u64>` that extraction workers update
+//! concurrently with each article's categories. Because a count is only final
+//! once every article has been processed, category node rows are held back
+//! during the pass and written at the end with a `count:int` column.
+
+use dashmap::DashMap;
+use std::borrow::Cow;
+
+/// Thread-safe count of articles per category name.
+#[derive(Debug, Default)]
+pub struct CategoryCounts {
+    counts: DashMap<String, u64>,
+}
+
+impl CategoryCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts one article in each of `names` (a category listed twice in an
+    /// article counts once). Returns how many names were seen for the first time.
+    pub fn record(&self, names: &[Cow<'_, str>]) -> u64 {
+        let mut names: Vec<&str> = names.iter().map(AsRef::as_ref).collect();
+        names.sort_unstable();
+        names.dedup();
+
+        let mut new_names = 0;
+        for name in names {
+            // Look up by reference first so known names don't allocate a key.
+            if let Some(mut count) = self.counts.get_mut(name) {
+                *count += 1;
+            } else {
+                let mut count = self.counts.entry(name.to_string()).or_insert(0);
+                if *count == 0 {
+                    new_names += 1;
+                }
+                *count += 1;
+            }
+        }
+        new_names
+    }
+
+    /// Number of distinct categories seen.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Article count for `name`, or 0 if no article is in it.
+    pub fn get(&self, name: &str) -> u64 {
+        self.counts.get(name).map_or(0, |count| *count)
+    }
+
+    /// `(name, count)` pairs sorted by name, so output is reproducible.
+    pub fn sorted(&self) -> Vec<(String, u64)> {
+        let mut rows: Vec<(String, u64)> = self
+            .counts
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        rows.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_counts_each_article_once_per_category() {
+        let counts = CategoryCounts::new();
+        let names = |list: &[&'static str]| -> Vec<Cow<'static, str>> {
+            list.iter().map(|s| Cow::Borrowed(*s)).collect()
+        };
+
+        assert_eq!(counts.record(&names(&["B", "A", "B"])), 2);
+        assert_eq!(counts.record(&names(&["A", "C"])), 1);
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts.get("A"), 2);
+        assert_eq!(counts.get("Missing"), 0);
+        assert_eq!(
+            counts.sorted(),
+            vec![
+                ("A".to_string(), 2),
+                ("B".to_string(), 1),
+                ("C".to_string(), 1)
+            ]
+        );
+    }
+}
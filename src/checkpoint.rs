@@ -4,6 +4,7 @@
 //! atomic write-via-rename for crash safety. Cleared on successful completion.
 
 use crate::config::CHECKPOINT_VERSION;
+use crate::shard::ShardStrategy;
 use crate::stats::ExtractionStats;
 use anyhow::{Context, Result};
 use bincode::Options;
@@ -12,7 +13,7 @@ use std::fs::{self, File};
 use std::io::{BufReader, BufWriter};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::time::SystemTime;
 use tracing::{debug, info, warn};
 
@@ -23,12 +24,17 @@ pub struct CheckpointStats {
     pub edges_extracted: u64,
     pub blobs_written: u64,
     pub invalid_links: u64,
+    pub red_links: u64,
     pub categories_found: u64,
     pub category_edges: u64,
     pub see_also_edges: u64,
     pub infoboxes_extracted: u64,
     pub images_found: u64,
     pub external_links_found: u64,
+    pub output_bytes: u64,
+    pub blobs_sampled_out: u64,
+    pub articles_excluded: u64,
+    pub self_links: u64,
 }
 
 /// A saved extraction checkpoint for resume support.
@@ -40,17 +46,29 @@ pub struct Checkpoint {
     pub output_dir: String,
     pub shard_count: u32,
     pub csv_shards: u32,
-    pub last_processed_id: u32,
+    pub shard_strategy: ShardStrategy,
+    pub last_processed_id: u64,
+    /// Pages past `last_processed_id` that had already finished (sorted);
+    /// resuming skips them as well.
+    pub completed_after: Vec<u64>,
     pub stats: CheckpointStats,
 }
 
+/// The pages a checkpoint covers: every page up to `last_id`, plus `after`,
+/// pages past it that finished while an earlier page was still in flight.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Completed {
+    pub last_id: u64,
+    pub after: Vec<u64>,
+}
+
 /// Returns the path to the checkpoint file for a given output directory.
 #[must_use]
 pub fn checkpoint_path(output_dir: &str) -> PathBuf {
     Path::new(output_dir).join("checkpoint.bin")
 }
 
-fn get_input_mtime(input_path: &str) -> Result<u64> {
+pub(crate) fn get_input_mtime(input_path: &str) -> Result<u64> {
     let metadata = fs::metadata(input_path)
         .with_context(|| format!("Failed to get metadata for: {}", input_path))?;
     let mtime = metadata
@@ -62,13 +80,9 @@ fn get_input_mtime(input_path: &str) -> Result<u64> {
     Ok(mtime)
 }
 
-/// Loads a checkpoint if it exists and matches the current extraction parameters.
-pub fn load_if_valid(
-    input_path: &str,
-    output_dir: &str,
-    shard_count: u32,
-    csv_shards: u32,
-) -> Result<Option<Checkpoint>> {
+/// Loads the checkpoint in `output_dir` without checking it against any run
+/// parameters. `None` if there is none or it cannot be decoded.
+pub fn load(output_dir: &str) -> Result<Option<Checkpoint>> {
     let path = checkpoint_path(output_dir);
 
     if !path.exists() {
@@ -82,12 +96,25 @@ pub fn load_if_valid(
 
     let options = bincode::options().with_limit(file_size.saturating_add(1024));
 
-    let checkpoint: Checkpoint = match options.deserialize_from(reader) {
-        Ok(c) => c,
+    match options.deserialize_from(reader) {
+        Ok(checkpoint) => Ok(Some(checkpoint)),
         Err(e) => {
             warn!(error = %e, "Checkpoint file is corrupt or unreadable");
-            return Ok(None);
+            Ok(None)
         }
+    }
+}
+
+/// Loads a checkpoint if it exists and matches the current extraction parameters.
+pub fn load_if_valid(
+    input_path: &str,
+    output_dir: &str,
+    shard_count: u32,
+    csv_shards: u32,
+    shard_strategy: ShardStrategy,
+) -> Result<Option<Checkpoint>> {
+    let Some(checkpoint) = load(output_dir)? else {
+        return Ok(None);
     };
 
     if checkpoint.version != CHECKPOINT_VERSION {
@@ -145,6 +172,15 @@ pub fn load_if_valid(
         return Ok(None);
     }
 
+    if checkpoint.shard_strategy != shard_strategy {
+        info!(
+            cached = %checkpoint.shard_strategy,
+            current = %shard_strategy,
+            "Checkpoint shard strategy mismatch"
+        );
+        return Ok(None);
+    }
+
     info!(
         last_id = checkpoint.last_processed_id,
         articles = checkpoint.stats.articles_processed,
@@ -154,6 +190,31 @@ pub fn load_if_valid(
     Ok(Some(checkpoint))
 }
 
+/// A checkpoint for resuming after `last_processed_id` without one on disk
+/// (`--resume-from`). Its stats are zero, so the run counts only what it
+/// processes itself.
+pub fn starting_after(
+    input_path: &str,
+    output_dir: &str,
+    shard_count: u32,
+    csv_shards: u32,
+    shard_strategy: ShardStrategy,
+    last_processed_id: u64,
+) -> Result<Checkpoint> {
+    Ok(Checkpoint {
+        version: CHECKPOINT_VERSION,
+        input_path: input_path.to_string(),
+        input_mtime: get_input_mtime(input_path)?,
+        output_dir: output_dir.to_string(),
+        shard_count,
+        csv_shards,
+        shard_strategy,
+        last_processed_id,
+        completed_after: Vec::new(),
+        stats: CheckpointStats::default(),
+    })
+}
+
 /// Removes the checkpoint file for the given output directory.
 pub fn clear(output_dir: &str) -> Result<()> {
     let path = checkpoint_path(output_dir);
@@ -173,8 +234,9 @@ pub struct CheckpointManager {
     output_dir: String,
     shard_count: u32,
     csv_shards: u32,
+    shard_strategy: ShardStrategy,
     interval: u32,
-    last_saved_id: AtomicU32,
+    last_saved_id: AtomicU64,
     pages_since_save: AtomicU32,
     save_lock: Mutex<()>,
 }
@@ -195,6 +257,7 @@ impl CheckpointManager {
         output_dir: &str,
         shard_count: u32,
         csv_shards: u32,
+        shard_strategy: ShardStrategy,
         interval: u32,
     ) -> Result<Self> {
         let input_mtime = get_input_mtime(input_path)?;
@@ -205,20 +268,37 @@ impl CheckpointManager {
             output_dir: output_dir.to_string(),
             shard_count,
             csv_shards,
+            shard_strategy,
             interval,
-            last_saved_id: AtomicU32::new(0),
+            last_saved_id: AtomicU64::new(0),
             pages_since_save: AtomicU32::new(0),
             save_lock: Mutex::new(()),
         })
     }
 
     /// Sets the last saved page ID (used when resuming from an existing checkpoint).
-    pub fn set_last_id(&self, id: u32) {
+    pub fn set_last_id(&self, id: u64) {
         self.last_saved_id.store(id, Ordering::Relaxed);
     }
 
     /// Double-checked locking: atomic counter for fast path, mutex for serialized saves.
-    pub fn maybe_save(&self, page_id: u32, stats: &ExtractionStats) -> Result<bool> {
+    pub fn maybe_save(&self, page_id: u64, stats: &ExtractionStats) -> Result<bool> {
+        self.maybe_save_with(stats, || {
+            Ok(Completed {
+                last_id: page_id,
+                after: Vec::new(),
+            })
+        })
+    }
+
+    /// Like [`maybe_save`](Self::maybe_save), but the pages to record come from
+    /// `before_save`, run under the save lock so it can flush the output those
+    /// pages wrote first. If it fails, no checkpoint is written.
+    pub fn maybe_save_with(
+        &self,
+        stats: &ExtractionStats,
+        before_save: impl FnOnce() -> Result<Completed>,
+    ) -> Result<bool> {
         let count = self.pages_since_save.fetch_add(1, Ordering::Relaxed) + 1;
 
         if count >= self.interval {
@@ -235,7 +315,8 @@ impl CheckpointManager {
                 return Ok(false);
             }
 
-            self.save(page_id, stats)?;
+            let completed = before_save()?;
+            self.save_completed(&completed, stats)?;
             self.pages_since_save.store(0, Ordering::Relaxed);
             return Ok(true);
         }
@@ -244,7 +325,20 @@ impl CheckpointManager {
     }
 
     /// Writes the current extraction state to disk atomically via `.tmp` + rename.
-    pub fn save(&self, page_id: u32, stats: &ExtractionStats) -> Result<()> {
+    pub fn save(&self, page_id: u64, stats: &ExtractionStats) -> Result<()> {
+        self.save_completed(
+            &Completed {
+                last_id: page_id,
+                after: Vec::new(),
+            },
+            stats,
+        )
+    }
+
+    /// Like [`save`](Self::save), also recording the pages that finished past
+    /// `completed.last_id`.
+    pub fn save_completed(&self, completed: &Completed, stats: &ExtractionStats) -> Result<()> {
+        let page_id = completed.last_id;
         let checkpoint = Checkpoint {
             version: CHECKPOINT_VERSION,
             input_path: self.input_path.clone(),
@@ -252,7 +346,9 @@ impl CheckpointManager {
             output_dir: self.output_dir.clone(),
             shard_count: self.shard_count,
             csv_shards: self.csv_shards,
+            shard_strategy: self.shard_strategy,
             last_processed_id: page_id,
+            completed_after: completed.after.clone(),
             stats: stats.to_checkpoint(),
         };
 
@@ -322,6 +418,7 @@ mod tests {
             dir.path().to_str().unwrap(),
             1000,
             1,
+            ShardStrategy::Hash,
         )
         .unwrap();
         assert!(result.is_none());
@@ -334,7 +431,9 @@ mod tests {
         let input_str = input_path.to_str().unwrap();
         let output_dir = dir.path().to_str().unwrap();
 
-        let manager = CheckpointManager::new(input_str, output_dir, 1000, 1, 100).unwrap();
+        let manager =
+            CheckpointManager::new(input_str, output_dir, 1000, 1, ShardStrategy::Hash, 100)
+                .unwrap();
 
         let stats = ExtractionStats::new();
         stats.inc_articles();
@@ -343,7 +442,7 @@ mod tests {
 
         manager.save(42, &stats).unwrap();
 
-        let loaded = load_if_valid(input_str, output_dir, 1000, 1)
+        let loaded = load_if_valid(input_str, output_dir, 1000, 1, ShardStrategy::Hash)
             .unwrap()
             .unwrap();
         assert_eq!(loaded.last_processed_id, 42);
@@ -358,7 +457,9 @@ mod tests {
         let input_str = input_path.to_str().unwrap();
         let output_dir = dir.path().to_str().unwrap();
 
-        let manager = CheckpointManager::new(input_str, output_dir, 1000, 1, 100).unwrap();
+        let manager =
+            CheckpointManager::new(input_str, output_dir, 1000, 1, ShardStrategy::Hash, 100)
+                .unwrap();
         manager.save(42, &ExtractionStats::new()).unwrap();
 
         // mtime has second granularity
@@ -366,7 +467,7 @@ mod tests {
         let mut file = File::create(&input_path).unwrap();
         writeln!(file, "modified content").unwrap();
 
-        let loaded = load_if_valid(input_str, output_dir, 1000, 1).unwrap();
+        let loaded = load_if_valid(input_str, output_dir, 1000, 1, ShardStrategy::Hash).unwrap();
         assert!(loaded.is_none());
     }
 
@@ -377,10 +478,12 @@ mod tests {
         let input_str = input_path.to_str().unwrap();
         let output_dir = dir.path().to_str().unwrap();
 
-        let manager = CheckpointManager::new(input_str, output_dir, 1000, 1, 100).unwrap();
+        let manager =
+            CheckpointManager::new(input_str, output_dir, 1000, 1, ShardStrategy::Hash, 100)
+                .unwrap();
         manager.save(42, &ExtractionStats::new()).unwrap();
 
-        let loaded = load_if_valid(input_str, output_dir, 500, 1).unwrap();
+        let loaded = load_if_valid(input_str, output_dir, 500, 1, ShardStrategy::Hash).unwrap();
         assert!(loaded.is_none());
     }
 
@@ -391,10 +494,13 @@ mod tests {
         let input_str = input_path.to_str().unwrap();
         let output_dir = dir.path().to_str().unwrap();
 
-        let manager = CheckpointManager::new(input_str, output_dir, 1000, 1, 100).unwrap();
+        let manager =
+            CheckpointManager::new(input_str, output_dir, 1000, 1, ShardStrategy::Hash, 100)
+                .unwrap();
         manager.save(42, &ExtractionStats::new()).unwrap();
 
-        let loaded = load_if_valid(input_str, "/different/output", 1000, 1).unwrap();
+        let loaded =
+            load_if_valid(input_str, "/different/output", 1000, 1, ShardStrategy::Hash).unwrap();
         assert!(loaded.is_none());
     }
 
@@ -405,7 +511,9 @@ mod tests {
         let input_str = input_path.to_str().unwrap();
         let output_dir = dir.path().to_str().unwrap();
 
-        let manager = CheckpointManager::new(input_str, output_dir, 1000, 1, 100).unwrap();
+        let manager =
+            CheckpointManager::new(input_str, output_dir, 1000, 1, ShardStrategy::Hash, 100)
+                .unwrap();
         manager.save(42, &ExtractionStats::new()).unwrap();
 
         let path = checkpoint_path(output_dir);
@@ -429,7 +537,8 @@ mod tests {
         let input_str = input_path.to_str().unwrap();
         let output_dir = dir.path().to_str().unwrap();
 
-        let manager = CheckpointManager::new(input_str, output_dir, 1000, 1, 3).unwrap();
+        let manager =
+            CheckpointManager::new(input_str, output_dir, 1000, 1, ShardStrategy::Hash, 3).unwrap();
         let stats = ExtractionStats::new();
 
         assert!(!manager.maybe_save(1, &stats).unwrap());
@@ -440,6 +549,27 @@ mod tests {
         assert!(!manager.maybe_save(4, &stats).unwrap());
         assert!(!manager.maybe_save(5, &stats).unwrap());
         assert!(manager.maybe_save(6, &stats).unwrap());
+
+        // A failed flush skips the save and leaves the counter due.
+        assert!(!manager.maybe_save(7, &stats).unwrap());
+        assert!(!manager.maybe_save(8, &stats).unwrap());
+        assert!(
+            manager
+                .maybe_save_with(&stats, || anyhow::bail!("disk full"))
+                .is_err()
+        );
+        assert_eq!(load(output_dir).unwrap().unwrap().last_processed_id, 6);
+        assert!(
+            manager
+                .maybe_save_with(&stats, || Ok(Completed {
+                    last_id: 10,
+                    after: vec![12, 15],
+                }))
+                .unwrap()
+        );
+        let loaded = load(output_dir).unwrap().unwrap();
+        assert_eq!(loaded.last_processed_id, 10);
+        assert_eq!(loaded.completed_after, [12, 15]);
     }
 
     #[test]
@@ -456,6 +586,7 @@ mod tests {
             dir.path().to_str().unwrap(),
             1000,
             1,
+            ShardStrategy::Hash,
         )
         .unwrap();
         assert!(result.is_none());
@@ -0,0 +1,239 @@
+//! Image metadata from a second, images-only dump (`--input PATH:images`).
+//!
+//! Article dumps reference images by filename only. `add_image_metadata()`
+//! runs after extraction: it collects the filenames in `image_nodes`, streams
+//! the images dump (typically a Wikimedia Commons dump) for `File:` pages with
+//! those names, reads each page's `{{Information}}` template, and rewrites the
+//! `image_nodes` files in place with `description`, `author` and `date`
+//! columns after `:LABEL`. Images without a matching page keep empty values.
+//!
+//! File page titles go through `content::normalize_image_name`, the same
+//! normalization extraction applies to `[[File:...]]` links, so both dumps key
+//! an image the same way. Only filenames already in `image_nodes` are held in
+//! memory. The files are staged as `{name}.csv.tmp` and renamed into place
+//! once every one is written.
+
+use crate::content::{normalize_image_name, to_plain_text};
+use crate::csv_util::{self, CsvType};
+use crate::infobox::{find_matching_close, split_at_depth_zero};
+use crate::parser::WikiReader;
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Namespace number of `File:` pages.
+const FILE_NAMESPACE: i32 = 6;
+
+/// Columns added to `image_nodes`, in order.
+pub const IMAGE_METADATA_COLUMNS: [&str; 3] = ["description", "author", "date"];
+
+static INFORMATION_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\{\{\s*information\s*[|}]").unwrap());
+
+/// `{{en|1=...}}`: a language template with its text as a named parameter.
+static LANGUAGE_PARAM_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([a-z]{2,3}(?:-[a-z]+)?)\s*\|\s*1\s*=").unwrap());
+
+/// Fields of a file page's `{{Information}}` template, as plain text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+}
+
+impl ImageInfo {
+    fn columns(&self) -> [&str; 3] {
+        [&self.description, &self.author, &self.date].map(|v| v.as_deref().unwrap_or(""))
+    }
+}
+
+/// Counts from an [`add_image_metadata`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageMetadataStats {
+    /// Rows in `image_nodes`.
+    pub images: u64,
+    /// `File:` pages read from the images dump.
+    pub file_pages: u64,
+    /// Images that got metadata from a matching file page.
+    pub matched: u64,
+}
+
+/// Template parameters of `inner` (the text between `{{` and `}}`), without
+/// the template name. `split_at_depth_zero` only tracks braces, so a segment
+/// that opens more `[[` than it closes is a piped link cut in two and is
+/// joined back with the segments after it.
+fn template_params(inner: &str) -> Vec<String> {
+    let mut params: Vec<String> = Vec::new();
+    let mut open_links = 0usize;
+    for segment in split_at_depth_zero(inner).into_iter().skip(1) {
+        match params.last_mut() {
+            Some(last) if open_links > 0 => {
+                last.push('|');
+                last.push_str(segment);
+            }
+            _ => params.push(segment.to_string()),
+        }
+        open_links = (open_links + segment.matches("[[").count())
+            .saturating_sub(segment.matches("]]").count());
+    }
+    params
+}
+
+/// Reads the `description`, `author` and `date` of the first `{{Information}}`
+/// template in a file page's wikitext. Keys match case-insensitively.
+/// Language wrappers such as `{{en|1=A cat}}` are reduced to their text.
+#[must_use]
+pub fn file_information(text: &str) -> ImageInfo {
+    let mut info = ImageInfo::default();
+    let Some(m) = INFORMATION_REGEX.find(text) else {
+        return info;
+    };
+    let Some(close) = find_matching_close(text.as_bytes(), m.start()) else {
+        return info;
+    };
+    for segment in template_params(&text[m.start() + 2..close]) {
+        let Some((key, value)) = segment.split_once('=') else {
+            continue;
+        };
+        let slot = match key.trim().to_ascii_lowercase().as_str() {
+            "description" => &mut info.description,
+            "author" => &mut info.author,
+            "date" => &mut info.date,
+            _ => continue,
+        };
+        let value = to_plain_text(&LANGUAGE_PARAM_REGEX.replace_all(value, "{{$1|"));
+        if slot.is_none() && !value.is_empty() {
+            *slot = Some(value);
+        }
+    }
+    info
+}
+
+/// Streams `images_dump` for `File:` pages named in `wanted` and returns their
+/// metadata by normalized filename, with the number of file pages read.
+fn collect_image_info(
+    images_dump: &str,
+    wanted: &FxHashSet<String>,
+) -> Result<(FxHashMap<String, ImageInfo>, u64)> {
+    let reader = WikiReader::new(images_dump, false)
+        .with_context(|| format!("Failed to open images dump {images_dump}"))?
+        .skip_timestamp(true);
+    let mut found = FxHashMap::default();
+    let mut file_pages = 0;
+    for page in reader {
+        if page.ns != Some(FILE_NAMESPACE) {
+            continue;
+        }
+        file_pages += 1;
+        let name = normalize_image_name(&page.title);
+        if !wanted.contains(name.as_ref()) || found.contains_key(name.as_ref()) {
+            continue;
+        }
+        let info = file_information(page.text.as_deref().unwrap_or_default());
+        found.insert(name.into_owned(), info);
+    }
+    Ok((found, file_pages))
+}
+
+/// Adds `description`, `author` and `date` columns to the `image_nodes` CSVs
+/// in `output_dir` from the `File:` pages of `images_dump`. Works on
+/// single-file and sharded layouts.
+pub fn add_image_metadata(output_dir: &str, images_dump: &str) -> Result<ImageMetadataStats> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    csv_util::validate_csv_files(output_dir, &layout)?;
+    let headers = csv_util::read_headers(output_dir, &layout, CsvType::ImageNodes)?;
+    if IMAGE_METADATA_COLUMNS
+        .iter()
+        .any(|column| headers.iter().any(|header| header == *column))
+    {
+        anyhow::bail!("image_nodes already has image metadata columns");
+    }
+
+    let mut wanted = FxHashSet::default();
+    csv_util::for_each_record(output_dir, &layout, CsvType::ImageNodes, |record| {
+        wanted.insert(record.get(0).unwrap_or_default().to_string());
+        Ok(())
+    })?;
+    let (found, file_pages) = collect_image_info(images_dump, &wanted)?;
+
+    let mut stats = ImageMetadataStats {
+        file_pages,
+        ..Default::default()
+    };
+    let files: Vec<PathBuf> = csv_util::layout_files(output_dir, &layout, CsvType::ImageNodes)
+        .into_iter()
+        .map(|file| Path::new(output_dir).join(file))
+        .collect();
+    let missing = ImageInfo::default();
+    for path in &files {
+        let tmp_path = path.with_extension("csv.tmp");
+        let mut reader =
+            csv::Reader::from_path(path).with_context(|| format!("Failed to open {path:?}"))?;
+        let mut writer = csv::Writer::from_path(&tmp_path)
+            .with_context(|| format!("Failed to create {tmp_path:?}"))?;
+        let mut header = reader
+            .headers()
+            .with_context(|| format!("Failed to read header of {path:?}"))?
+            .clone();
+        header.extend(IMAGE_METADATA_COLUMNS);
+        writer.write_record(&header)?;
+        for record in reader.records() {
+            let mut record = record.with_context(|| format!("Failed to read {path:?}"))?;
+            let info = match found.get(record.get(0).unwrap_or_default()) {
+                Some(info) => {
+                    stats.matched += 1;
+                    info
+                }
+                None => &missing,
+            };
+            record.extend(info.columns());
+            writer.write_record(&record)?;
+            stats.images += 1;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {tmp_path:?}"))?;
+    }
+    for path in &files {
+        let tmp_path = path.with_extension("csv.tmp");
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to rename {tmp_path:?} to {path:?}"))?;
+    }
+
+    info!(
+        images = stats.images,
+        file_pages = stats.file_pages,
+        matched = stats.matched,
+        "Added image metadata from images dump"
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn information_template_fields_become_plain_text() {
+        let text = "== Summary ==\n{{Information\n\
+                    |Description={{en|1=A [[cat]] on a '''mat'''}}\n\
+                    |Date=2019-05-04\n\
+                    |Source={{own}}\n\
+                    |author=[[User:Example|Example]]\n\
+                    }}\n[[Category:Cats]]";
+        assert_eq!(
+            file_information(text),
+            ImageInfo {
+                description: Some("A cat on a mat".to_string()),
+                author: Some("Example".to_string()),
+                date: Some("2019-05-04".to_string()),
+            }
+        );
+        assert_eq!(file_information("No template here."), ImageInfo::default());
+    }
+}
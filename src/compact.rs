@@ -0,0 +1,238 @@
+//! Dense renumbering of article IDs in the CSV output (`--compact-ids`).
+//!
+//! Wikipedia page IDs are sparse, which wastes space in array-indexed
+//! consumers. `compact_ids()` collects every article ID in `nodes` and both
+//! endpoints of `edges`, sorts them, and rewrites each article ID column to
+//! its rank (`0..N`), including the `:START_ID` of the optional `interwiki.csv`
+//! and `coordinates.csv`. Entity IDs (categories, images, external links) are
+//! left alone. The mapping is written to `id_map.csv` (`old_id,new_id`).
+//!
+//! Every rewritten file is staged as `{name}.tmp` and only renamed into place
+//! once all of them are written. JSON blobs, `redirect_map.csv` and checkpoints
+//! keep the original IDs; use `id_map.csv` to translate.
+
+use crate::config::{COORDINATES_BASE_NAME, ID_MAP_FILENAME, INTERWIKI_BASE_NAME, MERGE_BUF_SIZE};
+use crate::csv_util::{self, CsvType};
+use anyhow::{Context, Result};
+use csv::StringRecord;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Counts from a [`compact_ids`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactStats {
+    /// Distinct article IDs renumbered (the size of `id_map.csv`).
+    pub articles: u64,
+    /// CSV data rows rewritten across all files.
+    pub rows: u64,
+}
+
+/// Columns holding article IDs for each CSV type.
+fn article_id_columns(csv_type: CsvType) -> &'static [usize] {
+    match csv_type {
+        CsvType::Nodes
+        | CsvType::ArticleCategories
+        | CsvType::ArticleImages
+        | CsvType::ArticleExternalLinks => &[0],
+        CsvType::Edges => &[0, 1],
+        CsvType::Categories | CsvType::ImageNodes | CsvType::ExternalLinkNodes => &[],
+    }
+}
+
+/// Sorted, deduplicated article IDs from the nodes and edge endpoints.
+fn collect_ids(output_dir: &str, layout: &csv_util::CsvLayout) -> Result<Vec<u64>> {
+    let mut ids = Vec::new();
+    for csv_type in [CsvType::Nodes, CsvType::Edges] {
+        let columns = article_id_columns(csv_type);
+        csv_util::for_each_record(output_dir, layout, csv_type, |record| {
+            for &col in columns {
+                ids.push(csv_util::parse_id(
+                    record.as_byte_record(),
+                    col,
+                    csv_type.base_name(),
+                )?);
+            }
+            Ok(())
+        })?;
+    }
+    ids.sort_unstable();
+    ids.dedup();
+    Ok(ids)
+}
+
+/// Writes `file` with its article ID columns renumbered to `{file}.tmp`.
+/// Returns the temp path and the number of rows written.
+fn stage_rewrite(path: &Path, columns: &[usize], ids: &[u64]) -> Result<(PathBuf, u64)> {
+    let tmp_path = path.with_extension("csv.tmp");
+    let mut reader = csv::ReaderBuilder::new()
+        .buffer_capacity(MERGE_BUF_SIZE)
+        .from_path(path)
+        .with_context(|| format!("Failed to open {path:?}"))?;
+    let mut writer = csv::WriterBuilder::new()
+        .buffer_capacity(MERGE_BUF_SIZE)
+        .from_path(&tmp_path)
+        .with_context(|| format!("Failed to create {tmp_path:?}"))?;
+    writer.write_record(reader.headers()?)?;
+
+    let mut record = StringRecord::new();
+    let mut rewritten = StringRecord::new();
+    let mut itoa_buf = itoa::Buffer::new();
+    let mut rows = 0u64;
+    while reader
+        .read_record(&mut record)
+        .with_context(|| format!("Failed to read {path:?}"))?
+    {
+        rewritten.clear();
+        for (col, value) in record.iter().enumerate() {
+            if columns.contains(&col) {
+                let old = csv_util::parse_id(record.as_byte_record(), col, path)?;
+                let new = ids.binary_search(&old).ok().with_context(|| {
+                    format!("Article ID {old} in {path:?} is not in nodes or edges")
+                })?;
+                rewritten.push_field(itoa_buf.format(new));
+            } else {
+                rewritten.push_field(value);
+            }
+        }
+        writer.write_record(&rewritten)?;
+        rows += 1;
+    }
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write {tmp_path:?}"))?;
+    Ok((tmp_path, rows))
+}
+
+/// Renumbers article IDs in every CSV in `output_dir` to `0..N` (in original
+/// ID order), including `interwiki.csv` and `coordinates.csv` when present, and
+/// writes `id_map.csv`. Works on single-file and sharded layouts.
+pub fn compact_ids(output_dir: &str) -> Result<CompactStats> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    csv_util::validate_csv_files(output_dir, &layout)?;
+    let ids = collect_ids(output_dir, &layout)?;
+
+    let mut staged = Vec::new();
+    let mut rows = 0u64;
+    for &csv_type in CsvType::ALL {
+        let columns = article_id_columns(csv_type);
+        if columns.is_empty() {
+            continue;
+        }
+        for file in csv_util::layout_files(output_dir, &layout, csv_type) {
+            let path = Path::new(output_dir).join(file);
+            let (tmp_path, written) = stage_rewrite(&path, columns, &ids)?;
+            rows += written;
+            staged.push((tmp_path, path));
+        }
+    }
+    // The optional extras are always one file, keyed by article in `:START_ID`.
+    for base_name in [INTERWIKI_BASE_NAME, COORDINATES_BASE_NAME] {
+        let path = Path::new(output_dir).join(format!("{base_name}.csv"));
+        if path.exists() {
+            let (tmp_path, written) = stage_rewrite(&path, &[0], &ids)?;
+            rows += written;
+            staged.push((tmp_path, path));
+        }
+    }
+
+    let map_path = Path::new(output_dir).join(ID_MAP_FILENAME);
+    let map_tmp = map_path.with_extension("csv.tmp");
+    let mut writer = csv::Writer::from_path(&map_tmp)
+        .with_context(|| format!("Failed to create {map_tmp:?}"))?;
+    writer.write_record(["old_id", "new_id"])?;
+    let mut old_buf = itoa::Buffer::new();
+    let mut new_buf = itoa::Buffer::new();
+    for (new, old) in ids.iter().enumerate() {
+        writer.write_record([old_buf.format(*old), new_buf.format(new)])?;
+    }
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write {map_tmp:?}"))?;
+    staged.push((map_tmp, map_path));
+
+    for (tmp_path, path) in staged {
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to rename {tmp_path:?} to {path:?}"))?;
+    }
+
+    let stats = CompactStats {
+        articles: ids.len() as u64,
+        rows,
+    };
+    info!(
+        articles = stats.articles,
+        rows = stats.rows,
+        "Compacted article IDs"
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn compacts_sparse_ids_in_id_order() {
+        let dir = TempDir::new().unwrap();
+        csv_util::write_csv_fixture(
+            dir.path(),
+            &[
+                ("nodes.csv", "id:ID,title,:LABEL\n900,B,Page\n7,A,Page\n"),
+                // 5000 is an edge target outside the extracted nodes (e.g. past --limit).
+                (
+                    "edges.csv",
+                    ":START_ID,:END_ID,:TYPE,section\n7,900,LINKS_TO,lead\n900,5000,LINKS_TO,History\n",
+                ),
+                (
+                    "categories.csv",
+                    "id:ID(Category),name,:LABEL\nX,X,Category\n",
+                ),
+                (
+                    "article_categories.csv",
+                    ":START_ID,:END_ID(Category),:TYPE\n900,X,HAS_CATEGORY\n",
+                ),
+                ("interwiki.csv", ":START_ID,lang,title\n900,de,B\n"),
+                (
+                    "coordinates.csv",
+                    ":START_ID,latitude:double,longitude:double\n7,51.5,-0.125\n",
+                ),
+            ],
+        );
+
+        let stats = compact_ids(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            stats,
+            CompactStats {
+                articles: 3,
+                rows: 7
+            }
+        );
+
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(
+            read("nodes.csv"),
+            "id:ID,title,:LABEL\n1,B,Page\n0,A,Page\n"
+        );
+        assert_eq!(
+            read("edges.csv"),
+            ":START_ID,:END_ID,:TYPE,section\n0,1,LINKS_TO,lead\n1,2,LINKS_TO,History\n"
+        );
+        assert_eq!(
+            read("article_categories.csv"),
+            ":START_ID,:END_ID(Category),:TYPE\n1,X,HAS_CATEGORY\n"
+        );
+        assert_eq!(
+            read("categories.csv"),
+            "id:ID(Category),name,:LABEL\nX,X,Category\n"
+        );
+        assert_eq!(read("interwiki.csv"), ":START_ID,lang,title\n1,de,B\n");
+        assert_eq!(
+            read("coordinates.csv"),
+            ":START_ID,latitude:double,longitude:double\n0,51.5,-0.125\n"
+        );
+        assert_eq!(read("id_map.csv"), "old_id,new_id\n7,0\n900,1\n5000,2\n");
+        assert!(!dir.path().join("nodes.csv.tmp").exists());
+    }
+}
@@ -5,20 +5,59 @@
 
 // -- Extraction constants --
 
-/// Maximum depth for following redirect chains.
+/// Default for `--redirect-max-depth`: most titles visited when following a
+/// redirect chain, the requested title included.
 pub const REDIRECT_MAX_DEPTH: u32 = 5;
 
-/// Number of shards for blob storage (shard = id % SHARD_COUNT).
+/// Longest title MediaWiki allows, in bytes; pages with longer titles are skipped as malformed.
+pub const MAX_TITLE_BYTES: usize = 255;
+
+/// Unresolved-link ratio above which extraction warns about data quality.
+pub const LOW_RESOLUTION_WARN_RATIO: f64 = 0.4;
+
+/// Minimum resolved + unresolved links before the resolution-rate check applies.
+pub const LOW_RESOLUTION_MIN_LINKS: u64 = 1000;
+
+/// Most lead-prose sentences an article can have and still count as a likely bot stub.
+pub const AUTOGEN_MAX_LEAD_SENTENCES: usize = 1;
+
+/// Most lead-prose characters an article can have and still count as a likely bot stub.
+pub const AUTOGEN_MAX_LEAD_CHARS: usize = 300;
+
+/// Batch size at which `WikiIndex::resolve_many` switches to rayon.
+pub const RESOLVE_PARALLEL_THRESHOLD: usize = 4096;
+
+/// Namespaces whose pages are extracted as article nodes unless `--namespaces` is given.
+pub const DEFAULT_CONTENT_NAMESPACES: &[i32] = &[0];
+
+/// Namespace prefixes dropped from links when the dump has no `<siteinfo>` namespace list.
+pub const DEFAULT_LINK_NAMESPACES: &[&str] = &[
+    "Category",
+    "File",
+    "Template",
+    "Wikipedia",
+    "Help",
+    "Portal",
+    "Draft",
+    "User",
+    "Module",
+    "MediaWiki",
+];
+
+/// Namespace aliases MediaWiki accepts in links but `<siteinfo>` does not list.
+pub const LINK_NAMESPACE_ALIASES: &[&str] = &["Image"];
+
+/// Number of shards for blob storage (shard = `shard::page_shard(id, SHARD_COUNT, ..)`).
 pub const SHARD_COUNT: u32 = 1000;
 
 /// Progress update interval (tick every N pages).
 pub const PROGRESS_INTERVAL: u32 = 1000;
 
 /// Index cache format version. Bump when the format changes.
-pub const CACHE_VERSION: u32 = 2;
+pub const CACHE_VERSION: u32 = 9;
 
 /// Checkpoint format version. Bump when the format changes.
-pub const CHECKPOINT_VERSION: u32 = 3;
+pub const CHECKPOINT_VERSION: u32 = 11;
 
 /// Save a checkpoint every N articles.
 pub const CHECKPOINT_INTERVAL: u32 = 10_000;
@@ -31,6 +70,13 @@ pub const CSV_WRITER_BUF_SIZE: usize = 128 * 1024;
 /// BufReader / BufWriter capacity for merge operations (256 KB).
 pub const MERGE_BUF_SIZE: usize = 256 * 1024;
 
+/// Rows sorted in memory per run before spilling with `--sort-nodes` / `--sort-edges`.
+pub const SORT_RUN_ROWS: usize = 1_000_000;
+
+/// Relationship shard bytes deduplicated per read with `--dedup-edges` (256 MB);
+/// the `--dedup-pass-mb` default.
+pub const DEDUP_PASS_BYTES: u64 = 256 * 1024 * 1024;
+
 /// BufReader capacity for BZ2 decompression and cache/checkpoint I/O (256 KB).
 pub const BUFREADER_CAPACITY: usize = 256 * 1024;
 
@@ -40,18 +86,89 @@ pub const INDEX_INITIAL_ARTICLES: usize = 8_000_000;
 /// Pre-sized capacity for the redirect resolution hash map.
 pub const INDEX_INITIAL_REDIRECTS: usize = 10_000_000;
 
+/// Page IDs below this are tracked in a bitmap while indexing (at most 32 MB);
+/// higher IDs go in a hash set, so one outlier cannot inflate the bitmap.
+pub const SEEN_IDS_BITMAP_LIMIT: u64 = 1 << 28;
+
+/// Fingerprint slots per indexed article in each dedup set with
+/// `--dedup-strategy fingerprint` (8 bytes each).
+pub const FINGERPRINT_DEDUP_SLOTS_PER_ARTICLE: usize = 4;
+
+/// Fewest fingerprint slots per dedup set (64K slots, 512 KB).
+pub const FINGERPRINT_DEDUP_MIN_SLOTS: usize = 1 << 16;
+
+/// Most fingerprint slots per dedup set (16M slots, 128 MB).
+pub const FINGERPRINT_DEDUP_MAX_SLOTS: usize = 1 << 24;
+
+// -- SQLite output constants --
+
+/// Database filename written to the output directory with `--output-format sqlite`.
+pub const SQLITE_DB_FILENAME: &str = "graph.db";
+
+/// Template usage counts written to the output directory with `--template-stats`.
+pub const TEMPLATE_STATS_FILENAME: &str = "template_stats.csv";
+
+/// Article wikitext length histogram written to the output directory with `--length-histogram`.
+pub const LENGTH_HISTOGRAM_FILENAME: &str = "length_histogram.csv";
+
+/// Base name of the interlanguage link CSV (`interwiki.csv`) written with `--interwiki-links`.
+pub const INTERWIKI_BASE_NAME: &str = "interwiki";
+
+/// Base name of the article coordinates CSV (`coordinates.csv`) written with `--coordinates`.
+pub const COORDINATES_BASE_NAME: &str = "coordinates";
+
+/// Resolved redirects written to the output directory with `--emit-redirect-map`.
+pub const REDIRECT_MAP_FILENAME: &str = "redirect_map.csv";
+
+/// Old-to-new article ID mapping written to the output directory with `--compact-ids`.
+pub const ID_MAP_FILENAME: &str = "id_map.csv";
+
+/// Per-article inbound link counts written to the output directory with `--compute-in-degree`.
+pub const NODE_DEGREES_FILENAME: &str = "node_degrees.csv";
+
+/// Dump `<siteinfo>` metadata written to the output directory on every extraction.
+pub const DUMP_INFO_FILENAME: &str = "dump_info.json";
+
+/// Extraction run summary (input, settings, counters) written to the output directory.
+pub const MANIFEST_FILENAME: &str = "manifest.json";
+
+/// CSV files a SurrealDB load has finished, written to the output directory
+/// so `load --resume` can skip them.
+pub const LOAD_PROGRESS_FILENAME: &str = "load_progress.json";
+
+/// Relationship rows dropped per CSV type by `merge-csvs --dedup-edges`, written
+/// to the output directory for reruns and `load`'s count check.
+pub const MERGE_STATS_FILENAME: &str = "merge_stats.json";
+
+/// Rows inserted per SQLite transaction.
+pub const SQLITE_BATCH_SIZE: usize = 50_000;
+
+/// Rows buffered between extraction workers and the SQLite writer thread.
+pub const SQLITE_CHANNEL_CAPACITY: usize = 100_000;
+
 // -- SurrealDB constants --
 
-/// SurrealDB namespace.
+/// Default SurrealDB namespace (`--surreal-ns` / `SURREAL_NAMESPACE`).
 pub const SURREAL_NAMESPACE: &str = "dedalus";
 
-/// SurrealDB database name.
+/// Default SurrealDB database name (`--surreal-db` / `SURREAL_DATABASE`).
 pub const SURREAL_DATABASE: &str = "wikipedia";
 
 /// Default batch size for SurrealDB inserts.
 pub const SURREAL_BATCH_SIZE: usize = 10_000;
 
-/// Default database path (relative to output directory).
+/// Relative difference between the records a load left in the database and the
+/// counts in `manifest.json` above which the load reports a mismatch.
+pub const LOAD_COUNT_TOLERANCE: f64 = 0.001;
+
+/// Times a batch insert that failed with a transient error is retried.
+pub const LOAD_MAX_RETRIES: u32 = 3;
+
+/// Wait before the first retry of a batch insert, in milliseconds; doubled
+/// before each later retry.
+pub const LOAD_RETRY_BACKOFF_MS: u64 = 250;
+
+/// Default database path (relative to output directory; `--db-path` / `DEDALUS_DB_PATH`).
 pub const DEFAULT_DB_PATH: &str = "wikipedia.db";
 
 // -- Analytics constants --
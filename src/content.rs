@@ -1,25 +1,86 @@
 //! Regex-based text extraction from Wikipedia article wikitext.
 //!
 //! Provides functions for extracting abstracts, section headings, see-also links,
-//! categories, images, external links, and disambiguation detection. Uses
+//! categories, images, external links, interlanguage links, template names, and
+//! disambiguation detection, plus `to_plain_text()` for readable fragments. Uses
 //! SIMD-accelerated `memchr` for fast template stripping.
+//!
+//! # Library use
+//!
+//! Everything here works on a single article's wikitext and needs no dump,
+//! index or output directory, so dedalus can be used purely as a wikitext
+//! parser. [`extract_all`] runs the extractors the way `run_extraction` does
+//! and returns an [`ArticleContent`]; the individual functions, plus the
+//! re-exported [`extract_infoboxes`] and [`Infobox`], can be called on their own.
+//!
+//! ```
+//! use dedalus::content;
+//!
+//! let text = "'''Rust''' is a language.\n\
+//!             [[Category:Programming languages]]\n\
+//!             == See also ==\n* [[Go (programming language)]]\n";
+//! let article = content::extract_all(text);
+//! assert_eq!(article.categories, ["Programming languages"]);
+//! assert_eq!(article.see_also_links, ["Go (programming language)"]);
+//! assert_eq!(article.sections, ["See also"]);
+//! ```
+//!
+//! # Stability
+//!
+//! The public functions in this module, [`ArticleContent`] and the infobox
+//! re-exports are a supported API: their names and signatures only change in
+//! a breaking release. What they return is heuristic and may improve in any
+//! release (a template recognized as disambiguation, a link form handled), so
+//! compare results across versions rather than assume them fixed.
 
-use memchr::memchr2;
+use crate::config::{AUTOGEN_MAX_LEAD_CHARS, AUTOGEN_MAX_LEAD_SENTENCES};
+use crate::infobox;
+pub use crate::infobox::{Infobox, extract_infoboxes, infobox_images};
+use memchr::{memchr, memchr2};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::Serialize;
 use std::borrow::Cow;
 
-static CATEGORY_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\[\[Category:([^|\]]+?)(?:\|[^\]]+)?\]\]").unwrap());
+const CATEGORY_PATTERN: &str = r"\[\[Category:([^|\]]+?)(?:\|[^\]]+)?\]\]";
+const IMAGE_PATTERN: &str = r"(?i)\[\[(?:File|Image):([^|\]]+?)(?:\|[^\]]*)*\]\]";
+const EXTERNAL_LINK_PATTERN: &str = r"\[(https?://\S+?)(?:\s[^\]]+)?\]";
+const LINK_PATTERN: &str = r"\[\[([^|\]]+?)(?:\|[^\]]+)?\]\]";
+
+static CATEGORY_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(CATEGORY_PATTERN).unwrap());
 
 static SECTION_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(?m)^(={2,})\s*(.+?)\s*={2,}\s*$").unwrap());
 
-static IMAGE_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"(?i)\[\[(?:File|Image):([^|\]]+?)(?:\|[^\]]*)*\]\]").unwrap());
+static IMAGE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(IMAGE_PATTERN).unwrap());
 
-static EXTERNAL_LINK_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\[(https?://\S+?)(?:\s[^\]]+)?\]").unwrap());
+static EXTERNAL_LINK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(EXTERNAL_LINK_PATTERN).unwrap());
+
+/// The bracket patterns anchored at the start of the haystack, for
+/// `scan_brackets` to try at each `[` it finds. In the same order as
+/// `BRACKET_REGEXES`.
+static BRACKET_AT: Lazy<[Regex; 4]> = Lazy::new(|| {
+    [
+        LINK_PATTERN,
+        CATEGORY_PATTERN,
+        IMAGE_PATTERN,
+        EXTERNAL_LINK_PATTERN,
+    ]
+    .map(|pattern| Regex::new(&format!("^(?:{pattern})")).unwrap())
+});
+
+/// The extractors' own bracket regexes, which `scan_brackets` searches ahead
+/// with where an anchored try fails.
+static BRACKET_REGEXES: [&Lazy<Regex>; 4] = [
+    &LINK_REGEX,
+    &CATEGORY_REGEX,
+    &IMAGE_REGEX,
+    &EXTERNAL_LINK_REGEX,
+];
+
+/// Name of a `{{Template ...}}` transclusion, up to the first `|` or the closing `}}`.
+static TEMPLATE_NAME_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([^{}|\n]+?)\s*(?:\||\}\}|\n)").unwrap());
 
 static DISAMBIG_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"(?i)\{\{(?:disambig(?:uation)?|dab|hndis|geodis|disamb|surname|given name|human name disambiguation|place name disambiguation|hospital disambiguation|airport disambiguation|letter-numbercombdisambig|school disambiguation|road disambiguation|biology disambiguation|taxonomy disambiguation|species latin name disambiguation|mathematical disambiguation|chemistry disambiguation|music disambiguation)\b").unwrap()
@@ -30,14 +91,224 @@ static SEE_ALSO_HEADER: Lazy<Regex> =
 
 static NEXT_SECTION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^={2,}\s*[^=]").unwrap());
 
-pub static LINK_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\[\[([^|\]]+?)(?:\|[^\]]+)?\]\]").unwrap());
+/// Matches a `<ref>` tag: either a self-closing reuse (`<ref name=x/>`) or a full
+/// definition (`<ref ...>...</ref>`). The self-closing alternative comes first so a
+/// reuse never swallows text up to the next `</ref>`.
+static REF_TAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<ref(?:\s[^>]*)?/>|<ref(?:\s[^>]*)?>.*?</ref\s*>").unwrap());
+
+/// `{{cite web}}`, `{{Cite book}}`, `{{citation}}` -- but not `{{citation needed}}`.
+static CITE_TEMPLATE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\{\{\s*(?:cite[\s_]|citation\s*[|}])").unwrap());
+
+static AUTHORITY_CONTROL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\{\{\s*authority[ _]control\s*[|}]").unwrap());
+
+/// Opening of a `{{Coord|...}}` template (not `{{Coord missing}}`).
+static COORD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\{\{\s*coord\s*\|").unwrap());
+
+/// Source-database templates typical of mass-created stubs: `{{GNIS}}`,
+/// `{{GEOnet2}}`/`{{GEOnet3}}`, `{{GeoNames}}`, `{{INSEE}}`, and their `{{Cite ...}}` forms.
+static AUTOGEN_SOURCE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\{\{\s*(?:cite[\s_]+)?(?:gnis|geonet[23]?|geonames|insee)\s*[|}]").unwrap()
+});
+
+/// Section headings that bot stubs carry without adding prose.
+const BOILERPLATE_SECTIONS: &[&str] = &[
+    "references",
+    "external links",
+    "notes",
+    "sources",
+    "further reading",
+];
+
+/// A template with no nested templates, for innermost-first unwrapping.
+static INNER_TEMPLATE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{([^{}]*)\}\}").unwrap());
+
+/// A wikilink, capturing the target and optional label.
+static PLAIN_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[\[([^|\[\]]*)(?:\|([^\[\]]*))?\]\]").unwrap());
+
+/// `[url label]` external link; a bare `[url]` has no label and is dropped.
+static PLAIN_EXTERNAL_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[https?://[^\s\]]+(?:\s+([^\]]*))?\]").unwrap());
+
+/// HTML comments and tags (after `<ref>` blocks are gone).
+static HTML_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!--.*?-->|<[^>]+>").unwrap());
+
+/// Spans that hold no live markup: `<!-- -->` comments (an unclosed one runs to
+/// the end, as MediaWiki hides the rest of the page) and `<nowiki>`/`<pre>`
+/// blocks. Self-closing `<nowiki/>` comes first so it never opens a block.
+static NON_CONTENT_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?is)<!--.*?(?:-->|\z)|<(?:nowiki|pre)(?:\s[^>]*)?/>|<nowiki(?:\s[^>]*)?>.*?</nowiki\s*>|<pre(?:\s[^>]*)?>.*?</pre\s*>",
+    )
+    .unwrap()
+});
+
+pub static LINK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(LINK_PATTERN).unwrap());
+
+/// Everything [`extract_all`] finds in one article.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ArticleContent {
+    /// Lead section with templates stripped ([`extract_abstract`]).
+    pub abstract_text: String,
+    /// Section headings in document order.
+    pub sections: Vec<String>,
+    pub categories: Vec<String>,
+    /// File links plus infobox image fields, without repeats.
+    pub images: Vec<String>,
+    pub external_links: Vec<String>,
+    /// Link targets in the "See also" section.
+    pub see_also_links: Vec<String>,
+    pub infoboxes: Vec<Infobox>,
+    /// `(language code, title)` pairs.
+    pub interlanguage_links: Vec<(String, String)>,
+    /// `(latitude, longitude)` from the first `{{Coord}}` template.
+    pub coordinates: Option<(f64, f64)>,
+    pub is_disambiguation: bool,
+}
+
+/// Runs every extractor over `text` in one call, as extraction does:
+/// categories, images, external links, see-also and interlanguage links are
+/// read with comments and `<nowiki>`/`<pre>` blocks masked out
+/// ([`mask_non_content`]), and infobox image fields count as images. The
+/// bracket constructs come from a single [`scan_brackets`] pass.
+#[must_use]
+pub fn extract_all(text: &str) -> ArticleContent {
+    let live = mask_non_content(text);
+    let mut scan = scan_brackets(&live);
+    let infoboxes = extract_infoboxes(text);
+    let mut images = std::mem::take(&mut scan.images);
+    for name in infobox_images(&infoboxes) {
+        if !images.contains(&name) {
+            images.push(name);
+        }
+    }
+    let owned = |names: Vec<Cow<'_, str>>| names.into_iter().map(Cow::into_owned).collect();
+    ArticleContent {
+        abstract_text: extract_abstract(text),
+        sections: extract_sections(text),
+        interlanguage_links: interlanguage_links(&scan.links),
+        categories: owned(scan.categories),
+        images: owned(images),
+        external_links: owned(scan.external_links),
+        see_also_links: extract_see_also_links(&live),
+        coordinates: extract_coordinates(text),
+        is_disambiguation: is_disambiguation(text),
+        infoboxes,
+    }
+}
+
+/// Every `[`-delimited construct in one article, from [`scan_brackets`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BracketScan<'a> {
+    /// Byte offset and raw target of each `[[...]]` link, as [`LINK_REGEX`]
+    /// matches them.
+    pub links: Vec<(usize, &'a str)>,
+    /// As [`extract_categories`] returns them.
+    pub categories: Vec<Cow<'a, str>>,
+    /// As [`extract_images`] returns them.
+    pub images: Vec<Cow<'a, str>>,
+    /// As [`extract_external_links`] returns them.
+    pub external_links: Vec<Cow<'a, str>>,
+}
+
+/// Finds wiki links, categories, images and external links in one pass.
+///
+/// Every match of those patterns starts with `[`, so instead of one regex
+/// scan of the whole text per extractor, `memchr` walks the `[` bytes once and
+/// each pattern is tried, anchored, only where a cheap prefix check passes
+/// (`[[Category:`, `[[F`/`[[I`, `[http`). Where that try fails, the pattern is
+/// searched ahead once and skips every `[` before the match it finds, so an
+/// unterminated `[[` is not rescanned from each later `[`. Each pattern keeps
+/// its own end position, so matches are the non-overlapping, leftmost-first
+/// ones its `captures_iter` would give and the results are identical to the
+/// individual extractors.
+#[must_use]
+pub fn scan_brackets(text: &str) -> BracketScan<'_> {
+    const LINK: usize = 0;
+    const CATEGORY: usize = 1;
+    const IMAGE: usize = 2;
+    const EXTERNAL: usize = 3;
+
+    fn push_nonempty<'a>(values: &mut Vec<Cow<'a, str>>, value: Cow<'a, str>) {
+        if !value.is_empty() {
+            values.push(value);
+        }
+    }
+
+    let bytes = text.as_bytes();
+    let mut scan = BracketScan::default();
+    let mut ends = [0usize; 4];
+    type Match<'a> = (usize, usize, &'a str);
+    // Start, end and group of each pattern's next match, searched after a
+    // failed anchored try; `Some(None)` when the rest of the text has none.
+    let mut ahead: [Option<Option<Match<'_>>>; 4] = [None; 4];
+    for pos in memchr::memchr_iter(b'[', bytes) {
+        let rest = &bytes[pos + 1..];
+        let double = rest.first() == Some(&b'[');
+        let candidates = [
+            double,
+            double && rest[1..].starts_with(b"Category:"),
+            double
+                && rest
+                    .get(1)
+                    .is_some_and(|&b| !b.is_ascii() || matches!(b | 0x20, b'f' | b'i')),
+            rest.starts_with(b"http"),
+        ];
+        for (kind, candidate) in candidates.into_iter().enumerate() {
+            if !candidate || pos < ends[kind] {
+                continue;
+            }
+            let (end, value) = match ahead[kind] {
+                Some(None) => continue,
+                Some(Some((start, _, _))) if start > pos => continue,
+                Some(Some((start, end, value))) if start == pos => (end, value),
+                _ => {
+                    let Some(caps) = BRACKET_AT[kind].captures(&text[pos..]) else {
+                        let found = BRACKET_REGEXES[kind].captures_at(text, pos).map(|caps| {
+                            let whole = caps.get(0).unwrap();
+                            (whole.start(), whole.end(), caps.get(1).unwrap().as_str())
+                        });
+                        ahead[kind] = Some(found);
+                        continue;
+                    };
+                    (
+                        pos + caps.get(0).unwrap().end(),
+                        caps.get(1).unwrap().as_str(),
+                    )
+                }
+            };
+            ends[kind] = end;
+            ahead[kind] = None;
+            match kind {
+                LINK => scan.links.push((pos, value)),
+                CATEGORY => push_nonempty(&mut scan.categories, sanitize_field(value.trim())),
+                IMAGE => push_nonempty(&mut scan.images, normalize_image_name(value)),
+                EXTERNAL => push_nonempty(&mut scan.external_links, sanitize_field(value.trim())),
+                _ => unreachable!("one regex per bracket kind"),
+            }
+        }
+    }
+    scan
+}
 
 /// Returns the lead section (before the first `==` heading) with templates stripped.
 #[must_use]
 pub fn extract_abstract(text: &str) -> String {
+    extract_abstract_keeping(text, &[])
+}
+
+/// Like [`extract_abstract`], but templates named in `lead_templates` (e.g.
+/// `lang`, `nowrap`) are replaced by their last unnamed parameter instead of
+/// removed, so lead content wrapped in them survives. Maintenance templates
+/// such as `{{Multiple issues}}` are still stripped. Names are compared after
+/// the same normalization as [`extract_template_names`].
+#[must_use]
+pub fn extract_abstract_keeping(text: &str, lead_templates: &[String]) -> String {
     // Strip templates first so headings inside {{Infobox ...}} don't truncate the lead.
-    let stripped = strip_templates(text);
+    let stripped = strip_templates(text, lead_templates);
 
     let end_pos = SECTION_REGEX
         .find(&stripped)
@@ -58,6 +329,60 @@ pub fn extract_abstract(text: &str) -> String {
     result
 }
 
+/// Readable text of a wikitext fragment such as an infobox value.
+///
+/// Links become their label (or target), references, comments and HTML tags
+/// are dropped, bold/italic quotes are removed, and whitespace is collapsed.
+/// A template with a single unnamed parameter (`{{flag|France}}`,
+/// `{{nowrap|...}}`) is replaced by that parameter; any other template is
+/// removed, since its rendered text can't be known without expanding it.
+/// File and category links are removed.
+#[must_use]
+pub fn to_plain_text(text: &str) -> String {
+    let mut text = REF_TAG_REGEX.replace_all(text, "").into_owned();
+    text = HTML_REGEX.replace_all(&text, " ").into_owned();
+    text = PLAIN_LINK_REGEX
+        .replace_all(&text, |caps: &regex::Captures| {
+            let target = caps[1].trim();
+            if is_media_or_category(target) {
+                return String::new();
+            }
+            match caps.get(2).map(|label| label.as_str().trim()) {
+                Some(label) if !label.is_empty() => label.to_string(),
+                _ => target.trim_start_matches(':').to_string(),
+            }
+        })
+        .into_owned();
+    // Innermost first, so an unwrapped parameter can itself be a template.
+    while INNER_TEMPLATE_REGEX.is_match(&text) {
+        text = INNER_TEMPLATE_REGEX
+            .replace_all(&text, |caps: &regex::Captures| {
+                let mut parts = caps[1].split('|').skip(1);
+                match (parts.next(), parts.next()) {
+                    (Some(param), None) if !param.contains('=') => param.trim().to_string(),
+                    _ => String::new(),
+                }
+            })
+            .into_owned();
+    }
+    text = PLAIN_EXTERNAL_LINK_REGEX
+        .replace_all(&text, |caps: &regex::Captures| {
+            caps.get(1)
+                .map_or(String::new(), |label| label.as_str().to_string())
+        })
+        .into_owned();
+    let text = text.replace("'''", "").replace("''", "");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn is_media_or_category(target: &str) -> bool {
+    target.split_once(':').is_some_and(|(prefix, _)| {
+        ["file", "image", "category"]
+            .iter()
+            .any(|ns| prefix.trim().eq_ignore_ascii_case(ns))
+    })
+}
+
 /// Extracts section heading names from the article text.
 #[must_use]
 pub fn extract_sections(text: &str) -> Vec<String> {
@@ -91,6 +416,37 @@ pub fn extract_see_also_links(text: &str) -> Vec<String> {
         .collect()
 }
 
+/// Blanks out HTML comments and `<nowiki>`/`<pre>` blocks so links and
+/// categories inside them are not extracted.
+///
+/// Every byte of a masked span becomes a space, except newlines, which are
+/// kept: byte offsets and line starts are unchanged, so positions found in the
+/// result (`see_also_section_start`, `section_starts`) also hold for `text`.
+/// Text without such spans is returned borrowed.
+#[must_use]
+pub fn mask_non_content(text: &str) -> Cow<'_, str> {
+    if memchr(b'<', text.as_bytes()).is_none() {
+        return Cow::Borrowed(text);
+    }
+    let mut spans = NON_CONTENT_REGEX.find_iter(text).peekable();
+    if spans.peek().is_none() {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for span in spans {
+        result.push_str(&text[last..span.start()]);
+        result.extend(
+            span.as_str()
+                .bytes()
+                .map(|b| if b == b'\n' { '\n' } else { ' ' }),
+        );
+        last = span.end();
+    }
+    result.push_str(&text[last..]);
+    Cow::Owned(result)
+}
+
 /// Extracts category names from `[[Category:...]]` links.
 #[must_use]
 pub fn extract_categories(text: &str) -> Vec<Cow<'_, str>> {
@@ -133,11 +489,35 @@ fn sanitize_field(s: &str) -> Cow<'_, str> {
 pub fn extract_images(text: &str) -> Vec<Cow<'_, str>> {
     IMAGE_REGEX
         .captures_iter(text)
-        .filter_map(|c| c.get(1).map(|m| sanitize_field(m.as_str().trim())))
+        .filter_map(|c| c.get(1).map(|m| normalize_image_name(m.as_str())))
         .filter(|s| !s.is_empty())
         .collect()
 }
 
+/// Canonical image filename shared by `[[File:...]]` links and infobox fields:
+/// an optional `File:`/`Image:` prefix is dropped, underscores become spaces,
+/// and whitespace runs collapse to one space. Case is left as written.
+#[must_use]
+pub fn normalize_image_name(name: &str) -> Cow<'_, str> {
+    let mut name = name.trim();
+    for prefix in ["file:", "image:"] {
+        if name.len() >= prefix.len()
+            && name.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+        {
+            name = name[prefix.len()..].trim_start();
+        }
+    }
+    if !name.contains('_') && !name.contains("  ") {
+        return sanitize_field(name);
+    }
+    Cow::Owned(
+        name.replace('_', " ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
 /// Extracts URLs from `[http(s)://...]` external links.
 #[must_use]
 pub fn extract_external_links(text: &str) -> Vec<Cow<'_, str>> {
@@ -148,21 +528,422 @@ pub fn extract_external_links(text: &str) -> Vec<Cow<'_, str>> {
         .collect()
 }
 
+/// Query parameters that only track where a click came from, dropped by
+/// [`normalize_url`] along with any `utm_*` parameter.
+const TRACKING_QUERY_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_ga",
+];
+
+/// Canonical form of an external link URL for `--normalize-urls`, so trivially
+/// different URLs for the same page share one node.
+///
+/// `http` becomes `https`; the scheme and host are lowercased, a leading
+/// `www.` and the original scheme's default port (`:80` for `http`, `:443`
+/// for `https`) are dropped; the fragment, tracking parameters (`utm_*`,
+/// `TRACKING_QUERY_PARAMS`, in any case) and a trailing `/` are removed. The
+/// path and remaining query are left as written. This is lossy: a site that
+/// serves different pages on `www.` or over plain `http` is merged. A URL
+/// without `://` is returned unchanged.
+#[must_use]
+pub fn normalize_url(url: &str) -> Cow<'_, str> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Cow::Borrowed(url);
+    };
+    let scheme = scheme.to_ascii_lowercase();
+    let default_port = match scheme.as_str() {
+        "http" => Some(":80"),
+        "https" => Some(":443"),
+        _ => None,
+    };
+    let scheme = if scheme == "http" { "https" } else { &scheme };
+
+    let rest = rest.split_once('#').map_or(rest, |(before, _)| before);
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, rest) = rest.split_at(authority_end);
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let host = authority.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let host = default_port
+        .and_then(|port| host.strip_suffix(port))
+        .unwrap_or(host);
+
+    let params: Vec<&str> = query
+        .split('&')
+        .filter(|param| {
+            let key = param.split_once('=').map_or(*param, |(key, _)| key);
+            !key.is_empty()
+                && !key.to_ascii_lowercase().starts_with("utm_")
+                && !TRACKING_QUERY_PARAMS
+                    .iter()
+                    .any(|tracking| tracking.eq_ignore_ascii_case(key))
+        })
+        .collect();
+
+    let mut normalized = format!("{scheme}://{host}{}", path.trim_end_matches('/'));
+    if !params.is_empty() {
+        normalized.push('?');
+        normalized.push_str(&params.join("&"));
+    }
+    if normalized == url {
+        Cow::Borrowed(url)
+    } else {
+        Cow::Owned(normalized)
+    }
+}
+
+/// Wikipedia language codes other than two-letter ones, for interlanguage links.
+const LANGUAGE_CODES: &str = "ace ady als alt ami ang anp arc ary arz ast atj avk awa ban bar bat-smg bcl be-tarask \
+     be-x-old bjn blk bpy bug bxr cbk-zam cdo ceb chr chy ckb crh csb diq dsb dty eml ext \
+     fiu-vro frp frr fur gag gan gcr glk gom gor got guc gur guw hak haw hif hsb hyw ilo inh \
+     jam jbo kaa kab kbd kbp kcg koi krc ksh lad lbe lez lfn lij lld lmo lrc ltg mad mai \
+     map-bms mdf mhr min mni mnw mrj mwl myv mzn nah nap nds nds-nl new nia nov nqo nrm nso \
+     olo pag pam pap pcd pcm pdc pfl pih pms pnb pnt pwn rmy roa-rup roa-tara rue sah sat \
+     scn sco shi shn simple skr smn srn stq szl szy tay tcy tet tly trv tum tyv udm vec vep \
+     vls war wuu xal xmf yue zea zgh zh-classical zh-min-nan zh-yue";
+
+/// Whether `prefix` is a language edition code: two lowercase ASCII letters
+/// (except the `mw:` interwiki) or one of `LANGUAGE_CODES`.
+fn is_language_code(prefix: &str) -> bool {
+    match prefix.as_bytes() {
+        [a, b] => a.is_ascii_lowercase() && b.is_ascii_lowercase() && prefix != "mw",
+        _ => LANGUAGE_CODES
+            .split_ascii_whitespace()
+            .any(|code| code == prefix),
+    }
+}
+
+/// Extracts `(lang_code, title)` pairs from interlanguage links such as
+/// `[[de:Rust (Programmiersprache)]]`. Only language prefixes match, so
+/// namespace links (`[[File:x.jpg]]`), interwikis (`[[wikt:rust]]`) and
+/// inline links to another edition (`[[:fr:Rust]]`) are not included.
+#[must_use]
+pub fn extract_interlanguage_links(text: &str) -> Vec<(String, String)> {
+    language_pairs(
+        LINK_REGEX
+            .captures_iter(text)
+            .filter_map(|c| c.get(1).map(|m| m.as_str())),
+    )
+}
+
+/// [`extract_interlanguage_links`] over the `links` of a [`scan_brackets`] result.
+#[must_use]
+pub fn interlanguage_links(links: &[(usize, &str)]) -> Vec<(String, String)> {
+    language_pairs(links.iter().map(|(_, target)| *target))
+}
+
+fn language_pairs<'a>(targets: impl Iterator<Item = &'a str>) -> Vec<(String, String)> {
+    targets
+        .filter_map(|target| {
+            let (lang, title) = target.split_once(':')?;
+            let title = sanitize_field(title.trim());
+            (is_language_code(lang.trim()) && !title.is_empty())
+                .then(|| (lang.trim().to_string(), title.into_owned()))
+        })
+        .collect()
+}
+
+/// Names of all templates transcluded in `text`, including nested ones, one per
+/// use. Names are canonicalized as MediaWiki does (underscores as spaces,
+/// whitespace collapsed, first letter uppercase). Parser functions
+/// (`{{#if:...}}`), magic words (`{{DEFAULTSORT:...}}`), and `{{{parameters}}}`
+/// are skipped.
+#[must_use]
+pub fn extract_template_names(text: &str) -> Vec<Cow<'_, str>> {
+    TEMPLATE_NAME_REGEX
+        .captures_iter(text)
+        .filter(|c| {
+            let start = c.get(0).map_or(0, |m| m.start());
+            start == 0 || text.as_bytes()[start - 1] != b'{'
+        })
+        .filter_map(|c| c.get(1).map(|m| m.as_str()))
+        .filter(|name| !name.starts_with('#') && !name.contains(':'))
+        .map(normalize_template_name)
+        .collect()
+}
+
+fn normalize_template_name(name: &str) -> Cow<'_, str> {
+    let starts_lower = name.chars().next().is_some_and(char::is_lowercase);
+    if !starts_lower && !name.contains('_') && !name.contains("  ") {
+        return Cow::Borrowed(name);
+    }
+    let spaced = name.replace('_', " ");
+    let mut words = spaced.split_whitespace();
+    let mut result = String::with_capacity(name.len());
+    if let Some(first) = words.next() {
+        let mut chars = first.chars();
+        if let Some(c) = chars.next() {
+            result.extend(c.to_uppercase());
+            result.push_str(chars.as_str());
+        }
+    }
+    for word in words {
+        result.push(' ');
+        result.push_str(word);
+    }
+    Cow::Owned(result)
+}
+
+/// Byte offset and title of each section heading, in document order.
+#[must_use]
+pub fn section_starts(text: &str) -> Vec<(usize, &str)> {
+    SECTION_REGEX
+        .captures_iter(text)
+        .map(|c| (c.get(0).unwrap().start(), c.get(2).unwrap().as_str().trim()))
+        .collect()
+}
+
+/// Title of the section enclosing byte `offset`, or `"lead"` before the first
+/// heading. `sections` comes from `section_starts`.
+#[must_use]
+pub fn section_at<'a>(sections: &[(usize, &'a str)], offset: usize) -> &'a str {
+    match sections.partition_point(|(start, _)| *start <= offset) {
+        0 => "lead",
+        i => sections[i - 1].1,
+    }
+}
+
 /// Byte offset of the "See also" header, for position-based edge classification.
 #[must_use]
 pub fn see_also_section_start(text: &str) -> Option<usize> {
     SEE_ALSO_HEADER.find(text).map(|m| m.start())
 }
 
+/// Counts distinct references in the article.
+///
+/// Each `<ref>...</ref>` definition counts once, including any `{{cite ...}}`
+/// inside it. Named reuses (`<ref name=x/>`) are not counted again. Cite and
+/// citation templates outside of `<ref>` tags (e.g. a bibliography list) count
+/// once each.
+#[must_use]
+pub fn count_references(text: &str) -> usize {
+    let mut count = 0;
+    let mut last_end = 0;
+    for m in REF_TAG_REGEX.find_iter(text) {
+        count += CITE_TEMPLATE_REGEX
+            .find_iter(&text[last_end..m.start()])
+            .count();
+        if !m.as_str().ends_with("/>") {
+            count += 1;
+        }
+        last_end = m.end();
+    }
+    count + CITE_TEMPLATE_REGEX.find_iter(&text[last_end..]).count()
+}
+
+/// Extracts explicit `(scheme, id)` pairs from `{{Authority control}}` templates.
+///
+/// Only the parameterized form (`{{Authority control|VIAF=123|LCCN=n79021164}}`)
+/// carries ids in the wikitext; the bare `{{Authority control}}` is populated from
+/// Wikidata at render time and yields nothing here.
+#[must_use]
+pub fn extract_authority_ids(text: &str) -> Vec<(String, String)> {
+    let mut ids = Vec::new();
+    for m in AUTHORITY_CONTROL_REGEX.find_iter(text) {
+        let Some(close) = crate::infobox::find_matching_close(text.as_bytes(), m.start()) else {
+            continue;
+        };
+        let inner = &text[m.start() + 2..close];
+        for segment in crate::infobox::split_at_depth_zero(inner)
+            .into_iter()
+            .skip(1)
+        {
+            if let Some((scheme, id)) = segment.split_once('=') {
+                let (scheme, id) = (scheme.trim(), id.trim());
+                if !scheme.is_empty() && !id.is_empty() {
+                    ids.push((scheme.to_string(), id.to_string()));
+                }
+            }
+        }
+    }
+    ids
+}
+
+/// Degrees from 1-3 `deg|min|sec` parts, or `None` if a part is not a number
+/// or minutes/seconds are outside `0..60`.
+fn dms_degrees(parts: &[&str]) -> Option<f64> {
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+    let mut degrees = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        let value: f64 = part.parse().ok()?;
+        if i > 0 && !(0.0..60.0).contains(&value) {
+            return None;
+        }
+        degrees += value / 60f64.powi(i as i32);
+    }
+    Some(degrees)
+}
+
+/// Parses the positional parameters of one `{{Coord}}`: `lat|lon` in decimal
+/// degrees, or `d|m|s|N/S|d|m|s|E/W` with one to three parts per axis.
+fn parse_coord(params: &[&str]) -> Option<(f64, f64)> {
+    let (lat, lon) = match params.iter().position(|p| matches!(*p, "N" | "S")) {
+        Some(ns) => {
+            let rest = &params[ns + 1..];
+            let ew = rest.iter().position(|p| matches!(*p, "E" | "W"))?;
+            let lat = dms_degrees(&params[..ns])?;
+            let lon = dms_degrees(&rest[..ew])?;
+            (
+                if params[ns] == "S" { -lat } else { lat },
+                if rest[ew] == "W" { -lon } else { lon },
+            )
+        }
+        None => (params.first()?.parse().ok()?, params.get(1)?.parse().ok()?),
+    };
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// `(latitude, longitude)` in decimal degrees from the first well-formed
+/// `{{Coord}}` template, in either decimal (`{{Coord|40.7128|-74.0060}}`) or
+/// degrees-minutes-seconds form (`{{Coord|40|42|N|74|0|W}}`). Named
+/// parameters (`display=title`) and the trailing `type:city` string are
+/// ignored; malformed templates are skipped.
+#[must_use]
+pub fn extract_coordinates(text: &str) -> Option<(f64, f64)> {
+    COORD_REGEX.find_iter(text).find_map(|m| {
+        let close = crate::infobox::find_matching_close(text.as_bytes(), m.start())?;
+        let inner = &text[m.start() + 2..close];
+        let params: Vec<&str> = crate::infobox::split_at_depth_zero(inner)
+            .into_iter()
+            .skip(1)
+            .map(str::trim)
+            .filter(|p| !p.contains('='))
+            .collect();
+        parse_coord(&params)
+    })
+}
+
 /// Returns `true` if the article contains a disambiguation template.
 #[must_use]
 pub fn is_disambiguation(text: &str) -> bool {
     DISAMBIG_REGEX.is_match(text)
 }
 
-/// Strips `{{...}}` templates from text, handling nested braces.
+/// Heuristic for articles mass-created from a database (species, settlements, ...).
+///
+/// Conservative by design: an article must be *thin* and carry a *bot signature*.
+/// - Thin: at most `AUTOGEN_MAX_LEAD_SENTENCES` sentence(s) and
+///   `AUTOGEN_MAX_LEAD_CHARS` characters of lead prose (templates, refs,
+///   categories, files, lists and tables excluded), and no sections other than
+///   boilerplate ones (References, External links, Notes, Sources, Further reading).
+/// - Signature: a source-database template (`{{GNIS}}`, `{{GEOnet2}}`, ...), or
+///   exactly one infobox.
+///
+/// A short stub written by hand without an infobox or source template is not flagged.
+#[must_use]
+pub fn is_likely_autogenerated(text: &str) -> bool {
+    let only_boilerplate_sections = SECTION_REGEX.captures_iter(text).all(|c| {
+        let heading = c[2].trim();
+        BOILERPLATE_SECTIONS
+            .iter()
+            .any(|b| heading.eq_ignore_ascii_case(b))
+    });
+    if !only_boilerplate_sections {
+        return false;
+    }
+
+    let lead = extract_abstract(text);
+    let lead = REF_TAG_REGEX.replace_all(&lead, "");
+    let mut prose_chars = 0;
+    let mut sentences = 0;
+    for line in lead.lines() {
+        let line = line.trim();
+        let is_prose = !(line.is_empty()
+            || line.starts_with("[[Category:")
+            || line.starts_with("[[File:")
+            || line.starts_with("[[Image:")
+            || line.starts_with(['*', '#', '|', ':', '<'])
+            || line.starts_with("{|"));
+        if is_prose {
+            prose_chars += line.chars().count();
+            sentences += count_sentences(line);
+        }
+    }
+    if prose_chars == 0
+        || prose_chars > AUTOGEN_MAX_LEAD_CHARS
+        || sentences > AUTOGEN_MAX_LEAD_SENTENCES
+    {
+        return false;
+    }
+
+    AUTOGEN_SOURCE_REGEX.is_match(text) || infobox::extract_infoboxes(text).len() == 1
+}
+
+/// Counts `.`/`!`/`?` followed by whitespace or end of line, treating an
+/// unterminated trailing fragment as one more sentence.
+fn count_sentences(line: &str) -> usize {
+    let bytes = line.as_bytes();
+    let mut count = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        if matches!(b, b'.' | b'!' | b'?')
+            && bytes
+                .get(i + 1)
+                .is_none_or(|next| next.is_ascii_whitespace())
+        {
+            count += 1;
+        }
+    }
+    if !line.ends_with(['.', '!', '?']) {
+        count += 1;
+    }
+    count
+}
+
+/// Last unnamed parameter of a template body (`name|a|k=v|b` -> `b`). Pipes
+/// inside nested templates and links do not split parameters.
+fn last_unnamed_param(body: &str) -> Option<&str> {
+    let bytes = body.as_bytes();
+    let mut params = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'[' if bytes.get(i + 1) == Some(&bytes[i]) => {
+                depth += 1;
+                i += 1;
+            }
+            b'}' | b']' if bytes.get(i + 1) == Some(&bytes[i]) => {
+                depth -= 1;
+                i += 1;
+            }
+            b'|' if depth == 0 => {
+                params.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    params.push(&body[start..]);
+    params.into_iter().skip(1).rev().find(|param| {
+        param
+            .split_once('=')
+            .is_none_or(|(key, _)| key.contains(['{', '[']))
+    })
+}
+
+/// Text kept for a complete `{{...}}` span: its last unnamed parameter (with
+/// its own templates handled the same way) if the template is in `keep`.
+fn kept_template_text(template: &str, keep: &[String]) -> Option<String> {
+    let body = &template[2..template.len() - 2];
+    let name = body.split('|').next().unwrap_or_default().trim();
+    let name = normalize_template_name(name);
+    if !keep
+        .iter()
+        .any(|kept| normalize_template_name(kept.trim()) == name)
+    {
+        return None;
+    }
+    last_unnamed_param(body).map(|param| strip_templates(param, keep).trim().to_string())
+}
+
+/// Strips `{{...}}` templates from text, handling nested braces. Templates
+/// named in `keep` are replaced by their last unnamed parameter instead.
 /// Uses SIMD-accelerated memchr2 to skip over plain text between brace pairs.
-fn strip_templates(text: &str) -> String {
+fn strip_templates(text: &str, keep: &[String]) -> String {
     let mut result = String::with_capacity(text.len());
     let bytes = text.as_bytes();
     let mut i = 0;
@@ -179,6 +960,7 @@ fn strip_templates(text: &str) -> String {
                     if run_start < i {
                         result.push_str(&text[run_start..i]);
                     }
+                    let template_start = i;
                     // Inner loop: find matching '}}' using SIMD to skip between braces
                     let mut depth: i32 = 0;
                     while i + 1 < bytes.len() {
@@ -189,6 +971,12 @@ fn strip_templates(text: &str) -> String {
                             depth -= 1;
                             if depth == 0 {
                                 i += 2;
+                                if !keep.is_empty()
+                                    && let Some(kept) =
+                                        kept_template_text(&text[template_start..i], keep)
+                                {
+                                    result.push_str(&kept);
+                                }
                                 break;
                             }
                             i += 2;
@@ -222,6 +1010,47 @@ fn strip_templates(text: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn masked_comments_and_nowiki_hide_links_and_categories() {
+        let text = "Intro [[Rust]] <!-- [[Old link]] -->\n\
+                    <nowiki>[[Category:Not a category]]</nowiki> [[Category:Real]]\n\
+                    <pre>[[File:Code.png]]</pre> a<nowiki/>b [[Go]]\n\
+                    == See also ==\n* [[Python]]";
+        let masked = mask_non_content(text);
+        assert_eq!(masked.len(), text.len());
+        let links: Vec<&str> = LINK_REGEX
+            .captures_iter(&masked)
+            .map(|c| c.get(1).unwrap().as_str())
+            .collect();
+        assert_eq!(
+            links,
+            vec!["Rust", "Category:Real", "Go", "Python"],
+            "{masked}"
+        );
+        assert_eq!(extract_categories(&masked), vec!["Real"]);
+        assert!(extract_images(&masked).is_empty());
+        assert_eq!(
+            see_also_section_start(&masked),
+            see_also_section_start(text)
+        );
+
+        // A commented-out heading or an unclosed comment hides everything after it.
+        let text = "[[A]]\n<!--\n== See also ==\n-->\n[[B]] <!-- [[C]]";
+        let masked = mask_non_content(text);
+        assert_eq!(see_also_section_start(&masked), None);
+        assert_eq!(masked.lines().count(), text.lines().count());
+        let links: Vec<&str> = LINK_REGEX
+            .captures_iter(&masked)
+            .map(|c| c.get(1).unwrap().as_str())
+            .collect();
+        assert_eq!(links, vec!["A", "B"]);
+
+        assert!(matches!(
+            mask_non_content("No <b>markup</b> to mask"),
+            Cow::Borrowed(_)
+        ));
+    }
+
     #[test]
     fn category_simple() {
         let cats = extract_categories("[[Category:Science]]");
@@ -317,6 +1146,21 @@ mod tests {
         assert_eq!(images, vec!["lower.jpg", "upper.png"]);
     }
 
+    #[test]
+    fn images_normalize_underscores() {
+        let text = "[[File:Rust_logo.svg|thumb]]";
+        assert_eq!(extract_images(text), vec!["Rust logo.svg"]);
+    }
+
+    #[test]
+    fn normalize_image_name_forms() {
+        assert_eq!(normalize_image_name("Foo.jpg"), "Foo.jpg");
+        assert!(matches!(normalize_image_name("Foo.jpg"), Cow::Borrowed(_)));
+        assert_eq!(normalize_image_name(" File:Foo_bar.jpg "), "Foo bar.jpg");
+        assert_eq!(normalize_image_name("image:Foo  bar.png"), "Foo bar.png");
+        assert_eq!(normalize_image_name("Foo\nbar.svg"), "Foo bar.svg");
+    }
+
     #[test]
     fn external_links_basic() {
         let text = "[https://example.com Example] and [http://test.org Test Site]";
@@ -331,6 +1175,246 @@ mod tests {
         assert!(links.is_empty());
     }
 
+    #[test]
+    fn normalize_url_collapses_equivalent_urls() {
+        // http vs https, www., case, default port and trailing slash.
+        for url in [
+            "http://example.com",
+            "https://example.com/",
+            "HTTPS://WWW.Example.com:443/",
+            "http://www.example.com:80",
+        ] {
+            assert_eq!(normalize_url(url), "https://example.com", "{url}");
+        }
+        assert_eq!(
+            normalize_url("https://example.com/docs/Page/"),
+            "https://example.com/docs/Page"
+        );
+        // Fragments and tracking parameters go; other parameters stay in order.
+        assert_eq!(
+            normalize_url("https://example.com/a#section-2"),
+            "https://example.com/a"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/a?utm_source=x&id=7&fbclid=abc&b=2#top"),
+            "https://example.com/a?id=7&b=2"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/a?FBCLID=abc&Utm_Source=x&GClid=1&id=7"),
+            "https://example.com/a?id=7"
+        );
+        // Only the original scheme's default port is dropped.
+        assert_eq!(
+            normalize_url("http://example.com:443/a"),
+            "https://example.com:443/a"
+        );
+        assert_eq!(
+            normalize_url("https://example.com:80/a"),
+            "https://example.com:80/a"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/?utm_medium=email"),
+            "https://example.com"
+        );
+        assert!(matches!(
+            normalize_url("https://example.com/a?id=7"),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(normalize_url("mailto:someone"), "mailto:someone");
+    }
+
+    #[test]
+    fn interlanguage_links_match_language_prefixes() {
+        let text = "Rust is a language.\n[[de:Rust (Programmiersprache)]]\n[[fr:Rust]]\n\
+                    [[zh-yue:Rust]] [[simple:Rust (programming language)]]";
+        assert_eq!(
+            extract_interlanguage_links(text),
+            vec![
+                ("de".to_string(), "Rust (Programmiersprache)".to_string()),
+                ("fr".to_string(), "Rust".to_string()),
+                ("zh-yue".to_string(), "Rust".to_string()),
+                (
+                    "simple".to_string(),
+                    "Rust (programming language)".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn interlanguage_links_skip_other_prefixed_links() {
+        let text = "[[File:x.jpg|thumb]] [[Category:Languages]] [[wikt:rust]] [[:fr:Rust]] \
+                    [[mw:Help:Links]] [[Rust|see: Rust]] [[Rust: The Game]] [[De:Rust]] [[xx-yy:Rust]]";
+        assert!(extract_interlanguage_links(text).is_empty());
+    }
+
+    /// Asserts `text` yields `expected` (latitude, longitude), to within 1e-9.
+    fn assert_coordinates(text: &str, expected: (f64, f64)) {
+        let (lat, lon) = extract_coordinates(text).expect(text);
+        assert!(
+            (lat - expected.0).abs() < 1e-9 && (lon - expected.1).abs() < 1e-9,
+            "{text}: got ({lat}, {lon}), expected {expected:?}"
+        );
+    }
+
+    #[test]
+    fn coordinates_dms_form() {
+        assert_coordinates(
+            "New York {{Coord|40|42|46|N|74|0|22|W|type:city|display=inline,title}}",
+            (40.0 + 42.0 / 60.0 + 46.0 / 3600.0, -(74.0 + 22.0 / 3600.0)),
+        );
+        assert_coordinates("{{coord|40|42|N|74|0|W}}", (40.7, -74.0));
+    }
+
+    #[test]
+    fn coordinates_decimal_form() {
+        assert_coordinates(
+            "{{Coord|40.7128|-74.0060|display=title}}",
+            (40.7128, -74.006),
+        );
+        assert_coordinates(
+            "{{coord | 51.5 | N | 0.1275 | W | region:GB}}",
+            (51.5, -0.1275),
+        );
+    }
+
+    #[test]
+    fn coordinates_southern_and_western_hemispheres() {
+        assert_coordinates(
+            "Sydney {{Coord|33|52|S|151|12|E}}",
+            (-(33.0 + 52.0 / 60.0), 151.2),
+        );
+        assert_coordinates("Lima {{Coord|12|03|S|77|03|W}}", (-12.05, -77.05));
+    }
+
+    #[test]
+    fn coordinates_malformed_templates_are_ignored() {
+        assert_eq!(extract_coordinates("{{Coord missing|United States}}"), None);
+        assert_eq!(extract_coordinates("{{Coord|north|west}}"), None);
+        assert_eq!(extract_coordinates("{{Coord|40|75|N|74|0|W}}"), None);
+        assert_eq!(extract_coordinates("{{Coord|95|10}}"), None);
+        assert_eq!(extract_coordinates("{{Coord|40|42|N|74|0}}"), None);
+        assert_eq!(extract_coordinates("{{Coord|40|42"), None);
+        // The first well-formed template wins.
+        assert_coordinates("{{Coord|garbage}} {{Coord|1.5|2.5}}", (1.5, 2.5));
+    }
+
+    #[test]
+    fn template_names_include_nested_and_normalize() {
+        let text = "{{Infobox person\n| birth = {{birth date|1900|1|1}}\n}}\n\
+            {{cite web|url=x}} {{Cite_web |url=y}} {{  Reflist  }}";
+        assert_eq!(
+            extract_template_names(text),
+            vec![
+                "Infobox person",
+                "Birth date",
+                "Cite web",
+                "Cite web",
+                "Reflist"
+            ]
+        );
+    }
+
+    #[test]
+    fn template_names_skip_parser_functions_and_parameters() {
+        let text = "{{#if:{{{1|}}}|yes}} {{DEFAULTSORT:Smith, John}} {{{title}}} {{Stub}}";
+        assert_eq!(extract_template_names(text), vec!["Stub"]);
+    }
+
+    #[test]
+    fn references_cite_templates() {
+        let text = "Text.\n* {{cite web|url=https://a.example}}\n* {{Citation|title=B}}\n* {{Cite book|title=C}}";
+        assert_eq!(count_references(text), 3);
+    }
+
+    #[test]
+    fn references_ref_tags() {
+        let text = "A.<ref>Plain note</ref> B.<ref name=\"x\">{{cite web|url=u}}</ref>";
+        assert_eq!(count_references(text), 2);
+    }
+
+    #[test]
+    fn references_reused_refs_not_double_counted() {
+        let text =
+            "A.<ref name=\"x\">{{cite book|title=T}}</ref> B.<ref name=\"x\"/> C.<ref name=x />";
+        assert_eq!(count_references(text), 1);
+    }
+
+    #[test]
+    fn references_none() {
+        assert_eq!(count_references("No refs here.\n<references/>"), 0);
+        assert_eq!(count_references("Claim.{{citation needed}}"), 0);
+    }
+
+    #[test]
+    fn authority_ids_parameterized() {
+        let text = "Body.\n{{Authority control|VIAF=102333412|LCCN=n79021164|GND=118540238}}";
+        let ids = extract_authority_ids(text);
+        assert_eq!(
+            ids,
+            vec![
+                ("VIAF".to_string(), "102333412".to_string()),
+                ("LCCN".to_string(), "n79021164".to_string()),
+                ("GND".to_string(), "118540238".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn authority_ids_multiline_skips_empty_values() {
+        let text =
+            "{{authority control\n| VIAF = 123 \n| ISNI = \n| ORCID = 0000-0002-1825-0097\n}}";
+        let ids = extract_authority_ids(text);
+        assert_eq!(
+            ids,
+            vec![
+                ("VIAF".to_string(), "123".to_string()),
+                ("ORCID".to_string(), "0000-0002-1825-0097".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn authority_ids_bare_template_skipped() {
+        assert!(extract_authority_ids("End.\n{{Authority control}}").is_empty());
+        assert!(extract_authority_ids("{{Authority control files}}").is_empty());
+    }
+
+    #[test]
+    fn autogenerated_gnis_settlement_stub() {
+        let text = "'''Foo Creek''' is an unincorporated community in Bar County, Texas, United States.<ref>{{GNIS|1234567}}</ref>\n\n== References ==\n{{reflist}}\n\n{{BarCountyTX-geo-stub}}\n[[Category:Unincorporated communities in Texas]]";
+        assert!(is_likely_autogenerated(text));
+    }
+
+    #[test]
+    fn autogenerated_single_infobox_species_stub() {
+        let text = "{{Infobox moth\n| name = Examplea foo\n}}\n'''''Examplea foo''''' is a moth of the family Erebidae.\n\n[[Category:Moths described in 1900]]";
+        assert!(is_likely_autogenerated(text));
+    }
+
+    #[test]
+    fn human_article_not_autogenerated() {
+        let text = "{{Infobox settlement\n| name = Springfield\n}}\n'''Springfield''' is a city in Illinois. It is the state capital. The city has a long history.<ref>{{GNIS|42}}</ref>\n\n== History ==\nSettled in 1819.\n\n== References ==\n{{reflist}}";
+        assert!(!is_likely_autogenerated(text));
+    }
+
+    #[test]
+    fn short_stub_without_signature_not_autogenerated() {
+        assert!(!is_likely_autogenerated(
+            "'''Foo''' is a thing.\n[[Category:Things]]"
+        ));
+        assert!(!is_likely_autogenerated(
+            "{{Infobox person}}\n[[Category:People]]"
+        ));
+    }
+
+    #[test]
+    fn count_sentences_handles_decimals_and_fragments() {
+        assert_eq!(count_sentences("It is 3.5 km long."), 1);
+        assert_eq!(count_sentences("One. Two! Three?"), 3);
+        assert_eq!(count_sentences("No terminator"), 1);
+    }
+
     #[test]
     fn disambiguation_true() {
         assert!(is_disambiguation("{{disambiguation}}"));
@@ -363,6 +1447,25 @@ mod tests {
         assert_eq!(abs, "This is the abstract.");
     }
 
+    #[test]
+    fn abstract_keeps_allowlisted_lead_templates() {
+        let text = "{{Multiple issues|{{Refimprove|date=May 2020}}}}\n\
+                    {{Use dmy dates}}\n\
+                    '''Paris''' ({{lang|fr|Paris {{IPA|[pa.ʁi]}}}}) is the \
+                    {{nowrap|[[Capital city|capital]] of France}}.\n== History ==\n";
+        assert_eq!(extract_abstract(text), "'''Paris''' () is the .");
+        let keep = ["Lang".to_string(), "nowrap".to_string()];
+        assert_eq!(
+            extract_abstract_keeping(text, &keep),
+            "'''Paris''' (Paris) is the [[Capital city|capital]] of France."
+        );
+        // Named parameters are skipped; a template with none unnamed is dropped.
+        assert_eq!(
+            strip_templates("{{lang|fr|Bonjour|italic=no}} {{lang|code=fr}}", &keep),
+            "Bonjour "
+        );
+    }
+
     #[test]
     fn abstract_no_headings() {
         let text = "Just a simple article with no headings.";
@@ -377,30 +1480,68 @@ mod tests {
         assert_eq!(abs, "");
     }
 
+    #[test]
+    fn plain_text_unwraps_links_and_simple_templates() {
+        assert_eq!(
+            to_plain_text("[[Mozilla Foundation|Mozilla]] and [[Rust]]<ref>cite</ref>"),
+            "Mozilla and Rust"
+        );
+        assert_eq!(
+            to_plain_text("{{flag|France}} '''(capital)'''<br/>[[Paris]]"),
+            "France (capital) Paris"
+        );
+        // Multi-parameter and named-parameter templates are dropped.
+        assert_eq!(
+            to_plain_text("{{birth date|1990|1|1}} {{nowrap|{{small|2 km}}}}"),
+            "2 km"
+        );
+        assert_eq!(
+            to_plain_text("[[File:Logo.svg|thumb]] [https://example.org Site] [https://x.org]"),
+            "Site"
+        );
+    }
+
     #[test]
     fn strip_templates_basic() {
-        let result = strip_templates("{{template}} text after");
+        let result = strip_templates("{{template}} text after", &[]);
         assert_eq!(result, " text after");
     }
 
     #[test]
     fn strip_templates_nested() {
-        let result = strip_templates("{{outer {{inner}} end}} text");
+        let result = strip_templates("{{outer {{inner}} end}} text", &[]);
         assert_eq!(result, " text");
     }
 
     #[test]
     fn strip_templates_no_templates() {
-        let result = strip_templates("plain text");
+        let result = strip_templates("plain text", &[]);
         assert_eq!(result, "plain text");
     }
 
     #[test]
     fn strip_templates_multiple() {
-        let result = strip_templates("{{a}} middle {{b}} end");
+        let result = strip_templates("{{a}} middle {{b}} end", &[]);
         assert_eq!(result, " middle  end");
     }
 
+    #[test]
+    fn section_at_maps_offsets_to_enclosing_heading() {
+        let text = "Lead [[A]].\n== History ==\nOld [[B]].\n=== Early ===\n[[C]]";
+        let sections = section_starts(text);
+        assert_eq!(
+            sections.iter().map(|(_, t)| *t).collect::<Vec<_>>(),
+            vec!["History", "Early"]
+        );
+        assert_eq!(section_at(&sections, text.find("[[A]]").unwrap()), "lead");
+        assert_eq!(
+            section_at(&sections, text.find("[[B]]").unwrap()),
+            "History"
+        );
+        assert_eq!(section_at(&sections, text.find("[[C]]").unwrap()), "Early");
+        assert_eq!(section_at(&[], 10), "lead");
+    }
+
     #[test]
     fn see_also_section_start_found() {
         let text = "Intro.\n== History ==\nSome history.\n== See also ==\n* [[Rust]]";
@@ -417,7 +1558,113 @@ mod tests {
 
     #[test]
     fn strip_templates_unclosed_does_not_hang() {
-        let result = strip_templates("{{unclosed template text after");
+        let result = strip_templates("{{unclosed template text after", &[]);
         assert!(!result.contains("unclosed"));
     }
+
+    #[test]
+    fn extract_all_matches_the_individual_extractors() {
+        let text = "{{Infobox city\n| name = Springfield\n| image = Skyline.jpg\n}}\n\
+                    '''Springfield''' is a city.{{coord|39.8|-89.6}}\n\
+                    [[File:Skyline.jpg|thumb]] [[File:Park_view.png]]\n\
+                    <!-- [[Category:Hidden]] -->[[Category:Cities]]\n\
+                    == History ==\nFounded early. [https://example.org Site]\n\
+                    [[fr:Springfield (Illinois)]]\n";
+        let article = extract_all(text);
+        assert_eq!(article.abstract_text, extract_abstract(text));
+        assert_eq!(article.sections, ["History"]);
+        assert_eq!(article.categories, ["Cities"]);
+        assert_eq!(article.images, ["Skyline.jpg", "Park view.png"]);
+        assert_eq!(article.external_links, ["https://example.org"]);
+        assert_eq!(article.infoboxes.len(), 1);
+        assert_eq!(
+            article.interlanguage_links,
+            [("fr".to_string(), "Springfield (Illinois)".to_string())]
+        );
+        let (lat, lon) = article.coordinates.unwrap();
+        assert!((lat - 39.8).abs() < 1e-9 && (lon + 89.6).abs() < 1e-9);
+        assert!(!article.is_disambiguation);
+        assert!(article.see_also_links.is_empty());
+    }
+
+    /// `scan_brackets` must agree with the per-extractor regex scans.
+    fn assert_scan_matches_extractors(text: &str) {
+        let scan = scan_brackets(text);
+        let links: Vec<(usize, &str)> = LINK_REGEX
+            .captures_iter(text)
+            .map(|c| (c.get(0).unwrap().start(), c.get(1).unwrap().as_str()))
+            .collect();
+        assert_eq!(scan.links, links, "{text:?}");
+        assert_eq!(scan.categories, extract_categories(text), "{text:?}");
+        assert_eq!(scan.images, extract_images(text), "{text:?}");
+        assert_eq!(
+            scan.external_links,
+            extract_external_links(text),
+            "{text:?}"
+        );
+        assert_eq!(
+            interlanguage_links(&scan.links),
+            extract_interlanguage_links(text),
+            "{text:?}"
+        );
+    }
+
+    #[test]
+    fn bracket_scan_matches_individual_extractors() {
+        for text in [
+            "",
+            "plain text",
+            "[[Rust]] [[Go|golang]] [[Category:Languages|R]] [[File:Logo.png|thumb|A [[logo]]]]",
+            "[[Category:A]][[Category:B]][[category:lower]] [[Category: ]]",
+            "[[image:x.jpg]] [[FILE:Y_z.PNG|a|b]] [[Image:]] [[Filet mignon]]",
+            "[[File:a.jpg|caption with [[link]] and [http://example.org site]]]",
+            "[http://a.example b] [https://b.example] [[http://not-external]] [ftp://x]",
+            "[[de:Rust]] [[mw:Help]] [[simple:Rust]] [[:fr:Rust]] [[wikt:rust]]",
+            "[[unclosed [[Inner]] text]] [[a\nb]] [[]] [[|x]] [[x|]]",
+            "[[[Triple]]] [[Category:[[Nested]]]] ]] [[ [",
+            "[[Ünïcödé]] [[İmage:x.jpg]] [[ſile:x.jpg]] [http://é.example ü]",
+        ] {
+            assert_scan_matches_extractors(text);
+        }
+
+        // Unterminated brackets are each searched once, not again at every later `[`.
+        let unterminated = format!(
+            "{}[[end]] [http://x.example y] {}",
+            "[[a [http ".repeat(20_000),
+            "[[Category:[[File:".repeat(1_000)
+        );
+        assert_scan_matches_extractors(&unterminated);
+
+        // Deterministic fuzzing over bracket-heavy fragments.
+        let pieces = [
+            "[",
+            "]",
+            "[[",
+            "]]",
+            "|",
+            " ",
+            "\n",
+            "Category:",
+            "File:",
+            "image:",
+            "http://",
+            "https://x",
+            "de:",
+            "a",
+            "Rust",
+            "#s",
+            "_b",
+        ];
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        for _ in 0..2_000 {
+            let mut text = String::new();
+            for _ in 0..24 {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                text.push_str(pieces[(state % pieces.len() as u64) as usize]);
+            }
+            assert_scan_matches_extractors(&text);
+        }
+    }
 }
@@ -2,9 +2,11 @@
 //!
 //! Extracted from the former `import.rs` module. Provides shared helpers for
 //! detecting whether CSV output is single-file or sharded, generating file lists,
-//! and validating that all expected CSV files exist.
+//! validating that all expected CSV files exist, streaming the rows of one
+//! CSV type regardless of layout, and parsing the article IDs in those rows.
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
+use csv::{ByteRecord, StringRecord};
 use std::path::Path;
 
 /// A type of CSV file produced by extraction.
@@ -101,6 +103,28 @@ pub fn csv_files_for(base_name: &str, layout: &CsvLayout) -> Vec<String> {
     }
 }
 
+/// Filename of one part of a single-file CSV: `{base}.csv` for part 0, then
+/// `{base}_part001.csv`, `{base}_part002.csv`, ... (see `--max-shard-bytes`).
+pub fn part_filename(base_name: &str, part: u32) -> String {
+    if part == 0 {
+        format!("{base_name}.csv")
+    } else {
+        format!("{base_name}_part{part:03}.csv")
+    }
+}
+
+/// Returns the files of a single-file CSV type in order: `{base}.csv` (listed
+/// even if missing, so readers report it) followed by any rollover parts.
+pub fn single_file_parts(output_dir: &str, base_name: &str) -> Vec<String> {
+    let mut parts = vec![part_filename(base_name, 0)];
+    parts.extend(
+        (1u32..)
+            .map(|part| part_filename(base_name, part))
+            .take_while(|name| Path::new(output_dir).join(name).exists()),
+    );
+    parts
+}
+
 /// Validates that all expected CSV files exist in the output directory.
 pub fn validate_csv_files(output_dir: &str, layout: &CsvLayout) -> Result<()> {
     for csv_type in CsvType::ALL {
@@ -118,6 +142,93 @@ pub fn validate_csv_files(output_dir: &str, layout: &CsvLayout) -> Result<()> {
     Ok(())
 }
 
+/// Files holding `csv_type` in `output_dir`: every shard, or the single file
+/// followed by any `--max-shard-bytes` rollover parts.
+pub fn layout_files(output_dir: &str, layout: &CsvLayout, csv_type: CsvType) -> Vec<String> {
+    match layout {
+        CsvLayout::Single => single_file_parts(output_dir, csv_type.base_name()),
+        CsvLayout::Sharded { .. } => csv_files_for(csv_type.base_name(), layout),
+    }
+}
+
+/// Streams every data row of `csv_type` in `output_dir`, across all shards.
+///
+/// Files are read in shard order (or part order for a single-file layout split
+/// by `--max-shard-bytes`) with each header row skipped. A missing file is an
+/// error, as with `validate_csv_files`.
+pub fn for_each_record(
+    output_dir: &str,
+    layout: &CsvLayout,
+    csv_type: CsvType,
+    mut f: impl FnMut(&StringRecord) -> Result<()>,
+) -> Result<()> {
+    let mut record = StringRecord::new();
+    for file in layout_files(output_dir, layout, csv_type) {
+        let path = Path::new(output_dir).join(&file);
+        let mut reader = csv::ReaderBuilder::new()
+            .buffer_capacity(crate::config::MERGE_BUF_SIZE)
+            .from_path(&path)
+            .with_context(|| format!("Failed to open {path:?}"))?;
+        while reader
+            .read_record(&mut record)
+            .with_context(|| format!("Failed to read {path:?}"))?
+        {
+            f(&record)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses the article ID in `column` of `record`. `path` names the file in the
+/// error, or the CSV type's base name where rows are streamed across files.
+pub fn parse_id(record: &ByteRecord, column: usize, path: impl AsRef<Path>) -> Result<u64> {
+    let value = record.get(column).unwrap_or_default();
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .with_context(|| {
+            format!(
+                "Invalid article ID {:?} in column {column} of {}",
+                String::from_utf8_lossy(value),
+                path.as_ref().display()
+            )
+        })
+}
+
+/// Writes a single-file CSV fixture to `dir`: each `(name, content)` pair,
+/// then a header-only `{base_name}.csv` for every `CsvType` not among them.
+#[cfg(test)]
+pub(crate) fn write_csv_fixture(dir: &Path, files: &[(&str, &str)]) {
+    for (name, content) in files {
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+    for csv_type in CsvType::ALL {
+        let path = dir.join(format!("{}.csv", csv_type.base_name()));
+        if !path.exists() {
+            std::fs::write(path, "header\n").unwrap();
+        }
+    }
+}
+
+/// Header row of `csv_type` in `output_dir`, read from its first file.
+pub fn read_headers(
+    output_dir: &str,
+    layout: &CsvLayout,
+    csv_type: CsvType,
+) -> Result<StringRecord> {
+    let file = match layout {
+        CsvLayout::Single => part_filename(csv_type.base_name(), 0),
+        CsvLayout::Sharded { .. } => format!("{}_000.csv", csv_type.base_name()),
+    };
+    let path = Path::new(output_dir).join(file);
+    let mut reader =
+        csv::Reader::from_path(&path).with_context(|| format!("Failed to open {path:?}"))?;
+    Ok(reader
+        .headers()
+        .with_context(|| format!("Failed to read header of {path:?}"))?
+        .clone())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,4 +301,57 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Missing CSV file"));
     }
+
+    #[test]
+    fn for_each_record_streams_all_shards() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("nodes_000.csv"), "id:ID,title\n1,A\n").unwrap();
+        std::fs::write(dir.path().join("nodes_001.csv"), "id:ID,title\n2,B\n3,C\n").unwrap();
+        let mut ids = Vec::new();
+        for_each_record(
+            dir.path().to_str().unwrap(),
+            &CsvLayout::Sharded { count: 2 },
+            CsvType::Nodes,
+            |record| {
+                ids.push(record[0].to_string());
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn parse_id_names_the_file_and_column() {
+        let record = ByteRecord::from(vec!["42", "x"]);
+        assert_eq!(parse_id(&record, 0, "edges").unwrap(), 42);
+        let error = parse_id(&record, 1, "edges").unwrap_err().to_string();
+        assert_eq!(error, r#"Invalid article ID "x" in column 1 of edges"#);
+        assert!(parse_id(&record, 2, "edges").is_err());
+    }
+
+    #[test]
+    fn single_file_parts_lists_rollover_parts_in_order() {
+        let dir = TempDir::new().unwrap();
+        let out = dir.path().to_str().unwrap();
+        assert_eq!(single_file_parts(out, "edges"), vec!["edges.csv"]);
+
+        std::fs::write(dir.path().join("edges.csv"), "h\n1\n").unwrap();
+        std::fs::write(dir.path().join("edges_part001.csv"), "h\n2\n").unwrap();
+        std::fs::write(dir.path().join("edges_part002.csv"), "h\n3\n").unwrap();
+        // A gap ends the sequence.
+        std::fs::write(dir.path().join("edges_part004.csv"), "h\n4\n").unwrap();
+        assert_eq!(
+            single_file_parts(out, "edges"),
+            vec!["edges.csv", "edges_part001.csv", "edges_part002.csv"]
+        );
+
+        let mut rows = Vec::new();
+        for_each_record(out, &CsvLayout::Single, CsvType::Edges, |record| {
+            rows.push(record[0].to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(rows, vec!["1", "2", "3"]);
+    }
 }
@@ -0,0 +1,164 @@
+//! Concurrent first-seen sets for category, image, and external link dedup.
+//!
+//! `SeenSet::Exact` wraps a `DashSet<String>` and never emits a duplicate node.
+//! `SeenSet::Fingerprint` is a table of 64-bit fingerprints sized from the
+//! index's article count: memory is fixed for the run no matter how many
+//! distinct values the dump contains, at the cost of occasionally forgetting a
+//! value and writing its node row twice. It never reports an unseen value as
+//! seen (short of a full 64-bit hash collision), so no node is dropped;
+//! `merge_with_dedup` removes the rare duplicates when shards are merged.
+
+use crate::config::{
+    FINGERPRINT_DEDUP_MAX_SLOTS, FINGERPRINT_DEDUP_MIN_SLOTS, FINGERPRINT_DEDUP_SLOTS_PER_ARTICLE,
+};
+use dashmap::DashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Strategy for tracking which entity values have already been written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DedupStrategy {
+    /// Exact `DashSet<String>`; memory grows with the number of distinct values.
+    #[default]
+    Exact,
+    /// Fingerprint table sized from the article count; may write a few
+    /// duplicate node rows. Also accepted under its former name, `bloom`.
+    #[value(alias = "bloom")]
+    Fingerprint,
+}
+
+impl std::fmt::Display for DedupStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DedupStrategy::Exact => f.write_str("exact"),
+            DedupStrategy::Fingerprint => f.write_str("fingerprint"),
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash. Stable across runs and platforms (no random seed).
+#[must_use]
+pub fn fnv1a64(key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in key.as_bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Thread-safe first-seen set used during extraction.
+pub enum SeenSet {
+    Exact(DashSet<String>),
+    Fingerprint(Vec<AtomicU64>),
+}
+
+/// Fingerprint slots for a dump of `articles` articles:
+/// `FINGERPRINT_DEDUP_SLOTS_PER_ARTICLE` each, within
+/// `FINGERPRINT_DEDUP_MIN_SLOTS..=FINGERPRINT_DEDUP_MAX_SLOTS`.
+#[must_use]
+pub fn fingerprint_slots(articles: usize) -> usize {
+    articles
+        .saturating_mul(FINGERPRINT_DEDUP_SLOTS_PER_ARTICLE)
+        .clamp(FINGERPRINT_DEDUP_MIN_SLOTS, FINGERPRINT_DEDUP_MAX_SLOTS)
+}
+
+impl SeenSet {
+    /// Creates an empty set for the given strategy. `articles` (the index's
+    /// article count) sizes a `Fingerprint` table; `Exact` ignores it.
+    pub fn new(strategy: DedupStrategy, articles: usize) -> Self {
+        Self::with_slots(strategy, fingerprint_slots(articles))
+    }
+
+    fn with_slots(strategy: DedupStrategy, slots: usize) -> Self {
+        match strategy {
+            DedupStrategy::Exact => Self::Exact(DashSet::new()),
+            DedupStrategy::Fingerprint => {
+                Self::Fingerprint((0..slots).map(|_| AtomicU64::new(0)).collect())
+            }
+        }
+    }
+
+    /// Records `item` and returns `true` if this is the first time it was seen.
+    ///
+    /// For `Fingerprint`, a value whose slot was overwritten by another value since it
+    /// was last seen is reported as new again.
+    pub fn insert_new(&self, item: &str) -> bool {
+        match self {
+            Self::Exact(set) => !set.contains(item) && set.insert(item.to_owned()),
+            Self::Fingerprint(slots) => {
+                // 0 marks an empty slot, so remap the (astronomically rare) zero hash.
+                let fingerprint = fnv1a64(item).max(1);
+                let slot = &slots[(fingerprint % slots.len() as u64) as usize];
+                if slot.load(Ordering::Relaxed) == fingerprint {
+                    return false;
+                }
+                slot.swap(fingerprint, Ordering::Relaxed) != fingerprint
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_reports_first_sighting_only() {
+        let set = SeenSet::new(DedupStrategy::Exact, 0);
+        assert!(set.insert_new("Science"));
+        assert!(!set.insert_new("Science"));
+        assert!(set.insert_new("History"));
+    }
+
+    #[test]
+    fn fingerprint_reports_first_sighting_only() {
+        let set = SeenSet::with_slots(DedupStrategy::Fingerprint, 1024);
+        assert!(set.insert_new("Science"));
+        assert!(!set.insert_new("Science"));
+        assert!(set.insert_new("History"));
+        assert!(!set.insert_new("History"));
+    }
+
+    #[test]
+    fn fingerprint_never_drops_a_new_value() {
+        // A single slot forces every distinct value to collide: values get
+        // re-reported as new (duplicates) but a new value is never reported seen.
+        let set = SeenSet::with_slots(DedupStrategy::Fingerprint, 1);
+        for value in ["a", "b", "c", "d"] {
+            assert!(set.insert_new(value));
+        }
+        assert!(set.insert_new("a"));
+        assert!(!set.insert_new("a"));
+    }
+
+    #[test]
+    fn fingerprint_slots_follow_the_article_count() {
+        assert_eq!(fingerprint_slots(0), FINGERPRINT_DEDUP_MIN_SLOTS);
+        assert_eq!(
+            fingerprint_slots(1_000_000),
+            1_000_000 * FINGERPRINT_DEDUP_SLOTS_PER_ARTICLE
+        );
+        assert_eq!(fingerprint_slots(usize::MAX), FINGERPRINT_DEDUP_MAX_SLOTS);
+        let SeenSet::Fingerprint(slots) = SeenSet::new(DedupStrategy::Fingerprint, 100_000) else {
+            panic!("expected a fingerprint table");
+        };
+        assert_eq!(slots.len(), 100_000 * FINGERPRINT_DEDUP_SLOTS_PER_ARTICLE);
+    }
+
+    #[test]
+    fn bloom_still_selects_the_fingerprint_strategy() {
+        use clap::ValueEnum;
+        for name in ["fingerprint", "bloom"] {
+            assert_eq!(
+                DedupStrategy::from_str(name, false),
+                Ok(DedupStrategy::Fingerprint)
+            );
+        }
+    }
+
+    #[test]
+    fn fnv1a64_known_values() {
+        assert_eq!(fnv1a64(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a64("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,98 @@
+//! Inbound link counts per article (`--compute-in-degree`).
+//!
+//! In-degree is not known until every article has been processed, so
+//! `compute_in_degree()` runs after extraction: it counts `LINKS_TO` rows per
+//! `:END_ID` across the edge CSVs in an `FxHashMap<u64, u32>`, then writes
+//! `node_degrees.csv` (`id:ID,in_degree:int`) with one row per node, in node
+//! file order. Articles nobody links to get 0; link targets outside `nodes`
+//! (e.g. past `--limit`) are counted but not written.
+
+use crate::config::NODE_DEGREES_FILENAME;
+use crate::csv_util::{self, CsvType};
+use anyhow::{Context, Result};
+use rustc_hash::FxHashMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Counts from a [`compute_in_degree`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InDegreeStats {
+    /// Rows written to `node_degrees.csv`.
+    pub nodes: u64,
+    /// `LINKS_TO` edges counted.
+    pub links: u64,
+}
+
+/// Writes `node_degrees.csv` to `output_dir` with the number of `links_to`
+/// edges pointing at each node. `links_to` is the (possibly renamed) `:TYPE`
+/// of article links. Works on single-file and sharded layouts.
+pub fn compute_in_degree(output_dir: &str, links_to: &str) -> Result<(PathBuf, InDegreeStats)> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    csv_util::validate_csv_files(output_dir, &layout)?;
+
+    let mut in_degree: FxHashMap<u64, u32> = FxHashMap::default();
+    let mut stats = InDegreeStats::default();
+    csv_util::for_each_record(output_dir, &layout, CsvType::Edges, |record| {
+        if record.get(2) == Some(links_to) {
+            let target = csv_util::parse_id(record.as_byte_record(), 1, "edges")?;
+            *in_degree.entry(target).or_insert(0) += 1;
+            stats.links += 1;
+        }
+        Ok(())
+    })?;
+
+    let path = Path::new(output_dir).join(NODE_DEGREES_FILENAME);
+    let mut writer =
+        csv::Writer::from_path(&path).with_context(|| format!("Failed to create {path:?}"))?;
+    writer.write_record(["id:ID", "in_degree:int"])?;
+    let mut degree_buf = itoa::Buffer::new();
+    csv_util::for_each_record(output_dir, &layout, CsvType::Nodes, |record| {
+        let id = csv_util::parse_id(record.as_byte_record(), 0, "nodes")?;
+        let degree = in_degree.get(&id).copied().unwrap_or(0);
+        writer.write_record([&record[0], degree_buf.format(degree)])?;
+        stats.nodes += 1;
+        Ok(())
+    })?;
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write {path:?}"))?;
+
+    info!(
+        nodes = stats.nodes,
+        links = stats.links,
+        "Wrote article in-degrees"
+    );
+    Ok((path, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn counts_only_links_to_edges_per_target() {
+        let dir = TempDir::new().unwrap();
+        csv_util::write_csv_fixture(
+            dir.path(),
+            &[
+                (
+                    "nodes.csv",
+                    "id:ID,title,:LABEL\n1,A,Page\n2,B,Page\n3,C,Page\n",
+                ),
+                (
+                    "edges.csv",
+                    ":START_ID,:END_ID,:TYPE\n1,2,CITES\n3,2,CITES\n2,3,SEE_ALSO\n1,9,CITES\n",
+                ),
+            ],
+        );
+
+        let (path, stats) = compute_in_degree(dir.path().to_str().unwrap(), "CITES").unwrap();
+        assert_eq!(stats, InDegreeStats { nodes: 3, links: 3 });
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "id:ID,in_degree:int\n1,0\n2,2\n3,0\n"
+        );
+    }
+}
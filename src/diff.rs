@@ -0,0 +1,219 @@
+//! Comparison of two extraction output directories.
+//!
+//! `diff_outputs()` streams the node, edge, and category CSVs of both outputs
+//! (single-file or sharded) via `csv_util::for_each_record` and reports added
+//! and removed article IDs, per-type edge count deltas, and category set
+//! differences. Read-only; useful for regression-testing pipeline changes and
+//! comparing consecutive dumps.
+
+use crate::csv_util::{self, CsvType};
+use anyhow::{Context, Result};
+use rustc_hash::FxHashSet;
+use std::collections::BTreeMap;
+
+/// Differences between an old (`a`) and a new (`b`) extraction output.
+#[derive(Debug, Default, PartialEq)]
+pub struct OutputDiff {
+    /// Article node count in `a` and `b`.
+    pub node_counts: (usize, usize),
+    /// Article IDs present only in `b`, ascending.
+    pub nodes_added: Vec<u64>,
+    /// Article IDs present only in `a`, ascending.
+    pub nodes_removed: Vec<u64>,
+    /// Edge count per relationship type (`LINKS_TO`, `SEE_ALSO`) in `a` and `b`.
+    pub edge_counts: BTreeMap<String, (u64, u64)>,
+    /// Category count in `a` and `b`.
+    pub category_counts: (usize, usize),
+    /// Category names present only in `b`, sorted.
+    pub categories_added: Vec<String>,
+    /// Category names present only in `a`, sorted.
+    pub categories_removed: Vec<String>,
+}
+
+impl OutputDiff {
+    /// Returns `true` if the outputs have the same nodes, edge counts, and categories.
+    pub fn is_empty(&self) -> bool {
+        self.nodes_added.is_empty()
+            && self.nodes_removed.is_empty()
+            && self.categories_added.is_empty()
+            && self.categories_removed.is_empty()
+            && self.edge_counts.values().all(|(a, b)| a == b)
+    }
+}
+
+/// Compares the extraction outputs in `dir_a` (old) and `dir_b` (new).
+pub fn diff_outputs(dir_a: &str, dir_b: &str) -> Result<OutputDiff> {
+    let nodes_a = read_node_ids(dir_a)?;
+    let nodes_b = read_node_ids(dir_b)?;
+    let categories_a = read_categories(dir_a)?;
+    let categories_b = read_categories(dir_b)?;
+
+    let mut edge_counts: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for (type_name, count) in read_edge_counts(dir_a)? {
+        edge_counts.entry(type_name).or_default().0 = count;
+    }
+    for (type_name, count) in read_edge_counts(dir_b)? {
+        edge_counts.entry(type_name).or_default().1 = count;
+    }
+
+    let mut nodes_added: Vec<u64> = nodes_b.difference(&nodes_a).copied().collect();
+    let mut nodes_removed: Vec<u64> = nodes_a.difference(&nodes_b).copied().collect();
+    nodes_added.sort_unstable();
+    nodes_removed.sort_unstable();
+
+    let mut categories_added: Vec<String> =
+        categories_b.difference(&categories_a).cloned().collect();
+    let mut categories_removed: Vec<String> =
+        categories_a.difference(&categories_b).cloned().collect();
+    categories_added.sort_unstable();
+    categories_removed.sort_unstable();
+
+    Ok(OutputDiff {
+        node_counts: (nodes_a.len(), nodes_b.len()),
+        nodes_added,
+        nodes_removed,
+        edge_counts,
+        category_counts: (categories_a.len(), categories_b.len()),
+        categories_added,
+        categories_removed,
+    })
+}
+
+fn read_node_ids(output_dir: &str) -> Result<FxHashSet<u64>> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    let mut ids = FxHashSet::default();
+    csv_util::for_each_record(output_dir, &layout, CsvType::Nodes, |record| {
+        let id = record.get(0).context("Missing node ID")?;
+        ids.insert(
+            id.parse()
+                .with_context(|| format!("Invalid node ID {id:?} in {output_dir}"))?,
+        );
+        Ok(())
+    })?;
+    Ok(ids)
+}
+
+fn read_edge_counts(output_dir: &str) -> Result<BTreeMap<String, u64>> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    let mut counts = BTreeMap::new();
+    csv_util::for_each_record(output_dir, &layout, CsvType::Edges, |record| {
+        let edge_type = record.get(2).context("Missing edge type")?;
+        match counts.get_mut(edge_type) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(edge_type.to_string(), 1);
+            }
+        }
+        Ok(())
+    })?;
+    Ok(counts)
+}
+
+fn read_categories(output_dir: &str) -> Result<FxHashSet<String>> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    let mut names = FxHashSet::default();
+    csv_util::for_each_record(output_dir, &layout, CsvType::Categories, |record| {
+        let name = record.get(0).context("Missing category ID")?;
+        if !names.contains(name) {
+            names.insert(name.to_string());
+        }
+        Ok(())
+    })?;
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn write_output(dir: &Path, nodes: &[&str], edges: &[&str], categories: &[&str]) {
+        let write = |name: &str, header: &str, rows: &[&str]| {
+            let mut content = format!("{header}\n");
+            for row in rows {
+                content.push_str(row);
+                content.push('\n');
+            }
+            std::fs::write(dir.join(name), content).unwrap();
+        };
+        write("nodes.csv", "id:ID,title,:LABEL", nodes);
+        write("edges.csv", ":START_ID,:END_ID,:TYPE", edges);
+        write("categories.csv", "id:ID(Category),name,:LABEL", categories);
+    }
+
+    #[test]
+    fn diff_reports_node_edge_and_category_changes() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        write_output(
+            a.path(),
+            &["1,One,Page", "2,Two,Page", "3,Three,Page"],
+            &["1,2,LINKS_TO", "2,3,LINKS_TO", "1,3,SEE_ALSO"],
+            &["Science,Science,Category", "Math,Math,Category"],
+        );
+        write_output(
+            b.path(),
+            &["1,One,Page", "3,Three,Page", "4,Four,Page", "5,Five,Page"],
+            &["1,3,LINKS_TO", "3,4,LINKS_TO", "4,5,LINKS_TO"],
+            &["Science,Science,Category", "History,History,Category"],
+        );
+
+        let diff = diff_outputs(a.path().to_str().unwrap(), b.path().to_str().unwrap()).unwrap();
+        assert_eq!(diff.node_counts, (3, 4));
+        assert_eq!(diff.nodes_added, vec![4, 5]);
+        assert_eq!(diff.nodes_removed, vec![2]);
+        assert_eq!(diff.edge_counts["LINKS_TO"], (2, 3));
+        assert_eq!(diff.edge_counts["SEE_ALSO"], (1, 0));
+        assert_eq!(diff.category_counts, (2, 2));
+        assert_eq!(diff.categories_added, vec!["History"]);
+        assert_eq!(diff.categories_removed, vec!["Math"]);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_of_sharded_and_single_layouts_is_empty_when_identical() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        write_output(
+            a.path(),
+            &["1,One,Page", "2,Two,Page"],
+            &["1,2,LINKS_TO"],
+            &["Science,Science,Category"],
+        );
+        let shards = [
+            ("nodes", "id:ID,title,:LABEL", "1,One,Page", "2,Two,Page"),
+            ("edges", ":START_ID,:END_ID,:TYPE", "1,2,LINKS_TO", ""),
+            (
+                "categories",
+                "id:ID(Category),name,:LABEL",
+                "Science,Science,Category",
+                "Science,Science,Category",
+            ),
+        ];
+        for (base, header, row0, row1) in shards {
+            std::fs::write(
+                b.path().join(format!("{base}_000.csv")),
+                format!("{header}\n{row0}\n"),
+            )
+            .unwrap();
+            std::fs::write(
+                b.path().join(format!("{base}_001.csv")),
+                format!("{header}\n{row1}\n"),
+            )
+            .unwrap();
+        }
+
+        let diff = diff_outputs(a.path().to_str().unwrap(), b.path().to_str().unwrap()).unwrap();
+        assert!(diff.is_empty(), "{diff:?}");
+        assert_eq!(diff.category_counts, (1, 1));
+    }
+
+    #[test]
+    fn diff_missing_output_errors() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+        write_output(a.path(), &["1,One,Page"], &[], &[]);
+        assert!(diff_outputs(a.path().to_str().unwrap(), b.path().to_str().unwrap()).is_err());
+    }
+}
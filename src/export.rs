@@ -0,0 +1,185 @@
+//! Gzipped JSON Lines export of the extracted graph (`--export-graph-jsonl`).
+//!
+//! `export_graph_jsonl()` streams every row of the extraction CSVs back through
+//! `csv_util::for_each_record` and writes one JSON object per line to a single
+//! gzip stream. Each object has a `type` of `node` or `edge`; the remaining keys
+//! come from the CSV header with the import decorations dropped (`id:ID` ->
+//! `id`, `:START_ID` -> `start`, `:TYPE` -> `rel`, `:LABEL` -> `label`):
+//!
+//! ```text
+//! {"type":"node","id":1,"title":"Rust (programming language)","label":"Page"}
+//! {"type":"node","id":"Programming languages","name":"Programming languages","label":"Category"}
+//! {"type":"edge","start":1,"end":2,"rel":"LINKS_TO"}
+//! ```
+//!
+//! Article ids are JSON numbers; category, image and external-link nodes are
+//! keyed by their name, filename or URL. Optional columns (`section`,
+//! `via_redirect` and `weight` on edges, `count` on categories) are carried over
+//! when present.
+
+use crate::csv_util::{self, CsvType};
+use anyhow::{Context, Result, bail};
+use csv::StringRecord;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Serializer;
+use serde::ser::SerializeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+
+/// Record counts written by [`export_graph_jsonl`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraphExport {
+    pub nodes: u64,
+    pub edges: u64,
+}
+
+/// How a CSV column's values are written to JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    /// Article id: a JSON number (a string if it does not parse).
+    ArticleId,
+    /// Integer column (`count:int`, `weight:int`): a JSON number.
+    Count,
+    Bool,
+    Text,
+}
+
+#[derive(Debug)]
+struct Column {
+    key: String,
+    kind: ValueKind,
+}
+
+/// Whether rows of `csv_type` are nodes (as opposed to relationships).
+fn is_node(csv_type: CsvType) -> bool {
+    matches!(
+        csv_type,
+        CsvType::Nodes | CsvType::Categories | CsvType::ImageNodes | CsvType::ExternalLinkNodes
+    )
+}
+
+/// JSON key and value kind for one CSV header field.
+fn column(header: &str) -> Column {
+    let (key, kind) = match header {
+        "id:ID" => ("id", ValueKind::ArticleId),
+        ":START_ID" => ("start", ValueKind::ArticleId),
+        ":END_ID" => ("end", ValueKind::ArticleId),
+        ":TYPE" => ("rel", ValueKind::Text),
+        ":LABEL" => ("label", ValueKind::Text),
+        "via_redirect" => ("via_redirect", ValueKind::Bool),
+        "count:int" => ("count", ValueKind::Count),
+        "weight:int" => ("weight", ValueKind::Count),
+        h if h.starts_with("id:ID(") => ("id", ValueKind::Text),
+        h if h.starts_with(":END_ID(") => ("end", ValueKind::Text),
+        h => (h, ValueKind::Text),
+    };
+    Column {
+        key: key.to_string(),
+        kind,
+    }
+}
+
+/// Writes one record as a JSON object followed by a newline.
+fn write_record(
+    out: &mut impl Write,
+    kind: &str,
+    columns: &[Column],
+    record: &StringRecord,
+) -> Result<()> {
+    let mut ser = serde_json::Serializer::new(&mut *out);
+    let mut map = ser.serialize_map(Some(columns.len() + 1))?;
+    map.serialize_entry("type", kind)?;
+    for (column, value) in columns.iter().zip(record.iter()) {
+        match column.kind {
+            ValueKind::ArticleId | ValueKind::Count => match value.parse::<u64>() {
+                Ok(id) => map.serialize_entry(&column.key, &id)?,
+                Err(_) => map.serialize_entry(&column.key, value)?,
+            },
+            ValueKind::Bool => map.serialize_entry(&column.key, &(value == "true"))?,
+            ValueKind::Text => map.serialize_entry(&column.key, value)?,
+        }
+    }
+    map.end()?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Writes every node and edge CSV row in `output_dir` to `dest` as gzipped
+/// JSON Lines. Works on single-file and sharded layouts.
+pub fn export_graph_jsonl(output_dir: &str, dest: &Path) -> Result<GraphExport> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    csv_util::validate_csv_files(output_dir, &layout)?;
+    if dest.is_dir() {
+        bail!("Graph export path {dest:?} is a directory");
+    }
+
+    let file = File::create(dest).with_context(|| format!("Failed to create {dest:?}"))?;
+    let mut out = GzEncoder::new(BufWriter::new(file), Compression::default());
+    let mut counts = GraphExport::default();
+
+    // Nodes first so a streaming consumer sees both endpoints before an edge.
+    let (nodes, edges): (Vec<CsvType>, Vec<CsvType>) =
+        CsvType::ALL.iter().partition(|t| is_node(**t));
+    for csv_type in nodes.into_iter().chain(edges) {
+        let columns: Vec<Column> = csv_util::read_headers(output_dir, &layout, csv_type)?
+            .iter()
+            .map(column)
+            .collect();
+        let (kind, count) = if is_node(csv_type) {
+            ("node", &mut counts.nodes)
+        } else {
+            ("edge", &mut counts.edges)
+        };
+        csv_util::for_each_record(output_dir, &layout, csv_type, |record| {
+            *count += 1;
+            write_record(&mut out, kind, &columns, record)
+        })?;
+    }
+
+    out.finish()
+        .and_then(|mut writer| writer.flush())
+        .with_context(|| format!("Failed to write {dest:?}"))?;
+    info!(
+        nodes = counts.nodes,
+        edges = counts.edges,
+        path = ?dest,
+        "Exported graph as JSON Lines"
+    );
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_use_header_keys_and_typed_ids() {
+        let columns: Vec<Column> = [":START_ID", ":END_ID", ":TYPE", "section", "via_redirect"]
+            .into_iter()
+            .map(column)
+            .collect();
+        let mut out = Vec::new();
+        let record = StringRecord::from(vec!["1", "2", "LINKS_TO", "lead", "true"]);
+        write_record(&mut out, "edge", &columns, &record).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"type\":\"edge\",\"start\":1,\"end\":2,\"rel\":\"LINKS_TO\",\
+             \"section\":\"lead\",\"via_redirect\":true}\n"
+        );
+
+        let columns: Vec<Column> = ["id:ID(Category)", "name", ":LABEL"]
+            .into_iter()
+            .map(column)
+            .collect();
+        let mut out = Vec::new();
+        let record = StringRecord::from(vec!["42", "42", "Category"]);
+        write_record(&mut out, "node", &columns, &record).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"type\":\"node\",\"id\":\"42\",\"name\":\"42\",\"label\":\"Category\"}\n"
+        );
+    }
+}
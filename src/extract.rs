@@ -2,60 +2,178 @@
 //!
 //! Uses `rayon::par_bridge()` (or multistream parallel iteration) to process
 //! articles concurrently. `ShardedCsvWriter` distributes rows across N files
-//! by `page_id % csv_shards`. `DashSet` deduplicates categories, images, and
-//! external links across threads.
+//! by `shard::page_shard()` (`page_id % csv_shards` by default, or a hash of
+//! the page ID with `ShardStrategy::Hash`); blob directories use the same
+//! function. `SeenSet` (exact `DashSet` or a fingerprint table sized from the
+//! index) deduplicates categories, images, and external links across threads.
+//! With `BlobFormat::Jsonl`, blobs are appended to `blobs/blobs_NNN.jsonl`
+//! shards instead of being written one file per article.
+//! With `OutputFormat::Sqlite` the same rows go to a single `graph.db` instead.
+//! With `template_stats`, template uses are tallied in a `TemplateStats` map and
+//! written to `template_stats.csv` once all pages are processed. With
+//! `emit_redirect_map`, the index's resolved redirects go to `redirect_map.csv`.
+//! With `interwiki_links`, interlanguage links go to a single `interwiki.csv`;
+//! with `coordinates`, `{{Coord}}` positions go to `coordinates.csv`.
+//! The dump's `<siteinfo>` header is recorded in `dump_info.json` on every run.
 
-use crate::checkpoint::{Checkpoint, CheckpointManager};
-use crate::config::{CSV_WRITER_BUF_SIZE, PROGRESS_INTERVAL};
+use crate::category_counts::CategoryCounts;
+use crate::checkpoint::{Checkpoint, CheckpointManager, Completed};
+use crate::config::{
+    COORDINATES_BASE_NAME, CSV_WRITER_BUF_SIZE, DUMP_INFO_FILENAME, INTERWIKI_BASE_NAME,
+    MAX_TITLE_BYTES, PROGRESS_INTERVAL, SQLITE_DB_FILENAME,
+};
 use crate::content;
-use crate::content::LINK_REGEX;
-use crate::index::WikiIndex;
+use crate::csv_util::CsvType;
+use crate::dedup::{DedupStrategy, SeenSet, fingerprint_slots};
+use crate::index::{WikiIndex, normalize_title};
 use crate::infobox;
-use crate::models::{ArticleBlob, EdgeType, PageType};
+use crate::manifest::{self, Manifest};
+use crate::metrics::MetricsServer;
+use crate::models::{ArticleBlob, EdgeType, WikiPage};
 use crate::multistream::StreamRange;
-use crate::parser::WikiReader;
+use crate::parser::{self, WikiReader};
+use crate::schema::RelationshipNames;
+use crate::shard::{ShardStrategy, fmix64, page_shard};
+use crate::sqlite_writer::SqliteWriter;
 use crate::stats::ExtractionStats;
-use anyhow::{Context, Result};
-use dashmap::DashSet;
+use crate::template_stats::TemplateStats;
+use anyhow::{Context, Result, bail};
 use indicatif::ProgressBar;
 use rayon::prelude::*;
+use rustc_hash::FxHashSet;
+use signal_hook::SigId;
+use signal_hook::consts::SIGINT;
+use signal_hook::flag;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
 use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, mpsc};
+use std::thread;
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
-fn is_namespace_link(target: &str) -> bool {
-    match target.as_bytes().first() {
-        Some(b'C') => target.starts_with("Category:"),
-        Some(b'F') => target.starts_with("File:"),
-        Some(b'I') => target.starts_with("Image:"),
-        Some(b'T') => target.starts_with("Template:"),
-        Some(b'W') => target.starts_with("Wikipedia:"),
-        Some(b'H') => target.starts_with("Help:"),
-        Some(b'P') => target.starts_with("Portal:"),
-        Some(b'D') => target.starts_with("Draft:"),
-        Some(b'U') => target.starts_with("User:"),
-        Some(b'M') => target.starts_with("Module:") || target.starts_with("MediaWiki:"),
-        _ => false,
-    }
+/// Returns `true` if `target` is `Name:rest` where `Name` is one of the dump's
+/// namespaces. A title without a colon (e.g. "Category theory") is never a
+/// namespace link. Matching ignores surrounding whitespace, `_` vs space, and
+/// case, as MediaWiki does for namespace prefixes.
+fn is_namespace_link(target: &str, namespaces: &[String]) -> bool {
+    let Some((prefix, _)) = target.split_once(':') else {
+        return false;
+    };
+    let prefix = prefix.trim();
+    let fold = |c: u8| {
+        if c == b'_' {
+            b' '
+        } else {
+            c.to_ascii_lowercase()
+        }
+    };
+    namespaces.iter().any(|ns| {
+        ns.len() == prefix.len()
+            && ns
+                .bytes()
+                .zip(prefix.bytes())
+                .all(|(a, b)| fold(a) == fold(b))
+    })
 }
 
 fn strip_section_anchor(target: &str) -> &str {
     target.split('#').next().unwrap_or(target)
 }
 
-type CsvWriter = Arc<Mutex<csv::Writer<Box<dyn Write + Send>>>>;
+/// Whether an unresolved link target is shaped like an article title, so the
+/// miss is a red link (a missing article) rather than a malformed target. A
+/// red link fits in `MAX_TITLE_BYTES` and has none of the characters MediaWiki
+/// forbids in titles (`<>[]{}|`, control characters, `%XX` escapes, `~~~`),
+/// is not a relative path (`/Sub`, `../`), and has no interwiki or language
+/// prefix (`wikt:`, `fr:`).
+fn is_red_link(target: &str) -> bool {
+    let title = target.trim().trim_start_matches(':').trim();
+    if title.is_empty() || title.len() > MAX_TITLE_BYTES {
+        return false;
+    }
+    if title.starts_with('/') || title.starts_with("./") || title.starts_with("../") {
+        return false;
+    }
+    if title
+        .chars()
+        .any(|c| c.is_control() || matches!(c, '<' | '>' | '[' | ']' | '{' | '}' | '|'))
+        || title.contains("~~~")
+    {
+        return false;
+    }
+    let bytes = title.as_bytes();
+    if bytes
+        .windows(3)
+        .any(|w| w[0] == b'%' && w[1].is_ascii_hexdigit() && w[2].is_ascii_hexdigit())
+    {
+        return false;
+    }
+    // Interwiki and language prefixes are short, lowercase and have no spaces.
+    !title.split_once(':').is_some_and(|(prefix, _)| {
+        !prefix.is_empty() && prefix.bytes().all(|b| b.is_ascii_lowercase() || b == b'-')
+    })
+}
+
+type CsvWriter = Arc<Mutex<PartWriter>>;
+
+/// Adds every byte passed through to `inner` to `ExtractionStats::output_bytes`.
+struct CountingWriter<W> {
+    inner: W,
+    stats: Arc<ExtractionStats>,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.stats.add_output_bytes(n as u64);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// An output stream that can be pushed through to disk before a checkpoint.
+trait OutputFile: Write + Send {
+    /// Asks the OS to persist what has been flushed so far (`fsync`); call
+    /// `flush()` first.
+    fn sync_data(&self) -> std::io::Result<()>;
+}
+
+impl OutputFile for CountingWriter<BufWriter<File>> {
+    fn sync_data(&self) -> std::io::Result<()> {
+        self.inner.get_ref().sync_data()
+    }
+}
+
+impl OutputFile for std::io::Sink {
+    fn sync_data(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn counted(file: File, stats: &Arc<ExtractionStats>) -> Box<dyn OutputFile> {
+    Box::new(CountingWriter {
+        inner: BufWriter::with_capacity(CSV_WRITER_BUF_SIZE, file),
+        stats: Arc::clone(stats),
+    })
+}
 
 fn create_csv_writer(
     output_dir: &str,
     filename: &str,
     dry_run: bool,
     resuming: bool,
-) -> Result<CsvWriter> {
-    Ok(Arc::new(Mutex::new(if dry_run {
-        csv::Writer::from_writer(Box::new(std::io::sink()) as Box<dyn Write + Send>)
+    stats: &Arc<ExtractionStats>,
+) -> Result<csv::Writer<Box<dyn OutputFile>>> {
+    let counted = |file: File| counted(file, stats);
+    Ok(if dry_run {
+        csv::Writer::from_writer(Box::new(std::io::sink()) as Box<dyn OutputFile>)
     } else if resuming {
         let path = format!("{}/{}", output_dir, filename);
         let file = if Path::new(&path).exists() {
@@ -69,135 +187,498 @@ fn create_csv_writer(
         };
         csv::WriterBuilder::new()
             .has_headers(false)
-            .from_writer(
-                Box::new(BufWriter::with_capacity(CSV_WRITER_BUF_SIZE, file))
-                    as Box<dyn Write + Send>,
-            )
+            .from_writer(counted(file))
     } else {
         let file = File::create(format!("{}/{}", output_dir, filename))
             .with_context(|| format!("Failed to create {}", filename))?;
-        csv::Writer::from_writer(
-            Box::new(BufWriter::with_capacity(CSV_WRITER_BUF_SIZE, file)) as Box<dyn Write + Send>,
-        )
-    })))
+        csv::Writer::from_writer(counted(file))
+    })
+}
+
+/// Approximate encoded size of a CSV row (fields, separators, newline; quoting ignored).
+fn record_len<S: AsRef<str>>(row: &[S]) -> u64 {
+    row.iter().map(|v| v.as_ref().len() as u64 + 1).sum()
+}
+
+/// One CSV output file. With `--max-shard-bytes`, continues in
+/// `{base}_part001.csv`, `{base}_part002.csv`, ... once the current part is full.
+struct PartWriter {
+    writer: csv::Writer<Box<dyn OutputFile>>,
+    rollover: Option<Rollover>,
+}
+
+/// Rollover state for a size-capped single-file CSV.
+struct Rollover {
+    output_dir: String,
+    base_name: String,
+    max_bytes: u64,
+    part: u32,
+    part_bytes: u64,
+    header: Vec<String>,
+    stats: Arc<ExtractionStats>,
+}
+
+impl PartWriter {
+    /// Records the header for later parts, writing it now unless resuming.
+    fn set_header(&mut self, fields: &[&str], write: bool) -> csv::Result<()> {
+        if let Some(r) = &mut self.rollover {
+            r.header = fields.iter().map(|f| f.to_string()).collect();
+            if write {
+                r.part_bytes += record_len(fields);
+            }
+        }
+        if write {
+            self.writer.write_record(fields)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a row, first starting a new part if it would overflow a part
+    /// that already holds data.
+    fn write_record(&mut self, row: &[&str]) -> csv::Result<()> {
+        if let Some(r) = &mut self.rollover {
+            let len = record_len(row);
+            if r.part_bytes + len > r.max_bytes && r.part_bytes > record_len(&r.header) {
+                self.writer.flush()?;
+                r.part += 1;
+                let filename = crate::csv_util::part_filename(&r.base_name, r.part);
+                debug!(file = %filename, "CSV part full, rolling over");
+                let file = File::create(Path::new(&r.output_dir).join(&filename))?;
+                self.writer = csv::Writer::from_writer(counted(file, &r.stats));
+                self.writer.write_record(&r.header)?;
+                r.part_bytes = record_len(&r.header);
+            }
+            r.part_bytes += len;
+        }
+        self.writer.write_record(row)
+    }
+
+    /// Writes out the csv and file buffers of the current part and fsyncs it.
+    fn sync(&mut self) -> std::io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()
+    }
 }
 
-fn write_header(writer: &CsvWriter, fields: &[&str]) -> Result<()> {
-    writer
-        .lock()
-        .map_err(|e| anyhow::anyhow!("CSV writer lock poisoned (a writer thread panicked): {}", e))?
-        .write_record(fields)
-        .context("Failed to write CSV header")
+/// Deletes the `{base}_partNNN.csv` rollover parts an earlier run left in
+/// `output_dir`, so readers of a new single-file CSV do not append them.
+fn remove_stale_parts(output_dir: &str, base_name: &str) -> Result<()> {
+    for part in crate::csv_util::single_file_parts(output_dir, base_name)
+        .iter()
+        .skip(1)
+    {
+        let path = Path::new(output_dir).join(part);
+        fs::remove_file(&path).with_context(|| format!("Failed to remove stale {path:?}"))?;
+        debug!(file = %part, "Removed CSV part from an earlier run");
+    }
+    Ok(())
+}
+
+/// CSV writer settings shared by every table.
+#[derive(Clone, Copy)]
+struct CsvOptions {
+    shards: u32,
+    strategy: ShardStrategy,
+    /// Start a new part once a single-file CSV reaches this many bytes.
+    max_part_bytes: Option<u64>,
+    /// Route every row to a sink (dry run or `--blobs-only`).
+    skip: bool,
+    resuming: bool,
 }
 
 /// A set of CSV writers that shard rows by page ID.
 ///
-/// When `csv_shards == 1`, produces a single file (e.g. `edges.csv`).
+/// When `csv_shards == 1`, produces a single file (e.g. `edges.csv`), split into
+/// `edges_part001.csv`, ... when `max_part_bytes` is set.
 /// When `csv_shards > 1`, produces N files (e.g. `edges_000.csv`, `edges_001.csv`, ...).
 struct ShardedCsvWriter {
     writers: Vec<CsvWriter>,
+    strategy: ShardStrategy,
+    resuming: bool,
 }
 
 impl ShardedCsvWriter {
     fn new(
         output_dir: &str,
         base_name: &str,
-        csv_shards: u32,
-        dry_run: bool,
-        resuming: bool,
+        opts: CsvOptions,
+        stats: &Arc<ExtractionStats>,
     ) -> Result<Self> {
-        let mut writers = Vec::with_capacity(csv_shards as usize);
-        for shard in 0..csv_shards {
-            let filename = if csv_shards == 1 {
-                format!("{}.csv", base_name)
-            } else {
-                format!("{}_{:03}.csv", base_name, shard)
+        if opts.shards == 1 && !opts.skip && !opts.resuming {
+            remove_stale_parts(output_dir, base_name)?;
+        }
+        let mut writers = Vec::with_capacity(opts.shards as usize);
+        for shard in 0..opts.shards {
+            let writer = match opts.max_part_bytes {
+                Some(max_bytes) if opts.shards == 1 && !opts.skip => {
+                    // Resume appends to the last part already on disk.
+                    let part = if opts.resuming {
+                        crate::csv_util::single_file_parts(output_dir, base_name).len() as u32 - 1
+                    } else {
+                        0
+                    };
+                    let filename = crate::csv_util::part_filename(base_name, part);
+                    let part_bytes = if opts.resuming {
+                        fs::metadata(Path::new(output_dir).join(&filename))
+                            .map(|m| m.len())
+                            .unwrap_or(0)
+                    } else {
+                        0
+                    };
+                    PartWriter {
+                        writer: create_csv_writer(
+                            output_dir,
+                            &filename,
+                            false,
+                            opts.resuming,
+                            stats,
+                        )?,
+                        rollover: Some(Rollover {
+                            output_dir: output_dir.to_string(),
+                            base_name: base_name.to_string(),
+                            max_bytes,
+                            part,
+                            part_bytes,
+                            header: Vec::new(),
+                            stats: Arc::clone(stats),
+                        }),
+                    }
+                }
+                _ => {
+                    let filename = if opts.shards == 1 {
+                        format!("{}.csv", base_name)
+                    } else {
+                        format!("{}_{:03}.csv", base_name, shard)
+                    };
+                    PartWriter {
+                        writer: create_csv_writer(
+                            output_dir,
+                            &filename,
+                            opts.skip,
+                            opts.resuming,
+                            stats,
+                        )?,
+                        rollover: None,
+                    }
+                }
             };
-            writers.push(create_csv_writer(output_dir, &filename, dry_run, resuming)?);
+            writers.push(Arc::new(Mutex::new(writer)));
         }
-        Ok(Self { writers })
+        Ok(Self {
+            writers,
+            strategy: opts.strategy,
+            resuming: opts.resuming,
+        })
+    }
+
+    fn shard_for(&self, page_id: u64) -> &CsvWriter {
+        let idx = page_shard(page_id, self.writers.len() as u32, self.strategy);
+        &self.writers[idx as usize]
     }
 
+    /// Routes a row by a hash of its value rather than by the page that saw it,
+    /// so shared nodes land in the same shard regardless of rayon scheduling.
+    fn shard_for_key(&self, key: &str) -> &CsvWriter {
+        &self.writers[key_shard(key, self.writers.len())]
+    }
+}
+
+/// Destination for one table's rows during extraction. `ShardedCsvWriter`
+/// writes CSV shards; `SqliteTable` feeds one table of the SQLite output.
+trait ExtractionSink: Sync {
+    /// Writes the header row, if the format has one.
+    fn write_headers(&self, fields: &[&str]) -> Result<()>;
+
+    /// Makes every row written so far durable; checkpoints call it first.
+    fn sync(&self) -> Result<()>;
+
+    /// Writes a page's rows, holding the shard lock once for the whole batch.
+    fn write_page(&self, page_id: u64, what: &str, rows: &mut dyn FnMut(&mut WriteRow<'_>));
+
+    /// Writes a single row routed by a string key (see `ShardedCsvWriter::shard_for_key`).
+    fn write_keyed(&self, key: &str, row: &[&str], what: &str);
+}
+
+type Sink<'a> = Box<dyn ExtractionSink + 'a>;
+
+/// Callback handed to a page's row writer; called once per row.
+type WriteRow<'a> = dyn FnMut(&[&str]) + 'a;
+
+impl dyn ExtractionSink + '_ {
+    /// [`write_page`](ExtractionSink::write_page) for a one-shot closure.
+    fn write_rows(&self, page_id: u64, what: &str, rows: impl FnOnce(&mut dyn FnMut(&[&str]))) {
+        let mut rows = Some(rows);
+        self.write_page(page_id, what, &mut |write| {
+            if let Some(rows) = rows.take() {
+                rows(write);
+            }
+        });
+    }
+}
+
+impl ExtractionSink for ShardedCsvWriter {
+    /// Writes the header row to each file (skipped on resume, where the files
+    /// already have one) and keeps it for any rollover parts.
     fn write_headers(&self, fields: &[&str]) -> Result<()> {
         for writer in &self.writers {
-            write_header(writer, fields)?;
+            writer
+                .lock()
+                .map_err(|e| {
+                    anyhow::anyhow!("CSV writer lock poisoned (a writer thread panicked): {}", e)
+                })?
+                .set_header(fields, !self.resuming)
+                .context("Failed to write CSV header")?;
         }
         Ok(())
     }
 
-    fn shard_for(&self, page_id: u32) -> &CsvWriter {
-        let idx = (page_id as usize) % self.writers.len();
-        &self.writers[idx]
+    /// Syncs every shard (see [`PartWriter::sync`]), one lock at a time.
+    fn sync(&self) -> Result<()> {
+        for writer in &self.writers {
+            writer
+                .lock()
+                .map_err(|e| {
+                    anyhow::anyhow!("CSV writer lock poisoned (a writer thread panicked): {}", e)
+                })?
+                .sync()
+                .context("Failed to flush CSV output")?;
+        }
+        Ok(())
+    }
+
+    fn write_page(&self, page_id: u64, what: &str, rows: &mut dyn FnMut(&mut WriteRow<'_>)) {
+        if let Ok(mut writer) = self.shard_for(page_id).lock() {
+            rows(&mut |row| {
+                if let Err(e) = writer.write_record(row) {
+                    warn!(error = %e, "Failed to write {} record", what);
+                }
+            });
+        }
+    }
+
+    fn write_keyed(&self, key: &str, row: &[&str], what: &str) {
+        if let Ok(mut writer) = self.shard_for_key(key).lock()
+            && let Err(e) = writer.write_record(row)
+        {
+            warn!(error = %e, "Failed to write {} record", what);
+        }
     }
 }
 
-/// Extracts edges from article text, classifying as LinksTo or SeeAlso.
-/// Returns (deduplicated edges, invalid link count).
-fn process_article_edges(
-    text: &str,
+/// One table of the SQLite output. Rows go to the shared writer thread.
+struct SqliteTable<'a> {
+    db: &'a SqliteWriter,
+    table: CsvType,
+    stats: &'a ExtractionStats,
+}
+
+impl SqliteTable<'_> {
+    fn insert(&self, row: &[&str], what: &str) {
+        self.stats.add_output_bytes(row_bytes(row));
+        if let Err(e) = self.db.insert(self.table, row) {
+            warn!(error = %e, "Failed to write {} record", what);
+        }
+    }
+}
+
+impl ExtractionSink for SqliteTable<'_> {
+    /// SQLite tables carry their own schema.
+    fn write_headers(&self, _fields: &[&str]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Waits until the writer thread has committed every row queued so far.
+    fn sync(&self) -> Result<()> {
+        self.db.sync()
+    }
+
+    fn write_page(&self, _page_id: u64, what: &str, rows: &mut dyn FnMut(&mut WriteRow<'_>)) {
+        rows(&mut |row| self.insert(row, what));
+    }
+
+    fn write_keyed(&self, _key: &str, row: &[&str], what: &str) {
+        self.insert(row, what);
+    }
+}
+
+/// A sink for `csv_type`: a SQLite table when `sqlite` is set, else CSV shards.
+fn table_sink<'a>(
+    csv_type: CsvType,
+    output_dir: &str,
+    opts: CsvOptions,
+    sqlite: Option<&'a SqliteWriter>,
+    stats: &'a Arc<ExtractionStats>,
+) -> Result<Sink<'a>> {
+    Ok(match sqlite {
+        Some(db) => Box::new(SqliteTable {
+            db,
+            table: csv_type,
+            stats,
+        }),
+        None => Box::new(ShardedCsvWriter::new(
+            output_dir,
+            csv_type.base_name(),
+            opts,
+            stats,
+        )?),
+    })
+}
+
+/// Payload size of a SQLite row, used as its share of the output size budget.
+fn row_bytes(row: &[&str]) -> u64 {
+    row.iter().map(|v| v.len() as u64).sum()
+}
+
+/// Whether `--blob-sample-rate` keeps this page's blob: its hashed ID, scaled
+/// to `[0, 1)`, is below `rate`. Deterministic, so reruns pick the same pages.
+#[must_use]
+pub fn blob_sampled(page_id: u64, rate: f64) -> bool {
+    // Salted so the sample is independent of the blob shard choice.
+    let h = fmix64(page_id ^ 0x9e37_79b9_7f4a_7c15);
+    ((h >> 11) as f64 / (1u64 << 53) as f64) < rate
+}
+
+/// Stable shard index for a string key (64-bit FNV-1a).
+///
+/// Must not depend on process-random state (unlike `std`'s `RandomState`) so the
+/// same value maps to the same shard on every run and platform.
+fn key_shard(key: &str, shards: usize) -> usize {
+    (crate::dedup::fnv1a64(key) % shards as u64) as usize
+}
+
+/// An outbound article link: target ID, edge type, (with section context) the
+/// title of the section it appeared in, whether the link went through a
+/// redirect, and how many links in the article it stands for.
+type LocalEdge<'t> = (u64, EdgeType, Option<&'t str>, bool, u32);
+
+/// `--exclude-category` names as matched against extracted categories: a
+/// `Category:` prefix, or one named after any of the dump's `namespaces` (e.g.
+/// a localized `Kategorie:`), is dropped and the rest normalized like a title
+/// (`living_people` matches `[[Category:Living people]]`).
+fn excluded_category_set(names: &[String], namespaces: &[String]) -> FxHashSet<String> {
+    names
+        .iter()
+        .map(|name| {
+            let name = name.trim();
+            let name = match name.split_once(':') {
+                Some((prefix, rest))
+                    if prefix.trim().eq_ignore_ascii_case("category")
+                        || namespaces.iter().any(|ns| ns == prefix.trim()) =>
+                {
+                    rest
+                }
+                _ => name,
+            };
+            normalize_title(name).into_owned()
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Whether any category `text` declares is in `excluded`.
+fn has_excluded_category(text: &str, excluded: &FxHashSet<String>) -> bool {
+    content::extract_categories(&content::mask_non_content(text))
+        .iter()
+        .any(|category| excluded.contains(normalize_title(category).as_ref()))
+}
+
+/// Extracts edges from an article's `[[...]]` links (offset and raw target, from
+/// `content::scan_brackets`), classifying as LinksTo or SeeAlso.
+/// With `sections` (from `content::section_starts`), each edge also records its
+/// enclosing section and edges are deduplicated per section rather than per target.
+/// A target linked both directly and through a redirect keeps the direct edge.
+/// Each kept edge counts the links folded into it (its `weight:int`).
+/// Returns (deduplicated edges, invalid link count, red link count); red links
+/// are the invalid links that pass `is_red_link`.
+fn process_article_edges<'t>(
+    links: &[(usize, &str)],
     index: &WikiIndex,
+    namespaces: &[String],
     see_also_start: Option<usize>,
-) -> (Vec<(u32, EdgeType)>, u64) {
-    let mut local_edges: Vec<(u32, EdgeType)> = Vec::with_capacity(16);
+    sections: Option<&[(usize, &'t str)]>,
+    self_id: Option<u64>,
+) -> (Vec<LocalEdge<'t>>, u64, u64, u64) {
+    let mut local_edges: Vec<LocalEdge<'t>> = Vec::with_capacity(16);
     let mut invalid_count = 0u64;
+    let mut red_count = 0u64;
+    let mut self_count = 0u64;
+    // Namespace links can only resolve when non-main namespaces are indexed.
+    let main_only = index.content_namespaces() == [0];
 
-    for caps in LINK_REGEX.captures_iter(text) {
-        let target_title = strip_section_anchor(&caps[1]);
-        if target_title.is_empty() || is_namespace_link(target_title) {
+    for &(start, target) in links {
+        let target_title = strip_section_anchor(target);
+        if target_title.is_empty() {
+            continue;
+        }
+        let namespaced = is_namespace_link(target_title, namespaces);
+        if namespaced && main_only {
             continue;
         }
-        if let Some(target_id) = index.resolve_id(target_title) {
+        if let Some((target_id, via_redirect)) =
+            index.resolve_id_normalized_via_redirect(target_title)
+        {
+            // `self_id` is only set when self-links are skipped.
+            if Some(target_id) == self_id {
+                self_count += 1;
+                continue;
+            }
             let edge_type = match see_also_start {
-                Some(sa_start) if caps.get(0).unwrap().start() >= sa_start => EdgeType::SeeAlso,
+                Some(sa_start) if start >= sa_start => EdgeType::SeeAlso,
                 _ => EdgeType::LinksTo,
             };
-            local_edges.push((target_id, edge_type));
-        } else {
+            let section = sections.map(|s| content::section_at(s, start));
+            local_edges.push((target_id, edge_type, section, via_redirect, 1));
+        } else if !namespaced {
             invalid_count += 1;
+            if is_red_link(target_title) {
+                red_count += 1;
+            }
         }
     }
 
+    // `false` sorts first, so dedup keeps the direct link.
     local_edges.sort_unstable();
-    local_edges.dedup();
-    (local_edges, invalid_count)
+    local_edges.dedup_by(|later, kept| {
+        let same = (later.0, later.1, later.2) == (kept.0, kept.1, kept.2);
+        if same {
+            kept.4 += later.4;
+        }
+        same
+    });
+    (local_edges, invalid_count, red_count, self_count)
 }
 
 /// Deduplicates entity items against a global set, writes new nodes and all relationships.
+/// New node rows are routed by value hash; relationship rows by page ID.
 /// Returns (new unique count, total relationship count).
 fn write_dedup_entities(
     items: &[std::borrow::Cow<'_, str>],
-    dedup_set: &DashSet<String>,
-    node_writer: &ShardedCsvWriter,
-    rel_writer: &ShardedCsvWriter,
-    page_id: u32,
+    dedup_set: &SeenSet,
+    node_writer: &dyn ExtractionSink,
+    rel_writer: &dyn ExtractionSink,
+    page_id: u64,
     id_str: &str,
     label: &str,
     rel_type: &str,
 ) -> (u64, u64) {
     let mut new_items: Vec<&str> = Vec::new();
     for item in items {
-        if !dedup_set.contains(item.as_ref()) && dedup_set.insert(item.as_ref().to_owned()) {
+        if dedup_set.insert_new(item.as_ref()) {
             new_items.push(item.as_ref());
         }
     }
 
-    if !new_items.is_empty()
-        && let Ok(mut writer) = node_writer.shard_for(page_id).lock()
-    {
-        for name in &new_items {
-            if let Err(e) = writer.write_record([*name, *name, label]) {
-                warn!(error = %e, "Failed to write {} node record", label);
-            }
-        }
+    // Node rows shard by value so each shared node's shard is deterministic;
+    // relationship rows below still shard by page ID.
+    for name in &new_items {
+        node_writer.write_keyed(name, &[*name, *name, label], label);
     }
 
-    if let Ok(mut writer) = rel_writer.shard_for(page_id).lock() {
+    rel_writer.write_rows(page_id, rel_type, |write| {
         for item in items {
-            if let Err(e) = writer.write_record([id_str, item.as_ref(), rel_type]) {
-                warn!(error = %e, "Failed to write {} relationship record", rel_type);
-            }
+            write(&[id_str, item.as_ref(), rel_type]);
         }
-    }
+    });
 
     (new_items.len() as u64, items.len() as u64)
 }
@@ -206,15 +687,19 @@ fn write_dedup_entities(
 fn write_article_blob(
     output_dir: &str,
     shard_count: u32,
-    page_id: u32,
+    shard_strategy: ShardStrategy,
+    page_id: u64,
     blob: &ArticleBlob,
-    stats: &ExtractionStats,
+    stats: &Arc<ExtractionStats>,
 ) {
-    let shard = page_id % shard_count;
+    let shard = page_shard(page_id, shard_count, shard_strategy);
     let blob_path = format!("{}/blobs/{:03}/{}.json", output_dir, shard, page_id);
     match File::create(&blob_path) {
         Ok(f) => {
-            let mut w = BufWriter::new(f);
+            let mut w = CountingWriter {
+                inner: BufWriter::new(f),
+                stats: Arc::clone(stats),
+            };
             if let Err(e) = serde_json::to_writer(&mut w, blob) {
                 warn!(error = %e, path = %blob_path, "Failed to write blob");
             } else {
@@ -228,19 +713,485 @@ fn write_article_blob(
     }
 }
 
+/// JSON Lines blob shards for `BlobFormat::Jsonl`: `blobs/blobs_NNN.jsonl`,
+/// routed by `page_shard` like the per-file blob directories. Each shard is a
+/// buffered, locked writer, as for CSV shards; a blob is serialized before the
+/// lock is taken and written as one line.
+struct JsonlBlobWriter {
+    shards: Vec<Mutex<Box<dyn OutputFile>>>,
+    strategy: ShardStrategy,
+}
+
+impl JsonlBlobWriter {
+    fn new(
+        output_dir: &str,
+        shard_count: u32,
+        strategy: ShardStrategy,
+        resuming: bool,
+        stats: &Arc<ExtractionStats>,
+    ) -> Result<Self> {
+        let mut shards = Vec::with_capacity(shard_count as usize);
+        for shard in 0..shard_count {
+            let path = jsonl_blob_path(output_dir, shard);
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(resuming)
+                .truncate(!resuming)
+                .open(&path)
+                .with_context(|| format!("Failed to create blob shard {path:?}"))?;
+            shards.push(Mutex::new(counted(file, stats)));
+        }
+        Ok(Self { shards, strategy })
+    }
+
+    fn write(&self, page_id: u64, blob: &ArticleBlob, stats: &ExtractionStats) {
+        let mut line = match serde_json::to_vec(blob) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, id = page_id, "Failed to serialize blob");
+                return;
+            }
+        };
+        line.push(b'\n');
+        let shard = page_shard(page_id, self.shards.len() as u32, self.strategy);
+        if let Ok(mut writer) = self.shards[shard as usize].lock() {
+            match writer.write_all(&line) {
+                Ok(()) => stats.inc_blobs(),
+                Err(e) => warn!(error = %e, id = page_id, "Failed to write blob"),
+            }
+        }
+    }
+
+    fn sync(&self) -> Result<()> {
+        for shard in &self.shards {
+            let mut shard = shard
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Blob writer lock poisoned: {}", e))?;
+            shard
+                .flush()
+                .and_then(|()| shard.sync_data())
+                .context("Failed to flush blob output")?;
+        }
+        Ok(())
+    }
+}
+
+/// Path of one JSON Lines blob shard (`BlobFormat::Jsonl`).
+#[must_use]
+pub fn jsonl_blob_path(output_dir: &str, shard: u32) -> std::path::PathBuf {
+    Path::new(output_dir)
+        .join("blobs")
+        .join(format!("blobs_{shard:03}.jsonl"))
+}
+
+/// Per-article enrichment hook, called with the parsed page and its built-in
+/// blob just before the blob is serialized.
+///
+/// Runs concurrently on rayon worker threads, so it must be `Sync`; any shared
+/// state it captures needs its own synchronization (atomics, `Mutex`, etc.).
+/// Pages are processed in no particular order. It is not called for dry runs,
+/// `no_blobs` runs, or pages without text.
+pub type EnrichFn<'a> = dyn Fn(&WikiPage, &mut ArticleBlob) + Sync + 'a;
+
+/// Output format for the graph rows (nodes, edges, categories, ...).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Sharded Neo4j-style CSV files.
+    #[default]
+    Csv,
+    /// A single SQLite database (`graph.db`) with one table per CSV type.
+    Sqlite,
+}
+
+/// How article blobs are stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BlobFormat {
+    /// One JSON file per article: `blobs/NNN/{id}.json`.
+    #[default]
+    Files,
+    /// One JSON object per line in `blobs/blobs_NNN.jsonl` (see `jsonl_blob_path`).
+    Jsonl,
+}
+
+impl std::fmt::Display for BlobFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlobFormat::Files => f.write_str("files"),
+            BlobFormat::Jsonl => f.write_str("jsonl"),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Csv => f.write_str("csv"),
+            OutputFormat::Sqlite => f.write_str("sqlite"),
+        }
+    }
+}
+
 /// Configuration for the Wikipedia extraction pass.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ExtractionConfig<'a> {
     pub input_path: &'a str,
     pub output_dir: &'a str,
     pub index: &'a WikiIndex,
     pub shard_count: u32,
     pub csv_shards: u32,
+    /// How page IDs map to CSV shards and blob directories.
+    pub shard_strategy: ShardStrategy,
     pub limit: Option<u64>,
+    /// Articles the progress bar counts toward (usually the index's article
+    /// count); `None` shows a spinner with no percentage or ETA.
+    pub expected_articles: Option<u64>,
+    /// Stop dispatching pages once this many bytes of CSV, blob, and SQLite
+    /// output have been written (cumulative across resumes).
+    pub max_output_bytes: Option<u64>,
+    /// Stop dispatching pages once this much wall-clock time has passed since
+    /// extraction started. A timer thread sets the cancel flag, so the run ends
+    /// like a cancellation but flushes a checkpoint for `--resume`.
+    pub max_runtime: Option<Duration>,
+    /// Handle SIGINT (Ctrl-C) while extracting: the first one stops the run like
+    /// `max_runtime` and flushes a checkpoint; a second one exits at once with
+    /// status 130. Off for library callers that own their signal handling.
+    pub handle_interrupts: bool,
+    /// Serve the live `ExtractionStats` as Prometheus metrics on this address
+    /// (`GET /metrics`) while extraction runs.
+    pub metrics_addr: Option<SocketAddr>,
+    /// With `csv_shards == 1`, split each CSV into `{base}_partNNN.csv` parts of
+    /// at most roughly this many bytes.
+    pub max_shard_bytes: Option<u64>,
+    /// Return an error if more than this fraction of wiki links fail to resolve.
+    pub max_invalid_ratio: Option<f64>,
+    /// Write blobs for only this fraction of articles (chosen by `blob_sampled`);
+    /// CSV output is unaffected.
+    pub blob_sample_rate: Option<f64>,
     pub dry_run: bool,
+    /// Process pages sequentially on the calling thread. Slower, but the set
+    /// of articles kept under `limit` is always the first N in dump order.
+    pub single_thread: bool,
+    /// Write CSVs only; skip JSON blob creation and blob directory setup.
+    pub no_blobs: bool,
+    /// Write JSON blobs only; skip all CSV writers and their headers.
+    pub blobs_only: bool,
+    /// Include each article's resolved outbound edges in its blob (`ArticleBlob::links`).
+    pub blob_links: bool,
+    /// Per-article JSON files, or JSON Lines shards (same `page_shard` distribution).
+    pub blob_format: BlobFormat,
+    /// Add a `section` column to edge rows: the heading a link appeared under, or `lead`.
+    pub edge_section_context: bool,
+    /// Add a `via_redirect` column to edge rows: `true` if the link target was a redirect.
+    pub edge_redirect_flag: bool,
+    /// Add a `weight:int` column to edge rows: how many times the article links
+    /// the target (within the section, with `edge_section_context`).
+    pub weighted_edges: bool,
+    /// Write edges from an article to itself instead of skipping them (and
+    /// counting them in `ExtractionStats::self_links`).
+    pub keep_self_links: bool,
+    /// Write external link URLs in `content::normalize_url` form, so URLs that
+    /// differ only by scheme, `www.`, fragment or tracking parameters share a node.
+    pub normalize_urls: bool,
+    /// Fail on the first page with more than one `<revision>` (a full-history
+    /// dump) instead of extracting each page's latest revision.
+    pub single_revision: bool,
+    /// Count template uses across all articles into `template_stats.csv`.
+    pub template_stats: bool,
+    /// Bucket article wikitext lengths into `ExtractionStats::article_lengths`
+    /// and write `length_histogram.csv`.
+    pub length_histogram: bool,
+    /// Add a `count:int` column to `categories.csv` with each category's article
+    /// count. Category rows are written after the pass, once counts are final.
+    pub category_counts: bool,
+    /// Templates kept in blob abstracts as their last unnamed parameter
+    /// (`content::extract_abstract_keeping`); empty strips every template.
+    pub lead_templates: Vec<String>,
+    /// Write every resolved redirect to `redirect_map.csv` (`WikiIndex::write_redirect_map`).
+    pub emit_redirect_map: bool,
+    /// Write interlanguage links (`content::extract_interlanguage_links`) to
+    /// `interwiki.csv` as `:START_ID,lang,title`.
+    pub interwiki_links: bool,
+    /// Write each article's `{{Coord}}` position (`content::extract_coordinates`)
+    /// to `coordinates.csv` as `:START_ID,latitude:double,longitude:double`.
+    pub coordinates: bool,
+    /// `:TYPE` values written for edges and article-entity relationships.
+    pub relationship_names: RelationshipNames,
+    /// `:LABEL` of article node rows (`schema::DEFAULT_PAGE_LABEL` unless
+    /// `--page-label` overrides it).
+    pub page_label: String,
+    /// Namespace names filtered as namespace links in addition to
+    /// `WikiIndex::namespaces()`, e.g. localized prefixes for a dump without
+    /// `<siteinfo>`.
+    pub link_namespaces: Vec<String>,
+    /// Categories (with or without the `Category:` or localized namespace
+    /// prefix) whose articles are skipped entirely: no node, edges, entity rows
+    /// or blob. Links to them from other articles are still written, since an
+    /// article is only known to be excluded once its own page is read.
+    pub exclude_categories: Vec<String>,
+    pub dedup_strategy: DedupStrategy,
+    /// Where graph rows go: sharded CSVs or a single SQLite database.
+    pub output_format: OutputFormat,
     pub resume_from: Option<&'a Checkpoint>,
     pub checkpoint_mgr: Option<&'a CheckpointManager>,
     pub multistream_ranges: Option<&'a [StreamRange]>,
+    /// Optional hook to add custom fields (`ArticleBlob::extra`) to each blob.
+    pub enrich: Option<&'a EnrichFn<'a>>,
+}
+
+impl std::fmt::Debug for ExtractionConfig<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtractionConfig")
+            .field("input_path", &self.input_path)
+            .field("output_dir", &self.output_dir)
+            .field("index", &self.index)
+            .field("shard_count", &self.shard_count)
+            .field("csv_shards", &self.csv_shards)
+            .field("shard_strategy", &self.shard_strategy)
+            .field("limit", &self.limit)
+            .field("expected_articles", &self.expected_articles)
+            .field("max_output_bytes", &self.max_output_bytes)
+            .field("max_runtime", &self.max_runtime)
+            .field("metrics_addr", &self.metrics_addr)
+            .field("max_shard_bytes", &self.max_shard_bytes)
+            .field("max_invalid_ratio", &self.max_invalid_ratio)
+            .field("blob_sample_rate", &self.blob_sample_rate)
+            .field("dry_run", &self.dry_run)
+            .field("single_thread", &self.single_thread)
+            .field("no_blobs", &self.no_blobs)
+            .field("blobs_only", &self.blobs_only)
+            .field("blob_links", &self.blob_links)
+            .field("blob_format", &self.blob_format)
+            .field("edge_section_context", &self.edge_section_context)
+            .field("edge_redirect_flag", &self.edge_redirect_flag)
+            .field("weighted_edges", &self.weighted_edges)
+            .field("keep_self_links", &self.keep_self_links)
+            .field("normalize_urls", &self.normalize_urls)
+            .field("single_revision", &self.single_revision)
+            .field("template_stats", &self.template_stats)
+            .field("length_histogram", &self.length_histogram)
+            .field("category_counts", &self.category_counts)
+            .field("lead_templates", &self.lead_templates)
+            .field("emit_redirect_map", &self.emit_redirect_map)
+            .field("interwiki_links", &self.interwiki_links)
+            .field("coordinates", &self.coordinates)
+            .field("relationship_names", &self.relationship_names)
+            .field("page_label", &self.page_label)
+            .field("link_namespaces", &self.link_namespaces)
+            .field("exclude_categories", &self.exclude_categories)
+            .field("dedup_strategy", &self.dedup_strategy)
+            .field("output_format", &self.output_format)
+            .field("resume_from", &self.resume_from)
+            .field("checkpoint_mgr", &self.checkpoint_mgr)
+            .field("multistream_ranges", &self.multistream_ranges)
+            .field("enrich", &self.enrich.is_some())
+            .finish()
+    }
+}
+
+/// Timer thread for `ExtractionConfig::max_runtime`. When the budget elapses it
+/// marks the stats and sets the cancel flag; dropping it stops the timer.
+struct RuntimeBudget {
+    stop: Option<mpsc::Sender<()>>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl RuntimeBudget {
+    fn start(budget: Duration, stats: Arc<ExtractionStats>, cancel: Arc<AtomicBool>) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let handle = thread::spawn(move || {
+            // A dropped sender means extraction finished within the budget.
+            if stopped.recv_timeout(budget) == Err(mpsc::RecvTimeoutError::Timeout) {
+                stats.set_time_budget_exhausted();
+                cancel.store(true, Ordering::Relaxed);
+            }
+        });
+        Self {
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for RuntimeBudget {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Exit status for a second Ctrl-C during extraction (128 + SIGINT).
+const INTERRUPT_EXIT_STATUS: i32 = 130;
+
+/// Set while no [`InterruptHandler`] is installed, so SIGINT keeps its default
+/// action: signal-hook cannot remove its process-wide handler once installed.
+static SIGINT_DEFAULT: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+/// SIGINT handling for `ExtractionConfig::handle_interrupts`. The first Ctrl-C
+/// sets the cancel flag; a second one exits with `INTERRUPT_EXIT_STATUS`.
+/// Dropping it restores the default action.
+struct InterruptHandler {
+    interrupted: Arc<AtomicBool>,
+    ids: Vec<SigId>,
+}
+
+impl InterruptHandler {
+    fn install(cancel: &Arc<AtomicBool>) -> Result<Self> {
+        let default = SIGINT_DEFAULT.get_or_init(|| {
+            let default = Arc::new(AtomicBool::new(true));
+            if let Err(e) = flag::register_conditional_default(SIGINT, Arc::clone(&default)) {
+                warn!(error = %e, "Failed to keep the default SIGINT action");
+            }
+            default
+        });
+        let interrupted = Arc::new(AtomicBool::new(false));
+        // Actions run in registration order, so the exit check sees the flag
+        // as it was before this signal.
+        let ids = vec![
+            flag::register_conditional_shutdown(
+                SIGINT,
+                INTERRUPT_EXIT_STATUS,
+                Arc::clone(&interrupted),
+            )?,
+            flag::register(SIGINT, Arc::clone(&interrupted))?,
+            flag::register(SIGINT, Arc::clone(cancel))?,
+        ];
+        default.store(false, Ordering::Relaxed);
+        Ok(Self { interrupted, ids })
+    }
+
+    fn interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for InterruptHandler {
+    fn drop(&mut self) {
+        for id in self.ids.drain(..) {
+            signal_hook::low_level::unregister(id);
+        }
+        if let Some(default) = SIGINT_DEFAULT.get() {
+            default.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Where a dispatched page sits in the dump: its lane (the bz2 stream with
+/// multistream input, otherwise always 0) and its index within that lane.
+type Position = (usize, u64);
+
+/// Tracks which dispatched pages have finished writing their output, so a
+/// checkpoint never claims a page that is still in flight.
+///
+/// Pages leave each lane in dump order but finish out of order. The low-water
+/// mark is the highest page ID before which every dispatched page (and every
+/// page not yet dispatched from an open lane) has finished; pages finished
+/// past it are listed separately. A page abandoned after a stop request never
+/// finishes, so the mark stays below it and a resume processes it again.
+struct Completion {
+    state: Mutex<CompletionState>,
+}
+
+#[derive(Default)]
+struct CompletionState {
+    /// Next index for each lane that may still dispatch pages.
+    open: BTreeMap<usize, u64>,
+    /// Dispatched pages that have not finished.
+    pending: BTreeSet<Position>,
+    /// Finished pages past the first unfinished position, with their IDs.
+    finished: BTreeMap<Position, u64>,
+    low_water: u64,
+}
+
+impl Completion {
+    fn new(lanes: usize, resume_after_id: u64) -> Self {
+        Self {
+            state: Mutex::new(CompletionState {
+                open: (0..lanes).map(|lane| (lane, 0)).collect(),
+                low_water: resume_after_id,
+                ..CompletionState::default()
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CompletionState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Position for the next page of `lane`, pending until it finishes.
+    fn dispatch(&self, lane: usize) -> Position {
+        let mut state = self.lock();
+        let next = state.open.entry(lane).or_default();
+        let pos = (lane, *next);
+        *next += 1;
+        state.pending.insert(pos);
+        pos
+    }
+
+    /// Marks a dispatched page as written.
+    fn finish(&self, pos: Position, page_id: u64) {
+        let mut state = self.lock();
+        state.pending.remove(&pos);
+        state.finished.insert(pos, page_id);
+        state.advance();
+    }
+
+    /// Marks `lane` as exhausted: it dispatches no more pages.
+    fn close(&self, lane: usize) {
+        let mut state = self.lock();
+        state.open.remove(&lane);
+        state.advance();
+    }
+
+    fn completed(&self) -> Completed {
+        let state = self.lock();
+        let mut after: Vec<u64> = state.finished.values().copied().collect();
+        after.sort_unstable();
+        Completed {
+            last_id: state.low_water,
+            after,
+        }
+    }
+}
+
+impl CompletionState {
+    /// Moves every finished page before the first unfinished position under
+    /// the low-water mark.
+    fn advance(&mut self) {
+        let first_pending = self.pending.first().copied();
+        let first_undispatched = self
+            .open
+            .first_key_value()
+            .map(|(&lane, &next)| (lane, next));
+        let frontier = match (first_pending, first_undispatched) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        while let Some(entry) = self.finished.first_entry() {
+            if frontier.is_some_and(|frontier| *entry.key() >= frontier) {
+                break;
+            }
+            self.low_water = self.low_water.max(entry.remove());
+        }
+    }
+}
+
+/// Writes the dump's `<siteinfo>` metadata to `dump_info.json` in `output_dir`.
+fn write_dump_info(input_path: &str, output_dir: &str) -> Result<std::path::PathBuf> {
+    let info = parser::read_dump_info(input_path)
+        .with_context(|| format!("Failed to read dump header: {}", input_path))?;
+    let path = Path::new(output_dir).join(DUMP_INFO_FILENAME);
+    let file = File::create(&path).with_context(|| format!("Failed to create {:?}", path))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &info)
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    info!(
+        generator = info.generator.as_deref().unwrap_or("unknown"),
+        dbname = info.dbname.as_deref().unwrap_or("unknown"),
+        "Wrote dump info"
+    );
+    Ok(path)
 }
 
 /// Runs extraction with default stats/cancel state. Returns final statistics.
@@ -262,18 +1213,93 @@ pub fn run_extraction_with_stats(
     cancel: Arc<AtomicBool>,
     hide_progress: bool,
 ) -> Result<ExtractionStats> {
+    let started_at = manifest::unix_now();
     let path = config.input_path;
     let output_dir = config.output_dir;
     let index = config.index;
     let shard_count = config.shard_count;
     let csv_shards = config.csv_shards;
+    let shard_strategy = config.shard_strategy;
     let limit = config.limit;
+    let max_output_bytes = config.max_output_bytes;
     let dry_run = config.dry_run;
+    let single_thread = config.single_thread;
+    let no_blobs = config.no_blobs;
+    let blobs_only = config.blobs_only;
+    let blob_links = config.blob_links;
+    let blob_format = config.blob_format;
+    let edge_section_context = config.edge_section_context;
+    let edge_redirect_flag = config.edge_redirect_flag;
+    let weighted_edges = config.weighted_edges;
+    let keep_self_links = config.keep_self_links;
+    let normalize_urls = config.normalize_urls;
+    let template_stats = config.template_stats.then(TemplateStats::new);
+    let length_histogram = config.length_histogram;
+    let category_counts = config.category_counts.then(CategoryCounts::new);
+    let rel_names = &config.relationship_names;
+    let page_label = config.page_label.as_str();
+    crate::schema::validate_label(page_label)?;
+    let link_namespaces: Vec<String> = index
+        .namespaces()
+        .iter()
+        .chain(&config.link_namespaces)
+        .cloned()
+        .collect();
+    let excluded_categories = excluded_category_set(&config.exclude_categories, &link_namespaces);
+    let lead_templates = config.lead_templates.as_slice();
+    let dedup_strategy = config.dedup_strategy;
+    let output_format = config.output_format;
     let resume_from = config.resume_from;
     let checkpoint_mgr = config.checkpoint_mgr;
     let multistream_ranges = config.multistream_ranges;
+    let enrich = config.enrich;
     let resuming = resume_from.is_some();
+    if edge_redirect_flag && output_format == OutputFormat::Sqlite {
+        bail!("--edge-redirect-flag adds a column to edges.csv; use it with --output-format csv");
+    }
+    if weighted_edges && output_format == OutputFormat::Sqlite {
+        bail!("--weighted-edges adds a column to edges.csv; use it with --output-format csv");
+    }
+    if config.interwiki_links && output_format == OutputFormat::Sqlite {
+        bail!("--interwiki-links writes interwiki.csv; use it with --output-format csv");
+    }
+    if config.coordinates && output_format == OutputFormat::Sqlite {
+        bail!("--coordinates writes coordinates.csv; use it with --output-format csv");
+    }
+    if category_counts.is_some() && output_format == OutputFormat::Sqlite {
+        bail!("--category-counts adds a column to categories.csv; use it with --output-format csv");
+    }
+    if category_counts.is_some() && resuming {
+        bail!(
+            "--category-counts needs every article in one run; counts from before the checkpoint are not known"
+        );
+    }
+    if config.max_shard_bytes.is_some() && csv_shards > 1 {
+        bail!("--max-shard-bytes splits single-file CSVs; use it with --csv-shards 1");
+    }
+    if let Some(max) = config.max_invalid_ratio
+        && !(0.0..=1.0).contains(&max)
+    {
+        bail!("--max-invalid-ratio must be between 0 and 1, got {max}");
+    }
+    let blob_sample_rate = config.blob_sample_rate;
+    if let Some(rate) = blob_sample_rate
+        && !(0.0..=1.0).contains(&rate)
+    {
+        bail!("--blob-sample-rate must be between 0 and 1, got {rate}");
+    }
     let resume_after_id = resume_from.map(|cp| cp.last_processed_id).unwrap_or(0);
+    let completed_after = resume_from.map_or(&[][..], |cp| cp.completed_after.as_slice());
+    // Pages the checkpoint already covers.
+    let already_done = |page: &WikiPage| {
+        page.id <= resume_after_id || completed_after.binary_search(&page.id).is_ok()
+    };
+    if template_stats.is_some() && resuming {
+        warn!("--template-stats with --resume counts only the articles processed in this run");
+    }
+    if length_histogram && resuming {
+        warn!("--length-histogram with --resume counts only the articles processed in this run");
+    }
 
     let output_path = Path::new(output_dir);
     if !dry_run {
@@ -287,10 +1313,16 @@ pub fn run_extraction_with_stats(
 
         // Pre-create all blob shard directories once, avoiding millions of
         // redundant create_dir_all calls inside the parallel loop.
-        for shard in 0..shard_count {
-            let dir_path = format!("{}/blobs/{:03}", output_dir, shard);
+        if !no_blobs && blob_format == BlobFormat::Jsonl {
+            let dir_path = output_path.join("blobs");
             fs::create_dir_all(&dir_path)
-                .with_context(|| format!("Failed to create blob directory: {}", dir_path))?;
+                .with_context(|| format!("Failed to create blob directory: {:?}", dir_path))?;
+        } else if !no_blobs {
+            for shard in 0..shard_count {
+                let dir_path = format!("{}/blobs/{:03}", output_dir, shard);
+                fs::create_dir_all(&dir_path)
+                    .with_context(|| format!("Failed to create blob directory: {}", dir_path))?;
+            }
         }
     }
 
@@ -304,42 +1336,108 @@ pub fn run_extraction_with_stats(
     }
     if dry_run {
         info!("Dry run mode - no files will be written");
+    } else if no_blobs {
+        info!("Blob output disabled - writing CSVs only");
+    } else if blobs_only {
+        info!("CSV output disabled - writing blobs only");
+    }
+    let indexed_articles = index.stats().0;
+    if dedup_strategy == DedupStrategy::Fingerprint {
+        info!(
+            slots = fingerprint_slots(indexed_articles),
+            "Using fingerprint dedup; merge removes any duplicate node rows"
+        );
+        if csv_shards == 1 {
+            warn!(
+                "--dedup-strategy fingerprint with --csv-shards 1 skips merge, \
+                 so duplicate node rows may remain"
+            );
+        }
     }
 
-    let nodes_writer = ShardedCsvWriter::new(output_dir, "nodes", csv_shards, dry_run, resuming)?;
-    let edges_writer = ShardedCsvWriter::new(output_dir, "edges", csv_shards, dry_run, resuming)?;
-    let categories_writer =
-        ShardedCsvWriter::new(output_dir, "categories", csv_shards, dry_run, resuming)?;
-    let article_categories_writer = ShardedCsvWriter::new(
-        output_dir,
-        "article_categories",
-        csv_shards,
-        dry_run,
-        resuming,
-    )?;
-    let image_nodes_writer =
-        ShardedCsvWriter::new(output_dir, "image_nodes", csv_shards, dry_run, resuming)?;
-    let article_images_writer =
-        ShardedCsvWriter::new(output_dir, "article_images", csv_shards, dry_run, resuming)?;
-    let external_link_nodes_writer = ShardedCsvWriter::new(
-        output_dir,
-        "external_link_nodes",
-        csv_shards,
-        dry_run,
+    // Blobs-only runs route every CSV writer to a sink, same as a dry run.
+    let skip_csv = dry_run || blobs_only;
+    let sqlite = if output_format == OutputFormat::Sqlite && !skip_csv {
+        Some(SqliteWriter::open(
+            &output_path.join(SQLITE_DB_FILENAME),
+            !resuming,
+        )?)
+    } else {
+        None
+    };
+    let csv_opts = CsvOptions {
+        shards: csv_shards,
+        strategy: shard_strategy,
+        max_part_bytes: config.max_shard_bytes,
+        skip: skip_csv,
         resuming,
+    };
+    let jsonl_blobs = if blob_format == BlobFormat::Jsonl && !dry_run && !no_blobs {
+        Some(JsonlBlobWriter::new(
+            output_dir,
+            shard_count,
+            shard_strategy,
+            resuming,
+            &stats,
+        )?)
+    } else {
+        None
+    };
+    let sink = |csv_type| table_sink(csv_type, output_dir, csv_opts, sqlite.as_ref(), &stats);
+    let nodes_writer = sink(CsvType::Nodes)?;
+    let edges_writer = sink(CsvType::Edges)?;
+    let categories_writer = sink(CsvType::Categories)?;
+    let article_categories_writer = sink(CsvType::ArticleCategories)?;
+    let image_nodes_writer = sink(CsvType::ImageNodes)?;
+    let article_images_writer = sink(CsvType::ArticleImages)?;
+    let external_link_nodes_writer = sink(CsvType::ExternalLinkNodes)?;
+    let article_external_links_writer = sink(CsvType::ArticleExternalLinks)?;
+    // Optional extras are not `CsvType`s: one file whatever `--csv-shards`, so
+    // merge and load ignore them.
+    let extra_csv = |enabled: bool, base_name: &str, header: &[&str]| -> Result<Option<Sink>> {
+        if !enabled {
+            return Ok(None);
+        }
+        let opts = CsvOptions {
+            shards: 1,
+            max_part_bytes: None,
+            ..csv_opts
+        };
+        let writer = ShardedCsvWriter::new(output_dir, base_name, opts, &stats)?;
+        if !skip_csv {
+            writer.write_headers(header)?;
+        }
+        Ok(Some(Box::new(writer)))
+    };
+    let interwiki_writer = extra_csv(
+        config.interwiki_links,
+        INTERWIKI_BASE_NAME,
+        &[":START_ID", "lang", "title"],
     )?;
-    let article_external_links_writer = ShardedCsvWriter::new(
-        output_dir,
-        "article_external_links",
-        csv_shards,
-        dry_run,
-        resuming,
+    let coordinates_writer = extra_csv(
+        config.coordinates,
+        COORDINATES_BASE_NAME,
+        &[":START_ID", "latitude:double", "longitude:double"],
     )?;
 
-    if !resuming {
+    if !skip_csv {
         nodes_writer.write_headers(&["id:ID", "title", ":LABEL"])?;
-        edges_writer.write_headers(&[":START_ID", ":END_ID", ":TYPE"])?;
-        categories_writer.write_headers(&["id:ID(Category)", "name", ":LABEL"])?;
+        let mut edge_header = vec![":START_ID", ":END_ID", ":TYPE"];
+        if edge_section_context {
+            edge_header.push("section");
+        }
+        if edge_redirect_flag {
+            edge_header.push("via_redirect");
+        }
+        if weighted_edges {
+            edge_header.push("weight:int");
+        }
+        edges_writer.write_headers(&edge_header)?;
+        if category_counts.is_some() {
+            categories_writer.write_headers(&["id:ID(Category)", "name", ":LABEL", "count:int"])?;
+        } else {
+            categories_writer.write_headers(&["id:ID(Category)", "name", ":LABEL"])?;
+        }
         article_categories_writer.write_headers(&[":START_ID", ":END_ID(Category)", ":TYPE"])?;
         image_nodes_writer.write_headers(&["id:ID(Image)", "filename", ":LABEL"])?;
         article_images_writer.write_headers(&[":START_ID", ":END_ID(Image)", ":TYPE"])?;
@@ -352,15 +1450,72 @@ pub fn run_extraction_with_stats(
     }
 
     let stats_clone = Arc::clone(&stats);
-    let limit_counter = Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let limit_counter = Arc::new(AtomicU64::new(0));
     let limit_reached = Arc::new(AtomicBool::new(false));
-    let seen_categories: Arc<DashSet<String>> = Arc::new(DashSet::new());
-    let seen_images: Arc<DashSet<String>> = Arc::new(DashSet::new());
-    let seen_external_links: Arc<DashSet<String>> = Arc::new(DashSet::new());
+    let size_cap_reached = AtomicBool::new(false);
+    // First `(page id, revisions)` seen with several revisions under `single_revision`.
+    let multi_revision_page: OnceLock<(u64, u32)> = OnceLock::new();
+    let completion = Completion::new(
+        multistream_ranges.map_or(1, <[StreamRange]>::len),
+        resume_after_id,
+    );
+    // Checked before each page; sets `size_cap_reached` the first time the cap is hit.
+    let over_size_cap = || {
+        if size_cap_reached.load(Ordering::Relaxed) {
+            return true;
+        }
+        let hit = max_output_bytes.is_some_and(|max| stats.output_bytes() >= max);
+        if hit {
+            size_cap_reached.store(true, Ordering::Relaxed);
+        }
+        hit
+    };
+    let seen_categories = Arc::new(SeenSet::new(dedup_strategy, indexed_articles));
+    let seen_images = Arc::new(SeenSet::new(dedup_strategy, indexed_articles));
+    let seen_external_links = Arc::new(SeenSet::new(dedup_strategy, indexed_articles));
     let cancel_clone = Arc::clone(&cancel);
+    // Run before each periodic checkpoint so the rows it covers are on disk;
+    // a crash then leaves at most a partial tail past the checkpoint.
+    let sync_output = || -> Result<()> {
+        for sink in [
+            &nodes_writer,
+            &edges_writer,
+            &categories_writer,
+            &article_categories_writer,
+            &image_nodes_writer,
+            &article_images_writer,
+            &external_link_nodes_writer,
+            &article_external_links_writer,
+        ]
+        .into_iter()
+        .chain(interwiki_writer.as_ref())
+        .chain(coordinates_writer.as_ref())
+        {
+            sink.sync()?;
+        }
+        if let Some(jsonl) = &jsonl_blobs {
+            jsonl.sync()?;
+        }
+        Ok(())
+    };
 
+    // With a known total (capped by `limit`), show a bar with percentage and ETA.
+    let progress_total = config
+        .expected_articles
+        .map(|total| limit.map_or(total, |max| total.min(stats.articles() + max)));
     let pb = if hide_progress {
         ProgressBar::hidden()
+    } else if let Some(total) = progress_total {
+        let pb = ProgressBar::new(total).with_position(stats.articles());
+        pb.set_style(
+            indicatif::ProgressStyle::default_bar()
+                .template("{spinner:.cyan} [{bar:30.cyan/blue}] {percent}% (ETA {eta}) {msg}")
+                .expect("valid progress template")
+                .progress_chars("=> "),
+        );
+        pb.reset_eta();
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+        pb
     } else {
         let pb = ProgressBar::new_spinner();
         pb.set_style(
@@ -374,140 +1529,314 @@ pub fn run_extraction_with_stats(
     let pb = Arc::new(pb);
     let pb_clone = Arc::clone(&pb);
 
-    let process_page = |page: crate::models::WikiPage| {
-        if limit_reached.load(Ordering::Relaxed) || cancel_clone.load(Ordering::Relaxed) {
+    let process_page = |(pos, mut page): (Position, WikiPage)| {
+        if limit_reached.load(Ordering::Relaxed)
+            || cancel_clone.load(Ordering::Relaxed)
+            || over_size_cap()
+            || multi_revision_page.get().is_some()
+        {
+            return;
+        }
+        if config.single_revision && page.revisions > 1 {
+            let _ = multi_revision_page.set((page.id, page.revisions));
             return;
         }
-        if let Some(max) = limit {
-            let current = limit_counter.fetch_add(1, Ordering::Relaxed);
-            if current >= max {
-                limit_reached.store(true, Ordering::Relaxed);
+
+        if page.is_content_page(index.content_namespaces()) {
+            // Checked before the limit so excluded articles don't use up slots.
+            if !excluded_categories.is_empty()
+                && page
+                    .text
+                    .as_deref()
+                    .is_some_and(|text| has_excluded_category(text, &excluded_categories))
+            {
+                stats_clone.inc_articles_excluded();
+                completion.finish(pos, page.id);
                 return;
             }
-        }
 
-        if let PageType::Article = page.page_type {
+            // Reserve one of the `limit` article slots; the compare-and-swap
+            // keeps the count exact even when workers race for the last slot.
+            if let Some(max) = limit {
+                match limit_counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                    (n < max).then_some(n + 1)
+                }) {
+                    Ok(taken) if taken + 1 < max => {}
+                    Ok(_) => limit_reached.store(true, Ordering::Relaxed),
+                    Err(_) => {
+                        limit_reached.store(true, Ordering::Relaxed);
+                        return;
+                    }
+                }
+            }
+
             let mut itoa_buf = itoa::Buffer::new();
             let id_str = itoa_buf.format(page.id);
             stats_clone.inc_articles();
-
-            if let Ok(mut writer) = nodes_writer.shard_for(page.id).lock()
-                && let Err(e) = writer.write_record([id_str, &page.title, "Page"])
-            {
-                warn!(error = %e, "Failed to write node record");
+            if length_histogram {
+                stats_clone
+                    .article_lengths
+                    .record(page.text.as_deref().map_or(0, str::len));
             }
 
+            nodes_writer.write_rows(page.id, "node", |write| {
+                write(&[id_str, &page.title, page_label]);
+            });
+
             if let Some(text) = &page.text {
+                // Links, categories, images and external links are read from
+                // `live`, where comments and <nowiki>/<pre> blocks are blanked.
+                // Offsets match `text`.
+                let live = content::mask_non_content(text);
+                // One pass over the `[` bytes finds links, categories, images
+                // and external links.
+                let content::BracketScan {
+                    links,
+                    categories,
+                    images,
+                    external_links,
+                } = content::scan_brackets(&live);
+
                 // -- Edges --
-                let see_also_start = content::see_also_section_start(text);
-                let (local_edges, invalid_count) =
-                    process_article_edges(text, index, see_also_start);
+                let see_also_start = content::see_also_section_start(&live);
+                let sections = edge_section_context.then(|| content::section_starts(&live));
+                let (local_edges, invalid_count, red_count, self_count) = process_article_edges(
+                    &links,
+                    index,
+                    &link_namespaces,
+                    see_also_start,
+                    sections.as_deref(),
+                    (!keep_self_links).then_some(page.id),
+                );
                 let links_to_count = local_edges
                     .iter()
-                    .filter(|(_, t)| *t == EdgeType::LinksTo)
+                    .filter(|(_, t, ..)| *t == EdgeType::LinksTo)
                     .count() as u64;
                 let see_also_count = local_edges
                     .iter()
-                    .filter(|(_, t)| *t == EdgeType::SeeAlso)
+                    .filter(|(_, t, ..)| *t == EdgeType::SeeAlso)
                     .count() as u64;
                 stats_clone.add_edges(links_to_count);
                 stats_clone.add_see_also_edges(see_also_count);
                 stats_clone.add_invalid_links(invalid_count);
+                stats_clone.add_red_links(red_count);
+                stats_clone.add_self_links(self_count);
 
                 if !local_edges.is_empty() {
                     let mut edge_itoa = itoa::Buffer::new();
-                    if let Ok(mut writer) = edges_writer.shard_for(page.id).lock() {
-                        for (end_id, edge_type) in &local_edges {
+                    let mut weight_itoa = itoa::Buffer::new();
+                    edges_writer.write_rows(page.id, "edge", |write| {
+                        for (end_id, edge_type, section, via_redirect, weight) in &local_edges {
                             let end_str = edge_itoa.format(*end_id);
-                            let type_str = match edge_type {
-                                EdgeType::LinksTo => "LINKS_TO",
-                                EdgeType::SeeAlso => "SEE_ALSO",
-                            };
-                            if let Err(e) = writer.write_record([id_str, end_str, type_str]) {
-                                warn!(error = %e, "Failed to write edge record");
+                            let mut row = [id_str, end_str, rel_names.edge(*edge_type), "", "", ""];
+                            let mut len = 3;
+                            if let Some(section) = section {
+                                row[len] = section;
+                                len += 1;
+                            }
+                            if edge_redirect_flag {
+                                row[len] = if *via_redirect { "true" } else { "false" };
+                                len += 1;
                             }
+                            if weighted_edges {
+                                row[len] = weight_itoa.format(*weight);
+                                len += 1;
+                            }
+                            write(&row[..len]);
                         }
-                    }
+                    });
                 }
 
                 // -- Categories --
-                let categories = content::extract_categories(text);
-                if !categories.is_empty() {
+                if let Some(counts) = &category_counts
+                    && !categories.is_empty()
+                {
+                    // Node rows wait for the final counts; see the end of the pass.
+                    stats_clone.add_categories(counts.record(&categories));
+                    let rel_type = rel_names.has_category.as_str();
+                    article_categories_writer.write_rows(page.id, rel_type, |write| {
+                        for category in &categories {
+                            write(&[id_str, category.as_ref(), rel_type]);
+                        }
+                    });
+                    stats_clone.add_category_edges(categories.len() as u64);
+                } else if !categories.is_empty() {
                     let (new_count, rel_count) = write_dedup_entities(
                         &categories,
                         &seen_categories,
-                        &categories_writer,
-                        &article_categories_writer,
+                        &*categories_writer,
+                        &*article_categories_writer,
                         page.id,
                         id_str,
                         "Category",
-                        "HAS_CATEGORY",
+                        &rel_names.has_category,
                     );
                     stats_clone.add_categories(new_count);
                     stats_clone.add_category_edges(rel_count);
                 }
 
-                // -- Images --
-                let images = content::extract_images(text);
+                // -- Template usage --
+                if let Some(template_stats) = &template_stats {
+                    template_stats.record(&content::extract_template_names(text));
+                }
+
+                // -- Infoboxes --
+                let infoboxes = infobox::extract_infoboxes(text);
+                if !infoboxes.is_empty() {
+                    stats_clone.add_infoboxes(infoboxes.len() as u64);
+                }
+
+                // -- Images (file links plus infobox image fields) --
+                let mut images = images;
+                for name in infobox::infobox_images(&infoboxes) {
+                    if !images.contains(&name) {
+                        images.push(name);
+                    }
+                }
                 if !images.is_empty() {
                     let (new_count, _rel_count) = write_dedup_entities(
                         &images,
                         &seen_images,
-                        &image_nodes_writer,
-                        &article_images_writer,
+                        &*image_nodes_writer,
+                        &*article_images_writer,
                         page.id,
                         id_str,
                         "Image",
-                        "HAS_IMAGE",
+                        &rel_names.has_image,
                     );
                     stats_clone.add_images(new_count);
                 }
 
                 // -- External links --
-                let ext_links = content::extract_external_links(text);
-                if !ext_links.is_empty() {
+                let external_links = if normalize_urls {
+                    let mut normalized = Vec::with_capacity(external_links.len());
+                    for url in external_links {
+                        // Keep the extracted `Cow` when the URL is already canonical.
+                        let url = if let std::borrow::Cow::Owned(canonical) =
+                            content::normalize_url(&url)
+                        {
+                            std::borrow::Cow::Owned(canonical)
+                        } else {
+                            url
+                        };
+                        if !normalized.contains(&url) {
+                            normalized.push(url);
+                        }
+                    }
+                    normalized
+                } else {
+                    external_links
+                };
+                if !external_links.is_empty() {
                     let (new_count, _rel_count) = write_dedup_entities(
-                        &ext_links,
+                        &external_links,
                         &seen_external_links,
-                        &external_link_nodes_writer,
-                        &article_external_links_writer,
+                        &*external_link_nodes_writer,
+                        &*article_external_links_writer,
                         page.id,
                         id_str,
                         "ExternalLink",
-                        "HAS_LINK",
+                        &rel_names.has_link,
                     );
                     stats_clone.add_external_links(new_count);
                 }
 
-                // -- Infoboxes & blob --
-                let infoboxes = infobox::extract_infoboxes(text);
-                if !infoboxes.is_empty() {
-                    stats_clone.add_infoboxes(infoboxes.len() as u64);
+                // -- Interlanguage links --
+                if let Some(writer) = &interwiki_writer {
+                    let links = content::interlanguage_links(&links);
+                    if !links.is_empty() {
+                        writer.write_rows(page.id, "interwiki", |write| {
+                            for (lang, title) in &links {
+                                write(&[id_str, lang, title]);
+                            }
+                        });
+                    }
+                }
+
+                // -- Coordinates --
+                if let Some(writer) = &coordinates_writer
+                    && let Some((lat, lon)) = content::extract_coordinates(text)
+                {
+                    writer.write_rows(page.id, "coordinates", |write| {
+                        write(&[id_str, &lat.to_string(), &lon.to_string()]);
+                    });
                 }
 
-                if !dry_run {
-                    let blob = ArticleBlob {
+                // -- Blob --
+                let sampled = blob_sample_rate.is_none_or(|rate| blob_sampled(page.id, rate));
+                if !sampled {
+                    stats_clone.inc_blobs_sampled_out();
+                }
+                if !dry_run && !no_blobs && sampled {
+                    // The hook needs the whole page, so only move fields out without one.
+                    let (title, timestamp) = if enrich.is_some() {
+                        (page.title.clone(), page.timestamp.clone())
+                    } else {
+                        (std::mem::take(&mut page.title), page.timestamp.take())
+                    };
+                    let mut blob = ArticleBlob {
                         id: page.id,
-                        title: page.title,
-                        abstract_text: content::extract_abstract(text),
+                        title,
+                        abstract_text: content::extract_abstract_keeping(text, lead_templates),
                         categories: categories.into_iter().map(|c| c.into_owned()).collect(),
                         infoboxes,
                         sections: content::extract_sections(text),
-                        timestamp: page.timestamp,
+                        timestamp,
                         is_disambiguation: content::is_disambiguation(text),
+                        is_likely_autogenerated: content::is_likely_autogenerated(text),
+                        reference_count: content::count_references(text),
+                        authority_ids: content::extract_authority_ids(text),
+                        links: if blob_links {
+                            let mut links: Vec<(u64, String)> = local_edges
+                                .iter()
+                                .map(|(end_id, edge_type, ..)| {
+                                    (*end_id, rel_names.edge(*edge_type).to_owned())
+                                })
+                                .collect();
+                            // Section context can repeat a target; blobs list it once.
+                            // Sorting makes the dedup independent of edge order.
+                            links.sort_unstable();
+                            links.dedup();
+                            links
+                        } else {
+                            Vec::new()
+                        },
+                        extra: serde_json::Map::new(),
                     };
-                    write_article_blob(output_dir, shard_count, page.id, &blob, &stats_clone);
+                    if let Some(enrich) = enrich {
+                        enrich(&page, &mut blob);
+                    }
+                    if let Some(jsonl) = &jsonl_blobs {
+                        jsonl.write(page.id, &blob, &stats_clone);
+                    } else {
+                        write_article_blob(
+                            output_dir,
+                            shard_count,
+                            shard_strategy,
+                            page.id,
+                            &blob,
+                            &stats_clone,
+                        );
+                    }
                 }
             }
 
+            completion.finish(pos, page.id);
             if let Some(mgr) = checkpoint_mgr
-                && let Err(e) = mgr.maybe_save(page.id, &stats_clone)
+                && let Err(e) = mgr.maybe_save_with(&stats_clone, || {
+                    // Taken before the flush: every page it covers has already
+                    // handed its rows to the writers.
+                    let completed = completion.completed();
+                    sync_output()?;
+                    Ok(completed)
+                })
             {
                 warn!(error = %e, "Failed to save checkpoint");
             }
 
             let articles = stats_clone.articles();
             if articles.is_multiple_of(PROGRESS_INTERVAL as u64) {
+                pb_clone.set_position(articles);
                 pb_clone.set_message(format!(
                     "Extracting: {} articles, {} edges, {} blobs",
                     articles,
@@ -515,51 +1844,250 @@ pub fn run_extraction_with_stats(
                     stats_clone.blobs()
                 ));
             }
+        } else {
+            completion.finish(pos, page.id);
         }
     };
 
+    // Stops pulling pages (and decompressing) once the limit or size cap is hit or cancelled.
+    let keep_going = |_: &WikiPage| {
+        !limit_reached.load(Ordering::Relaxed)
+            && !cancel.load(Ordering::Relaxed)
+            && !over_size_cap()
+            && multi_revision_page.get().is_none()
+    };
+
+    let runtime_budget = config
+        .max_runtime
+        .map(|budget| RuntimeBudget::start(budget, Arc::clone(&stats), Arc::clone(&cancel)));
+    let metrics_server = config
+        .metrics_addr
+        .map(|addr| MetricsServer::start(addr, Arc::clone(&stats)))
+        .transpose()?;
+    let interrupt_handler = config
+        .handle_interrupts
+        .then(|| InterruptHandler::install(&cancel))
+        .transpose()
+        .context("Failed to install the SIGINT handler")?;
+
     #[allow(clippy::needless_borrows_for_generic_args)]
-    if let Some(ranges) = multistream_ranges {
+    if single_thread {
+        info!("Single-threaded extraction");
+        let pages: Box<dyn Iterator<Item = WikiPage>> = if let Some(ranges) = multistream_ranges {
+            Box::new(crate::multistream::iter_pages(path, ranges, false))
+        } else {
+            Box::new(
+                WikiReader::new(path, false)
+                    .with_context(|| format!("Failed to open wiki dump: {}", path))?,
+            )
+        };
+        pages
+            .filter(|page| !already_done(page))
+            .take_while(keep_going)
+            .map(|page| (completion.dispatch(0), page))
+            .for_each(&process_page);
+    } else if let Some(ranges) = multistream_ranges {
         info!(
             streams = ranges.len(),
             "Using multistream parallel extraction"
         );
-        crate::multistream::par_iter_pages(path, ranges, false)
-            .filter(|page| page.id > resume_after_id)
-            .for_each(&process_page);
+        // Streams are handed out in dump order rather than split in halves
+        // like `par_iter`, so the checkpoint's low-water mark keeps moving.
+        ranges
+            .iter()
+            .enumerate()
+            .par_bridge()
+            .for_each(|(stream, range)| {
+                crate::multistream::iter_pages(path, std::slice::from_ref(range), false)
+                    .filter(|page| !already_done(page))
+                    .map(|page| (completion.dispatch(stream), page))
+                    .for_each(&process_page);
+                completion.close(stream);
+            });
     } else {
         let reader = WikiReader::new(path, false)
             .with_context(|| format!("Failed to open wiki dump: {}", path))?;
         reader
-            .filter(|page| page.id > resume_after_id)
+            .filter(|page| !already_done(page))
+            .take_while(keep_going)
+            .map(|page| (completion.dispatch(0), page))
             .par_bridge()
             .for_each(&process_page);
     }
 
+    if interrupt_handler
+        .as_ref()
+        .is_some_and(InterruptHandler::interrupted)
+    {
+        stats.set_interrupted();
+    }
+    drop((runtime_budget, metrics_server));
     pb.finish_and_clear();
 
+    if let Some(counts) = &category_counts {
+        let mut itoa_buf = itoa::Buffer::new();
+        for (name, count) in counts.sorted() {
+            let count = itoa_buf.format(count);
+            categories_writer.write_keyed(&name, &[&name, &name, "Category", count], "Category");
+        }
+        info!(
+            categories = counts.len(),
+            "Wrote category nodes with article counts"
+        );
+    }
+
+    // Release every sink (flushing CSV buffers) before the SQLite writer is
+    // joined, since the sinks borrow it.
+    drop((
+        jsonl_blobs,
+        nodes_writer,
+        edges_writer,
+        categories_writer,
+        article_categories_writer,
+        image_nodes_writer,
+        article_images_writer,
+        external_link_nodes_writer,
+        article_external_links_writer,
+        interwiki_writer,
+        coordinates_writer,
+    ));
+    if let Some(db) = sqlite {
+        let rows = db.finish().context("SQLite output failed")?;
+        info!(rows, "Wrote {}", SQLITE_DB_FILENAME);
+    }
+
+    if let Some((id, revisions)) = multi_revision_page.get() {
+        bail!(
+            "Page {id} has {revisions} revisions, so this looks like a full-history dump; \
+             use a pages-articles dump, or drop --single-revision to keep each page's latest revision"
+        );
+    }
+
+    if let Some(template_stats) = &template_stats
+        && !dry_run
+    {
+        let path = template_stats.write_csv(output_dir)?;
+        info!(templates = template_stats.len(), path = ?path, "Wrote template usage counts");
+    }
+
+    if length_histogram && !dry_run {
+        let path = stats.article_lengths.write_csv(output_dir)?;
+        info!(path = ?path, "Wrote article length histogram");
+    }
+
+    if config.emit_redirect_map && !dry_run {
+        let (path, redirects) = index.write_redirect_map(output_dir)?;
+        info!(redirects, path = ?path, "Wrote redirect map");
+    }
+
+    if !dry_run {
+        write_dump_info(path, output_dir)?;
+    }
+
+    let size_capped = size_cap_reached.load(Ordering::Relaxed);
+    if size_capped || stats.time_budget_exhausted() || stats.interrupted() {
+        // Pages dropped after the stop request are not covered, so the resume
+        // picks them up.
+        let completed = completion.completed();
+        let last_id = completed.last_id;
+        if stats.interrupted() {
+            warn!(last_id, "Interrupted; stopped dispatching new pages");
+        } else if size_capped {
+            warn!(
+                output_bytes = stats.output_bytes(),
+                max_output_bytes = max_output_bytes.unwrap_or_default(),
+                last_id,
+                "Output size cap reached; stopped dispatching new pages"
+            );
+        } else {
+            warn!(
+                max_runtime_secs = config.max_runtime.unwrap_or_default().as_secs(),
+                last_id, "Time budget exhausted; stopped dispatching new pages"
+            );
+        }
+        // Outputs are flushed above, so the checkpoint covers everything written.
+        if let Some(mgr) = checkpoint_mgr {
+            mgr.save_completed(&completed, &stats)
+                .context("Failed to save checkpoint after stopping early")?;
+        }
+    }
+    if !dry_run {
+        let complete = !(size_capped || stats.time_budget_exhausted() || stats.interrupted());
+        let path = Manifest::for_run(config, &stats, started_at, complete)?.write(output_dir)?;
+        info!(path = ?path, complete, "Wrote run manifest");
+    }
+    // Held until the checkpoint is saved, so a single Ctrl-C while output is
+    // flushed cannot kill the process midway.
+    drop(interrupt_handler);
+
     info!(
         articles = stats.articles(),
         edges = stats.edges(),
         blobs = stats.blobs(),
         invalid_links = stats.invalid(),
+        red_links = stats.red_links(),
         categories = stats.categories(),
         infoboxes = stats.infoboxes(),
         "Extraction complete"
     );
 
+    if let Some(ratio) = stats.low_resolution_rate() {
+        warn!(
+            unresolved_pct = format!("{:.1}", ratio * 100.0),
+            edges = stats.edges(),
+            invalid_links = stats.invalid(),
+            "Low link resolution rate: a large share of wiki links did not match an indexed title. \
+             Check title normalization, and that the index was built from the same dump \
+             and language edition"
+        );
+    }
+    if stats.articles() == 0
+        && !size_capped
+        && !stats.time_budget_exhausted()
+        && !stats.interrupted()
+    {
+        warn!(
+            input = path,
+            pages = index.page_count(),
+            "No articles extracted: the dump has no non-redirect pages in the selected \
+             namespaces, so the CSVs contain only headers"
+        );
+    }
+
+    // Checked last so all output (and any checkpoint) is flushed for inspection.
+    if let Some(max) = config.max_invalid_ratio {
+        let ratio = stats.invalid_link_ratio();
+        if ratio > max {
+            bail!(
+                "{:.1}% of wiki links did not resolve ({} of {}), above --max-invalid-ratio {}",
+                ratio * 100.0,
+                stats.invalid(),
+                stats.edges() + stats.invalid(),
+                max
+            );
+        }
+    }
+
     Ok(
         Arc::try_unwrap(stats).unwrap_or_else(|arc| ExtractionStats {
             articles_processed: std::sync::atomic::AtomicU64::new(arc.articles()),
             edges_extracted: std::sync::atomic::AtomicU64::new(arc.edges()),
             blobs_written: std::sync::atomic::AtomicU64::new(arc.blobs()),
             invalid_links: std::sync::atomic::AtomicU64::new(arc.invalid()),
+            red_links: std::sync::atomic::AtomicU64::new(arc.red_links()),
             categories_found: std::sync::atomic::AtomicU64::new(arc.categories()),
             category_edges: std::sync::atomic::AtomicU64::new(arc.category_edges()),
             see_also_edges: std::sync::atomic::AtomicU64::new(arc.see_also_edges()),
             infoboxes_extracted: std::sync::atomic::AtomicU64::new(arc.infoboxes()),
             images_found: std::sync::atomic::AtomicU64::new(arc.images()),
             external_links_found: std::sync::atomic::AtomicU64::new(arc.external_links()),
+            output_bytes: std::sync::atomic::AtomicU64::new(arc.output_bytes()),
+            blobs_sampled_out: std::sync::atomic::AtomicU64::new(arc.blobs_sampled_out()),
+            articles_excluded: std::sync::atomic::AtomicU64::new(arc.articles_excluded()),
+            self_links: std::sync::atomic::AtomicU64::new(arc.self_links()),
+            time_budget_exhausted: AtomicBool::new(arc.time_budget_exhausted()),
+            interrupted: AtomicBool::new(arc.interrupted()),
+            article_lengths: arc.article_lengths.clone(),
         }),
     )
 }
@@ -567,7 +2095,7 @@ pub fn run_extraction_with_stats(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::SHARD_COUNT;
+    use crate::content::LINK_REGEX;
 
     #[test]
     fn link_regex_simple_link() {
@@ -640,34 +2168,192 @@ mod tests {
     }
 
     #[test]
-    fn shard_calculation() {
-        assert_eq!(0 % SHARD_COUNT, 0);
-        assert_eq!(1 % SHARD_COUNT, 1);
-        assert_eq!(999 % SHARD_COUNT, 999);
-        assert_eq!(1000 % SHARD_COUNT, 0);
-        assert_eq!(1001 % SHARD_COUNT, 1);
-        assert_eq!(123456 % SHARD_COUNT, 456);
+    fn blob_sample_keeps_about_rate_of_ids() {
+        let ids = 1..=100_000u64;
+        assert!(ids.clone().all(|id| !blob_sampled(id, 0.0)));
+        assert!(ids.clone().all(|id| blob_sampled(id, 1.0)));
+        let kept = ids.clone().filter(|&id| blob_sampled(id, 0.01)).count();
+        assert!((800..1200).contains(&kept), "kept {kept} of 100000");
+        // A larger rate keeps a superset.
+        assert!(
+            ids.filter(|&id| blob_sampled(id, 0.01))
+                .all(|id| blob_sampled(id, 0.1))
+        );
     }
 
     #[test]
-    fn shard_stays_within_bounds() {
-        for id in [0u32, 1, 500, 999, 1000, 99999, u32::MAX] {
-            assert!(id % SHARD_COUNT < SHARD_COUNT);
+    fn key_shard_is_stable_and_in_bounds() {
+        // Pinned values: changing the hash would reshuffle node shards between releases.
+        assert_eq!(key_shard("", 8), (0xcbf2_9ce4_8422_2325u64 % 8) as usize);
+        for key in [
+            "Science",
+            "Programming languages",
+            "Example.jpg",
+            "https://a.b",
+        ] {
+            let shard = key_shard(key, 8);
+            assert!(shard < 8);
+            assert_eq!(shard, key_shard(key, 8));
         }
+        assert_eq!(key_shard("anything", 1), 0);
+    }
+
+    #[test]
+    fn excluded_categories_drop_category_namespace_prefixes() {
+        let names = [
+            "category:Living_people",
+            "Kategorie:Lebende Person",
+            "Foo: Bar",
+        ]
+        .map(String::from);
+        let namespaces = ["Datei".to_string(), "Kategorie".to_string()];
+        let excluded = excluded_category_set(&names, &namespaces);
+        assert!(excluded.contains("Living people"));
+        assert!(excluded.contains("Lebende Person"));
+        // Not a namespace, so part of the category name.
+        assert!(excluded.contains("Foo: Bar"));
+        assert_eq!(excluded.len(), 3);
+    }
+
+    #[test]
+    fn completion_mark_waits_for_earlier_pages() {
+        let completion = Completion::new(1, 100);
+        let [a, b, c] = [0; 3].map(|_| completion.dispatch(0));
+        completion.finish(b, 102);
+        completion.finish(c, 103);
+        assert_eq!(
+            completion.completed(),
+            Completed {
+                last_id: 100,
+                after: vec![102, 103],
+            }
+        );
+        completion.finish(a, 101);
+        assert_eq!(completion.completed().last_id, 103);
+        assert!(completion.completed().after.is_empty());
+
+        // An abandoned page never finishes, so the mark stays below it.
+        let [_abandoned, e] = [0; 2].map(|_| completion.dispatch(0));
+        completion.finish(e, 105);
+        assert_eq!(
+            completion.completed(),
+            Completed {
+                last_id: 103,
+                after: vec![105],
+            }
+        );
+    }
+
+    #[test]
+    fn completion_mark_waits_for_earlier_streams() {
+        let completion = Completion::new(2, 0);
+        let late = completion.dispatch(1);
+        completion.finish(late, 20);
+        completion.close(1);
+        // Stream 0 has not dispatched anything yet.
+        assert_eq!(completion.completed().last_id, 0);
+        let early = completion.dispatch(0);
+        completion.finish(early, 10);
+        assert_eq!(completion.completed().last_id, 10);
+        completion.close(0);
+        assert_eq!(
+            completion.completed(),
+            Completed {
+                last_id: 20,
+                after: Vec::new(),
+            }
+        );
     }
 
     #[test]
     fn namespace_filter_works() {
-        assert!(is_namespace_link("Category:Science"));
-        assert!(is_namespace_link("File:Example.jpg"));
-        assert!(is_namespace_link("Image:Logo.png"));
-        assert!(is_namespace_link("Template:Infobox"));
-        assert!(is_namespace_link("Wikipedia:About"));
-        assert!(is_namespace_link("Help:Editing"));
-        assert!(is_namespace_link("Portal:Science"));
-        assert!(is_namespace_link("Draft:New article"));
-        assert!(!is_namespace_link("Rust (programming language)"));
-        assert!(!is_namespace_link("Python"));
+        let index = WikiIndex::from_maps(Default::default(), Default::default());
+        let ns = index.namespaces();
+        assert!(is_namespace_link("Category:Science", ns));
+        assert!(is_namespace_link("File:Example.jpg", ns));
+        assert!(is_namespace_link("Image:Logo.png", ns));
+        assert!(is_namespace_link("Template:Infobox", ns));
+        assert!(is_namespace_link("Wikipedia:About", ns));
+        assert!(is_namespace_link("Help:Editing", ns));
+        assert!(is_namespace_link("Portal:Science", ns));
+        assert!(is_namespace_link("Draft:New article", ns));
+        assert!(!is_namespace_link("Rust (programming language)", ns));
+        assert!(!is_namespace_link("Python", ns));
+    }
+
+    #[test]
+    fn namespace_filter_requires_colon_and_known_prefix() {
+        let index = WikiIndex::from_maps(Default::default(), Default::default())
+            .with_namespaces(&["Category".to_string(), "User talk".to_string()]);
+        let ns = index.namespaces();
+        assert!(!is_namespace_link("Category theory", ns));
+        assert!(is_namespace_link("Category:Theory", ns));
+        assert!(is_namespace_link("category:Theory", ns));
+        assert!(is_namespace_link("User_talk:Example", ns));
+        assert!(
+            is_namespace_link("Image:Logo.png", ns),
+            "alias always applies"
+        );
+        // Not in the parsed set, and unrelated colon titles are kept.
+        assert!(!is_namespace_link("Template:Infobox", ns));
+        assert!(!is_namespace_link("Star Wars: Episode IV", ns));
+    }
+
+    #[test]
+    fn red_links_are_counted_apart_from_malformed_targets() {
+        let index = WikiIndex::from_maps(
+            [("Rust".to_string(), 1)].into_iter().collect(),
+            Default::default(),
+        );
+        let text = "[[Rust]] [[Missing article]] [[Foo%20Bar]] [[wikt:word]] \
+                    [[/Subpage]] [[Category:Science]]";
+        let (edges, invalid, red, _) = process_article_edges(
+            &content::scan_brackets(text).links,
+            &index,
+            index.namespaces(),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(edges.len(), 1);
+        assert_eq!((invalid, red), (4, 1));
+
+        assert!(is_red_link("Missing article"));
+        assert!(is_red_link("Star Wars: Episode IV"));
+        assert!(is_red_link(":Leading colon"));
+        assert!(!is_red_link("fr:Paris"));
+        assert!(!is_red_link("Foo%20Bar"));
+        assert!(!is_red_link("Sig ~~~~"));
+        assert!(!is_red_link(&"x".repeat(MAX_TITLE_BYTES + 1)));
+    }
+
+    #[test]
+    fn repeated_links_fold_into_one_weighted_edge() {
+        let index = WikiIndex::from_maps(
+            [("Rust".to_string(), 1), ("Go".to_string(), 2)]
+                .into_iter()
+                .collect(),
+            [(
+                "Rustlang".to_string(),
+                crate::index::Redirect {
+                    target: "Rust".to_string(),
+                    id: 3,
+                },
+            )]
+            .into_iter()
+            .collect(),
+        );
+        let text = "[[Rust]] and [[Rust|the language]], [[Go]], [[Rustlang]] again.";
+        let (edges, ..) = process_article_edges(
+            &content::scan_brackets(text).links,
+            &index,
+            index.namespaces(),
+            None,
+            None,
+            None,
+        );
+        let weights: Vec<(u64, bool, u32)> = edges.iter().map(|e| (e.0, e.3, e.4)).collect();
+        assert_eq!(weights, vec![(1, false, 3), (2, false, 1)]);
     }
 
     #[test]
@@ -680,4 +2366,44 @@ mod tests {
         );
         assert_eq!(strip_section_anchor("#Section_only"), "");
     }
+
+    #[test]
+    fn synced_shards_read_back_as_complete_csv() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let output_dir = dir.path().to_str().unwrap();
+        let stats = Arc::new(ExtractionStats::new());
+        let opts = CsvOptions {
+            shards: 2,
+            strategy: ShardStrategy::Hash,
+            max_part_bytes: None,
+            skip: false,
+            resuming: false,
+        };
+        let sink: Sink =
+            Box::new(ShardedCsvWriter::new(output_dir, "edges", opts, &stats).unwrap());
+        sink.write_headers(&[":START_ID", ":END_ID", ":TYPE"])
+            .unwrap();
+        for id in 0..50u64 {
+            let target = (id + 1).to_string();
+            sink.write_rows(id, "edge", |write| {
+                write(&[&id.to_string(), &target, "LINKS_TO"]);
+            });
+        }
+        sink.sync().unwrap();
+
+        // Read while the writers are still open, as after a crash.
+        let mut rows = 0;
+        for shard in 0..2 {
+            let path = dir.path().join(format!("edges_{shard:03}.csv"));
+            let content = fs::read_to_string(&path).unwrap();
+            assert!(content.ends_with('\n'), "{path:?} ends mid-row");
+            let mut reader = csv::Reader::from_reader(content.as_bytes());
+            for record in reader.records() {
+                assert_eq!(record.unwrap().len(), 3);
+                rows += 1;
+            }
+        }
+        assert_eq!(rows, 50);
+        drop(sink);
+    }
 }
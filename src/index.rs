@@ -1,24 +1,179 @@
 //! In-memory title-to-ID index with redirect chain resolution.
 //!
 //! `WikiIndex` wraps pre-sized `FxHashMap`s (8M articles, 10M redirects) for
-//! fast lookup of page IDs by title. Follows redirect chains up to 5 hops.
-//! Supports both sequential and multistream parallel index building.
+//! fast lookup of page IDs by title. Follows redirect chains up to
+//! `REDIRECT_MAX_DEPTH` hops by default, adjustable with
+//! `with_redirect_max_depth()`. Supports both sequential and multistream
+//! parallel index building, and incremental updates from a delta dump via
+//! `update_from()`. Also carries the dump's namespace names (from `<siteinfo>`)
+//! used to filter namespace links, and the content namespaces whose pages are
+//! indexed as articles (ns 0 by default).
+//! `write_redirect_map()` dumps every resolvable redirect to `redirect_map.csv`.
+//!
+//! Keys are titles exactly as the dump stores them, which MediaWiki has already
+//! normalized. Link targets written by editors are not: `resolve_id_normalized()`
+//! falls back to `normalize_title()` (underscores to spaces, first letter
+//! upper-cased) when the exact title misses.
 
-use crate::config::{PROGRESS_INTERVAL, REDIRECT_MAX_DEPTH};
-use crate::models::PageType;
+use crate::config::{
+    DEFAULT_CONTENT_NAMESPACES, DEFAULT_LINK_NAMESPACES, LINK_NAMESPACE_ALIASES, PROGRESS_INTERVAL,
+    REDIRECT_MAP_FILENAME, REDIRECT_MAX_DEPTH, RESOLVE_PARALLEL_THRESHOLD, SEEN_IDS_BITMAP_LIMIT,
+};
+use crate::models::{PageType, WikiPage};
 use crate::multistream::StreamRange;
 use crate::parser::WikiReader;
 use anyhow::{Context, Result};
 use indicatif::ProgressBar;
 use rayon::prelude::*;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// A redirect page: the title it points to and its own page ID. The ID lets a
+/// delta find a redirect's old title when the redirect is moved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Redirect {
+    pub target: String,
+    pub id: u64,
+}
 
 /// In-memory title-to-ID index with redirect resolution.
 pub struct WikiIndex {
-    title_to_id: FxHashMap<String, u32>,
-    redirects: FxHashMap<String, String>,
+    title_to_id: FxHashMap<String, u64>,
+    redirects: FxHashMap<String, Redirect>,
+    namespaces: Vec<String>,
+    content_namespaces: Vec<i32>,
+    /// Pages of every kind seen by the indexing pass (0 if unknown).
+    page_count: u64,
+    /// Article ID to title, only with [`with_reverse_lookup`](WikiIndex::with_reverse_lookup).
+    id_to_title: Option<FxHashMap<u64, String>>,
+    /// Pages whose ID was already seen by the indexing pass (0 if unknown).
+    duplicate_ids: u64,
+    /// Most titles visited while resolving one link, the link's own included.
+    redirect_max_depth: u32,
+}
+
+/// Page IDs seen while indexing, to count repeats. Real dumps use dense IDs
+/// (enwiki's ~80M fit a 10 MB bitmap, grown only as far as the highest ID);
+/// IDs from `SEEN_IDS_BITMAP_LIMIT` on go in a set.
+#[derive(Default)]
+struct SeenIds {
+    bits: Vec<u64>,
+    large: FxHashSet<u64>,
+    duplicates: u64,
+}
+
+impl SeenIds {
+    fn insert(&mut self, id: u64) {
+        let new = if id >= SEEN_IDS_BITMAP_LIMIT {
+            self.large.insert(id)
+        } else {
+            let (word, bit) = ((id / 64) as usize, 1u64 << (id % 64));
+            if word >= self.bits.len() {
+                self.bits.resize(word + 1, 0);
+            }
+            let new = self.bits[word] & bit == 0;
+            self.bits[word] |= bit;
+            new
+        };
+        self.duplicates += u64::from(!new);
+    }
+
+    /// Combines the IDs seen by two workers, counting IDs both saw as duplicates.
+    fn merge(mut self, mut other: Self) -> Self {
+        if other.bits.len() > self.bits.len() {
+            std::mem::swap(&mut self.bits, &mut other.bits);
+        }
+        for (word, theirs) in self.bits.iter_mut().zip(&other.bits) {
+            self.duplicates += u64::from((*word & theirs).count_ones());
+            *word |= theirs;
+        }
+        for id in other.large {
+            self.duplicates += u64::from(!self.large.insert(id));
+        }
+        self.duplicates += other.duplicates;
+        self
+    }
+}
+
+/// Logs a warning when the dump repeats page IDs.
+fn warn_duplicate_ids(duplicates: u64) {
+    if duplicates > 0 {
+        warn!(
+            duplicates,
+            "Dump repeats page IDs; extraction will write duplicate Page nodes (see --strict-ids)"
+        );
+    }
+}
+
+/// MediaWiki's default title normalization: underscores become spaces, runs
+/// of whitespace collapse to one, and the first character is upper-cased.
+/// Borrows `title` when it is already normalized.
+pub fn normalize_title(title: &str) -> Cow<'_, str> {
+    let starts_lower = title.chars().next().is_some_and(char::is_lowercase);
+    let trimmed = title.trim();
+    if !starts_lower
+        && trimmed.len() == title.len()
+        && !title.contains(['_', '\t'])
+        && !title.contains("  ")
+    {
+        return Cow::Borrowed(title);
+    }
+    let spaced = trimmed.replace('_', " ");
+    let mut words = spaced.split_whitespace();
+    let mut result = String::with_capacity(title.len());
+    if let Some(first) = words.next() {
+        let mut chars = first.chars();
+        if let Some(c) = chars.next() {
+            result.extend(c.to_uppercase());
+            result.push_str(chars.as_str());
+        }
+    }
+    for word in words {
+        result.push(' ');
+        result.push_str(word);
+    }
+    Cow::Owned(result)
+}
+
+/// Sorted, deduplicated copy of a content namespace list.
+fn normalize_content_namespaces(namespaces: &[i32]) -> Vec<i32> {
+    let mut namespaces = namespaces.to_vec();
+    namespaces.sort_unstable();
+    namespaces.dedup();
+    namespaces
+}
+
+/// Namespace names used for link filtering: those parsed from `<siteinfo>`
+/// (or `DEFAULT_LINK_NAMESPACES` if none were) plus `LINK_NAMESPACE_ALIASES`.
+fn link_namespaces(parsed: &[String]) -> Vec<String> {
+    let mut namespaces: Vec<String> = if parsed.is_empty() {
+        DEFAULT_LINK_NAMESPACES
+            .iter()
+            .map(|ns| ns.to_string())
+            .collect()
+    } else {
+        parsed.to_vec()
+    };
+    for alias in LINK_NAMESPACE_ALIASES {
+        if !namespaces.iter().any(|ns| ns == alias) {
+            namespaces.push(alias.to_string());
+        }
+    }
+    namespaces
+}
+
+/// Redirect map from `(title, target)` pairs, with page ID 0, for tests that
+/// do not exercise redirect moves.
+#[cfg(test)]
+fn redirect_map(pairs: impl IntoIterator<Item = (String, String)>) -> FxHashMap<String, Redirect> {
+    pairs
+        .into_iter()
+        .map(|(title, target)| (title, Redirect { target, id: 0 }))
+        .collect()
 }
 
 impl std::fmt::Debug for WikiIndex {
@@ -26,6 +181,12 @@ impl std::fmt::Debug for WikiIndex {
         f.debug_struct("WikiIndex")
             .field("articles", &self.title_to_id.len())
             .field("redirects", &self.redirects.len())
+            .field("namespaces", &self.namespaces.len())
+            .field("content_namespaces", &self.content_namespaces)
+            .field("page_count", &self.page_count)
+            .field("reverse_lookup", &self.id_to_title.is_some())
+            .field("duplicate_ids", &self.duplicate_ids)
+            .field("redirect_max_depth", &self.redirect_max_depth)
             .finish()
     }
 }
@@ -33,15 +194,21 @@ impl std::fmt::Debug for WikiIndex {
 impl WikiIndex {
     /// Builds the index by streaming through the dump in skip-text mode.
     pub fn build(path: &str) -> Result<Self> {
-        let mut title_to_id: FxHashMap<String, u32> = FxHashMap::with_capacity_and_hasher(
+        Self::build_for_namespaces(path, DEFAULT_CONTENT_NAMESPACES)
+    }
+
+    /// Like [`build`](Self::build), indexing non-redirect pages in any of
+    /// `content_namespaces` as articles (see `WikiPage::is_content_page`).
+    pub fn build_for_namespaces(path: &str, content_namespaces: &[i32]) -> Result<Self> {
+        let mut title_to_id: FxHashMap<String, u64> = FxHashMap::with_capacity_and_hasher(
             crate::config::INDEX_INITIAL_ARTICLES,
             Default::default(),
         );
-        let mut redirects: FxHashMap<String, String> = FxHashMap::with_capacity_and_hasher(
+        let mut redirects: FxHashMap<String, Redirect> = FxHashMap::with_capacity_and_hasher(
             crate::config::INDEX_INITIAL_REDIRECTS,
             Default::default(),
         );
-        let reader = WikiReader::new(path, true)
+        let mut reader = WikiReader::new(path, true)
             .with_context(|| format!("Failed to open wiki dump at: {}", path))?
             .skip_timestamp(true);
         let pb = ProgressBar::new_spinner();
@@ -54,16 +221,20 @@ impl WikiIndex {
 
         info!("Building index from: {}", path);
         let mut page_count: u64 = 0;
+        let mut seen_ids = SeenIds::default();
 
-        for page in reader {
-            match page.page_type {
-                PageType::Article => {
-                    title_to_id.insert(page.title, page.id);
-                }
-                PageType::Redirect(target) => {
-                    redirects.insert(page.title, target);
-                }
-                _ => {}
+        for page in reader.by_ref() {
+            seen_ids.insert(page.id);
+            if page.is_content_page(content_namespaces) {
+                title_to_id.insert(page.title, page.id);
+            } else if let PageType::Redirect(target) = page.page_type {
+                redirects.insert(
+                    page.title,
+                    Redirect {
+                        target,
+                        id: page.id,
+                    },
+                );
             }
             page_count += 1;
             if page_count.is_multiple_of(PROGRESS_INTERVAL as u64) {
@@ -79,20 +250,32 @@ impl WikiIndex {
         pb.finish_and_clear();
 
         info!(
+            pages = page_count,
             articles = title_to_id.len(),
             redirects = redirects.len(),
             "Index built successfully"
         );
+        warn_duplicate_ids(seen_ids.duplicates);
 
         Ok(Self {
             title_to_id,
             redirects,
+            namespaces: link_namespaces(reader.namespaces()),
+            content_namespaces: normalize_content_namespaces(content_namespaces),
+            page_count,
+            id_to_title: None,
+            duplicate_ids: seen_ids.duplicates,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
         })
     }
 
     /// Build index using multistream parallel parsing.
     /// Each rayon worker decompresses and parses streams independently.
-    pub fn build_multistream(dump_path: &str, ranges: &[StreamRange]) -> Result<Self> {
+    pub fn build_multistream(
+        dump_path: &str,
+        ranges: &[StreamRange],
+        content_namespaces: &[i32],
+    ) -> Result<Self> {
         info!(
             streams = ranges.len(),
             "Building index from multistream dump: {}", dump_path
@@ -109,86 +292,321 @@ impl WikiIndex {
         let page_count = AtomicU64::new(0);
 
         // Parallel fold: each thread collects into local vecs, then reduce merges
-        let (articles_vec, redirects_vec) = ranges
+        let (articles_vec, redirects_vec, seen_ids) = ranges
             .par_iter()
             .fold(
-                || (Vec::new(), Vec::new()),
-                |(mut articles, mut redirects), range| {
+                || (Vec::new(), Vec::new(), SeenIds::default()),
+                |(mut articles, mut redirects, mut seen_ids), range| {
                     let pages = crate::multistream::parse_stream_for_index(dump_path, range);
                     for page in pages {
+                        seen_ids.insert(page.id);
                         let count = page_count.fetch_add(1, Ordering::Relaxed);
                         if (count + 1).is_multiple_of(PROGRESS_INTERVAL as u64) {
                             pb.set_message(format!("Indexing: {} pages", count + 1));
                         }
-                        match page.page_type {
-                            PageType::Article => {
-                                articles.push((page.title, page.id));
-                            }
-                            PageType::Redirect(target) => {
-                                redirects.push((page.title, target));
-                            }
-                            _ => {}
+                        if page.is_content_page(content_namespaces) {
+                            articles.push((page.title, page.id));
+                        } else if let PageType::Redirect(target) = page.page_type {
+                            redirects.push((
+                                page.title,
+                                Redirect {
+                                    target,
+                                    id: page.id,
+                                },
+                            ));
                         }
                     }
-                    (articles, redirects)
+                    (articles, redirects, seen_ids)
                 },
             )
             .reduce(
-                || (Vec::new(), Vec::new()),
-                |(mut a1, mut r1), (a2, r2)| {
+                || (Vec::new(), Vec::new(), SeenIds::default()),
+                |(mut a1, mut r1, s1), (a2, r2, s2)| {
                     a1.extend(a2);
                     r1.extend(r2);
-                    (a1, r1)
+                    (a1, r1, s1.merge(s2))
                 },
             );
 
         pb.finish_and_clear();
 
-        let mut title_to_id: FxHashMap<String, u32> =
+        let mut title_to_id: FxHashMap<String, u64> =
             FxHashMap::with_capacity_and_hasher(articles_vec.len(), Default::default());
         for (title, id) in articles_vec {
             title_to_id.insert(title, id);
         }
 
-        let mut redirects: FxHashMap<String, String> =
+        let mut redirects: FxHashMap<String, Redirect> =
             FxHashMap::with_capacity_and_hasher(redirects_vec.len(), Default::default());
-        for (title, target) in redirects_vec {
-            redirects.insert(title, target);
+        for (title, redirect) in redirects_vec {
+            redirects.insert(title, redirect);
         }
 
+        let page_count = page_count.into_inner();
         info!(
+            pages = page_count,
             articles = title_to_id.len(),
             redirects = redirects.len(),
             "Index built successfully (multistream)"
         );
+        warn_duplicate_ids(seen_ids.duplicates);
 
+        let namespaces = crate::multistream::parse_siteinfo_namespaces(dump_path, ranges);
         Ok(Self {
             title_to_id,
             redirects,
+            namespaces: link_namespaces(&namespaces),
+            content_namespaces: normalize_content_namespaces(content_namespaces),
+            page_count,
+            id_to_title: None,
+            duplicate_ids: seen_ids.duplicates,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
         })
     }
 
     /// Returns references to the underlying title-to-ID and redirect maps.
     #[must_use]
-    pub fn maps(&self) -> (&FxHashMap<String, u32>, &FxHashMap<String, String>) {
+    pub fn maps(&self) -> (&FxHashMap<String, u64>, &FxHashMap<String, Redirect>) {
         (&self.title_to_id, &self.redirects)
     }
 
-    /// Constructs an index from pre-built maps (e.g. deserialized from cache).
+    /// Constructs an index from pre-built maps (e.g. deserialized from cache),
+    /// filtering links with the default namespace list.
     pub fn from_maps(
-        title_to_id: FxHashMap<String, u32>,
-        redirects: FxHashMap<String, String>,
+        title_to_id: FxHashMap<String, u64>,
+        redirects: FxHashMap<String, Redirect>,
     ) -> Self {
         Self {
             title_to_id,
             redirects,
+            namespaces: link_namespaces(&[]),
+            content_namespaces: DEFAULT_CONTENT_NAMESPACES.to_vec(),
+            page_count: 0,
+            id_to_title: None,
+            duplicate_ids: 0,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
         }
     }
 
+    /// Replaces the namespaces whose pages count as articles.
+    #[must_use]
+    pub fn with_content_namespaces(mut self, content_namespaces: &[i32]) -> Self {
+        self.content_namespaces = normalize_content_namespaces(content_namespaces);
+        self
+    }
+
+    /// Namespaces whose pages are indexed and extracted as articles, ascending.
+    #[must_use]
+    pub fn content_namespaces(&self) -> &[i32] {
+        &self.content_namespaces
+    }
+
+    /// Sets the indexing pass's page count (e.g. restored from the cache).
+    #[must_use]
+    pub fn with_page_count(mut self, page_count: u64) -> Self {
+        self.page_count = page_count;
+        self
+    }
+
+    /// Pages of every kind (articles, redirects, other namespaces) seen while
+    /// building the index; 0 for an index assembled from maps.
+    #[must_use]
+    pub fn page_count(&self) -> u64 {
+        self.page_count
+    }
+
+    /// Builds the ID-to-title map behind [`resolve_title`](Self::resolve_title).
+    /// Off by default: it holds a second copy of every article title (roughly
+    /// doubling the article map's memory, ~50 bytes per article on top of the
+    /// title), which the extraction passes never need. Kept up to date by
+    /// [`update_from`](Self::update_from) and [`remove_titles`](Self::remove_titles).
+    #[must_use]
+    pub fn with_reverse_lookup(mut self) -> Self {
+        self.rebuild_reverse_lookup();
+        self
+    }
+
+    fn rebuild_reverse_lookup(&mut self) {
+        let mut id_to_title =
+            FxHashMap::with_capacity_and_hasher(self.title_to_id.len(), Default::default());
+        for (title, &id) in &self.title_to_id {
+            id_to_title.insert(id, title.clone());
+        }
+        self.id_to_title = Some(id_to_title);
+    }
+
+    /// Title of the article with page ID `id`. Always `None` unless the index
+    /// was built [`with_reverse_lookup`](Self::with_reverse_lookup); redirect
+    /// pages have no entry.
+    #[must_use]
+    pub fn resolve_title(&self, id: u64) -> Option<&str> {
+        self.id_to_title.as_ref()?.get(&id).map(String::as_str)
+    }
+
+    /// Sets the indexing pass's duplicate ID count (e.g. restored from the cache).
+    #[must_use]
+    pub fn with_duplicate_ids(mut self, duplicate_ids: u64) -> Self {
+        self.duplicate_ids = duplicate_ids;
+        self
+    }
+
+    /// Pages the indexing pass saw with an ID an earlier page already had;
+    /// each would become a duplicate `Page` node. 0 for an index assembled
+    /// from maps.
+    #[must_use]
+    pub fn duplicate_ids(&self) -> u64 {
+        self.duplicate_ids
+    }
+
+    /// Sets how many titles a redirect chain may visit before resolution gives
+    /// up (default `REDIRECT_MAX_DEPTH`, 5). The requested title counts, so a
+    /// depth of `n` follows at most `n - 1` redirects; 1 disables redirects.
+    /// Each extra level is one more hash lookup per link, paid only by links
+    /// that go through a long chain or loop, so raising it costs little.
+    #[must_use]
+    pub fn with_redirect_max_depth(mut self, depth: u32) -> Self {
+        self.redirect_max_depth = depth.max(1);
+        self
+    }
+
+    /// See [`with_redirect_max_depth`](Self::with_redirect_max_depth).
+    #[must_use]
+    pub fn redirect_max_depth(&self) -> u32 {
+        self.redirect_max_depth
+    }
+
+    /// Replaces the namespace names used for link filtering; an empty list
+    /// restores the defaults.
+    #[must_use]
+    pub fn with_namespaces(mut self, namespaces: &[String]) -> Self {
+        self.namespaces = link_namespaces(namespaces);
+        self
+    }
+
+    /// Namespace names whose `Name:` prefix marks a link as a namespace link.
+    #[must_use]
+    pub fn namespaces(&self) -> &[String] {
+        &self.namespaces
+    }
+
+    /// Applies a delta dump (e.g. an incremental `pages-articles` export) on top
+    /// of this index. Pages in the delta are authoritative for their title and ID;
+    /// later pages in the delta win over earlier ones:
+    ///
+    /// - **Article** (any non-redirect in a content namespace): `title -> id`
+    ///   is inserted or replaced and any redirect at that title is dropped.
+    /// - **Redirect**: the redirect is inserted or retargeted and any article
+    ///   entry at that title is dropped (article turned into a redirect).
+    /// - **Special** (moved out of the content namespaces): the title is removed
+    ///   from both maps.
+    ///
+    /// If a page's ID was previously indexed under a different title, as an
+    /// article or a redirect (a page move), the old title is removed first.
+    ///
+    /// Dumps do not record deletions, so pages deleted outright must be passed
+    /// to [`remove_titles`](Self::remove_titles) (e.g. from a deletion log).
+    pub fn update_from(&mut self, delta_path: &str) -> Result<()> {
+        let reader = WikiReader::new(delta_path, true)
+            .with_context(|| format!("Failed to open delta dump at: {}", delta_path))?
+            .skip_timestamp(true);
+
+        info!("Applying index delta from: {}", delta_path);
+        let (articles_before, redirects_before) = self.stats();
+        let pages = self.apply_delta(reader);
+        let (articles, redirects) = self.stats();
+
+        info!(
+            pages,
+            articles,
+            redirects,
+            article_change = articles as i64 - articles_before as i64,
+            redirect_change = redirects as i64 - redirects_before as i64,
+            "Index delta applied"
+        );
+        Ok(())
+    }
+
+    /// Applies delta pages (see [`update_from`](Self::update_from)) and returns
+    /// the number of pages consumed.
+    fn apply_delta(&mut self, pages: impl IntoIterator<Item = WikiPage>) -> usize {
+        // Deltas are small, so collect them to find moved pages in one pass
+        // over the (large) base map instead of keeping a reverse ID index.
+        let pages: Vec<WikiPage> = pages.into_iter().collect();
+        let delta_titles: FxHashMap<u64, &str> =
+            pages.iter().map(|p| (p.id, p.title.as_str())).collect();
+
+        let is_moved = |title: &str, id: u64| {
+            delta_titles
+                .get(&id)
+                .is_some_and(|new_title| *new_title != title)
+        };
+        let moved: Vec<String> = self
+            .title_to_id
+            .iter()
+            .filter(|(title, id)| is_moved(title, **id))
+            .map(|(title, _)| title.clone())
+            .collect();
+        for title in &moved {
+            debug!(title = %title, "Removing moved title");
+            self.title_to_id.remove(title);
+        }
+        let moved_redirects: Vec<String> = self
+            .redirects
+            .iter()
+            .filter(|(title, redirect)| is_moved(title, redirect.id))
+            .map(|(title, _)| title.clone())
+            .collect();
+        for title in &moved_redirects {
+            debug!(title = %title, "Removing moved redirect");
+            self.redirects.remove(title);
+        }
+
+        let count = pages.len();
+        let had_reverse = self.id_to_title.take().is_some();
+        for page in pages {
+            if page.is_content_page(&self.content_namespaces) {
+                self.redirects.remove(&page.title);
+                self.title_to_id.insert(page.title, page.id);
+            } else if let PageType::Redirect(target) = page.page_type {
+                self.title_to_id.remove(&page.title);
+                self.redirects.insert(
+                    page.title,
+                    Redirect {
+                        target,
+                        id: page.id,
+                    },
+                );
+            } else {
+                self.title_to_id.remove(&page.title);
+                self.redirects.remove(&page.title);
+            }
+        }
+        if had_reverse {
+            self.rebuild_reverse_lookup();
+        }
+        count
+    }
+
+    /// Removes deleted pages from the index. Returns how many titles were found.
+    pub fn remove_titles<'a>(&mut self, titles: impl IntoIterator<Item = &'a str>) -> usize {
+        let titles: FxHashSet<&str> = titles.into_iter().collect();
+        titles
+            .into_iter()
+            .filter(|title| {
+                let article = self.title_to_id.remove(*title);
+                if let (Some(id), Some(id_to_title)) = (article, self.id_to_title.as_mut()) {
+                    id_to_title.remove(&id);
+                }
+                let redirect = self.redirects.remove(*title).is_some();
+                article.is_some() || redirect
+            })
+            .count()
+    }
+
     #[cfg(test)]
     #[allow(clippy::type_complexity)]
-    pub fn to_serializable(&self) -> (Vec<(String, u32)>, Vec<(String, String)>) {
-        let articles: Vec<(String, u32)> = self
+    pub fn to_serializable(&self) -> (Vec<(String, u64)>, Vec<(String, String)>) {
+        let articles: Vec<(String, u64)> = self
             .title_to_id
             .iter()
             .map(|(k, v)| (k.clone(), *v))
@@ -196,19 +614,25 @@ impl WikiIndex {
         let redirects: Vec<(String, String)> = self
             .redirects
             .iter()
-            .map(|(k, v)| (k.clone(), v.clone()))
+            .map(|(k, v)| (k.clone(), v.target.clone()))
             .collect();
         (articles, redirects)
     }
 
     #[cfg(test)]
     pub fn from_serializable(
-        articles: Vec<(String, u32)>,
+        articles: Vec<(String, u64)>,
         redirects: Vec<(String, String)>,
     ) -> Self {
         Self {
             title_to_id: articles.into_iter().collect(),
-            redirects: redirects.into_iter().collect(),
+            redirects: redirect_map(redirects),
+            namespaces: link_namespaces(&[]),
+            content_namespaces: DEFAULT_CONTENT_NAMESPACES.to_vec(),
+            page_count: 0,
+            id_to_title: None,
+            duplicate_ids: 0,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
         }
     }
 
@@ -220,15 +644,24 @@ impl WikiIndex {
 
     /// Resolves a page title to its numeric ID, following redirect chains.
     #[must_use]
-    pub fn resolve_id(&self, title: &str) -> Option<u32> {
+    pub fn resolve_id(&self, title: &str) -> Option<u64> {
+        self.resolve_id_via_redirect(title).map(|(id, _)| id)
+    }
+
+    /// Like [`resolve_id`](Self::resolve_id), also reporting whether at least
+    /// one redirect was followed, i.e. whether
+    /// [`resolve_with_chain`](Self::resolve_with_chain) would return a chain
+    /// longer than the requested title alone.
+    #[must_use]
+    pub fn resolve_id_via_redirect(&self, title: &str) -> Option<(u64, bool)> {
         let mut current = title;
         let mut depth = 0;
 
-        while depth < REDIRECT_MAX_DEPTH {
+        while depth < self.redirect_max_depth {
             if let Some(id) = self.title_to_id.get(current) {
-                return Some(*id);
+                return Some((*id, depth > 0));
             }
-            if let Some(target) = self.redirects.get(current) {
+            if let Some(Redirect { target, .. }) = self.redirects.get(current) {
                 debug!(from = current, to = target, "Following redirect");
                 current = target;
                 depth += 1;
@@ -239,22 +672,124 @@ impl WikiIndex {
         debug!(title = title, "Redirect chain too deep");
         None
     }
+
+    /// Like [`resolve_id`](Self::resolve_id), but a title that misses is
+    /// retried in its [`normalize_title`] form, so `Rust_(programming_language)`
+    /// and `rust (programming language)` find `Rust (programming language)`.
+    /// Exact titles are tried first, so lowercase titles on wikis without
+    /// first-letter capitalization still resolve to themselves.
+    #[must_use]
+    pub fn resolve_id_normalized(&self, title: &str) -> Option<u64> {
+        self.resolve_id_normalized_via_redirect(title)
+            .map(|(id, _)| id)
+    }
+
+    /// [`resolve_id_normalized`](Self::resolve_id_normalized) with the redirect
+    /// flag of [`resolve_id_via_redirect`](Self::resolve_id_via_redirect).
+    #[must_use]
+    pub fn resolve_id_normalized_via_redirect(&self, title: &str) -> Option<(u64, bool)> {
+        self.resolve_id_via_redirect(title)
+            .or_else(|| match normalize_title(title) {
+                Cow::Owned(normalized) => self.resolve_id_via_redirect(&normalized),
+                Cow::Borrowed(_) => None,
+            })
+    }
+
+    /// Resolves a batch of titles, in order. Large batches are resolved in
+    /// parallel with rayon.
+    #[must_use]
+    pub fn resolve_many<'a>(&self, titles: impl IntoIterator<Item = &'a str>) -> Vec<Option<u64>> {
+        let titles: Vec<&str> = titles.into_iter().collect();
+        if titles.len() < RESOLVE_PARALLEL_THRESHOLD {
+            titles.iter().map(|title| self.resolve_id(title)).collect()
+        } else {
+            titles
+                .par_iter()
+                .map(|title| self.resolve_id(title))
+                .collect()
+        }
+    }
+
+    /// Like [`resolve_id`](Self::resolve_id), but also returns every title
+    /// visited: the requested title, any intermediate redirects, and finally
+    /// the article title. A direct hit yields a single-element chain.
+    #[must_use]
+    pub fn resolve_with_chain(&self, title: &str) -> Option<(u64, Vec<&str>)> {
+        let mut chain = Vec::new();
+        let mut current = title;
+
+        for _ in 0..self.redirect_max_depth {
+            if let Some((article, id)) = self.title_to_id.get_key_value(current) {
+                chain.push(article.as_str());
+                return Some((*id, chain));
+            }
+            let (redirect, Redirect { target, .. }) = self.redirects.get_key_value(current)?;
+            chain.push(redirect.as_str());
+            current = target;
+        }
+        debug!(title = title, chain = ?chain, "Redirect chain too deep");
+        None
+    }
+
+    /// Every redirect that resolves to an article, as `(source_title,
+    /// target_title, target_id)` sorted by source. The target is the article
+    /// at the end of the chain, not the redirect's immediate target; broken
+    /// and too-deep chains are left out.
+    #[must_use]
+    pub fn resolved_redirects(&self) -> Vec<(&str, &str, u64)> {
+        let mut rows: Vec<(&str, &str, u64)> = self
+            .redirects
+            .keys()
+            .filter_map(|source| {
+                let (id, chain) = self.resolve_with_chain(source)?;
+                Some((source.as_str(), *chain.last()?, id))
+            })
+            .collect();
+        rows.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        rows
+    }
+
+    /// Writes [`resolved_redirects`](Self::resolved_redirects) to
+    /// `redirect_map.csv` (`source_title,target_title,target_id`) in
+    /// `output_dir`. Returns the path and the number of rows.
+    pub fn write_redirect_map(&self, output_dir: &str) -> Result<(PathBuf, usize)> {
+        let path = Path::new(output_dir).join(REDIRECT_MAP_FILENAME);
+        let mut writer = csv::Writer::from_path(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        writer.write_record(["source_title", "target_title", "target_id"])?;
+        let rows = self.resolved_redirects();
+        let mut itoa_buf = itoa::Buffer::new();
+        for (source, target, id) in &rows {
+            writer.write_record([*source, *target, itoa_buf.format(*id)])?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok((path, rows.len()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn make_index(articles: Vec<(&str, u32)>, redirects: Vec<(&str, &str)>) -> WikiIndex {
+    fn make_index(articles: Vec<(&str, u64)>, redirects: Vec<(&str, &str)>) -> WikiIndex {
         WikiIndex {
+            namespaces: link_namespaces(&[]),
+            content_namespaces: DEFAULT_CONTENT_NAMESPACES.to_vec(),
+            page_count: 0,
+            id_to_title: None,
+            duplicate_ids: 0,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
             title_to_id: articles
                 .into_iter()
                 .map(|(k, v)| (k.to_string(), v))
                 .collect(),
-            redirects: redirects
-                .into_iter()
-                .map(|(k, v)| (k.to_string(), v.to_string()))
-                .collect(),
+            redirects: redirect_map(
+                redirects
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string())),
+            ),
         }
     }
 
@@ -288,14 +823,20 @@ mod tests {
             redirects.push((format!("R{}", i), format!("R{}", i + 1)));
         }
         let final_title = format!("R{}", REDIRECT_MAX_DEPTH - 1);
-        let articles = vec![(final_title.as_str(), 1u32)];
+        let articles = vec![(final_title.as_str(), 1u64)];
 
         let index = WikiIndex {
+            namespaces: link_namespaces(&[]),
+            content_namespaces: DEFAULT_CONTENT_NAMESPACES.to_vec(),
+            page_count: 0,
+            id_to_title: None,
+            duplicate_ids: 0,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
             title_to_id: articles
                 .into_iter()
                 .map(|(k, v)| (k.to_string(), v))
                 .collect(),
-            redirects: redirects.into_iter().map(|(k, v)| (k, v)).collect(),
+            redirects: redirect_map(redirects),
         };
 
         assert_eq!(index.resolve_id("R0"), Some(1));
@@ -311,13 +852,45 @@ mod tests {
         let final_title = format!("R{}", REDIRECT_MAX_DEPTH + 1);
 
         let index = WikiIndex {
+            namespaces: link_namespaces(&[]),
+            content_namespaces: DEFAULT_CONTENT_NAMESPACES.to_vec(),
+            page_count: 0,
+            id_to_title: None,
+            duplicate_ids: 0,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
             title_to_id: [(final_title, 1)].into_iter().collect(),
-            redirects: redirects.into_iter().collect(),
+            redirects: redirect_map(redirects),
         };
 
         assert_eq!(index.resolve_id("R0"), None);
     }
 
+    #[test]
+    fn redirect_max_depth_is_configurable() {
+        // Six titles: R0 -> R1 -> ... -> R5 (the article).
+        let redirects: Vec<(String, String)> = (0..5)
+            .map(|i| (format!("R{i}"), format!("R{}", i + 1)))
+            .collect();
+        let redirects: Vec<(&str, &str)> = redirects
+            .iter()
+            .map(|(from, to)| (from.as_str(), to.as_str()))
+            .collect();
+        let index = make_index(vec![("R5", 1)], redirects);
+        assert_eq!(index.redirect_max_depth(), REDIRECT_MAX_DEPTH);
+        assert_eq!(index.resolve_id("R0"), None);
+
+        let index = index.with_redirect_max_depth(6);
+        assert_eq!(index.resolve_id("R0"), Some(1));
+        assert_eq!(index.resolve_with_chain("R0").unwrap().1.len(), 6);
+        let index = index.with_redirect_max_depth(5);
+        assert_eq!(index.resolve_id("R0"), None);
+        assert_eq!(index.resolve_id("R1"), Some(1));
+
+        let index = index.with_redirect_max_depth(1);
+        assert_eq!(index.resolve_id("R4"), None);
+        assert_eq!(index.resolve_id("R5"), Some(1));
+    }
+
     #[test]
     fn resolve_circular_redirect() {
         let index = make_index(vec![], vec![("A", "B"), ("B", "C"), ("C", "A")]);
@@ -356,6 +929,98 @@ mod tests {
         assert_eq!(index.resolve_id("RUST"), None);
     }
 
+    #[test]
+    fn resolve_normalized_ignores_underscores_and_first_letter_case() {
+        let index = make_index(
+            vec![("Rust (programming language)", 1), ("iPod", 2)],
+            vec![("Rust lang", "Rust (programming language)")],
+        );
+        for title in [
+            "Rust (programming language)",
+            "Rust_(programming_language)",
+            "rust (programming language)",
+            "rust_(programming_language)",
+            " Rust  (programming language) ",
+        ] {
+            assert_eq!(index.resolve_id_normalized(title), Some(1), "{title:?}");
+        }
+        assert_eq!(index.resolve_id("Rust_(programming_language)"), None);
+        assert_eq!(
+            index.resolve_id_normalized_via_redirect("rust_lang"),
+            Some((1, true))
+        );
+        // An exact lowercase title is not upper-cased away.
+        assert_eq!(index.resolve_id_normalized("iPod"), Some(2));
+        // Only the first letter is case-insensitive.
+        assert_eq!(
+            index.resolve_id_normalized("Rust (Programming Language)"),
+            None
+        );
+        assert!(matches!(normalize_title("Already fine"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn resolve_title_roundtrips_resolve_id() {
+        let titles = ["Rust", "Python (programming language)", "C++", "Zürich"];
+        let articles = titles
+            .iter()
+            .zip(1u64..)
+            .map(|(t, id)| (*t, id * 10))
+            .collect();
+        let index = make_index(articles, vec![("Rustlang", "Rust")]);
+        assert_eq!(index.resolve_title(10), None, "off unless requested");
+
+        let mut index = index.with_reverse_lookup();
+        for title in titles {
+            assert_eq!(
+                index.resolve_title(index.resolve_id(title).unwrap()),
+                Some(title)
+            );
+        }
+        assert_eq!(
+            index.resolve_title(index.resolve_id("Rustlang").unwrap()),
+            Some("Rust")
+        );
+        assert_eq!(index.resolve_title(99), None);
+
+        index.remove_titles(["C++"]);
+        assert_eq!(index.resolve_title(30), None);
+        index.apply_delta([delta_page(10, "Rust (language)", PageType::Article)]);
+        assert_eq!(index.resolve_title(10), Some("Rust (language)"));
+    }
+
+    #[test]
+    fn seen_ids_count_repeats_within_and_across_workers() {
+        let mut a = SeenIds::default();
+        for id in [1, 64, 5_000, 1 << 40, 64] {
+            a.insert(id);
+        }
+        assert_eq!(a.duplicates, 1);
+        let mut b = SeenIds::default();
+        for id in [2, 5_000, 1 << 40, 1 << 20] {
+            b.insert(id);
+        }
+        assert_eq!(b.duplicates, 0);
+        let merged = SeenIds::default().merge(b).merge(a);
+        assert_eq!(merged.duplicates, 3);
+    }
+
+    #[test]
+    fn seen_ids_bitmap_grows_only_to_the_highest_small_id() {
+        let mut seen = SeenIds::default();
+        seen.insert(u64::from(u32::MAX) - 1);
+        seen.insert(SEEN_IDS_BITMAP_LIMIT);
+        assert!(seen.bits.is_empty());
+        assert_eq!(seen.large.len(), 2);
+
+        seen.insert(1_000);
+        assert_eq!(seen.bits.len(), 1_000 / 64 + 1);
+        seen.insert(SEEN_IDS_BITMAP_LIMIT - 1);
+        assert_eq!(seen.bits.len(), (SEEN_IDS_BITMAP_LIMIT / 64) as usize);
+        seen.insert(u64::from(u32::MAX) - 1);
+        assert_eq!(seen.duplicates, 1);
+    }
+
     #[test]
     fn serialization_roundtrip() {
         let original = make_index(
@@ -384,6 +1049,233 @@ mod tests {
         assert_eq!(redirect_count, 2);
     }
 
+    #[test]
+    fn resolve_many_mixed_batch() {
+        let index = make_index(vec![("Rust", 1), ("Python", 2)], vec![("RS", "Rust")]);
+        let results = index.resolve_many(["RS", "Python", "Missing"]);
+        assert_eq!(results, vec![Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn resolve_many_parallel_batch_preserves_order() {
+        let index = make_index(vec![("Rust", 1), ("Python", 2)], vec![("RS", "Rust")]);
+        let titles: Vec<&str> = ["RS", "Python", "Missing"]
+            .into_iter()
+            .cycle()
+            .take(RESOLVE_PARALLEL_THRESHOLD + 3)
+            .collect();
+        let results = index.resolve_many(titles.iter().copied());
+        assert_eq!(results.len(), titles.len());
+        for (chunk, expected) in results
+            .chunks(3)
+            .zip(std::iter::repeat([Some(1), Some(2), None]))
+        {
+            assert_eq!(chunk, &expected[..chunk.len()]);
+        }
+    }
+
+    #[test]
+    fn resolve_with_chain_direct_hit() {
+        let index = make_index(vec![("Rust", 1)], vec![]);
+        assert_eq!(index.resolve_with_chain("Rust"), Some((1, vec!["Rust"])));
+    }
+
+    #[test]
+    fn resolve_with_chain_follows_redirects() {
+        let index = make_index(vec![("C", 3)], vec![("A", "B"), ("B", "C")]);
+        assert_eq!(
+            index.resolve_with_chain("A"),
+            Some((3, vec!["A", "B", "C"]))
+        );
+    }
+
+    #[test]
+    fn resolve_with_chain_miss_and_cycle() {
+        let index = make_index(vec![], vec![("A", "B"), ("B", "A"), ("X", "Gone")]);
+        assert_eq!(index.resolve_with_chain("Missing"), None);
+        assert_eq!(index.resolve_with_chain("X"), None);
+        assert_eq!(index.resolve_with_chain("A"), None);
+    }
+
+    #[test]
+    fn resolve_with_chain_agrees_with_resolve_id_at_depth_limit() {
+        let ok: Vec<(String, String)> = (0..REDIRECT_MAX_DEPTH - 1)
+            .map(|i| (format!("R{}", i), format!("R{}", i + 1)))
+            .collect();
+        let index = WikiIndex {
+            namespaces: link_namespaces(&[]),
+            content_namespaces: DEFAULT_CONTENT_NAMESPACES.to_vec(),
+            page_count: 0,
+            id_to_title: None,
+            duplicate_ids: 0,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
+            title_to_id: [(format!("R{}", REDIRECT_MAX_DEPTH - 1), 1)]
+                .into_iter()
+                .collect(),
+            redirects: redirect_map(ok),
+        };
+        let (id, chain) = index.resolve_with_chain("R0").unwrap();
+        assert_eq!(Some(id), index.resolve_id("R0"));
+        assert_eq!(chain.len(), REDIRECT_MAX_DEPTH as usize);
+
+        // One more hop than resolve_id follows
+        let too_deep: Vec<(String, String)> = (0..REDIRECT_MAX_DEPTH)
+            .map(|i| (format!("R{}", i), format!("R{}", i + 1)))
+            .collect();
+        let index = WikiIndex {
+            namespaces: link_namespaces(&[]),
+            content_namespaces: DEFAULT_CONTENT_NAMESPACES.to_vec(),
+            page_count: 0,
+            id_to_title: None,
+            duplicate_ids: 0,
+            redirect_max_depth: REDIRECT_MAX_DEPTH,
+            title_to_id: [(format!("R{}", REDIRECT_MAX_DEPTH), 1)]
+                .into_iter()
+                .collect(),
+            redirects: redirect_map(too_deep),
+        };
+        assert_eq!(index.resolve_id("R0"), None);
+        assert_eq!(index.resolve_with_chain("R0"), None);
+    }
+
+    fn delta_page(id: u64, title: &str, page_type: PageType) -> WikiPage {
+        WikiPage {
+            id,
+            title: title.to_string(),
+            page_type,
+            text: None,
+            ns: Some(0),
+            timestamp: None,
+            offset: None,
+            revisions: 1,
+            text_bytes: None,
+            text_sha1: None,
+        }
+    }
+
+    #[test]
+    fn delta_adds_new_articles_and_redirects() {
+        let mut index = make_index(vec![("Rust", 1)], vec![]);
+        let consumed = index.apply_delta(vec![
+            delta_page(2, "Go", PageType::Article),
+            delta_page(3, "Golang", PageType::Redirect("Go".to_string())),
+        ]);
+        assert_eq!(consumed, 2);
+        assert_eq!(index.resolve_id("Rust"), Some(1));
+        assert_eq!(index.resolve_id("Go"), Some(2));
+        assert_eq!(index.resolve_id("Golang"), Some(2));
+        assert_eq!(index.stats(), (2, 1));
+    }
+
+    #[test]
+    fn delta_retargets_changed_redirect() {
+        let mut index = make_index(vec![("A", 1), ("B", 2)], vec![("R", "A")]);
+        index.apply_delta(vec![delta_page(
+            9,
+            "R",
+            PageType::Redirect("B".to_string()),
+        )]);
+        assert_eq!(index.resolve_id("R"), Some(2));
+    }
+
+    #[test]
+    fn delta_article_becomes_redirect_and_back() {
+        let mut index = make_index(vec![("A", 1), ("B", 2)], vec![]);
+        index.apply_delta(vec![delta_page(
+            1,
+            "A",
+            PageType::Redirect("B".to_string()),
+        )]);
+        assert_eq!(index.resolve_id("A"), Some(2));
+        assert_eq!(index.stats(), (1, 1));
+
+        index.apply_delta(vec![delta_page(1, "A", PageType::Article)]);
+        assert_eq!(index.resolve_id("A"), Some(1));
+        assert_eq!(index.stats(), (2, 0));
+    }
+
+    #[test]
+    fn delta_page_move_drops_old_title() {
+        let mut index = make_index(vec![("Old name", 1), ("Other", 2)], vec![]);
+        index.apply_delta(vec![
+            delta_page(1, "New name", PageType::Article),
+            delta_page(5, "Old name", PageType::Redirect("New name".to_string())),
+        ]);
+        assert_eq!(index.resolve_id("New name"), Some(1));
+        assert_eq!(index.resolve_id("Old name"), Some(1));
+        assert_eq!(index.resolve_id("Other"), Some(2));
+        assert_eq!(index.stats(), (2, 1));
+    }
+
+    #[test]
+    fn delta_redirect_move_drops_old_title() {
+        let mut index = make_index(vec![("A", 1)], vec![]);
+        index.apply_delta(vec![delta_page(
+            7,
+            "Old redirect",
+            PageType::Redirect("A".to_string()),
+        )]);
+        index.apply_delta(vec![delta_page(
+            7,
+            "New redirect",
+            PageType::Redirect("A".to_string()),
+        )]);
+        assert_eq!(index.resolve_id("New redirect"), Some(1));
+        assert_eq!(index.resolve_id("Old redirect"), None);
+        assert_eq!(index.stats(), (1, 1));
+
+        // A redirect moved and turned into an article.
+        index.apply_delta(vec![delta_page(7, "B", PageType::Article)]);
+        assert_eq!(index.resolve_id("New redirect"), None);
+        assert_eq!(index.stats(), (2, 0));
+    }
+
+    #[test]
+    fn delta_special_page_removes_title() {
+        let mut index = make_index(vec![("A", 1)], vec![("R", "A")]);
+        index.apply_delta(vec![
+            delta_page(1, "A", PageType::Special),
+            delta_page(2, "R", PageType::Special),
+        ]);
+        assert_eq!(index.stats(), (0, 0));
+    }
+
+    #[test]
+    fn remove_titles_deletes_articles_and_redirects() {
+        let mut index = make_index(vec![("A", 1), ("B", 2)], vec![("R", "A")]);
+        let removed = index.remove_titles(["A", "R", "Missing"]);
+        assert_eq!(removed, 2);
+        assert_eq!(index.resolve_id("A"), None);
+        assert_eq!(index.resolve_id("R"), None);
+        assert_eq!(index.resolve_id("B"), Some(2));
+    }
+
+    #[test]
+    fn resolve_id_via_redirect_matches_chain_length() {
+        let index = make_index(vec![("Article", 7)], vec![("B", "Article"), ("A", "B")]);
+        for title in ["Article", "A", "B"] {
+            let (_, chain) = index.resolve_with_chain(title).unwrap();
+            assert_eq!(
+                index.resolve_id_via_redirect(title),
+                Some((7, chain.len() > 1)),
+                "{title}"
+            );
+        }
+        assert_eq!(index.resolve_id_via_redirect("Missing"), None);
+    }
+
+    #[test]
+    fn resolved_redirects_follow_chains_and_skip_broken() {
+        let index = make_index(
+            vec![("Article", 7)],
+            vec![("B", "Article"), ("A", "B"), ("Broken", "Missing")],
+        );
+        assert_eq!(
+            index.resolved_redirects(),
+            vec![("A", "Article", 7), ("B", "Article", 7)]
+        );
+    }
+
     #[test]
     fn stats_empty_index() {
         let index = make_index(vec![], vec![]);
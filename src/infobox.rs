@@ -3,8 +3,14 @@
 //! Correctly handles nested `{{...}}` templates using depth tracking with
 //! SIMD-accelerated `memchr` to skip over plain text between brace pairs.
 
+use crate::content::{normalize_image_name, to_plain_text};
 use memchr::{memchr, memchr2, memchr3};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Infobox fields whose value is an image filename (matched case-insensitively,
+/// with spaces treated as underscores).
+pub const INFOBOX_IMAGE_FIELDS: &[&str] = &["image", "logo", "flag", "coat_of_arms", "photo"];
 
 /// A parsed `{{Infobox ...}}` template with its type name and key-value fields.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -13,6 +19,20 @@ pub struct Infobox {
     pub fields: Vec<(String, String)>,
 }
 
+impl Infobox {
+    /// Readable value of `key` via `content::to_plain_text` (links unwrapped,
+    /// simple templates replaced by their argument, markup dropped). Keys match
+    /// case-insensitively with spaces treated as underscores; the first match
+    /// wins. `None` if the field is missing or has no readable text. The raw
+    /// value in `fields` is unchanged.
+    #[must_use]
+    pub fn clean_value(&self, key: &str) -> Option<String> {
+        let (_, value) = self.fields.iter().find(|(k, _)| keys_match(k, key))?;
+        let clean = to_plain_text(value);
+        (!clean.is_empty()).then_some(clean)
+    }
+}
+
 /// Extracts all `{{Infobox ...}}` templates from article wikitext.
 pub fn extract_infoboxes(text: &str) -> Vec<Infobox> {
     let mut results = Vec::new();
@@ -35,6 +55,55 @@ pub fn extract_infoboxes(text: &str) -> Vec<Infobox> {
     results
 }
 
+/// Image filenames referenced by known image fields (`INFOBOX_IMAGE_FIELDS`),
+/// normalized like `content::extract_images` output so both paths share nodes.
+///
+/// Accepts bare names (`Foo.jpg`), prefixed names (`File:Foo.jpg`), and links
+/// (`[[File:Foo.jpg|200px]]`). Values built from templates are skipped, as are
+/// values without a file extension (e.g. `none`).
+#[must_use]
+pub fn infobox_images(infoboxes: &[Infobox]) -> Vec<Cow<'_, str>> {
+    let mut images: Vec<Cow<'_, str>> = Vec::new();
+    for (key, value) in infoboxes.iter().flat_map(|ib| &ib.fields) {
+        if !is_image_field(key) {
+            continue;
+        }
+        if let Some(name) = image_value(value)
+            && !images.contains(&name)
+        {
+            images.push(name);
+        }
+    }
+    images
+}
+
+fn is_image_field(key: &str) -> bool {
+    INFOBOX_IMAGE_FIELDS
+        .iter()
+        .any(|field| keys_match(key, field))
+}
+
+/// Field key equality ignoring ASCII case, with spaces and underscores equal.
+fn keys_match(a: &str, b: &str) -> bool {
+    let fold = |c: u8| match c {
+        b' ' => b'_',
+        c => c.to_ascii_lowercase(),
+    };
+    a.len() == b.len() && a.bytes().zip(b.bytes()).all(|(a, b)| fold(a) == fold(b))
+}
+
+fn image_value(value: &str) -> Option<Cow<'_, str>> {
+    // Drop trailing HTML comments (`Foo.jpg <!-- caption -->`).
+    let value = value.split("<!--").next().unwrap_or(value).trim();
+    if value.contains("{{") {
+        return None;
+    }
+    let value = value.strip_prefix("[[").unwrap_or(value);
+    let value = value.split(['|', ']']).next().unwrap_or(value);
+    let name = normalize_image_name(value);
+    name.contains('.').then_some(name)
+}
+
 /// Case-insensitive search for `{{Infobox` on raw bytes.
 /// Uses SIMD-accelerated memchr to jump to `{` candidates instead of scanning every byte.
 fn find_infobox_start(bytes: &[u8]) -> Option<usize> {
@@ -81,7 +150,7 @@ fn find_infobox_start(bytes: &[u8]) -> Option<usize> {
 
 /// Finds the matching `}}` for a `{{` starting at `start`.
 /// Uses SIMD-accelerated memchr2 to skip over plain text between braces.
-fn find_matching_close(bytes: &[u8], start: usize) -> Option<usize> {
+pub(crate) fn find_matching_close(bytes: &[u8], start: usize) -> Option<usize> {
     let mut depth: i32 = 0;
     let mut i = start;
     while i + 1 < bytes.len() {
@@ -139,7 +208,7 @@ fn parse_infobox_inner(inner: &str) -> Option<Infobox> {
 
 /// Splits on `|` at brace depth 0, respecting nested `{{ }}`.
 /// Uses SIMD-accelerated memchr3 to skip to the next `{`, `}`, or `|` character.
-fn split_at_depth_zero(content: &str) -> Vec<&str> {
+pub(crate) fn split_at_depth_zero(content: &str) -> Vec<&str> {
     let mut segments = Vec::new();
     let bytes = content.as_bytes();
     let mut depth: i32 = 0;
@@ -306,4 +375,41 @@ mod tests {
         let deserialized: Infobox = serde_json::from_str(&json).unwrap();
         assert_eq!(infobox, deserialized);
     }
+
+    #[test]
+    fn clean_value_strips_markup_and_keeps_raw() {
+        let text = "{{Infobox programming language\n| Designer = [[Graydon Hoare]]\n\
+                    | country = {{flag|France}}\n| logo = {{plainlist|a|b}}\n}}";
+        let infobox = &extract_infoboxes(text)[0];
+        assert_eq!(
+            infobox.clean_value("designer").as_deref(),
+            Some("Graydon Hoare")
+        );
+        assert_eq!(infobox.clean_value("Country").as_deref(), Some("France"));
+        assert_eq!(infobox.clean_value("logo"), None);
+        assert_eq!(infobox.clean_value("missing"), None);
+        assert_eq!(infobox.fields[0].1, "[[Graydon Hoare]]");
+        assert_eq!(infobox.fields[1].1, "{{flag|France}}");
+    }
+
+    #[test]
+    fn infobox_images_from_known_fields() {
+        let text = "{{Infobox country\n| name = Freedonia\n| image = Freedonia_skyline.jpg\n\
+                    | Flag = File:Flag of Freedonia.svg\n| coat of arms = [[File:Arms.png|100px]]\n\
+                    | logo = none\n| caption = Not.an.image\n}}";
+        let infoboxes = extract_infoboxes(text);
+        assert_eq!(
+            infobox_images(&infoboxes),
+            vec!["Freedonia skyline.jpg", "Flag of Freedonia.svg", "Arms.png"]
+        );
+    }
+
+    #[test]
+    fn infobox_images_skips_templates_and_comments() {
+        let text = "{{Infobox person\n| image = {{Multiple image|a.jpg|b.jpg}}\n\
+                    | photo = Portrait.jpg <!-- 2019 -->\n}}\n\
+                    {{Infobox company\n| logo = Portrait.jpg\n}}";
+        let infoboxes = extract_infoboxes(text);
+        assert_eq!(infobox_images(&infoboxes), vec!["Portrait.jpg"]);
+    }
 }
@@ -29,13 +29,29 @@
 //! - [`parser`] -- Streaming XML parser with BZ2 decompression
 //! - [`index`] -- Title-to-ID mapping with redirect resolution
 //! - [`extract`] -- Parallel extraction with CSV sharding
+//! - [`shard`] -- Page-ID to shard mapping for CSV shards and blob directories
 //! - [`merge`] -- CSV shard merging with deduplication
+//! - [`manifest`] -- Run summary (`manifest.json`) checked before merge and load
+//! - [`metrics`] -- Prometheus text endpoint for live extraction counters (`--metrics-addr`)
+//! - [`compact`] -- Dense renumbering of article IDs (`--compact-ids`)
+//! - [`degree`] -- Inbound link counts per article (`--compute-in-degree`)
+//! - [`commons`] -- Image metadata from an images-only dump (`--input PATH:images`)
+//! - [`sort`] -- External merge sort of node and edge CSVs by article ID (`--sort-nodes`)
+//! - [`export`] -- Gzipped JSON Lines export of the extracted graph
+//! - [`sample`] -- Small bz2 sub-dumps carved from a full dump (`dedalus sample`)
+//! - [`validate`] -- Consistency checks over CSV output (including in-progress runs) and JSON blobs
+//! - [`diff`] -- Comparison of two extraction outputs (nodes, edge counts, categories)
+//! - [`dedup`] -- Exact and fixed-memory first-seen sets for entity deduplication
+//! - [`sqlite_writer`] -- Single-file SQLite extraction output (`--output-format sqlite`)
 //! - [`surrealdb_writer`] -- SurrealDB embedded loader (reads CSVs, writes to RocksDB)
 //! - [`analytics`] -- Graph analytics (PageRank, Louvain, degree)
 //! - [`csv_util`] -- CSV layout detection and validation utilities
 //! - [`content`] -- Text extraction (abstracts, sections, links, categories)
 //! - [`infobox`] -- Structured infobox parsing with nested template support
+//! - [`template_stats`] -- Concurrent template usage counts (`--template-stats`)
+//! - [`category_counts`] -- Concurrent per-category article counts (`--category-counts`)
 //! - [`models`] -- Core data types (WikiPage, ArticleBlob, PageType)
+//! - [`schema`] -- Relationship type names, overridable for existing graph schemas
 //! - [`cache`] -- Index persistence with zero-copy serialization
 //! - [`checkpoint`] -- Extraction progress checkpointing
 //! - [`stats`] -- Thread-safe atomic counters for extraction metrics
@@ -43,27 +59,44 @@
 
 pub mod analytics;
 pub mod cache;
+pub mod category_counts;
 pub mod checkpoint;
+pub mod commons;
+pub mod compact;
 pub mod config;
 pub mod content;
 pub mod csv_util;
+pub mod dedup;
+pub mod degree;
+pub mod diff;
+pub mod export;
 pub mod extract;
 pub mod index;
 pub mod infobox;
+pub mod manifest;
 pub mod merge;
+pub mod metrics;
 pub mod models;
 pub mod multistream;
 pub mod parser;
+pub mod sample;
+pub mod schema;
+pub mod shard;
+pub mod sort;
+pub mod sqlite_writer;
 pub mod stats;
 pub mod surrealdb_writer;
+pub mod template_stats;
 pub mod tui;
+pub mod validate;
 
 // Re-export primary API types for convenient library use.
 pub use checkpoint::{Checkpoint, CheckpointManager};
 pub use csv_util::CsvType;
-pub use extract::ExtractionConfig;
+pub use extract::{EnrichFn, ExtractionConfig, OutputFormat};
 pub use index::WikiIndex;
 pub use models::{ArticleBlob, EdgeType, PageType, WikiPage};
 pub use parser::WikiReader;
+pub use shard::ShardStrategy;
 pub use stats::ExtractionStats;
 pub use surrealdb_writer::SurrealWriterConfig;
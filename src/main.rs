@@ -8,11 +8,17 @@ use anyhow::{Context, Result, bail};
 use clap::{Args, Parser, Subcommand};
 use dedalus::cache;
 use dedalus::checkpoint::{self, CheckpointManager};
-use dedalus::surrealdb_writer::SurrealWriterConfig;
+use dedalus::csv_util::{CsvLayout, CsvType};
+use dedalus::dedup::DedupStrategy;
+use dedalus::extract::{BlobFormat, OutputFormat};
+use dedalus::schema::{DEFAULT_PAGE_LABEL, RelationshipNames};
+use dedalus::shard::ShardStrategy;
+use dedalus::surrealdb_writer::{RetryPolicy, SurrealWriterConfig};
 use std::fs;
+use std::net::SocketAddr;
 use std::path::Path;
 use std::process::ExitCode;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tracing::{Level, error, info, warn};
 use tracing_subscriber::FmtSubscriber;
 
@@ -45,15 +51,27 @@ enum Commands {
     Pipeline(PipelineArgs),
     /// Show output directory statistics
     Stats(StatsArgs),
+    /// Compare two extraction outputs (nodes, edge counts, categories)
+    Diff(DiffArgs),
+    /// Resolve titles against a cached index, showing any redirect chain
+    Resolve(ResolveArgs),
+    /// Write the first N (or a random sample of) pages of a dump as a new bz2 dump
+    Sample(SampleArgs),
+    /// Check CSV output for malformed rows and dangling article IDs
+    Validate(ValidateArgs),
+    /// Check that every JSON blob parses (per-article files and JSON Lines shards)
+    VerifyBlobs(VerifyBlobsArgs),
     /// Launch interactive TUI for configuration and monitoring
     Tui,
 }
 
 #[derive(Args)]
 struct ExtractArgs {
-    /// Path to the Wikipedia dump file (.xml.bz2)
-    #[arg(short, long)]
-    input: String,
+    /// Dump to read, as PATH[:ROLE]: the Wikipedia dump (.xml.bz2, .xml.gz, .xml.zst or
+    /// plain .xml), plus optionally an images dump (PATH:images) whose File pages add
+    /// metadata to image_nodes
+    #[arg(short, long, value_name = "PATH[:ROLE]", required = true)]
+    input: Vec<String>,
 
     /// Output directory for generated files
     #[arg(short, long)]
@@ -67,22 +85,197 @@ struct ExtractArgs {
     #[arg(long, default_value_t = 8)]
     csv_shards: u32,
 
-    /// Limit number of pages to process (for testing)
+    /// How page IDs map to CSV shards and blob directories
+    #[arg(long, value_enum, default_value_t = ShardStrategy::Modulo)]
+    shard_strategy: ShardStrategy,
+
+    /// Limit number of articles to process (for testing)
     #[arg(long)]
     limit: Option<u64>,
 
+    /// Stop once this many bytes of CSV/blob output are written (checkpoint kept for --resume)
+    #[arg(long)]
+    max_output_bytes: Option<u64>,
+
+    /// Stop dispatching pages after this wall-clock time, e.g. `3600s`, `90m`, `2h`
+    /// (checkpoint kept for --resume)
+    #[arg(long, value_parser = parse_duration)]
+    max_runtime: Option<Duration>,
+
+    /// Serve live extraction counters as Prometheus metrics at http://ADDR/metrics,
+    /// e.g. `127.0.0.1:9100`
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Split each single-file CSV into `{name}_partNNN.csv` parts of about this many bytes
+    /// (needs --csv-shards 1)
+    #[arg(long)]
+    max_shard_bytes: Option<u64>,
+
     /// Dry run - don't write output files
     #[arg(long)]
     dry_run: bool,
 
+    /// Process pages sequentially (deterministic; `--limit N` keeps the first N articles)
+    #[arg(long)]
+    single_thread: bool,
+
+    /// Write CSVs only; skip JSON blob output
+    #[arg(long)]
+    no_blobs: bool,
+
+    /// Write JSON blobs only; skip all CSV output
+    #[arg(long, conflicts_with = "no_blobs")]
+    blobs_only: bool,
+
+    /// Include each article's resolved outbound links (id, type) in its JSON blob
+    #[arg(long, conflicts_with = "no_blobs")]
+    blob_links: bool,
+
+    /// Blob layout: one JSON file per article, or JSON Lines shards (blobs/blobs_NNN.jsonl)
+    #[arg(long, value_enum, default_value_t = BlobFormat::Files)]
+    blob_format: BlobFormat,
+
+    /// Add a section column to edges (the heading each link appears under, or "lead")
+    #[arg(long)]
+    edge_section_context: bool,
+
+    /// Add a via_redirect column to edges (true if the link target was a redirect)
+    #[arg(long)]
+    edge_redirect_flag: bool,
+
+    /// Add a weight:int column to edges: how many times the article links the target
+    #[arg(long)]
+    weighted_edges: bool,
+
+    /// Write edges from an article to itself (skipped and counted by default)
+    #[arg(long)]
+    keep_self_links: bool,
+
+    /// Normalize external link URLs (https, no www., fragment or tracking parameters)
+    /// so variants share a node
+    #[arg(long)]
+    normalize_urls: bool,
+
+    /// Fail if a page has more than one revision (a full-history dump) instead of
+    /// keeping the latest
+    #[arg(long)]
+    single_revision: bool,
+
+    /// Count template uses across all articles into template_stats.csv
+    #[arg(long)]
+    template_stats: bool,
+
+    /// Report a log-scale histogram of article wikitext lengths (summary and length_histogram.csv)
+    #[arg(long)]
+    length_histogram: bool,
+
+    /// Add a count:int column to categories.csv with the number of articles in each category
+    #[arg(long)]
+    category_counts: bool,
+
+    /// Templates whose last unnamed parameter is kept in blob abstracts (e.g. lang,nowrap)
+    #[arg(long, value_delimiter = ',')]
+    lead_templates: Vec<String>,
+
+    /// Write every resolved redirect to redirect_map.csv (source_title,target_title,target_id)
+    #[arg(long)]
+    emit_redirect_map: bool,
+
+    /// Write interlanguage links ([[de:...]]) to interwiki.csv (:START_ID,lang,title)
+    #[arg(long)]
+    interwiki_links: bool,
+
+    /// Write {{Coord}} positions to coordinates.csv (:START_ID,latitude:double,longitude:double)
+    #[arg(long)]
+    coordinates: bool,
+
+    /// After extraction, renumber article IDs densely (0..N) in all CSVs and write id_map.csv
+    #[arg(long)]
+    compact_ids: bool,
+
+    /// After extraction, sort nodes by id with a memory-bounded external merge sort
+    #[arg(long)]
+    sort_nodes: bool,
+
+    /// After extraction, sort edges by start id (same external sort as --sort-nodes)
+    #[arg(long)]
+    sort_edges: bool,
+
+    /// After extraction, count inbound LINKS_TO edges per article into node_degrees.csv
+    #[arg(long)]
+    compute_in_degree: bool,
+
+    /// After extraction, write all nodes and edges as gzipped JSON Lines to this path
+    #[arg(long, value_name = "PATH")]
+    export_graph_jsonl: Option<String>,
+
+    /// Rename a relationship type in the output, as OLD=NEW (e.g. LINKS_TO=CITES; repeatable)
+    #[arg(long, value_name = "OLD=NEW")]
+    relationship_name: Vec<String>,
+
+    /// Node label written for article rows (:LABEL in nodes.csv), e.g. WikiArticle
+    #[arg(long, default_value = DEFAULT_PAGE_LABEL)]
+    page_label: String,
+
+    /// Entity dedup strategy (fingerprint bounds memory by the article count; merge
+    /// removes its rare duplicates)
+    #[arg(long, value_enum, default_value_t = DedupStrategy::Exact)]
+    dedup_strategy: DedupStrategy,
+
+    /// Graph output format: sharded CSVs, or a single SQLite graph.db
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv, conflicts_with = "blobs_only")]
+    output_format: OutputFormat,
+
     /// Resume from last checkpoint if available
     #[arg(long)]
     resume: bool,
 
+    /// Ignore any checkpoint and append to the existing output, starting after this page ID
+    #[arg(long, value_name = "ID", conflicts_with_all = ["resume", "clean"])]
+    resume_from: Option<u64>,
+
+    /// Fail if the dump repeats a page ID instead of warning
+    #[arg(long)]
+    strict_ids: bool,
+
     /// Force rebuild of index cache
     #[arg(long)]
     no_cache: bool,
 
+    /// Delta dump to apply on top of the base index (repeatable, applied in order)
+    #[arg(long)]
+    index_delta: Vec<String>,
+
+    /// Namespaces whose pages are extracted as article nodes (e.g. 0,100)
+    #[arg(long, value_delimiter = ',', default_values_t = [0])]
+    namespaces: Vec<i32>,
+
+    /// Most titles a redirect chain may visit when resolving a link, the link's own included
+    #[arg(long, default_value_t = dedalus::config::REDIRECT_MAX_DEPTH)]
+    redirect_max_depth: u32,
+
+    /// Extra namespace prefixes whose links are not article links (e.g. Kategorie,Datei),
+    /// added to those read from the dump's siteinfo
+    #[arg(long, value_delimiter = ',')]
+    link_namespaces: Vec<String>,
+
+    /// Skip articles in this category entirely: no node, edges or blob (repeatable)
+    #[arg(long, value_name = "CATEGORY")]
+    exclude_category: Vec<String>,
+
+    /// Exit with code 3 if too many wiki links fail to resolve (see LOW_RESOLUTION_WARN_RATIO)
+    #[arg(long)]
+    fail_on_low_resolution: bool,
+
+    /// Fail extraction if the fraction of unresolved wiki links exceeds this (0.0-1.0)
+    #[arg(long)]
+    max_invalid_ratio: Option<f64>,
+
+    /// Write JSON blobs for only this fraction of articles (0.0-1.0, by hashed ID)
+    #[arg(long, conflicts_with = "no_blobs")]
+    blob_sample_rate: Option<f64>,
+
     /// Checkpoint interval in articles processed
     #[arg(long, default_value_t = dedalus::config::CHECKPOINT_INTERVAL)]
     checkpoint_interval: u32,
@@ -103,16 +296,48 @@ struct LoadArgs {
     output: String,
 
     /// Path for the SurrealDB database directory
-    #[arg(long, default_value = dedalus::config::DEFAULT_DB_PATH)]
+    #[arg(long, env = "DEDALUS_DB_PATH", default_value = dedalus::config::DEFAULT_DB_PATH)]
     db_path: String,
 
+    /// SurrealDB namespace to load into
+    #[arg(long, env = "SURREAL_NAMESPACE", default_value = dedalus::config::SURREAL_NAMESPACE)]
+    surreal_ns: String,
+
+    /// SurrealDB database to load into
+    #[arg(long, env = "SURREAL_DATABASE", default_value = dedalus::config::SURREAL_DATABASE)]
+    surreal_db: String,
+
     /// Batch size for SurrealDB inserts
     #[arg(long, default_value_t = dedalus::config::SURREAL_BATCH_SIZE)]
     batch_size: usize,
 
+    /// Times a batch that fails with a transient error (conflict, timeout) is retried
+    #[arg(long, default_value_t = dedalus::config::LOAD_MAX_RETRIES)]
+    max_retries: u32,
+
     /// Clear existing database before loading
     #[arg(long)]
     clean: bool,
+
+    /// Relationship type renames used at extraction, as OLD=NEW (names the edge table)
+    #[arg(long, value_name = "OLD=NEW")]
+    relationship_name: Vec<String>,
+
+    /// Report what the load would add or conflict with, without writing anything
+    #[arg(long)]
+    plan: bool,
+
+    /// Print the files and SurrealQL statements the load would run, without opening the database
+    #[arg(long, conflicts_with = "plan")]
+    dry_run: bool,
+
+    /// Fail if the loaded record counts differ from manifest.json instead of warning
+    #[arg(long)]
+    strict_counts: bool,
+
+    /// Skip CSV files an earlier, interrupted load finished (recorded in load_progress.json)
+    #[arg(long, conflicts_with = "clean")]
+    resume: bool,
 }
 
 #[derive(Args)]
@@ -122,9 +347,17 @@ struct AnalyticsArgs {
     output: String,
 
     /// Path for the SurrealDB database directory
-    #[arg(long, default_value = dedalus::config::DEFAULT_DB_PATH)]
+    #[arg(long, env = "DEDALUS_DB_PATH", default_value = dedalus::config::DEFAULT_DB_PATH)]
     db_path: String,
 
+    /// SurrealDB namespace to read the graph from
+    #[arg(long, env = "SURREAL_NAMESPACE", default_value = dedalus::config::SURREAL_NAMESPACE)]
+    surreal_ns: String,
+
+    /// SurrealDB database to read the graph from
+    #[arg(long, env = "SURREAL_DATABASE", default_value = dedalus::config::SURREAL_DATABASE)]
+    surreal_db: String,
+
     /// Number of PageRank iterations
     #[arg(long, default_value_t = dedalus::config::PAGERANK_ITERATIONS)]
     pagerank_iterations: u32,
@@ -143,13 +376,28 @@ struct MergeCsvsArgs {
     /// Archive sharded CSVs to output/shards/ after merging
     #[arg(long)]
     archive: bool,
+
+    /// Re-merge every CSV type, even those already merged by an earlier run
+    #[arg(long)]
+    force_merge: bool,
+
+    /// Drop duplicate rows from edges and article_* relationship CSVs while merging
+    #[arg(long)]
+    dedup_edges: bool,
+
+    /// Megabytes of a relationship shard deduplicated per read with --dedup-edges; each of
+    /// the up to four relationship types merged at once holds about this much row data
+    #[arg(long, default_value_t = dedalus::config::DEDUP_PASS_BYTES >> 20)]
+    dedup_pass_mb: u64,
 }
 
 #[derive(Args)]
 struct PipelineArgs {
-    /// Path to the Wikipedia dump file (.xml.bz2)
-    #[arg(short, long)]
-    input: String,
+    /// Dump to read, as PATH[:ROLE]: the Wikipedia dump (.xml.bz2, .xml.gz, .xml.zst or
+    /// plain .xml), plus optionally an images dump (PATH:images) whose File pages add
+    /// metadata to image_nodes
+    #[arg(short, long, value_name = "PATH[:ROLE]", required = true)]
+    input: Vec<String>,
 
     /// Output directory for generated files
     #[arg(short, long)]
@@ -163,18 +411,181 @@ struct PipelineArgs {
     #[arg(long, default_value_t = 8)]
     csv_shards: u32,
 
-    /// Limit number of pages to process (for testing)
+    /// How page IDs map to CSV shards and blob directories
+    #[arg(long, value_enum, default_value_t = ShardStrategy::Modulo)]
+    shard_strategy: ShardStrategy,
+
+    /// Limit number of articles to process (for testing)
     #[arg(long)]
     limit: Option<u64>,
 
+    /// Stop once this many bytes of CSV/blob output are written (checkpoint kept for --resume)
+    #[arg(long)]
+    max_output_bytes: Option<u64>,
+
+    /// Stop dispatching pages after this wall-clock time, e.g. `3600s`, `90m`, `2h`
+    /// (checkpoint kept for --resume)
+    #[arg(long, value_parser = parse_duration)]
+    max_runtime: Option<Duration>,
+
+    /// Serve live extraction counters as Prometheus metrics at http://ADDR/metrics,
+    /// e.g. `127.0.0.1:9100`
+    #[arg(long, value_name = "ADDR")]
+    metrics_addr: Option<SocketAddr>,
+
+    /// Split each single-file CSV into `{name}_partNNN.csv` parts of about this many bytes
+    /// (needs --csv-shards 1)
+    #[arg(long)]
+    max_shard_bytes: Option<u64>,
+
+    /// Write CSVs only; skip JSON blob output
+    #[arg(long)]
+    no_blobs: bool,
+
+    /// Include each article's resolved outbound links (id, type) in its JSON blob
+    #[arg(long, conflicts_with = "no_blobs")]
+    blob_links: bool,
+
+    /// Blob layout: one JSON file per article, or JSON Lines shards (blobs/blobs_NNN.jsonl)
+    #[arg(long, value_enum, default_value_t = BlobFormat::Files)]
+    blob_format: BlobFormat,
+
+    /// Add a section column to edges (the heading each link appears under, or "lead")
+    #[arg(long)]
+    edge_section_context: bool,
+
+    /// Add a via_redirect column to edges (true if the link target was a redirect)
+    #[arg(long)]
+    edge_redirect_flag: bool,
+
+    /// Add a weight:int column to edges: how many times the article links the target
+    #[arg(long)]
+    weighted_edges: bool,
+
+    /// Write edges from an article to itself (skipped and counted by default)
+    #[arg(long)]
+    keep_self_links: bool,
+
+    /// Normalize external link URLs (https, no www., fragment or tracking parameters)
+    /// so variants share a node
+    #[arg(long)]
+    normalize_urls: bool,
+
+    /// Fail if a page has more than one revision (a full-history dump) instead of
+    /// keeping the latest
+    #[arg(long)]
+    single_revision: bool,
+
+    /// Count template uses across all articles into template_stats.csv
+    #[arg(long)]
+    template_stats: bool,
+
+    /// Report a log-scale histogram of article wikitext lengths (summary and length_histogram.csv)
+    #[arg(long)]
+    length_histogram: bool,
+
+    /// Add a count:int column to categories.csv with the number of articles in each category
+    #[arg(long)]
+    category_counts: bool,
+
+    /// Templates whose last unnamed parameter is kept in blob abstracts (e.g. lang,nowrap)
+    #[arg(long, value_delimiter = ',')]
+    lead_templates: Vec<String>,
+
+    /// Write every resolved redirect to redirect_map.csv (source_title,target_title,target_id)
+    #[arg(long)]
+    emit_redirect_map: bool,
+
+    /// Write interlanguage links ([[de:...]]) to interwiki.csv (:START_ID,lang,title)
+    #[arg(long)]
+    interwiki_links: bool,
+
+    /// Write {{Coord}} positions to coordinates.csv (:START_ID,latitude:double,longitude:double)
+    #[arg(long)]
+    coordinates: bool,
+
+    /// After extraction, renumber article IDs densely (0..N) in all CSVs and write id_map.csv
+    #[arg(long)]
+    compact_ids: bool,
+
+    /// After extraction, sort nodes by id with a memory-bounded external merge sort
+    #[arg(long)]
+    sort_nodes: bool,
+
+    /// After extraction, sort edges by start id (same external sort as --sort-nodes)
+    #[arg(long)]
+    sort_edges: bool,
+
+    /// After extraction, count inbound LINKS_TO edges per article into node_degrees.csv
+    #[arg(long)]
+    compute_in_degree: bool,
+
+    /// After extraction, write all nodes and edges as gzipped JSON Lines to this path
+    #[arg(long, value_name = "PATH")]
+    export_graph_jsonl: Option<String>,
+
+    /// Rename a relationship type in the output, as OLD=NEW (e.g. LINKS_TO=CITES; repeatable)
+    #[arg(long, value_name = "OLD=NEW")]
+    relationship_name: Vec<String>,
+
+    /// Node label written for article rows (:LABEL in nodes.csv), e.g. WikiArticle
+    #[arg(long, default_value = DEFAULT_PAGE_LABEL)]
+    page_label: String,
+
+    /// Entity dedup strategy (fingerprint bounds memory by the article count; merge
+    /// removes its rare duplicates)
+    #[arg(long, value_enum, default_value_t = DedupStrategy::Exact)]
+    dedup_strategy: DedupStrategy,
+
     /// Resume from last checkpoint if available
     #[arg(long)]
     resume: bool,
 
+    /// Ignore any checkpoint and append to the existing output, starting after this page ID
+    #[arg(long, value_name = "ID", conflicts_with_all = ["resume", "clean"])]
+    resume_from: Option<u64>,
+
+    /// Fail if the dump repeats a page ID instead of warning
+    #[arg(long)]
+    strict_ids: bool,
+
     /// Force rebuild of index cache
     #[arg(long)]
     no_cache: bool,
 
+    /// Delta dump to apply on top of the base index (repeatable, applied in order)
+    #[arg(long)]
+    index_delta: Vec<String>,
+
+    /// Namespaces whose pages are extracted as article nodes (e.g. 0,100)
+    #[arg(long, value_delimiter = ',', default_values_t = [0])]
+    namespaces: Vec<i32>,
+
+    /// Most titles a redirect chain may visit when resolving a link, the link's own included
+    #[arg(long, default_value_t = dedalus::config::REDIRECT_MAX_DEPTH)]
+    redirect_max_depth: u32,
+
+    /// Extra namespace prefixes whose links are not article links (e.g. Kategorie,Datei),
+    /// added to those read from the dump's siteinfo
+    #[arg(long, value_delimiter = ',')]
+    link_namespaces: Vec<String>,
+
+    /// Skip articles in this category entirely: no node, edges or blob (repeatable)
+    #[arg(long, value_name = "CATEGORY")]
+    exclude_category: Vec<String>,
+
+    /// Exit with code 3 if too many wiki links fail to resolve (see LOW_RESOLUTION_WARN_RATIO)
+    #[arg(long)]
+    fail_on_low_resolution: bool,
+
+    /// Fail extraction if the fraction of unresolved wiki links exceeds this (0.0-1.0)
+    #[arg(long)]
+    max_invalid_ratio: Option<f64>,
+
+    /// Write JSON blobs for only this fraction of articles (0.0-1.0, by hashed ID)
+    #[arg(long, conflicts_with = "no_blobs")]
+    blob_sample_rate: Option<f64>,
+
     /// Checkpoint interval in articles processed
     #[arg(long, default_value_t = dedalus::config::CHECKPOINT_INTERVAL)]
     checkpoint_interval: u32,
@@ -183,10 +594,22 @@ struct PipelineArgs {
     #[arg(long)]
     clean: bool,
 
+    /// Fail if the loaded record counts differ from manifest.json instead of warning
+    #[arg(long)]
+    strict_counts: bool,
+
     /// Path for the SurrealDB database directory
-    #[arg(long, default_value = dedalus::config::DEFAULT_DB_PATH)]
+    #[arg(long, env = "DEDALUS_DB_PATH", default_value = dedalus::config::DEFAULT_DB_PATH)]
     db_path: String,
 
+    /// SurrealDB namespace to load into
+    #[arg(long, env = "SURREAL_NAMESPACE", default_value = dedalus::config::SURREAL_NAMESPACE)]
+    surreal_ns: String,
+
+    /// SurrealDB database to load into
+    #[arg(long, env = "SURREAL_DATABASE", default_value = dedalus::config::SURREAL_DATABASE)]
+    surreal_db: String,
+
     /// Skip the load + analytics steps (extract + merge only)
     #[arg(long)]
     no_load: bool,
@@ -199,11 +622,80 @@ struct PipelineArgs {
     #[arg(long)]
     no_archive: bool,
 
+    /// Re-merge every CSV type, even those already merged by an earlier run
+    #[arg(long)]
+    force_merge: bool,
+
+    /// Drop duplicate rows from edges and article_* relationship CSVs while merging
+    #[arg(long)]
+    dedup_edges: bool,
+
+    /// Megabytes of a relationship shard deduplicated per read with --dedup-edges; each of
+    /// the up to four relationship types merged at once holds about this much row data
+    #[arg(long, default_value_t = dedalus::config::DEDUP_PASS_BYTES >> 20)]
+    dedup_pass_mb: u64,
+
     /// Path to multistream index file (.txt.bz2) for parallel parsing
     #[arg(long)]
     multistream_index: Option<String>,
 }
 
+/// Advisory exit code for `--fail-on-low-resolution`.
+const LOW_RESOLUTION_EXIT_CODE: u8 = 3;
+
+/// Returned by `run_extract` under `--fail-on-low-resolution` so `main` can
+/// exit with `LOW_RESOLUTION_EXIT_CODE` after the summary has been printed.
+#[derive(Debug)]
+struct LowResolutionRate(f64);
+
+impl std::fmt::Display for LowResolutionRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.1}% of wiki links did not resolve (threshold {:.0}%)",
+            self.0 * 100.0,
+            dedalus::config::LOW_RESOLUTION_WARN_RATIO * 100.0
+        )
+    }
+}
+
+impl std::error::Error for LowResolutionRate {}
+
+/// Advisory exit code for an extraction that found no articles.
+const NO_ARTICLES_EXIT_CODE: u8 = 4;
+
+/// Returned by `run_extract` when a complete run processed no articles (a
+/// redirects-only or empty dump), so nothing downstream treats it as a success.
+#[derive(Debug)]
+struct NoArticles;
+
+impl std::fmt::Display for NoArticles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "no articles were extracted; check --input and --namespaces"
+        )
+    }
+}
+
+impl std::error::Error for NoArticles {}
+
+/// Exit code after Ctrl-C stopped an extraction (128 + SIGINT).
+const INTERRUPTED_EXIT_CODE: u8 = 130;
+
+/// Returned by `run_extract` when Ctrl-C stopped the run, after the summary and
+/// checkpoint are written, so `pipeline` goes no further.
+#[derive(Debug)]
+struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "interrupted; rerun with --resume to continue")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
 #[derive(Args)]
 struct StatsArgs {
     /// Output directory to inspect
@@ -211,7 +703,171 @@ struct StatsArgs {
     output: String,
 }
 
-fn run_extract(args: ExtractArgs) -> Result<()> {
+#[derive(Args)]
+struct ResolveArgs {
+    /// Index cache written by extract (e.g. out/index.cache)
+    #[arg(long)]
+    index: String,
+
+    /// Titles to resolve; read one per line from stdin when none are given
+    titles: Vec<String>,
+}
+
+#[derive(Args)]
+struct SampleArgs {
+    /// Path or http(s):// URL of the Wikipedia dump (.xml.bz2, .xml.gz, .xml.zst or plain .xml)
+    #[arg(short, long)]
+    input: String,
+
+    /// Path of the sample dump to write (.xml.bz2)
+    #[arg(short, long)]
+    output: String,
+
+    /// Number of pages to write
+    #[arg(long, default_value_t = 1000)]
+    pages: u64,
+
+    /// Keep each page with this probability (by hashed page ID) instead of taking the first N
+    #[arg(long)]
+    rate: Option<f64>,
+}
+
+#[derive(Args)]
+struct ValidateArgs {
+    /// Output directory to validate
+    #[arg(short, long)]
+    output: String,
+
+    /// Accept an in-progress extraction: unflushed tails and missing files are warnings
+    #[arg(long)]
+    partial: bool,
+}
+
+#[derive(Args)]
+struct VerifyBlobsArgs {
+    /// Output directory whose blobs/ tree to check
+    #[arg(short, long)]
+    output: String,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// Old output directory
+    #[arg(short)]
+    a: String,
+
+    /// New output directory
+    #[arg(short)]
+    b: String,
+
+    /// Maximum added/removed IDs and categories to list per section
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+}
+
+/// `extract` and `pipeline` read the dump twice (index, then extraction) and
+/// key their caches and checkpoints on its file metadata, so a URL is refused
+/// rather than downloaded twice.
+fn require_local_dump(input: &str) -> Result<()> {
+    if dedalus::parser::is_url(input) {
+        bail!(
+            "{input} is a URL; extraction reads the dump twice and needs a local file.\n\n\
+             Download it first, or stream a small sub-dump from it with:\n  \
+             dedalus sample -i {input} -o sample.xml.bz2 --pages 1000"
+        );
+    }
+    Ok(())
+}
+
+/// `--input` values split by role: `PATH` or `PATH:articles` for the dump to
+/// extract, `PATH:images` for a dump whose `File:` pages describe its images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DumpInputs {
+    articles: String,
+    images: Option<String>,
+}
+
+impl DumpInputs {
+    /// Parses `PATH[:ROLE]` values. A suffix that is not a role belongs to the
+    /// path, so URLs and paths containing `:` need no escaping.
+    fn parse(values: &[String]) -> Result<Self> {
+        let mut articles = None;
+        let mut images = None;
+        for value in values {
+            let (path, slot, role) = match value.rsplit_once(':') {
+                Some((path, "articles")) => (path, &mut articles, "articles"),
+                Some((path, "images")) => (path, &mut images, "images"),
+                _ => (value.as_str(), &mut articles, "articles"),
+            };
+            if slot.replace(path.to_string()).is_some() {
+                bail!("--input: only one {role} dump is supported");
+            }
+        }
+        let articles = articles.context(
+            "--input: an articles dump is required (PATH or PATH:articles); \
+             PATH:images only adds image metadata",
+        )?;
+        Ok(Self { articles, images })
+    }
+}
+
+/// What `run_pipeline` needs from a finished `run_extract`.
+struct ExtractOutcome {
+    /// `--max-runtime` stopped the run.
+    timed_out: bool,
+    /// Unresolved-link ratio, when it is high enough to warn about
+    /// (`ExtractionStats::low_resolution_rate`).
+    low_resolution: Option<f64>,
+}
+
+/// Runs the extract subcommand. Reports whether `--max-runtime` stopped the
+/// run before every page was processed.
+fn run_extract(args: ExtractArgs) -> Result<ExtractOutcome> {
+    let inputs = DumpInputs::parse(&args.input)?;
+    let input = inputs.articles.as_str();
+    require_local_dump(input)?;
+    let relationship_names = RelationshipNames::from_overrides(&args.relationship_name)?;
+    dedalus::schema::validate_label(&args.page_label)?;
+    if args.export_graph_jsonl.is_some()
+        && (args.blobs_only || args.output_format == OutputFormat::Sqlite)
+    {
+        bail!(
+            "--export-graph-jsonl reads the CSV output; it needs CSVs \
+             (not --blobs-only or --output-format sqlite)"
+        );
+    }
+    if args.compact_ids && (args.blobs_only || args.output_format == OutputFormat::Sqlite) {
+        bail!(
+            "--compact-ids rewrites the CSV output; it needs CSVs \
+             (not --blobs-only or --output-format sqlite)"
+        );
+    }
+    if (args.sort_nodes || args.sort_edges)
+        && (args.blobs_only || args.output_format == OutputFormat::Sqlite)
+    {
+        bail!(
+            "--sort-nodes/--sort-edges rewrite the CSV output; they need CSVs \
+             (not --blobs-only or --output-format sqlite)"
+        );
+    }
+    if args.compute_in_degree && (args.blobs_only || args.output_format == OutputFormat::Sqlite) {
+        bail!(
+            "--compute-in-degree reads the edge CSVs; it needs CSVs \
+             (not --blobs-only or --output-format sqlite)"
+        );
+    }
+    if let Some(images) = &inputs.images {
+        if args.blobs_only || args.output_format == OutputFormat::Sqlite {
+            bail!(
+                "An images dump adds columns to image_nodes CSVs; it needs CSVs \
+                 (not --blobs-only or --output-format sqlite)"
+            );
+        }
+        require_local_dump(images)?;
+        if !Path::new(images).exists() {
+            bail!("Images dump not found: {images}");
+        }
+    }
     if args.clean {
         let output_path = Path::new(&args.output);
         if output_path.exists() {
@@ -225,78 +881,115 @@ fn run_extract(args: ExtractArgs) -> Result<()> {
         .with_context(|| format!("Failed to create output directory: {}", args.output))?;
 
     // Resolve multistream index: explicit flag > auto-detect from filename
-    let multistream_index_path = args
-        .multistream_index
-        .clone()
-        .or_else(|| dedalus::multistream::detect_index_path(&args.input));
-
-    let multistream_ranges = if let Some(ref idx_path) = multistream_index_path {
-        info!(index = %idx_path, "Using multistream parallel parsing");
-        let ranges = dedalus::multistream::parse_multistream_index(idx_path, &args.input)?;
-        info!(streams = ranges.len(), "Multistream index parsed");
-        Some(ranges)
-    } else {
-        None
-    };
+    let multistream_ranges =
+        dedalus::multistream::resolve_ranges(input, args.multistream_index.as_deref())?;
 
     let start_indexing = Instant::now();
     let cache_path = cache::cache_path(&args.output);
 
-    let index = if args.no_cache {
+    let deltas = &args.index_delta;
+    let mut sorted_namespaces = args.namespaces.clone();
+    sorted_namespaces.sort_unstable();
+    sorted_namespaces.dedup();
+    let cached = if args.no_cache {
         info!("Cache disabled, building fresh index");
-        let idx = if let Some(ref ranges) = multistream_ranges {
-            dedalus::index::WikiIndex::build_multistream(&args.input, ranges)?
-        } else {
-            dedalus::index::WikiIndex::build(&args.input)?
-        };
-        if !args.dry_run
-            && let Err(e) = cache::save_index(&idx, &args.input, &args.output)
-        {
-            warn!(error = %e, "Failed to save index cache");
-        }
-        idx
-    } else if let Some(idx) = cache::try_load_index(&cache_path, &args.input)? {
-        info!("Loaded index from cache");
-        idx
+        None
     } else {
-        info!("Building index (cache miss or invalid)");
-        let idx = if let Some(ref ranges) = multistream_ranges {
-            dedalus::index::WikiIndex::build_multistream(&args.input, ranges)?
-        } else {
-            dedalus::index::WikiIndex::build(&args.input)?
-        };
-        if !args.dry_run
-            && let Err(e) = cache::save_index(&idx, &args.input, &args.output)
-        {
-            warn!(error = %e, "Failed to save index cache");
+        cache::try_load_index_with_deltas(&cache_path, input, deltas)?.filter(|(idx, _)| {
+            let matches = idx.content_namespaces() == sorted_namespaces.as_slice();
+            if !matches {
+                info!(
+                    cached = ?idx.content_namespaces(),
+                    requested = ?sorted_namespaces,
+                    "Cached index covers different namespaces"
+                );
+            }
+            matches
+        })
+    };
+    let (mut index, applied) = match cached {
+        Some((idx, applied)) => {
+            info!("Loaded index from cache");
+            (idx, Some(applied))
+        }
+        None => {
+            if !args.no_cache {
+                info!("Building index (cache miss or invalid)");
+            }
+            let idx = if let Some(ref ranges) = multistream_ranges {
+                dedalus::index::WikiIndex::build_multistream(input, ranges, &args.namespaces)?
+            } else {
+                dedalus::index::WikiIndex::build_for_namespaces(input, &args.namespaces)?
+            };
+            (idx, None)
         }
-        idx
     };
+    // Only deltas newer than the cached lineage need applying
+    let pending = &deltas[applied.unwrap_or(0)..];
+    for delta in pending {
+        index.update_from(delta)?;
+    }
+    if (applied.is_none() || !pending.is_empty())
+        && !args.dry_run
+        && let Err(e) = cache::save_index_with_deltas(&index, input, deltas, &args.output)
+    {
+        warn!(error = %e, "Failed to save index cache");
+    }
+    let index = index.with_redirect_max_depth(args.redirect_max_depth);
+    if args.strict_ids && index.duplicate_ids() > 0 {
+        bail!(
+            "{} page(s) in {} repeat an earlier page ID (--strict-ids); \
+             each would become a duplicate Page node",
+            index.duplicate_ids(),
+            input
+        );
+    }
 
     let indexing_duration = start_indexing.elapsed();
+    let (indexed_articles, _) = index.stats();
     info!(
         duration_secs = indexing_duration.as_secs_f64(),
+        pages = index.page_count(),
+        articles = indexed_articles,
         "Indexing complete"
     );
 
     let checkpoint_mgr = if !args.dry_run {
         Some(CheckpointManager::new(
-            &args.input,
+            input,
             &args.output,
             args.shard_count,
             args.csv_shards,
+            args.shard_strategy,
             args.checkpoint_interval,
         )?)
     } else {
         None
     };
 
-    let checkpoint = if args.resume && !args.clean {
+    let checkpoint = if let Some(last_id) = args.resume_from {
+        warn!(
+            last_id,
+            "--resume-from ignores the checkpoint and appends after page {last_id}; keeping the existing output consistent with that ID is up to you"
+        );
+        if let Some(ref mgr) = checkpoint_mgr {
+            mgr.set_last_id(last_id);
+        }
+        Some(checkpoint::starting_after(
+            input,
+            &args.output,
+            args.shard_count,
+            args.csv_shards,
+            args.shard_strategy,
+            last_id,
+        )?)
+    } else if args.resume && !args.clean {
         match checkpoint::load_if_valid(
-            &args.input,
+            input,
             &args.output,
             args.shard_count,
             args.csv_shards,
+            args.shard_strategy,
         )? {
             Some(cp) => {
                 info!(
@@ -321,29 +1014,139 @@ fn run_extract(args: ExtractArgs) -> Result<()> {
     info!("Starting extraction pass");
     let start_extracting = Instant::now();
     let extraction_config = dedalus::extract::ExtractionConfig {
-        input_path: &args.input,
+        input_path: input,
         output_dir: &args.output,
         index: &index,
         shard_count: args.shard_count,
         csv_shards: args.csv_shards,
+        shard_strategy: args.shard_strategy,
         limit: args.limit,
+        expected_articles: Some(indexed_articles as u64),
+        max_output_bytes: args.max_output_bytes,
+        max_runtime: args.max_runtime,
+        handle_interrupts: true,
+        metrics_addr: args.metrics_addr,
+        max_shard_bytes: args.max_shard_bytes,
+        max_invalid_ratio: args.max_invalid_ratio,
+        blob_sample_rate: args.blob_sample_rate,
         dry_run: args.dry_run,
+        single_thread: args.single_thread,
+        no_blobs: args.no_blobs,
+        blobs_only: args.blobs_only,
+        blob_links: args.blob_links,
+        blob_format: args.blob_format,
+        edge_section_context: args.edge_section_context,
+        edge_redirect_flag: args.edge_redirect_flag,
+        weighted_edges: args.weighted_edges,
+        keep_self_links: args.keep_self_links,
+        normalize_urls: args.normalize_urls,
+        single_revision: args.single_revision,
+        template_stats: args.template_stats,
+        length_histogram: args.length_histogram,
+        category_counts: args.category_counts,
+        lead_templates: args.lead_templates,
+        emit_redirect_map: args.emit_redirect_map,
+        interwiki_links: args.interwiki_links,
+        coordinates: args.coordinates,
+        relationship_names,
+        page_label: args.page_label,
+        link_namespaces: args.link_namespaces,
+        exclude_categories: args.exclude_category,
+        dedup_strategy: args.dedup_strategy,
+        output_format: args.output_format,
         resume_from: checkpoint.as_ref(),
         checkpoint_mgr: checkpoint_mgr.as_ref(),
         multistream_ranges: multistream_ranges.as_deref(),
+        enrich: None,
+    };
+    let stats = dedalus::extract::run_extraction(&extraction_config)?;
+    let extraction_duration = start_extracting.elapsed();
+    info!(
+        duration_secs = extraction_duration.as_secs_f64(),
+        "Extraction complete"
+    );
+
+    // A size-capped, timed-out or interrupted run keeps its checkpoint so
+    // `--resume` can continue it.
+    let size_capped = args
+        .max_output_bytes
+        .is_some_and(|max| stats.output_bytes() >= max);
+    let timed_out = stats.time_budget_exhausted();
+    let interrupted = stats.interrupted();
+    let stopped_early = size_capped || timed_out || interrupted;
+    if !stopped_early
+        && let Some(ref mgr) = checkpoint_mgr
+        && let Err(e) = mgr.clear()
+    {
+        warn!(error = %e, "Failed to clear checkpoint");
+    }
+
+    // A run stopped early is resumed with the original IDs, so compact only finished output.
+    let compacted = if args.compact_ids && !args.dry_run && !stopped_early {
+        Some(dedalus::compact::compact_ids(&args.output)?)
+    } else {
+        if args.compact_ids && stopped_early {
+            warn!(
+                "Skipping --compact-ids: the run stopped early; compact after it is resumed to completion"
+            );
+        }
+        None
+    };
+
+    let sort_types: Vec<CsvType> = [
+        (args.sort_nodes, CsvType::Nodes),
+        (args.sort_edges, CsvType::Edges),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, csv_type)| enabled.then_some(csv_type))
+    .collect();
+    let sorted = if !sort_types.is_empty() && !args.dry_run && !stopped_early {
+        Some(dedalus::sort::sort_csvs(&args.output, &sort_types)?)
+    } else {
+        if !sort_types.is_empty() && stopped_early {
+            warn!(
+                "Skipping --sort-nodes/--sort-edges: the run stopped early; sort after it is resumed to completion"
+            );
+        }
+        None
+    };
+
+    // Resuming appends rows without the metadata columns, so only add them at the end.
+    let image_metadata = match &inputs.images {
+        Some(images) if !args.dry_run && !stopped_early => {
+            Some(dedalus::commons::add_image_metadata(&args.output, images)?)
+        }
+        Some(_) if stopped_early => {
+            warn!(
+                "Skipping the images dump: the run stopped early; it is read after the run is resumed to completion"
+            );
+            None
+        }
+        _ => None,
+    };
+
+    // Partial output would undercount links to articles not yet processed.
+    let in_degrees = if args.compute_in_degree && !args.dry_run && !stopped_early {
+        Some(dedalus::degree::compute_in_degree(
+            &args.output,
+            &extraction_config.relationship_names.links_to,
+        )?)
+    } else {
+        if args.compute_in_degree && stopped_early {
+            warn!(
+                "Skipping --compute-in-degree: the run stopped early; compute it after the run is resumed to completion"
+            );
+        }
+        None
     };
-    let stats = dedalus::extract::run_extraction(&extraction_config)?;
-    let extraction_duration = start_extracting.elapsed();
-    info!(
-        duration_secs = extraction_duration.as_secs_f64(),
-        "Extraction complete"
-    );
 
-    if let Some(ref mgr) = checkpoint_mgr
-        && let Err(e) = mgr.clear()
-    {
-        warn!(error = %e, "Failed to clear checkpoint");
-    }
+    let graph_export = match &args.export_graph_jsonl {
+        Some(path) if !args.dry_run => Some((
+            path,
+            dedalus::export::export_graph_jsonl(&args.output, Path::new(path))?,
+        )),
+        _ => None,
+    };
 
     println!();
     println!("=== Summary ===");
@@ -361,47 +1164,339 @@ fn run_extract(args: ExtractArgs) -> Result<()> {
     );
     println!();
     println!("Articles processed: {}", stats.articles());
+    if stats.articles_excluded() > 0 {
+        println!(
+            "Articles excluded:  {} (--exclude-category)",
+            stats.articles_excluded()
+        );
+    }
+    if index.duplicate_ids() > 0 {
+        println!(
+            "Duplicate page IDs: {} (duplicate Page nodes written)",
+            index.duplicate_ids()
+        );
+    }
+    if args.blobs_only {
+        println!("CSV output:         skipped (--blobs-only)");
+    } else if args.output_format == OutputFormat::Sqlite && !args.dry_run {
+        println!(
+            "SQLite output:      {}/{}",
+            args.output,
+            dedalus::config::SQLITE_DB_FILENAME
+        );
+    }
+    let csv_output = !args.blobs_only && !args.dry_run && args.output_format == OutputFormat::Csv;
+    let sharded_csv_output = csv_output && args.csv_shards > 1;
+    if csv_output {
+        let layout = if sharded_csv_output {
+            CsvLayout::Sharded {
+                count: args.csv_shards,
+            }
+        } else {
+            CsvLayout::Single
+        };
+        let loadable = if sharded_csv_output {
+            "merge required before load"
+        } else {
+            "ready for load"
+        };
+        println!("CSV layout:         {} -- {}", layout, loadable);
+    }
     println!("Edges extracted:    {}", stats.edges());
     println!("See also edges:     {}", stats.see_also_edges());
-    println!("Blobs written:      {}", stats.blobs());
-    println!("Invalid links:      {}", stats.invalid());
+    if stats.self_links() > 0 {
+        println!(
+            "Self-links skipped: {} (--keep-self-links to write them)",
+            stats.self_links()
+        );
+    }
+    if args.no_blobs {
+        println!("Blobs written:      0 (skipped)");
+    } else if args.blob_sample_rate.is_some() {
+        println!(
+            "Blobs written:      {} (sampled {} of {} articles)",
+            stats.blobs(),
+            stats.articles() - stats.blobs_sampled_out(),
+            stats.articles()
+        );
+    } else {
+        println!("Blobs written:      {}", stats.blobs());
+    }
+    println!(
+        "Invalid links:      {} ({} red links, {} other)",
+        stats.invalid(),
+        stats.red_links(),
+        stats.invalid() - stats.red_links()
+    );
     println!("Categories found:   {}", stats.categories());
     println!("Category edges:     {}", stats.category_edges());
     println!("Infoboxes found:    {}", stats.infoboxes());
     println!("Images found:       {}", stats.images());
     println!("External links:     {}", stats.external_links());
+    if !args.dry_run {
+        println!("Output written:     {}", format_size(stats.output_bytes()));
+    }
+    if args.length_histogram {
+        print_length_histogram(&stats.article_lengths);
+    }
+    if args.template_stats && !args.dry_run {
+        println!(
+            "Template stats:     {}/{}",
+            args.output,
+            dedalus::config::TEMPLATE_STATS_FILENAME
+        );
+    }
+    if args.interwiki_links && !args.dry_run {
+        println!(
+            "Interwiki links:    {}/{}.csv",
+            args.output,
+            dedalus::config::INTERWIKI_BASE_NAME
+        );
+    }
+    if args.coordinates && !args.dry_run {
+        println!(
+            "Coordinates:        {}/{}.csv",
+            args.output,
+            dedalus::config::COORDINATES_BASE_NAME
+        );
+    }
+    if args.emit_redirect_map && !args.dry_run {
+        println!(
+            "Redirect map:       {}/{}",
+            args.output,
+            dedalus::config::REDIRECT_MAP_FILENAME
+        );
+    }
+    if let Some(compacted) = &compacted {
+        println!(
+            "Compacted IDs:      {} articles -> {}/{}",
+            compacted.articles,
+            args.output,
+            dedalus::config::ID_MAP_FILENAME
+        );
+    }
+    if let Some(sorted) = &sorted {
+        println!(
+            "Sorted by ID:       {} rows in {} files ({} runs spilled)",
+            sorted.rows, sorted.files, sorted.spilled_runs
+        );
+    }
+    if let Some(metadata) = &image_metadata {
+        println!(
+            "Image metadata:     {} of {} images ({} file pages read)",
+            metadata.matched, metadata.images, metadata.file_pages
+        );
+    }
+    if let Some((path, degrees)) = &in_degrees {
+        println!(
+            "In-degrees:         {} articles, {} links -> {}",
+            degrees.nodes,
+            degrees.links,
+            path.display()
+        );
+    }
+    if let Some((path, export)) = &graph_export {
+        println!(
+            "Graph export:       {} ({} nodes, {} edges)",
+            path, export.nodes, export.edges
+        );
+    }
+    if interrupted {
+        println!();
+        println!(
+            "STOPPED: interrupted after {} articles; rerun with --resume to continue",
+            stats.articles()
+        );
+    } else if let Some(max) = args.max_output_bytes
+        && size_capped
+    {
+        println!();
+        println!(
+            "STOPPED: output size cap reached ({} of {}); rerun with --resume and a larger --max-output-bytes to continue",
+            format_size(stats.output_bytes()),
+            format_size(max)
+        );
+    } else if let Some(budget) = args.max_runtime
+        && timed_out
+    {
+        println!();
+        println!(
+            "STOPPED: time budget of {}s exhausted after {} articles; rerun with --resume to continue",
+            budget.as_secs(),
+            stats.articles()
+        );
+    }
 
-    Ok(())
+    if sharded_csv_output {
+        println!();
+        println!(
+            "NOTE: `dedalus load` needs single-file CSVs. `dedalus pipeline` merges shards automatically; \
+             after a standalone extract, run `dedalus merge-csvs -o {}` first (or extract with --csv-shards 1).",
+            args.output
+        );
+    }
+
+    if interrupted {
+        return Err(Interrupted.into());
+    }
+
+    // run_extraction has already warned about a low rate.
+    let low_resolution = stats.low_resolution_rate();
+    if let Some(ratio) = low_resolution
+        && args.fail_on_low_resolution
+    {
+        return Err(LowResolutionRate(ratio).into());
+    }
+
+    if stats.articles() == 0 && !stopped_early {
+        println!();
+        if stats.articles_excluded() > 0 {
+            println!(
+                "WARNING: no articles were extracted -- all {} were skipped by --exclude-category; the CSVs contain only headers",
+                stats.articles_excluded()
+            );
+        } else {
+            println!(
+                "WARNING: no articles were extracted -- the dump has no non-redirect pages in namespaces {:?}; the CSVs contain only headers",
+                args.namespaces
+            );
+        }
+        return Err(NoArticles.into());
+    }
+
+    Ok(ExtractOutcome {
+        timed_out,
+        low_resolution,
+    })
 }
 
 fn run_load(args: LoadArgs) -> Result<()> {
     let config = SurrealWriterConfig {
         output_dir: args.output,
         db_path: args.db_path,
+        namespace: args.surreal_ns,
+        database: args.surreal_db,
         batch_size: args.batch_size,
         clean: args.clean,
+        relationship_names: RelationshipNames::from_overrides(&args.relationship_name)?,
+        strict_counts: args.strict_counts,
+        retry: RetryPolicy {
+            max_retries: args.max_retries,
+            ..Default::default()
+        },
+        resume: args.resume,
     };
 
+    if args.dry_run {
+        let script = dedalus::surrealdb_writer::dry_run_load(&config)?;
+        print_load_script(&script, &config);
+        return Ok(());
+    }
+
     let rt = tokio::runtime::Builder::new_multi_thread()
         .thread_name("dedalus-load-worker")
         .enable_io()
         .enable_time()
         .build()?;
 
+    if args.plan {
+        let plan = rt.block_on(dedalus::surrealdb_writer::plan_surreal_load(&config))?;
+        print_load_plan(&plan, config.clean);
+        return Ok(());
+    }
+
     let stats = rt.block_on(dedalus::surrealdb_writer::run_surreal_load(config))?;
 
     println!();
     println!("=== Load Summary ===");
     println!("Articles loaded:  {}", stats.articles_loaded);
     println!("Edges loaded:     {}", stats.edges_loaded);
+    if stats.files_skipped > 0 {
+        println!(
+            "Files skipped:    {} (already loaded, --resume)",
+            stats.files_skipped
+        );
+    }
     println!("Elapsed:          {:.2}s", stats.elapsed_secs);
+    print_count_mismatches(&stats.count_mismatches);
 
     Ok(())
 }
 
+fn print_load_script(script: &dedalus::surrealdb_writer::LoadScript, config: &SurrealWriterConfig) {
+    println!();
+    println!("=== Load Dry Run (nothing executed) ===");
+    println!(
+        "Database:         {}{}",
+        script.db_path,
+        if script.removes_db {
+            " (exists; removed by --clean)"
+        } else {
+            ""
+        }
+    );
+    println!(
+        "Namespace:        {} / {}",
+        config.namespace, config.database
+    );
+    println!("Schema:");
+    for statement in &script.schema {
+        println!("  {statement}");
+    }
+    println!("Rows (batches of {}):", config.batch_size);
+    for step in &script.steps {
+        println!("  {}: {}", step.file.display(), step.statement);
+    }
+}
+
+/// Prints the differences between a loaded database and `manifest.json`.
+fn print_count_mismatches(mismatches: &[String]) {
+    if mismatches.is_empty() {
+        return;
+    }
+    println!();
+    println!("WARNING: the loaded database does not match the extraction manifest:");
+    for mismatch in mismatches {
+        println!("  - {mismatch}");
+    }
+    println!("  Check the load log for failed batches; --strict-counts makes this an error.");
+}
+
+fn print_load_plan(plan: &dedalus::surrealdb_writer::LoadPlan, clean: bool) {
+    println!();
+    println!("=== Load Plan (nothing written) ===");
+    println!(
+        "CSV rows:         {} articles, {} edges",
+        plan.to_load.articles, plan.to_load.edges
+    );
+    match plan.existing {
+        Some(existing) => println!(
+            "Database has:     {} articles, {} {} edges{}",
+            existing.articles,
+            existing.edges,
+            plan.relation_table,
+            if clean { " (removed by --clean)" } else { "" }
+        ),
+        None => println!("Database has:     nothing yet (would be created)"),
+    }
+    if plan.is_clean() {
+        println!(
+            "Load would add {} articles and {} {} edges.",
+            plan.to_load.articles, plan.to_load.edges, plan.relation_table
+        );
+    } else {
+        println!("Conflicts:");
+        for conflict in &plan.conflicts {
+            println!("  - {conflict}");
+        }
+    }
+}
+
 fn run_analytics(args: AnalyticsArgs) -> Result<()> {
     let config = dedalus::analytics::AnalyticsConfig {
         db_path: args.db_path,
+        namespace: args.surreal_ns,
+        database: args.surreal_db,
         output_dir: args.output,
         pagerank_iterations: args.pagerank_iterations,
         pagerank_damping: args.damping,
@@ -431,7 +1526,9 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
     let overall_start = Instant::now();
 
     // Pre-flight: validate dump file
-    let input_path = Path::new(&args.input);
+    let inputs = DumpInputs::parse(&args.input)?;
+    require_local_dump(&inputs.articles)?;
+    let input_path = Path::new(&inputs.articles);
     if !input_path.exists() {
         bail!(
             "Wikipedia dump not found: {}\n\n\
@@ -439,11 +1536,11 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
              dedalus pipeline -i path/to/dump.xml.bz2 -o output/\n\n\
              Download Wikipedia dumps from:\n  \
              https://dumps.wikimedia.org/enwiki/latest/",
-            args.input
+            inputs.articles
         );
     }
     let dump_size = fs::metadata(input_path)?.len();
-    println!("==> Using Wikipedia dump: {}", args.input);
+    println!("==> Using Wikipedia dump: {}", inputs.articles);
     println!("    Size: {}", format_size(dump_size));
     println!();
 
@@ -459,30 +1556,86 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
     // Step 1: Extract
     let mut step = 1;
     println!("==> Step {step}/{step_count}: Extracting Wikipedia dump...");
-    println!("    Input:       {}", args.input);
+    println!("    Input:       {}", inputs.articles);
+    if let Some(images) = &inputs.images {
+        println!("    Images:      {images}");
+    }
     println!("    Output:      {}", args.output);
     println!("    CSV shards:  {}", args.csv_shards);
     println!("    Blob shards: {}", args.shard_count);
     if let Some(limit) = args.limit {
-        println!("    Limit:       {} pages", limit);
+        println!("    Limit:       {} articles", limit);
+    }
+    if args.no_blobs {
+        println!("    Blobs:       skipped (--no-blobs)");
     }
     println!();
 
-    run_extract(ExtractArgs {
+    let extracted = run_extract(ExtractArgs {
         input: args.input.clone(),
         output: args.output.clone(),
         shard_count: args.shard_count,
         csv_shards: args.csv_shards,
+        shard_strategy: args.shard_strategy,
         limit: args.limit,
+        max_output_bytes: args.max_output_bytes,
+        max_runtime: args.max_runtime,
+        metrics_addr: args.metrics_addr,
+        max_shard_bytes: args.max_shard_bytes,
+        max_invalid_ratio: args.max_invalid_ratio,
+        blob_sample_rate: args.blob_sample_rate,
         dry_run: false,
+        single_thread: false,
+        no_blobs: args.no_blobs,
+        blobs_only: false,
+        blob_links: args.blob_links,
+        blob_format: args.blob_format,
+        edge_section_context: args.edge_section_context,
+        edge_redirect_flag: args.edge_redirect_flag,
+        weighted_edges: args.weighted_edges,
+        keep_self_links: args.keep_self_links,
+        normalize_urls: args.normalize_urls,
+        single_revision: args.single_revision,
+        template_stats: args.template_stats,
+        length_histogram: args.length_histogram,
+        category_counts: args.category_counts,
+        lead_templates: args.lead_templates,
+        emit_redirect_map: args.emit_redirect_map,
+        interwiki_links: args.interwiki_links,
+        coordinates: args.coordinates,
+        compact_ids: args.compact_ids,
+        sort_nodes: args.sort_nodes,
+        sort_edges: args.sort_edges,
+        compute_in_degree: args.compute_in_degree,
+        export_graph_jsonl: args.export_graph_jsonl.clone(),
+        relationship_name: args.relationship_name.clone(),
+        page_label: args.page_label.clone(),
+        link_namespaces: args.link_namespaces.clone(),
+        exclude_category: args.exclude_category.clone(),
+        dedup_strategy: args.dedup_strategy,
+        output_format: OutputFormat::Csv,
         resume: args.resume,
+        resume_from: args.resume_from,
+        strict_ids: args.strict_ids,
         no_cache: args.no_cache,
+        index_delta: args.index_delta,
+        namespaces: args.namespaces,
+        redirect_max_depth: args.redirect_max_depth,
+        // Checked after the load, so a low rate does not stop the pipeline.
+        fail_on_low_resolution: false,
         checkpoint_interval: args.checkpoint_interval,
         clean: args.clean,
         multistream_index: args.multistream_index.clone(),
     })
     .context("Extraction step failed")?;
 
+    // Merging or loading partial output would only run past the time budget.
+    if extracted.timed_out {
+        println!();
+        println!("==> Stopping after extraction: time budget exhausted; rerun with --resume");
+        return Ok(());
+    }
+
     // Step 2: Merge (conditional)
     step += 1;
     if args.csv_shards > 1 {
@@ -491,7 +1644,16 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
             "==> Step {step}/{step_count}: Merging {} CSV shards...",
             args.csv_shards
         );
-        dedalus::merge::merge_csv_shards(&args.output).context("Merge step failed")?;
+        let merge_stats = dedalus::merge::merge_csv_shards_with(
+            &args.output,
+            args.force_merge,
+            args.dedup_edges,
+            args.dedup_pass_mb << 20,
+        )
+        .context("Merge step failed")?;
+        if args.dedup_edges {
+            println!("    {}", merge_stats.duplicate_rows_summary());
+        }
 
         if !args.no_archive {
             println!("==> Archiving sharded CSV files...");
@@ -511,8 +1673,14 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
         let load_config = SurrealWriterConfig {
             output_dir: args.output.clone(),
             db_path: args.db_path.clone(),
+            namespace: args.surreal_ns.clone(),
+            database: args.surreal_db.clone(),
             batch_size: dedalus::config::SURREAL_BATCH_SIZE,
             clean: args.clean,
+            relationship_names: RelationshipNames::from_overrides(&args.relationship_name)?,
+            strict_counts: args.strict_counts,
+            retry: RetryPolicy::default(),
+            resume: false,
         };
 
         let rt = tokio::runtime::Builder::new_multi_thread()
@@ -528,6 +1696,7 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
             "    Loaded {} articles, {} edges in {:.1}s",
             load_stats.articles_loaded, load_stats.edges_loaded, load_stats.elapsed_secs
         );
+        print_count_mismatches(&load_stats.count_mismatches);
 
         // Step 4: Analytics
         if !args.no_analytics {
@@ -537,6 +1706,8 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
 
             let analytics_config = dedalus::analytics::AnalyticsConfig {
                 db_path: args.db_path.clone(),
+                namespace: args.surreal_ns.clone(),
+                database: args.surreal_db.clone(),
                 output_dir: args.output.clone(),
                 ..Default::default()
             };
@@ -577,6 +1748,11 @@ fn run_pipeline(args: PipelineArgs) -> Result<()> {
     }
     println!();
 
+    if let Some(ratio) = extracted.low_resolution
+        && args.fail_on_low_resolution
+    {
+        return Err(LowResolutionRate(ratio).into());
+    }
     Ok(())
 }
 
@@ -591,6 +1767,18 @@ fn run_stats(args: StatsArgs) -> Result<()> {
     println!("Directory: {}", args.output);
     println!();
 
+    // Dump metadata recorded at extraction
+    let dump_info = fs::read_to_string(output_dir.join(dedalus::config::DUMP_INFO_FILENAME))
+        .ok()
+        .and_then(|s| serde_json::from_str::<dedalus::models::DumpInfo>(&s).ok());
+    if let Some(info) = dump_info {
+        let unknown = || "unknown".to_string();
+        println!("Dump:");
+        println!("  Generator: {}", info.generator.unwrap_or_else(unknown));
+        println!("  Database:  {}", info.dbname.unwrap_or_else(unknown));
+        println!();
+    }
+
     // CSV files
     println!("CSV Files:");
     let mut csv_files: Vec<_> = fs::read_dir(output_dir)?
@@ -622,9 +1810,13 @@ fn run_stats(args: StatsArgs) -> Result<()> {
     if blobs_dir.exists() {
         let mut blob_count = 0u64;
         let mut blob_size = 0u64;
+        let mut jsonl_files = 0u64;
         if let Ok(shard_dirs) = fs::read_dir(&blobs_dir) {
             for shard_dir in shard_dirs.filter_map(|e| e.ok()) {
-                if shard_dir.file_type().map(|t| t.is_dir()).unwrap_or(false)
+                if shard_dir.file_name().to_string_lossy().ends_with(".jsonl") {
+                    jsonl_files += 1;
+                    blob_size += shard_dir.metadata().map(|m| m.len()).unwrap_or(0);
+                } else if shard_dir.file_type().map(|t| t.is_dir()).unwrap_or(false)
                     && let Ok(files) = fs::read_dir(shard_dir.path())
                 {
                     for file in files.filter_map(|e| e.ok()) {
@@ -637,6 +1829,9 @@ fn run_stats(args: StatsArgs) -> Result<()> {
             }
         }
         println!("  Total blobs: {}", blob_count);
+        if jsonl_files > 0 {
+            println!("  JSONL files: {}", jsonl_files);
+        }
         println!("  Disk usage:  {}", format_size(blob_size));
     } else {
         println!("  None found");
@@ -693,6 +1888,215 @@ fn dir_size(path: &Path) -> u64 {
     total
 }
 
+fn run_resolve(args: ResolveArgs) -> Result<()> {
+    let index = cache::load_index(Path::new(&args.index))?;
+    let titles = if args.titles.is_empty() {
+        std::io::stdin()
+            .lines()
+            .collect::<std::io::Result<Vec<_>>>()
+            .context("Failed to read titles from stdin")?
+    } else {
+        args.titles
+    };
+    let titles: Vec<&str> = titles
+        .iter()
+        .map(|title| title.trim())
+        .filter(|title| !title.is_empty())
+        .collect();
+    print!("{}", resolve_report(&index, &titles));
+    Ok(())
+}
+
+/// One tab-separated line per title: the title, then its ID and the redirect
+/// chain followed (`title -> ... -> article`), or `NOT FOUND`.
+fn resolve_report(index: &dedalus::WikiIndex, titles: &[&str]) -> String {
+    let mut report = String::new();
+    for title in titles {
+        let line = match index.resolve_with_chain(title) {
+            Some((id, chain)) => format!("{title}\t{id}\t{}\n", chain.join(" -> ")),
+            None => format!("{title}\tNOT FOUND\n"),
+        };
+        report.push_str(&line);
+    }
+    report
+}
+
+fn run_sample(args: SampleArgs) -> Result<()> {
+    let stats =
+        dedalus::sample::write_sample(&args.input, Path::new(&args.output), args.pages, args.rate)?;
+    println!(
+        "Wrote {} of {} pages read to {}",
+        stats.pages_written, stats.pages_read, args.output
+    );
+    Ok(())
+}
+
+fn run_validate(args: ValidateArgs) -> Result<()> {
+    let report = dedalus::validate::validate_output(&args.output, args.partial)?;
+
+    println!(
+        "==> Validation{}",
+        if report.partial { " (partial)" } else { "" }
+    );
+    println!();
+    if let Some((last_id, articles)) = report.checkpoint {
+        println!("Checkpoint:           {articles} articles, last page ID {last_id}");
+    }
+    println!("Nodes:                {}", report.nodes);
+    println!("Edges:                {}", report.edges);
+    println!("Article relationships: {}", report.article_relationships);
+    println!("Unknown start IDs:    {}", report.unknown_starts);
+    println!("Unknown edge targets: {}", report.unknown_ends);
+    println!("Malformed rows:       {}", report.malformed_rows);
+    println!("Unflushed tails:      {}", report.truncated_tails);
+    for warning in &report.warnings {
+        println!("WARNING: {warning}");
+    }
+    for problem in &report.problems {
+        println!("PROBLEM: {problem}");
+    }
+    if !report.is_ok() {
+        bail!(
+            "Validation failed with {} problem(s)",
+            report.problems.len()
+        );
+    }
+    println!();
+    println!("OK");
+    Ok(())
+}
+
+/// Corrupt blobs listed by `verify-blobs` before the rest are summarized.
+const MAX_LISTED_CORRUPT_BLOBS: usize = 20;
+
+fn run_verify_blobs(args: VerifyBlobsArgs) -> Result<()> {
+    let report = dedalus::validate::verify_blobs(&args.output)?;
+
+    println!("==> Blob Verification");
+    println!();
+    println!("Files checked:        {}", report.files);
+    println!("Valid blobs:          {}", report.valid);
+    println!("Corrupt blobs:        {}", report.corrupt.len());
+    for corrupt in report.corrupt.iter().take(MAX_LISTED_CORRUPT_BLOBS) {
+        println!("CORRUPT: {corrupt}");
+    }
+    if report.corrupt.len() > MAX_LISTED_CORRUPT_BLOBS {
+        println!(
+            "... and {} more",
+            report.corrupt.len() - MAX_LISTED_CORRUPT_BLOBS
+        );
+    }
+    if !report.is_ok() {
+        bail!("{} corrupt blob(s)", report.corrupt.len());
+    }
+    println!();
+    println!("OK");
+    Ok(())
+}
+
+fn run_diff(args: DiffArgs) -> Result<()> {
+    let diff = dedalus::diff::diff_outputs(&args.a, &args.b)?;
+
+    println!("==> Output Diff");
+    println!();
+    println!("Old: {}", args.a);
+    println!("New: {}", args.b);
+    println!();
+
+    println!(
+        "Nodes: {} -> {} (+{} / -{})",
+        diff.node_counts.0,
+        diff.node_counts.1,
+        diff.nodes_added.len(),
+        diff.nodes_removed.len()
+    );
+    print_diff_sample("Added", &diff.nodes_added, args.limit);
+    print_diff_sample("Removed", &diff.nodes_removed, args.limit);
+    println!();
+
+    println!("Edges:");
+    if diff.edge_counts.is_empty() {
+        println!("  None found");
+    }
+    for (edge_type, (old, new)) in &diff.edge_counts {
+        println!(
+            "  {:<10} {} -> {} ({:+})",
+            edge_type,
+            old,
+            new,
+            *new as i64 - *old as i64
+        );
+    }
+    println!();
+
+    println!(
+        "Categories: {} -> {} (+{} / -{})",
+        diff.category_counts.0,
+        diff.category_counts.1,
+        diff.categories_added.len(),
+        diff.categories_removed.len()
+    );
+    print_diff_sample("Added", &diff.categories_added, args.limit);
+    print_diff_sample("Removed", &diff.categories_removed, args.limit);
+    println!();
+
+    if diff.is_empty() {
+        println!("No differences.");
+    }
+    Ok(())
+}
+
+fn print_diff_sample<T: std::fmt::Display>(label: &str, items: &[T], limit: usize) {
+    if items.is_empty() || limit == 0 {
+        return;
+    }
+    let shown: Vec<String> = items.iter().take(limit).map(|i| i.to_string()).collect();
+    let more = items.len().saturating_sub(limit);
+    if more > 0 {
+        println!("  {}: {} ... and {} more", label, shown.join(", "), more);
+    } else {
+        println!("  {}: {}", label, shown.join(", "));
+    }
+}
+
+/// Parses a duration given in seconds (`3600` or `3600s`), minutes (`90m`) or hours (`2h`).
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (digits, unit_secs) = match s.as_bytes().last() {
+        Some(b's') => (&s[..s.len() - 1], 1),
+        Some(b'm') => (&s[..s.len() - 1], 60),
+        Some(b'h') => (&s[..s.len() - 1], 3600),
+        _ => (s, 1),
+    };
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid duration {s:?}; expected e.g. 3600s, 90m or 2h"))?;
+    match value.checked_mul(unit_secs) {
+        Some(0) => Err("duration must be greater than zero".to_string()),
+        Some(secs) => Ok(Duration::from_secs(secs)),
+        None => Err(format!("duration {s:?} is too large")),
+    }
+}
+
+/// Prints the non-empty `--length-histogram` buckets with their share of articles.
+fn print_length_histogram(lengths: &dedalus::stats::LengthHistogram) {
+    let counts = lengths.counts();
+    let total: u64 = counts.iter().sum();
+    println!("Article lengths (bytes of wikitext):");
+    for (bucket, &count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+        let range = match dedalus::stats::length_bucket_range(bucket) {
+            (min, Some(max)) if min == max => min.to_string(),
+            (min, Some(max)) => format!("{min}-{max}"),
+            (min, None) => format!("{min}+"),
+        };
+        println!(
+            "  {:>17}: {} ({:.1}%)",
+            range,
+            count,
+            count as f64 * 100.0 / total as f64
+        );
+    }
+}
+
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = 1024 * 1024;
@@ -734,13 +2138,22 @@ fn main() -> ExitCode {
     tracing::subscriber::set_global_default(subscriber).expect("Failed to set tracing subscriber");
 
     let result = match cli.command {
-        Commands::Extract(args) => run_extract(args),
+        Commands::Extract(args) => run_extract(args).map(drop),
         Commands::Load(args) => run_load(args),
         Commands::Analytics(args) => run_analytics(args),
         Commands::MergeCsvs(args) => {
             let output = args.output.clone();
             let archive = args.archive;
-            dedalus::merge::merge_csv_shards(&output).and_then(|()| {
+            dedalus::merge::merge_csv_shards_with(
+                &output,
+                args.force_merge,
+                args.dedup_edges,
+                args.dedup_pass_mb << 20,
+            )
+            .and_then(|stats| {
+                if args.dedup_edges {
+                    println!("{}", stats.duplicate_rows_summary());
+                }
                 if archive {
                     dedalus::merge::archive_shards(&output)
                 } else {
@@ -750,6 +2163,11 @@ fn main() -> ExitCode {
         }
         Commands::Pipeline(args) => run_pipeline(args),
         Commands::Stats(args) => run_stats(args),
+        Commands::Diff(args) => run_diff(args),
+        Commands::Resolve(args) => run_resolve(args),
+        Commands::Sample(args) => run_sample(args),
+        Commands::Validate(args) => run_validate(args),
+        Commands::VerifyBlobs(args) => run_verify_blobs(args),
         Commands::Tui => unreachable!(),
     };
 
@@ -758,9 +2176,145 @@ fn main() -> ExitCode {
             info!("Completed successfully");
             ExitCode::SUCCESS
         }
+        Err(e) if e.downcast_ref::<LowResolutionRate>().is_some() => {
+            error!("Error: {:#}", e);
+            ExitCode::from(LOW_RESOLUTION_EXIT_CODE)
+        }
+        Err(e) if e.downcast_ref::<Interrupted>().is_some() => {
+            error!("Error: {:#}", e);
+            ExitCode::from(INTERRUPTED_EXIT_CODE)
+        }
+        Err(e) if e.downcast_ref::<NoArticles>().is_some() => {
+            error!("Error: {:#}", e);
+            ExitCode::from(NO_ARTICLES_EXIT_CODE)
+        }
         Err(e) => {
             error!("Error: {:#}", e);
             ExitCode::FAILURE
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_load(args: &[&str]) -> LoadArgs {
+        let argv = ["dedalus", "load", "-o", "out"].iter().chain(args);
+        match Cli::try_parse_from(argv).unwrap().command {
+            Commands::Load(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn resolve_report_reads_titles_from_a_saved_cache() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("dump.xml.bz2");
+        fs::write(&input, "dump").unwrap();
+        let out = dir.path().to_str().unwrap();
+
+        let articles = [("Rust (programming language)".to_string(), 1)];
+        let redirects = [(
+            "Rust".to_string(),
+            dedalus::index::Redirect {
+                target: "Rust (programming language)".to_string(),
+                id: 2,
+            },
+        )];
+        let index = dedalus::WikiIndex::from_maps(
+            articles.into_iter().collect(),
+            redirects.into_iter().collect(),
+        );
+        cache::save_index(&index, input.to_str().unwrap(), out).unwrap();
+
+        let loaded = cache::load_index(&cache::cache_path(out)).unwrap();
+        assert_eq!(
+            resolve_report(&loaded, &["Rust (programming language)", "Rust", "Go"]),
+            "Rust (programming language)\t1\tRust (programming language)\n\
+             Rust\t1\tRust -> Rust (programming language)\n\
+             Go\tNOT FOUND\n"
+        );
+    }
+
+    #[test]
+    fn parse_duration_accepts_seconds_minutes_and_hours() {
+        assert_eq!(parse_duration("3600s"), Ok(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("90m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("1.5h").is_err());
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn db_settings_fall_back_to_env_then_default() {
+        // SAFETY: this is the only test in the binary that touches these variables.
+        unsafe {
+            std::env::remove_var("DEDALUS_DB_PATH");
+            std::env::remove_var("SURREAL_NAMESPACE");
+            std::env::remove_var("SURREAL_DATABASE");
+        }
+        let args = parse_load(&[]);
+        assert_eq!(args.db_path, dedalus::config::DEFAULT_DB_PATH);
+        assert_eq!(args.surreal_ns, dedalus::config::SURREAL_NAMESPACE);
+        assert_eq!(args.surreal_db, dedalus::config::SURREAL_DATABASE);
+
+        unsafe {
+            std::env::set_var("DEDALUS_DB_PATH", "/data/graph.db");
+            std::env::set_var("SURREAL_NAMESPACE", "prod");
+            std::env::set_var("SURREAL_DATABASE", "enwiki");
+        }
+        let args = parse_load(&[]);
+        assert_eq!(args.db_path, "/data/graph.db");
+        assert_eq!(args.surreal_ns, "prod");
+        assert_eq!(args.surreal_db, "enwiki");
+
+        // An explicit flag still wins over the environment.
+        let args = parse_load(&["--surreal-ns", "staging"]);
+        assert_eq!(args.surreal_ns, "staging");
+        assert_eq!(args.surreal_db, "enwiki");
+
+        unsafe {
+            std::env::remove_var("DEDALUS_DB_PATH");
+            std::env::remove_var("SURREAL_NAMESPACE");
+            std::env::remove_var("SURREAL_DATABASE");
+        }
+    }
+
+    #[test]
+    fn inputs_split_by_role() {
+        let parse = |values: &[&str]| {
+            DumpInputs::parse(&values.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            parse(&["enwiki.xml.bz2", "commons.xml.bz2:images"]).unwrap(),
+            DumpInputs {
+                articles: "enwiki.xml.bz2".to_string(),
+                images: Some("commons.xml.bz2".to_string()),
+            }
+        );
+        // A suffix that is not a role stays part of the path.
+        assert_eq!(
+            parse(&["C:dumps/enwiki.xml.bz2:articles"])
+                .unwrap()
+                .articles,
+            "C:dumps/enwiki.xml.bz2"
+        );
+        assert_eq!(
+            parse(&["https://example.org/enwiki.xml.bz2"])
+                .unwrap()
+                .articles,
+            "https://example.org/enwiki.xml.bz2"
+        );
+        let err = parse(&["commons.xml.bz2:images"]).unwrap_err();
+        assert!(
+            err.to_string()
+                .ends_with("(PATH or PATH:articles); PATH:images only adds image metadata"),
+            "{err}"
+        );
+        assert!(parse(&["a.xml.bz2", "b.xml.bz2"]).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! Summary of an extraction run written next to its output (`manifest.json`).
+//!
+//! `run_extraction()` writes a [`Manifest`] once every sink is flushed: the
+//! input dump's path, modification time and size, the sharding and output
+//! settings, every `ExtractionStats` counter, the index's duplicate page IDs,
+//! the crate version and the run's start and end times (Unix seconds). A run
+//! that stopped early (output size cap, time budget, Ctrl-C) is recorded with
+//! `complete: false`, and `interrupted`/`time_budget_exhausted` say why;
+//! resuming it rewrites the manifest with the cumulative counters.
+//!
+//! `merge-csvs` and `load` call [`check_output`] before touching the CSVs, so
+//! shards left over from a run with a different `--csv-shards`, or a manifest
+//! from a newer dedalus, are reported instead of silently mixed in. Output
+//! written before manifests existed has none and is accepted as before.
+
+use crate::checkpoint::{self, CheckpointStats};
+use crate::config::MANIFEST_FILENAME;
+use crate::extract::ExtractionConfig;
+use crate::shard::ShardStrategy;
+use crate::stats::ExtractionStats;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{debug, warn};
+
+/// Manifest format version; bumped when a field changes meaning.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// What an extraction run read, how it wrote its output, and what it counted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    /// `CARGO_PKG_VERSION` of the dedalus that wrote the output.
+    pub dedalus_version: String,
+    pub input_path: String,
+    /// Modification time of the input dump, in Unix seconds.
+    pub input_mtime: u64,
+    /// Size of the input dump in bytes.
+    pub input_size: u64,
+    pub csv_shards: u32,
+    /// Blob directories under `blobs/`.
+    pub shard_count: u32,
+    pub shard_strategy: ShardStrategy,
+    /// `csv` or `sqlite`.
+    pub output_format: String,
+    pub blobs_only: bool,
+    pub started_at: u64,
+    pub finished_at: u64,
+    /// `false` if the run stopped before the end of the dump and can be resumed.
+    pub complete: bool,
+    /// Ctrl-C stopped the run.
+    #[serde(default)]
+    pub interrupted: bool,
+    /// `--max-runtime` stopped the run.
+    #[serde(default)]
+    pub time_budget_exhausted: bool,
+    /// Pages the indexing pass saw with an ID an earlier page already had;
+    /// each was written as a duplicate `Page` node.
+    #[serde(default)]
+    pub duplicate_ids: u64,
+    pub stats: CheckpointStats,
+}
+
+/// Current time in Unix seconds.
+#[must_use]
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn manifest_path(output_dir: &str) -> PathBuf {
+    Path::new(output_dir).join(MANIFEST_FILENAME)
+}
+
+impl Manifest {
+    /// Describes the run configured by `config`, ending now.
+    pub fn for_run(
+        config: &ExtractionConfig,
+        stats: &ExtractionStats,
+        started_at: u64,
+        complete: bool,
+    ) -> Result<Self> {
+        let input_size = fs::metadata(config.input_path)
+            .with_context(|| format!("Failed to get metadata for: {}", config.input_path))?
+            .len();
+        Ok(Self {
+            version: MANIFEST_VERSION,
+            dedalus_version: env!("CARGO_PKG_VERSION").to_string(),
+            input_path: config.input_path.to_string(),
+            input_mtime: checkpoint::get_input_mtime(config.input_path)?,
+            input_size,
+            csv_shards: config.csv_shards,
+            shard_count: config.shard_count,
+            shard_strategy: config.shard_strategy,
+            output_format: config.output_format.to_string(),
+            blobs_only: config.blobs_only,
+            started_at,
+            finished_at: unix_now(),
+            complete,
+            interrupted: stats.interrupted(),
+            time_budget_exhausted: stats.time_budget_exhausted(),
+            duplicate_ids: config.index.duplicate_ids(),
+            stats: stats.to_checkpoint(),
+        })
+    }
+
+    /// A complete run over `dump.xml.bz2` with `csv_shards` CSV shards that
+    /// counted `stats`.
+    #[cfg(test)]
+    pub(crate) fn for_test(csv_shards: u32, stats: CheckpointStats) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            dedalus_version: env!("CARGO_PKG_VERSION").to_string(),
+            input_path: "dump.xml.bz2".to_string(),
+            input_mtime: 1,
+            input_size: 2,
+            csv_shards,
+            shard_count: crate::config::SHARD_COUNT,
+            shard_strategy: ShardStrategy::Modulo,
+            output_format: "csv".to_string(),
+            blobs_only: false,
+            started_at: 3,
+            finished_at: 4,
+            complete: true,
+            interrupted: false,
+            time_budget_exhausted: false,
+            duplicate_ids: 0,
+            stats,
+        }
+    }
+
+    /// Writes the manifest to `output_dir`, replacing any earlier one.
+    pub fn write(&self, output_dir: &str) -> Result<PathBuf> {
+        let path = manifest_path(output_dir);
+        let file = File::create(&path).with_context(|| format!("Failed to create {path:?}"))?;
+        let mut out = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut out, self)
+            .with_context(|| format!("Failed to write {path:?}"))?;
+        out.flush()
+            .with_context(|| format!("Failed to write {path:?}"))?;
+        Ok(path)
+    }
+
+    /// Checks that CSVs found with `found_shards` shards (1 for a single-file
+    /// or merged layout) can be processed as the output of this run.
+    pub fn check(&self, found_shards: u32) -> Result<()> {
+        if self.version > MANIFEST_VERSION {
+            bail!(
+                "{MANIFEST_FILENAME} has version {} (written by dedalus {}); \
+                 this build reads up to version {MANIFEST_VERSION}",
+                self.version,
+                self.dedalus_version
+            );
+        }
+        if found_shards > 1 && found_shards != self.csv_shards {
+            bail!(
+                "Found {found_shards} CSV shards, but {MANIFEST_FILENAME} records an extraction \
+                 with --csv-shards {}; remove shards left over from an earlier run",
+                self.csv_shards
+            );
+        }
+        if !self.complete {
+            warn!(
+                articles = self.stats.articles_processed,
+                "{MANIFEST_FILENAME} records an extraction that stopped early; \
+                 resume it to process the rest of the dump"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Loads the manifest in `output_dir`. `None` if there is none.
+pub fn load(output_dir: &str) -> Result<Option<Manifest>> {
+    let path = manifest_path(output_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = File::open(&path).with_context(|| format!("Failed to open {path:?}"))?;
+    let manifest = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse {path:?}"))?;
+    Ok(Some(manifest))
+}
+
+/// Loads the manifest in `output_dir`, if any, and [checks](Manifest::check)
+/// it against `found_shards`.
+pub fn check_output(output_dir: &str, found_shards: u32) -> Result<()> {
+    match load(output_dir)? {
+        Some(manifest) => manifest.check(found_shards),
+        None => {
+            debug!(
+                output_dir,
+                "No {MANIFEST_FILENAME}; skipping compatibility check"
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn manifest(csv_shards: u32, complete: bool) -> Manifest {
+        Manifest {
+            complete,
+            interrupted: !complete,
+            ..Manifest::for_test(csv_shards, CheckpointStats::default())
+        }
+    }
+
+    #[test]
+    fn check_rejects_mismatched_shards_and_newer_versions() {
+        let dir = TempDir::new().unwrap();
+        let output_dir = dir.path().to_str().unwrap();
+        check_output(output_dir, 4).unwrap();
+
+        manifest(4, false).write(output_dir).unwrap();
+        let loaded = load(output_dir).unwrap().unwrap();
+        assert_eq!(loaded.csv_shards, 4);
+        assert!(!loaded.complete);
+        assert!(loaded.interrupted);
+        check_output(output_dir, 4).unwrap();
+        // Merged output has a single file per type.
+        check_output(output_dir, 1).unwrap();
+        assert!(check_output(output_dir, 8).is_err());
+
+        let mut newer = manifest(4, true);
+        newer.version = MANIFEST_VERSION + 1;
+        assert!(newer.check(4).is_err());
+    }
+}
@@ -3,39 +3,386 @@
 //! Concatenates sharded CSV files into single merged files with cross-shard
 //! deduplication of categories, images, and external links using `FxHashSet`.
 //! Uses streaming I/O with 256KB buffers.
+//!
+//! Each merged file is written to `{name}.csv.tmp` and renamed into place once
+//! complete, so an interrupted merge never leaves a partial `{name}.csv`. On a
+//! rerun, types whose merged file is already valid (see `merged_file_is_current`)
+//! are skipped unless `force` is set.
+//!
+//! The eight types read and write disjoint files and keep their dedup sets to
+//! themselves, so they are merged concurrently on the rayon pool.
+//!
+//! With `dedup_edges`, relationship types (`edges` and the `article_*` files)
+//! also drop repeated identical rows. Their rows are sharded by the article ID
+//! in the first column, so a duplicate can only sit in the same shard as its
+//! original. A shard is deduplicated in one read per `pass_bytes` of its file:
+//! each pass keeps the keys of one hash partition of its rows, and a bitset of
+//! one bit per row carries the repeats found to the last pass, which writes.
+//! Up to four relationship types merge at once, so the sets hold roughly
+//! four times `pass_bytes` of row data at peak (`--dedup-pass-mb`). The rows
+//! dropped from each type are saved in `merge_stats.json` ([`DroppedRows`]), so
+//! a rerun that skips a merged type still reports them and `load` can expect
+//! that many fewer edges.
 
 use anyhow::{Context, Result, bail};
-use csv::{Reader, Writer};
-use rustc_hash::FxHashSet;
+use csv::{ByteRecord, Reader, Writer};
+use rayon::prelude::*;
+use rustc_hash::{FxHashSet, FxHasher};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::{self, File};
+use std::hash::Hasher;
 use std::io::{BufReader, BufWriter};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tracing::info;
 
+/// How the rows of one CSV type are deduplicated while merging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dedup {
+    /// Keep every row.
+    None,
+    /// Keep the first row for each ID (first column) across all shards.
+    Ids,
+    /// Keep the first copy of each identical row within a shard, holding the
+    /// keys of about `pass_bytes` of shard file in memory per read.
+    RecordsPerShard { pass_bytes: u64 },
+}
+
+/// Counts from a [`merge_csv_shards`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeStats {
+    /// CSV types merged.
+    pub merged: u64,
+    /// CSV types skipped because their merged file was already current.
+    pub skipped: u64,
+    /// Repeated relationship rows dropped under `dedup_edges`, including those
+    /// [`DroppedRows`] records for skipped types.
+    pub duplicate_rows: u64,
+}
+
+impl MergeStats {
+    /// The line `merge-csvs`/`pipeline` print for `--dedup-edges`, noting any
+    /// types skipped as already merged, whose counts come from `merge_stats.json`.
+    #[must_use]
+    pub fn duplicate_rows_summary(&self) -> String {
+        match self.skipped {
+            0 => format!("Duplicate rows dropped: {}", self.duplicate_rows),
+            skipped => format!(
+                "Duplicate rows dropped: {} ({skipped} types skipped as already merged)",
+                self.duplicate_rows
+            ),
+        }
+    }
+}
+
+/// Relationship rows each merged file lost to `dedup_edges`, by CSV type,
+/// saved as `merge_stats.json` by the merge that wrote the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DroppedRows {
+    pub rows: BTreeMap<String, u64>,
+}
+
+impl DroppedRows {
+    fn path(output_dir: &str) -> PathBuf {
+        Path::new(output_dir).join(crate::config::MERGE_STATS_FILENAME)
+    }
+
+    /// Reads the counts saved in `output_dir`; empty if there are none.
+    pub fn load(output_dir: &str) -> Result<Self> {
+        let path = Self::path(output_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let file = File::open(&path).with_context(|| format!("Failed to open {path:?}"))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("Failed to parse {path:?}"))
+    }
+
+    fn save(&self, output_dir: &str) -> Result<()> {
+        let path = Self::path(output_dir);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {tmp_path:?}"))?;
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to rename {tmp_path:?} to {path:?}"))
+    }
+
+    /// Rows dropped from `{base_name}.csv` (0 if none were recorded).
+    #[must_use]
+    pub fn get(&self, base_name: &str) -> u64 {
+        self.rows.get(base_name).copied().unwrap_or(0)
+    }
+}
+
+/// CSV types in merge order, with whether their rows need cross-shard dedup.
+const MERGE_PLAN: &[(&str, bool)] = &[
+    ("nodes", false),
+    ("edges", false),
+    ("categories", true),
+    ("article_categories", false),
+    ("image_nodes", true),
+    ("article_images", false),
+    ("external_link_nodes", true),
+    ("article_external_links", false),
+];
+
 /// Merges sharded CSV files into single files suitable for neo4j-admin import.
 ///
 /// Performs cross-shard deduplication for categories, images, and external links.
-pub fn merge_csv_shards(output_dir: &str) -> Result<()> {
+/// Types already merged by an earlier (possibly interrupted) run are skipped
+/// unless `force` is set.
+pub fn merge_csv_shards(output_dir: &str, force: bool) -> Result<MergeStats> {
+    merge_csv_shards_with(output_dir, force, false, crate::config::DEDUP_PASS_BYTES)
+}
+
+/// [`merge_csv_shards`], additionally dropping duplicate relationship rows
+/// within each shard when `dedup_edges` is set. The number dropped is
+/// reported in [`MergeStats::duplicate_rows`]. A shard is read once per
+/// `dedup_pass_bytes` of its size, which bounds the rows held in memory.
+pub fn merge_csv_shards_with(
+    output_dir: &str,
+    force: bool,
+    dedup_edges: bool,
+    dedup_pass_bytes: u64,
+) -> Result<MergeStats> {
     info!("Detecting CSV shards in: {}", output_dir);
 
     // Detect shard count from nodes_*.csv
     let shard_count = detect_shard_count(output_dir)?;
     info!("  Found {} shards", shard_count);
+    crate::manifest::check_output(output_dir, shard_count)?;
 
-    // Merge each CSV type
-    merge_simple(output_dir, "nodes", shard_count)?;
-    merge_simple(output_dir, "edges", shard_count)?;
-    merge_with_dedup(output_dir, "categories", shard_count)?; // Needs dedup
-    merge_simple(output_dir, "article_categories", shard_count)?;
-    merge_with_dedup(output_dir, "image_nodes", shard_count)?; // Needs dedup
-    merge_simple(output_dir, "article_images", shard_count)?;
-    merge_with_dedup(output_dir, "external_link_nodes", shard_count)?; // Needs dedup
-    merge_simple(output_dir, "article_external_links", shard_count)?;
+    let dedup_pass_bytes = dedup_edges.then_some(dedup_pass_bytes);
+    let stats = merge_types(output_dir, shard_count, force, dedup_pass_bytes)?;
 
     info!("Merge complete. Single CSV files ready for --admin-import.");
+    Ok(stats)
+}
+
+/// Merges every type in `MERGE_PLAN` concurrently on the current rayon pool.
+/// Relationship rows are deduplicated when `dedup_pass_bytes` is set.
+fn merge_types(
+    output_dir: &str,
+    shard_count: u32,
+    force: bool,
+    dedup_pass_bytes: Option<u64>,
+) -> Result<MergeStats> {
+    let merge = |&(base_name, dedup_ids): &(&str, bool)| {
+        let dedup = match dedup_pass_bytes {
+            _ if dedup_ids => Dedup::Ids,
+            Some(pass_bytes) if is_relationship(base_name) => Dedup::RecordsPerShard { pass_bytes },
+            _ => Dedup::None,
+        };
+        if !force && merged_file_is_current(output_dir, base_name, shard_count, dedup)? {
+            info!("  Skipping {} (already merged)", base_name);
+            return Ok(None);
+        }
+        if dedup == Dedup::Ids {
+            merge_with_dedup(output_dir, base_name, shard_count)?;
+            Ok(Some(0))
+        } else {
+            merge_simple(output_dir, base_name, shard_count, dedup).map(Some)
+        }
+    };
+    let results: Vec<Option<u64>> = MERGE_PLAN.par_iter().map(merge).collect::<Result<_>>()?;
+
+    let mut dropped = DroppedRows::load(output_dir)?;
+    let mut stats = MergeStats::default();
+    for (&(base_name, _), result) in MERGE_PLAN.iter().zip(results) {
+        match result {
+            Some(duplicates) => {
+                stats.merged += 1;
+                stats.duplicate_rows += duplicates;
+                if is_relationship(base_name) {
+                    dropped.rows.insert(base_name.to_string(), duplicates);
+                }
+            }
+            None => {
+                stats.skipped += 1;
+                stats.duplicate_rows += dropped.get(base_name);
+            }
+        }
+    }
+    if stats.merged > 0 {
+        dropped.save(output_dir)?;
+    }
+    Ok(stats)
+}
+
+/// Whether `base_name` holds article-to-X relationship rows keyed by article ID.
+fn is_relationship(base_name: &str) -> bool {
+    base_name == "edges" || base_name.starts_with("article_")
+}
+
+/// Set key for a whole record: each field prefixed by its length, so
+/// `("1", "23")` and `("12", "3")` stay distinct.
+fn record_key(record: &ByteRecord) -> Vec<u8> {
+    let mut key = Vec::with_capacity(record.as_slice().len() + 4 * record.len());
+    for field in record {
+        key.extend_from_slice(&(field.len() as u32).to_le_bytes());
+        key.extend_from_slice(field);
+    }
+    key
+}
+
+/// Calls `visit` with each row of the shard at `path`, in file order, and
+/// whether it is the first copy of itself in the shard.
+///
+/// Rows are split by a hash of their key into one partition per `pass_bytes`
+/// of the file, and the shard is read once per partition, so only that
+/// partition's keys are held. Repeats are marked in a bitset of one bit per
+/// row; the last read calls `visit`.
+fn for_each_distinct(
+    path: &Path,
+    pass_bytes: u64,
+    mut visit: impl FnMut(&ByteRecord, bool) -> Result<()>,
+) -> Result<()> {
+    let size = fs::metadata(path)
+        .with_context(|| format!("Failed to read size of {:?}", path))?
+        .len();
+    let passes = size.div_ceil(pass_bytes.max(1)).max(1);
+    let mut repeats: Vec<u64> = Vec::new();
+    let mut record = ByteRecord::new();
+    for pass in 0..passes {
+        let mut seen = FxHashSet::default();
+        let mut reader = open_csv(path)?;
+        let mut row = 0usize;
+        while reader.read_byte_record(&mut record)? {
+            let (word, bit) = (row / 64, 1u64 << (row % 64));
+            if word == repeats.len() {
+                repeats.push(0);
+            }
+            if (passes == 1 || partition(&record, passes) == pass)
+                && !seen.insert(record_key(&record))
+            {
+                repeats[word] |= bit;
+            }
+            if pass + 1 == passes {
+                visit(&record, repeats[word] & bit == 0)?;
+            }
+            row += 1;
+        }
+    }
     Ok(())
 }
 
+/// Which of `passes` dedup partitions `record` belongs to.
+fn partition(record: &ByteRecord, passes: u64) -> u64 {
+    let mut hasher = FxHasher::default();
+    for field in record {
+        hasher.write_usize(field.len());
+        hasher.write(field);
+    }
+    crate::shard::fmix64(hasher.finish()) % passes
+}
+
+fn shard_path(output_dir: &str, base_name: &str, shard: u32) -> PathBuf {
+    Path::new(output_dir).join(format!("{}_{:03}.csv", base_name, shard))
+}
+
+fn open_csv(path: &Path) -> Result<Reader<BufReader<File>>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {:?}", path))?;
+    Ok(Reader::from_reader(BufReader::with_capacity(
+        crate::config::MERGE_BUF_SIZE,
+        file,
+    )))
+}
+
+fn modified(path: &Path) -> Result<SystemTime> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Failed to read modification time of {:?}", path))
+}
+
+/// Returns `true` if `{base_name}.csv` exists, is no older than any shard, has
+/// the shards' header, and holds the expected number of rows: the shard total,
+/// the number of distinct IDs, or the per-shard distinct rows, by `dedup`.
+fn merged_file_is_current(
+    output_dir: &str,
+    base_name: &str,
+    shard_count: u32,
+    dedup: Dedup,
+) -> Result<bool> {
+    let merged_path = Path::new(output_dir).join(format!("{}.csv", base_name));
+    if !merged_path.exists() {
+        return Ok(false);
+    }
+    let merged_mtime = modified(&merged_path)?;
+
+    let mut shard_header = None;
+    let mut total_rows = 0u64;
+    let mut seen_ids = FxHashSet::default();
+    for shard in 0..shard_count {
+        let path = shard_path(output_dir, base_name, shard);
+        if modified(&path)? > merged_mtime {
+            return Ok(false);
+        }
+        let mut reader = open_csv(&path)?;
+        if shard_header.is_none() {
+            shard_header = Some(reader.headers()?.clone());
+        }
+        if let Dedup::RecordsPerShard { pass_bytes } = dedup {
+            for_each_distinct(&path, pass_bytes, |_, first| {
+                total_rows += u64::from(first);
+                Ok(())
+            })?;
+            continue;
+        }
+        for result in reader.records() {
+            let record = result?;
+            if dedup == Dedup::Ids {
+                seen_ids.insert(record.get(0).context("Missing node ID")?.to_string());
+            } else {
+                total_rows += 1;
+            }
+        }
+    }
+    let expected_rows = if dedup == Dedup::Ids {
+        seen_ids.len() as u64
+    } else {
+        total_rows
+    };
+
+    let mut merged = open_csv(&merged_path)?;
+    if Some(merged.headers()?) != shard_header.as_ref() {
+        return Ok(false);
+    }
+    let mut merged_rows = 0u64;
+    for result in merged.records() {
+        if result.is_err() {
+            return Ok(false);
+        }
+        merged_rows += 1;
+    }
+    Ok(merged_rows == expected_rows)
+}
+
+/// Creates the `{base_name}.csv.tmp` writer that `finish_merged` renames into place.
+fn create_merged_writer(output_dir: &str, base_name: &str) -> Result<Writer<BufWriter<File>>> {
+    let tmp_path = Path::new(output_dir).join(format!("{}.csv.tmp", base_name));
+    let file =
+        File::create(&tmp_path).with_context(|| format!("Failed to create {:?}", tmp_path))?;
+    Ok(Writer::from_writer(BufWriter::with_capacity(
+        crate::config::MERGE_BUF_SIZE,
+        file,
+    )))
+}
+
+/// Flushes the temp file and atomically renames it to `{base_name}.csv`.
+fn finish_merged(
+    mut writer: Writer<BufWriter<File>>,
+    output_dir: &str,
+    base_name: &str,
+) -> Result<()> {
+    writer.flush()?;
+    drop(writer);
+    let tmp_path = Path::new(output_dir).join(format!("{}.csv.tmp", base_name));
+    let output_path = Path::new(output_dir).join(format!("{}.csv", base_name));
+    fs::rename(&tmp_path, &output_path)
+        .with_context(|| format!("Failed to rename {:?} to {:?}", tmp_path, output_path))
+}
+
 /// Detect shard count by counting nodes_*.csv files.
 fn detect_shard_count(output_dir: &str) -> Result<u32> {
     let count = (0u32..)
@@ -51,70 +398,63 @@ fn detect_shard_count(output_dir: &str) -> Result<u32> {
     Ok(count)
 }
 
-/// Simple concatenation for CSV types without deduplication needs
-fn merge_simple(output_dir: &str, base_name: &str, shard_count: u32) -> Result<()> {
+/// Concatenation for CSV types without ID deduplication. With
+/// `Dedup::RecordsPerShard`, repeated identical rows within a shard are dropped
+/// and their number returned.
+fn merge_simple(output_dir: &str, base_name: &str, shard_count: u32, dedup: Dedup) -> Result<u64> {
     info!("  Merging {}...", base_name);
 
-    let output_path = Path::new(output_dir).join(format!("{}.csv", base_name));
-    let mut writer = Writer::from_writer(BufWriter::with_capacity(
-        crate::config::MERGE_BUF_SIZE,
-        File::create(&output_path)?,
-    ));
+    let mut writer = create_merged_writer(output_dir, base_name)?;
 
     // Write header from first shard
-    let first_shard = Path::new(output_dir).join(format!("{}_{:03}.csv", base_name, 0));
-    let mut first_reader = Reader::from_reader(BufReader::with_capacity(
-        crate::config::MERGE_BUF_SIZE,
-        File::open(&first_shard)?,
-    ));
+    let mut first_reader = open_csv(&shard_path(output_dir, base_name, 0))?;
     writer.write_record(first_reader.headers()?)?;
 
     // Copy data rows from all shards
+    let mut duplicates = 0u64;
     for shard in 0..shard_count {
-        let shard_path = Path::new(output_dir).join(format!("{}_{:03}.csv", base_name, shard));
-        let mut reader = Reader::from_reader(BufReader::with_capacity(
-            crate::config::MERGE_BUF_SIZE,
-            File::open(&shard_path)?,
-        ));
+        let path = shard_path(output_dir, base_name, shard);
+        if let Dedup::RecordsPerShard { pass_bytes } = dedup {
+            for_each_distinct(&path, pass_bytes, |record, first| {
+                if first {
+                    writer.write_byte_record(record)?;
+                } else {
+                    duplicates += 1;
+                }
+                Ok(())
+            })?;
+            continue;
+        }
+        let mut reader = open_csv(&path)?;
 
         for result in reader.records() {
-            let record = result?;
-            writer.write_record(&record)?;
+            writer.write_record(&result?)?;
         }
     }
 
-    writer.flush()?;
-    Ok(())
+    finish_merged(writer, output_dir, base_name)?;
+    if matches!(dedup, Dedup::RecordsPerShard { .. }) {
+        info!("    Duplicate rows dropped: {}", duplicates);
+    }
+    Ok(duplicates)
 }
 
 /// Merge with deduplication for node files (first column is ID)
 fn merge_with_dedup(output_dir: &str, base_name: &str, shard_count: u32) -> Result<()> {
     info!("  Merging {} (with deduplication)...", base_name);
 
-    let output_path = Path::new(output_dir).join(format!("{}.csv", base_name));
-    let mut writer = Writer::from_writer(BufWriter::with_capacity(
-        crate::config::MERGE_BUF_SIZE,
-        File::create(&output_path)?,
-    ));
+    let mut writer = create_merged_writer(output_dir, base_name)?;
 
     // Track seen node IDs
     let mut seen_ids = FxHashSet::default();
 
     // Write header from first shard
-    let first_shard = Path::new(output_dir).join(format!("{}_{:03}.csv", base_name, 0));
-    let mut first_reader = Reader::from_reader(BufReader::with_capacity(
-        crate::config::MERGE_BUF_SIZE,
-        File::open(&first_shard)?,
-    ));
+    let mut first_reader = open_csv(&shard_path(output_dir, base_name, 0))?;
     writer.write_record(first_reader.headers()?)?;
 
     // Read all shards, skip duplicates
     for shard in 0..shard_count {
-        let shard_path = Path::new(output_dir).join(format!("{}_{:03}.csv", base_name, shard));
-        let mut reader = Reader::from_reader(BufReader::with_capacity(
-            crate::config::MERGE_BUF_SIZE,
-            File::open(&shard_path)?,
-        ));
+        let mut reader = open_csv(&shard_path(output_dir, base_name, shard))?;
 
         for result in reader.records() {
             let record = result?;
@@ -127,7 +467,7 @@ fn merge_with_dedup(output_dir: &str, base_name: &str, shard_count: u32) -> Resu
         }
     }
 
-    writer.flush()?;
+    finish_merged(writer, output_dir, base_name)?;
     info!("    Unique nodes: {}", seen_ids.len());
     Ok(())
 }
@@ -235,7 +575,7 @@ mod tests {
             &["3,Article3,Article", "4,Article4,Article"],
         )?;
 
-        merge_simple(temp_dir.path().to_str().unwrap(), "nodes", 2)?;
+        merge_simple(temp_dir.path().to_str().unwrap(), "nodes", 2, Dedup::None)?;
 
         // Verify merged file
         let merged_path = temp_dir.path().join("nodes.csv");
@@ -260,6 +600,7 @@ mod tests {
         assert!(!is_shard_file("something.txt"));
         assert!(!is_shard_file("nodes_00.csv")); // only 2 digits
         assert!(!is_shard_file("_000.csv")); // no base name
+        assert!(!is_shard_file("edges_part001.csv")); // --max-shard-bytes part
     }
 
     #[test]
@@ -340,4 +681,216 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn merge_leaves_no_temp_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        create_test_shard(
+            temp_dir.path(),
+            "nodes",
+            0,
+            "id:ID,title,:LABEL",
+            &["1,A,Page"],
+        )?;
+
+        merge_simple(temp_dir.path().to_str().unwrap(), "nodes", 1, Dedup::None)?;
+
+        assert!(temp_dir.path().join("nodes.csv").exists());
+        assert!(!temp_dir.path().join("nodes.csv.tmp").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn merged_file_is_current_checks_rows_and_header() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().to_str().unwrap();
+        let header = "id:ID(Category),name,:LABEL";
+        create_test_shard(temp_dir.path(), "categories", 0, header, &["A,A,Category"])?;
+        create_test_shard(
+            temp_dir.path(),
+            "categories",
+            1,
+            header,
+            &["A,A,Category", "B,B,Category"],
+        )?;
+        assert!(!merged_file_is_current(dir, "categories", 2, Dedup::Ids)?);
+
+        merge_with_dedup(dir, "categories", 2)?;
+        assert!(merged_file_is_current(dir, "categories", 2, Dedup::Ids)?);
+        // As a plain concatenation the 2 merged rows would be short of 3.
+        assert!(!merged_file_is_current(dir, "categories", 2, Dedup::None)?);
+
+        let merged = temp_dir.path().join("categories.csv");
+        fs::write(&merged, format!("{}\nA,A,Category\n", header))?;
+        assert!(!merged_file_is_current(dir, "categories", 2, Dedup::Ids)?);
+        fs::write(&merged, "id,name,label\nA,A,Category\nB,B,Category\n")?;
+        assert!(!merged_file_is_current(dir, "categories", 2, Dedup::Ids)?);
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_edges_drops_repeated_rows_within_a_shard() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().to_str().unwrap();
+        let header = ":START_ID,:END_ID,:TYPE";
+        create_test_shard(
+            temp_dir.path(),
+            "edges",
+            0,
+            header,
+            &["2,5,LINKS_TO", "2,7,LINKS_TO", "2,5,LINKS_TO"],
+        )?;
+        create_test_shard(temp_dir.path(), "edges", 1, header, &["3,5,LINKS_TO"])?;
+        let dedup = Dedup::RecordsPerShard {
+            pass_bytes: crate::config::DEDUP_PASS_BYTES,
+        };
+        assert!(!merged_file_is_current(dir, "edges", 2, dedup)?);
+
+        assert_eq!(merge_simple(dir, "edges", 2, dedup)?, 1);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("edges.csv"))?,
+            ":START_ID,:END_ID,:TYPE\n2,5,LINKS_TO\n2,7,LINKS_TO\n3,5,LINKS_TO\n"
+        );
+        assert!(merged_file_is_current(dir, "edges", 2, dedup)?);
+        // Without dedup the merged file is one row short.
+        assert!(!merged_file_is_current(dir, "edges", 2, Dedup::None)?);
+
+        let a = ByteRecord::from(vec!["1", "23"]);
+        let b = ByteRecord::from(vec!["12", "3"]);
+        assert_ne!(record_key(&a), record_key(&b));
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_edges_in_several_passes_matches_one_pass() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().to_str().unwrap();
+        let rows: Vec<String> = (0..200)
+            .map(|i| format!("{},{},LINKS_TO", i % 7, i % 13))
+            .collect();
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        create_test_shard(
+            temp_dir.path(),
+            "edges",
+            0,
+            ":START_ID,:END_ID,:TYPE",
+            &rows,
+        )?;
+
+        let one_pass = Dedup::RecordsPerShard {
+            pass_bytes: u64::MAX,
+        };
+        assert_eq!(merge_simple(dir, "edges", 1, one_pass)?, 200 - 91);
+        let expected = fs::read_to_string(temp_dir.path().join("edges.csv"))?;
+
+        // The shard is about 2 KB, so this splits it into about 20 passes.
+        let many_passes = Dedup::RecordsPerShard { pass_bytes: 100 };
+        assert_eq!(merge_simple(dir, "edges", 1, many_passes)?, 200 - 91);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("edges.csv"))?,
+            expected
+        );
+        assert!(merged_file_is_current(dir, "edges", 1, many_passes)?);
+        Ok(())
+    }
+
+    /// Three shards of every `MERGE_PLAN` type, each relationship shard with
+    /// one repeated row.
+    fn create_plan_shards(dir: &Path) -> Result<()> {
+        for &(base_name, dedup_ids) in MERGE_PLAN {
+            for shard in 0..3u32 {
+                let rows: Vec<String> = if dedup_ids {
+                    // Shared IDs across shards exercise the dedup path.
+                    vec![
+                        format!("shared,{base_name}"),
+                        format!("{base_name}{shard},x"),
+                    ]
+                } else {
+                    vec![
+                        format!("{shard}0,{base_name}"),
+                        format!("{shard}1,{base_name}"),
+                        format!("{shard}0,{base_name}"),
+                    ]
+                };
+                let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+                create_test_shard(dir, base_name, shard, "id,value", &rows)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parallel_merge_matches_sequential_merge() -> Result<()> {
+        let sequential = TempDir::new()?;
+        let parallel = TempDir::new()?;
+        for dir in [sequential.path(), parallel.path()] {
+            create_plan_shards(dir)?;
+        }
+
+        let dedup = Some(crate::config::DEDUP_PASS_BYTES);
+        // A one-thread pool merges the types one after another.
+        let stats = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()?
+            .install(|| merge_types(sequential.path().to_str().unwrap(), 3, true, dedup))?;
+        assert_eq!(
+            merge_types(parallel.path().to_str().unwrap(), 3, true, dedup)?,
+            stats
+        );
+        // One repeated row per shard in each of the four relationship types.
+        assert_eq!(
+            stats,
+            MergeStats {
+                merged: 8,
+                skipped: 0,
+                duplicate_rows: 12
+            }
+        );
+
+        for &(base_name, _) in MERGE_PLAN {
+            let name = format!("{base_name}.csv");
+            let expected = fs::read_to_string(sequential.path().join(&name))?;
+            assert!(expected.lines().count() > 3, "{name} was not merged");
+            assert_eq!(
+                fs::read_to_string(parallel.path().join(&name))?,
+                expected,
+                "{name}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn rerun_reports_rows_dropped_by_the_merge_that_wrote_the_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().to_str().unwrap();
+        create_plan_shards(temp_dir.path())?;
+        let dedup = Some(crate::config::DEDUP_PASS_BYTES);
+
+        let first = merge_types(dir, 3, false, dedup)?;
+        assert_eq!(first.duplicate_rows, 12);
+        assert_eq!(first.duplicate_rows_summary(), "Duplicate rows dropped: 12");
+        assert_eq!(DroppedRows::load(dir)?.get("edges"), 3);
+        assert_eq!(DroppedRows::load(dir)?.get("categories"), 0);
+
+        let rerun = merge_types(dir, 3, false, dedup)?;
+        assert_eq!(
+            rerun,
+            MergeStats {
+                merged: 0,
+                skipped: 8,
+                duplicate_rows: 12
+            }
+        );
+        assert_eq!(
+            rerun.duplicate_rows_summary(),
+            "Duplicate rows dropped: 12 (8 types skipped as already merged)"
+        );
+
+        // Re-merging without dedup keeps every row and records none dropped.
+        fs::remove_file(temp_dir.path().join("edges.csv"))?;
+        assert_eq!(merge_types(dir, 3, false, None)?.merged, 4);
+        assert_eq!(DroppedRows::load(dir)?.get("edges"), 0);
+        Ok(())
+    }
 }
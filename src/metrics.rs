@@ -0,0 +1,289 @@
+//! Prometheus text endpoint for live extraction counters (`--metrics-addr`).
+//!
+//! `MetricsServer::start()` binds a `TcpListener` and serves `GET /metrics` from
+//! a background thread, reading the `ExtractionStats` atomics at scrape time, so
+//! the workers never wait on it. Requests are answered one at a time; a
+//! scraper polling every few seconds is the expected client. Each connection
+//! gets `REQUEST_TIMEOUT` in total to send its request and `WRITE_TIMEOUT` per
+//! write of the response, so a stalled client cannot hold up the next scrape or
+//! the server's shutdown. Every counter is exported as `dedalus_<name>_total`;
+//! rates such as articles per second are left to PromQL
+//! (`rate(dedalus_articles_processed_total[1m])`).
+
+use crate::stats::ExtractionStats;
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// How long a connection may take to send its whole request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long one write of the response may block on a client that is not reading.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Reads from a client socket until a fixed deadline. A plain read timeout
+/// restarts with every read, so a client trickling one byte at a time would
+/// never hit it.
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "metrics request took too long",
+            ));
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Content type of the Prometheus text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Renders `stats` in the Prometheus text exposition format.
+#[must_use]
+pub fn render(stats: &ExtractionStats) -> String {
+    let counters = [
+        ("articles_processed", "Articles processed", stats.articles()),
+        (
+            "edges_extracted",
+            "Article link edges written",
+            stats.edges(),
+        ),
+        ("blobs_written", "JSON blobs written", stats.blobs()),
+        (
+            "invalid_links",
+            "Wiki links that did not resolve",
+            stats.invalid(),
+        ),
+        (
+            "red_links",
+            "Unresolved links to valid-looking titles",
+            stats.red_links(),
+        ),
+        (
+            "categories_found",
+            "Distinct categories found",
+            stats.categories(),
+        ),
+        (
+            "category_edges",
+            "Article-category edges written",
+            stats.category_edges(),
+        ),
+        (
+            "see_also_edges",
+            "See also edges written",
+            stats.see_also_edges(),
+        ),
+        (
+            "infoboxes_extracted",
+            "Infoboxes extracted",
+            stats.infoboxes(),
+        ),
+        ("images_found", "Distinct images found", stats.images()),
+        (
+            "external_links_found",
+            "Distinct external links found",
+            stats.external_links(),
+        ),
+        (
+            "output_bytes",
+            "Bytes of CSV, blob and SQLite output",
+            stats.output_bytes(),
+        ),
+        (
+            "blobs_sampled_out",
+            "Blobs skipped by --blob-sample-rate",
+            stats.blobs_sampled_out(),
+        ),
+        (
+            "articles_excluded",
+            "Articles skipped by --exclude-category",
+            stats.articles_excluded(),
+        ),
+        (
+            "self_links",
+            "Links from an article to itself",
+            stats.self_links(),
+        ),
+    ];
+    let mut out = String::new();
+    for (name, help, value) in counters {
+        let _ = writeln!(out, "# HELP dedalus_{name}_total {help}.");
+        let _ = writeln!(out, "# TYPE dedalus_{name}_total counter");
+        let _ = writeln!(out, "dedalus_{name}_total {value}");
+    }
+    out
+}
+
+/// Reads one request and answers it: the metrics for `GET /metrics`, 404 otherwise.
+fn respond(stream: TcpStream, stats: &ExtractionStats) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut reader = BufReader::new(DeadlineReader {
+        stream: &stream,
+        deadline: Instant::now() + REQUEST_TIMEOUT,
+    });
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers so the client sees a clean close.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", CONTENT_TYPE, render(stats)),
+        _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+/// Background HTTP server for `ExtractionConfig::metrics_addr`; dropping it
+/// stops the server.
+pub struct MetricsServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    /// Binds `addr` and starts serving `stats`. Port 0 picks a free port; see
+    /// [`MetricsServer::local_addr`].
+    pub fn start(addr: SocketAddr, stats: Arc<ExtractionStats>) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .with_context(|| format!("Failed to bind metrics endpoint on {addr}"))?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopping = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopping.load(Ordering::Relaxed) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => {
+                        if let Err(e) = respond(stream, &stats) {
+                            debug!(error = %e, "Metrics request failed");
+                        }
+                    }
+                    Err(e) => warn!(error = %e, "Failed to accept metrics connection"),
+                }
+            }
+        });
+        info!(%addr, "Serving metrics at http://{addr}/metrics");
+        Ok(Self {
+            addr,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// The address the server is listening on.
+    #[must_use]
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake the blocking accept so the thread sees the flag.
+        let mut wake = self.addr;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect_timeout(&wake, REQUEST_TIMEOUT);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn serves_current_counters_and_404s_other_paths() {
+        let stats = Arc::new(ExtractionStats::new());
+        let server =
+            MetricsServer::start("127.0.0.1:0".parse().unwrap(), Arc::clone(&stats)).unwrap();
+        stats.inc_articles();
+        stats.add_edges(7);
+
+        let response = get(server.local_addr(), "/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains("# TYPE dedalus_articles_processed_total counter\n"));
+        assert!(response.contains("\ndedalus_articles_processed_total 1\n"));
+        assert!(response.contains("\ndedalus_edges_extracted_total 7\n"));
+
+        stats.inc_articles();
+        assert!(
+            get(server.local_addr(), "/metrics").contains("\ndedalus_articles_processed_total 2\n")
+        );
+        assert!(get(server.local_addr(), "/").starts_with("HTTP/1.1 404"));
+        drop(server);
+    }
+
+    #[test]
+    fn a_trickling_client_does_not_stall_shutdown() {
+        let server = MetricsServer::start(
+            "127.0.0.1:0".parse().unwrap(),
+            Arc::new(ExtractionStats::new()),
+        )
+        .unwrap();
+        let mut slow = TcpStream::connect(server.local_addr()).unwrap();
+        let trickle = thread::spawn(move || {
+            // One byte at a time, each well inside a per-read timeout.
+            for _ in 0..40 {
+                if slow.write_all(b"G").is_err() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(250));
+            }
+        });
+        thread::sleep(Duration::from_millis(100));
+
+        let started = Instant::now();
+        drop(server);
+        assert!(
+            started.elapsed() < REQUEST_TIMEOUT * 2,
+            "{:?}",
+            started.elapsed()
+        );
+        trickle.join().unwrap();
+    }
+}
@@ -1,8 +1,9 @@
 //! Core data types for the extraction pipeline.
 //!
 //! Defines `WikiPage` (parsed from XML), `PageType` (Article/Redirect/Special),
-//! `EdgeType` (LinksTo/SeeAlso), and `ArticleBlob` (enriched JSON output with
-//! conditional serialization for compact storage).
+//! `EdgeType` (LinksTo/SeeAlso), `ArticleBlob` (enriched JSON output with
+//! conditional serialization for compact storage), and `DumpInfo` (the dump's
+//! `<siteinfo>` header).
 
 use crate::infobox::Infobox;
 use serde::{Deserialize, Serialize};
@@ -29,7 +30,7 @@ impl fmt::Display for PageType {
 /// A single page extracted from a Wikipedia XML dump.
 #[derive(Debug, Clone)]
 pub struct WikiPage {
-    pub id: u32,
+    pub id: u64,
     pub title: String,
     pub page_type: PageType,
     /// `None` during the indexing pass (skip_text mode).
@@ -38,6 +39,32 @@ pub struct WikiPage {
     pub ns: Option<i32>,
     /// Revision timestamp from `<timestamp>` tag.
     pub timestamp: Option<String>,
+    /// Byte offset of `<page>` in the decompressed stream; `None` unless the
+    /// reader was built with `track_offsets(true)`.
+    pub offset: Option<u64>,
+    /// Number of `<revision>` elements; more than 1 only in full-history dumps,
+    /// where `text` and `timestamp` come from the last (latest) revision.
+    pub revisions: u32,
+    /// `bytes` attribute of the latest revision's `<text>` element, if any.
+    pub text_bytes: Option<u64>,
+    /// `sha1` attribute of the latest revision's `<text>` element, if any.
+    pub text_sha1: Option<String>,
+}
+
+impl WikiPage {
+    /// `true` if this is a non-redirect page in one of `namespaces`, i.e. one
+    /// that extraction treats as an article. Pages without an `<ns>` tag are
+    /// namespace 0 unless their title prefix marked them special.
+    #[must_use]
+    pub fn is_content_page(&self, namespaces: &[i32]) -> bool {
+        match self.page_type {
+            PageType::Article => namespaces.contains(&self.ns.unwrap_or(0)),
+            PageType::Special => self
+                .ns
+                .is_some_and(|ns| ns != 0 && namespaces.contains(&ns)),
+            PageType::Redirect(_) => false,
+        }
+    }
 }
 
 /// Type of edge between two Wikipedia articles.
@@ -47,23 +74,35 @@ pub enum EdgeType {
     SeeAlso,
 }
 
-impl fmt::Display for EdgeType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl EdgeType {
+    /// Default relationship type written to the edges CSV (see `schema::RelationshipNames`).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
         match self {
-            EdgeType::LinksTo => f.write_str("LINKS_TO"),
-            EdgeType::SeeAlso => f.write_str("SEE_ALSO"),
+            EdgeType::LinksTo => "LINKS_TO",
+            EdgeType::SeeAlso => "SEE_ALSO",
         }
     }
 }
 
+impl fmt::Display for EdgeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 fn is_false(v: &bool) -> bool {
     !*v
 }
 
+fn is_zero(v: &usize) -> bool {
+    *v == 0
+}
+
 /// Enriched article content written as a JSON blob per article.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ArticleBlob {
-    pub id: u32,
+    pub id: u64,
     pub title: String,
     pub abstract_text: String,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
@@ -76,6 +115,34 @@ pub struct ArticleBlob {
     pub timestamp: Option<String>,
     #[serde(skip_serializing_if = "is_false", default)]
     pub is_disambiguation: bool,
+    /// Thin article that looks mass-created from a database (`content::is_likely_autogenerated`).
+    #[serde(skip_serializing_if = "is_false", default)]
+    pub is_likely_autogenerated: bool,
+    /// Number of distinct references (`<ref>` definitions and cite templates).
+    #[serde(skip_serializing_if = "is_zero", default)]
+    pub reference_count: usize,
+    /// `(scheme, id)` pairs from a parameterized `{{Authority control}}` template.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub authority_ids: Vec<(String, String)>,
+    /// Resolved outbound `(target id, edge type)` pairs, written with `--blob-links`.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub links: Vec<(u64, String)>,
+    /// Custom fields added by an `ExtractionConfig::enrich` hook.
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty", default)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Dump metadata from the `<siteinfo>` header, written to `dump_info.json`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DumpInfo {
+    /// MediaWiki version that produced the dump (e.g. `MediaWiki 1.42.0-wmf.5`).
+    pub generator: Option<String>,
+    /// Wiki database name (e.g. `enwiki`).
+    pub dbname: Option<String>,
+    /// URL of the wiki's main page.
+    pub base: Option<String>,
+    /// Non-main namespace names, in declaration order.
+    pub namespaces: Vec<String>,
 }
 
 #[cfg(test)]
@@ -93,6 +160,11 @@ mod tests {
             sections: vec![],
             timestamp: None,
             is_disambiguation: false,
+            is_likely_autogenerated: false,
+            reference_count: 0,
+            authority_ids: vec![],
+            links: vec![],
+            extra: serde_json::Map::new(),
         };
         let json = serde_json::to_string(&blob).unwrap();
         assert!(json.contains("\"id\":42"));
@@ -104,6 +176,11 @@ mod tests {
         assert!(!json.contains("sections"));
         assert!(!json.contains("timestamp"));
         assert!(!json.contains("is_disambiguation"));
+        assert!(!json.contains("is_likely_autogenerated"));
+        assert!(!json.contains("reference_count"));
+        assert!(!json.contains("authority_ids"));
+        assert!(!json.contains("links"));
+        assert!(!json.contains("extra"));
     }
 
     #[test]
@@ -120,12 +197,57 @@ mod tests {
             sections: vec!["History".to_string()],
             timestamp: Some("2024-01-01T00:00:00Z".to_string()),
             is_disambiguation: true,
+            is_likely_autogenerated: true,
+            reference_count: 3,
+            authority_ids: vec![("VIAF".to_string(), "123".to_string())],
+            links: vec![(7, "LINKS_TO".to_string())],
+            extra: serde_json::Map::from_iter([("sentiment".to_string(), 0.5.into())]),
         };
         let json = serde_json::to_string(&blob).unwrap();
         let deserialized: ArticleBlob = serde_json::from_str(&json).unwrap();
         assert_eq!(blob, deserialized);
     }
 
+    #[test]
+    fn article_blob_parses_fully_populated_json() {
+        // Pins the on-disk key names: blobs written by earlier runs must keep parsing.
+        let json = r#"{"id":42,"title":"Test","abstract_text":"Abstract",
+            "categories":["Science"],
+            "infoboxes":[{"infobox_type":"Infobox person","fields":[["name","Test"]]}],
+            "sections":["History"],"timestamp":"2024-01-01T00:00:00Z",
+            "is_disambiguation":true,"is_likely_autogenerated":true,"reference_count":3,
+            "authority_ids":[["VIAF","123"]],"links":[[7,"LINKS_TO"]],
+            "extra":{"sentiment":0.5}}"#;
+        let blob: ArticleBlob = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            blob,
+            ArticleBlob {
+                id: 42,
+                title: "Test".to_string(),
+                abstract_text: "Abstract".to_string(),
+                categories: vec!["Science".to_string()],
+                infoboxes: vec![Infobox {
+                    infobox_type: "Infobox person".to_string(),
+                    fields: vec![("name".to_string(), "Test".to_string())],
+                }],
+                sections: vec!["History".to_string()],
+                timestamp: Some("2024-01-01T00:00:00Z".to_string()),
+                is_disambiguation: true,
+                is_likely_autogenerated: true,
+                reference_count: 3,
+                authority_ids: vec![("VIAF".to_string(), "123".to_string())],
+                links: vec![(7, "LINKS_TO".to_string())],
+                extra: serde_json::Map::from_iter([("sentiment".to_string(), 0.5.into())]),
+            }
+        );
+        let reparsed: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&blob).unwrap()).unwrap();
+        assert_eq!(
+            reparsed,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+    }
+
     #[test]
     fn article_blob_roundtrip() {
         let original = ArticleBlob {
@@ -137,6 +259,11 @@ mod tests {
             sections: vec![],
             timestamp: None,
             is_disambiguation: false,
+            is_likely_autogenerated: false,
+            reference_count: 0,
+            authority_ids: vec![],
+            links: vec![],
+            extra: serde_json::Map::new(),
         };
         let json = serde_json::to_string(&original).unwrap();
         let deserialized: ArticleBlob = serde_json::from_str(&json).unwrap();
@@ -154,6 +281,11 @@ mod tests {
             sections: vec![],
             timestamp: None,
             is_disambiguation: false,
+            is_likely_autogenerated: false,
+            reference_count: 0,
+            authority_ids: vec![],
+            links: vec![],
+            extra: serde_json::Map::new(),
         };
         let json = serde_json::to_string_pretty(&blob).unwrap();
         assert!(json.contains('\n'));
@@ -172,6 +304,10 @@ mod tests {
         assert!(blob.sections.is_empty());
         assert!(blob.timestamp.is_none());
         assert!(!blob.is_disambiguation);
+        assert!(!blob.is_likely_autogenerated);
+        assert_eq!(blob.reference_count, 0);
+        assert!(blob.authority_ids.is_empty());
+        assert!(blob.links.is_empty());
     }
 
     #[test]
@@ -183,6 +319,10 @@ mod tests {
             text: Some("Content".to_string()),
             ns: None,
             timestamp: None,
+            offset: None,
+            revisions: 1,
+            text_bytes: None,
+            text_sha1: None,
         };
         assert!(matches!(page.page_type, PageType::Article));
     }
@@ -196,6 +336,10 @@ mod tests {
             text: None,
             ns: None,
             timestamp: None,
+            offset: None,
+            revisions: 1,
+            text_bytes: None,
+            text_sha1: None,
         };
         match &page.page_type {
             PageType::Redirect(target) => assert_eq!(target, "New Name"),
@@ -212,10 +356,37 @@ mod tests {
             text: None,
             ns: None,
             timestamp: None,
+            offset: None,
+            revisions: 1,
+            text_bytes: None,
+            text_sha1: None,
         };
         assert!(matches!(page.page_type, PageType::Special));
     }
 
+    #[test]
+    fn content_page_follows_namespace_set() {
+        let page = |page_type, ns| WikiPage {
+            id: 1,
+            title: "T".to_string(),
+            page_type,
+            text: None,
+            ns,
+            timestamp: None,
+            offset: None,
+            revisions: 1,
+            text_bytes: None,
+            text_sha1: None,
+        };
+        assert!(page(PageType::Article, Some(0)).is_content_page(&[0]));
+        assert!(page(PageType::Article, None).is_content_page(&[0]));
+        assert!(!page(PageType::Article, Some(0)).is_content_page(&[100]));
+        assert!(!page(PageType::Special, Some(100)).is_content_page(&[0]));
+        assert!(page(PageType::Special, Some(100)).is_content_page(&[0, 100]));
+        assert!(!page(PageType::Special, None).is_content_page(&[0, 100]));
+        assert!(!page(PageType::Redirect("X".into()), Some(0)).is_content_page(&[0]));
+    }
+
     #[test]
     fn wiki_page_optional_text() {
         let with_text = WikiPage {
@@ -225,6 +396,10 @@ mod tests {
             text: Some("content".to_string()),
             ns: None,
             timestamp: None,
+            offset: None,
+            revisions: 1,
+            text_bytes: None,
+            text_sha1: None,
         };
         let without_text = WikiPage {
             id: 2,
@@ -233,6 +408,10 @@ mod tests {
             text: None,
             ns: None,
             timestamp: None,
+            offset: None,
+            revisions: 1,
+            text_bytes: None,
+            text_sha1: None,
         };
         assert!(with_text.text.is_some());
         assert!(without_text.text.is_none());
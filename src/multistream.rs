@@ -124,6 +124,27 @@ fn parse_stream_inner(
     Ok(parser.collect())
 }
 
+/// Namespace names from the `<siteinfo>` header stream that precedes the first
+/// page stream. Returns an empty list if there is no header or it cannot be read.
+pub fn parse_siteinfo_namespaces(dump_path: &str, ranges: &[StreamRange]) -> Vec<String> {
+    let Some(first) = ranges.first().filter(|r| r.offset > 0) else {
+        return Vec::new();
+    };
+    let header = File::open(dump_path).map(|file| BzDecoder::new(file.take(first.offset)));
+    match header {
+        Ok(decoder) => {
+            let mut parser = PageParser::new(decoder, true);
+            // The header holds no pages; this just drives the parser through it.
+            parser.by_ref().for_each(drop);
+            parser.namespaces().to_vec()
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to read siteinfo header stream");
+            Vec::new()
+        }
+    }
+}
+
 /// Parse pages from a single stream for index building (skip_text=true).
 pub fn parse_stream_for_index(dump_path: &str, range: &StreamRange) -> Vec<WikiPage> {
     parse_stream(dump_path, range, true)
@@ -144,24 +165,37 @@ pub fn par_iter_pages<'a>(
         .flat_map_iter(move |range| parse_stream(dump_path, range, skip_text))
 }
 
+/// Sequential counterpart of [`par_iter_pages`]: parses streams one at a time
+/// in index order on the calling thread.
+pub fn iter_pages<'a>(
+    dump_path: &'a str,
+    ranges: &'a [StreamRange],
+    skip_text: bool,
+) -> impl Iterator<Item = WikiPage> + 'a {
+    ranges
+        .iter()
+        .flat_map(move |range| parse_stream(dump_path, range, skip_text))
+}
+
 /// Try to auto-detect the multistream index file from the dump path.
 ///
 /// Wikipedia naming convention:
 ///   Dump:  `*-multistream.xml.bz2`
 ///   Index: `*-multistream-index.txt.bz2`
+///
+/// Only the file name is rewritten, so directories named like dumps don't
+/// confuse the lookup.
 pub fn detect_index_path(dump_path: &str) -> Option<String> {
-    // Check if the dump filename contains "multistream"
-    if !dump_path.contains("multistream") {
-        return None;
-    }
-
-    // Replace `.xml.bz2` with `-index.txt.bz2`
-    let index_path = dump_path.replace(".xml.bz2", "-index.txt.bz2");
-    if index_path == dump_path {
+    let path = std::path::Path::new(dump_path);
+    let file_name = path.file_name()?.to_str()?;
+    let stem = file_name.strip_suffix(".xml.bz2")?;
+    if !stem.contains("multistream") {
         return None;
     }
 
-    if std::path::Path::new(&index_path).exists() {
+    let index_path = path.with_file_name(format!("{stem}-index.txt.bz2"));
+    if index_path.exists() {
+        let index_path = index_path.to_string_lossy().into_owned();
         info!(path = %index_path, "Auto-detected multistream index file");
         Some(index_path)
     } else {
@@ -169,6 +203,32 @@ pub fn detect_index_path(dump_path: &str) -> Option<String> {
     }
 }
 
+/// Stream ranges for parallel parsing, or `None` to read the dump serially.
+///
+/// Uses `explicit_index` if given, otherwise [`detect_index_path`]. Without an
+/// index the dump is still readable (multistream dumps are valid concatenated
+/// bz2), just single-threaded, so a missing index is logged rather than an error.
+pub fn resolve_ranges(
+    dump_path: &str,
+    explicit_index: Option<&str>,
+) -> Result<Option<Vec<StreamRange>>> {
+    let index_path = match explicit_index {
+        Some(path) => Some(path.to_string()),
+        None => detect_index_path(dump_path),
+    };
+    let Some(index_path) = index_path else {
+        info!(
+            dump = %dump_path,
+            "No multistream index found, reading dump serially"
+        );
+        return Ok(None);
+    };
+    info!(index = %index_path, "Using multistream parallel parsing");
+    let ranges = parse_multistream_index(&index_path, dump_path)?;
+    info!(streams = ranges.len(), "Multistream index parsed");
+    Ok(Some(ranges))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -320,6 +380,43 @@ mod tests {
         assert_eq!(detected.as_deref(), Some(index_path.to_str().unwrap()));
     }
 
+    #[test]
+    fn resolve_ranges_uses_sibling_index_or_falls_back() {
+        let (dump, index) = create_multistream_dump();
+        let dir = TempDir::new().unwrap();
+        let dump_path = dir
+            .path()
+            .join("testwiki-pages-articles-multistream.xml.bz2");
+        fs::copy(dump.path(), &dump_path).unwrap();
+        let dump_path = dump_path.to_str().unwrap();
+
+        assert!(resolve_ranges(dump_path, None).unwrap().is_none());
+
+        fs::copy(
+            index.path(),
+            dir.path()
+                .join("testwiki-pages-articles-multistream-index.txt.bz2"),
+        )
+        .unwrap();
+        let ranges = resolve_ranges(dump_path, None).unwrap().unwrap();
+        assert_eq!(ranges.len(), 2);
+        let titles: Vec<String> = par_iter_pages(dump_path, &ranges, true)
+            .map(|p| p.title)
+            .collect();
+        assert_eq!(titles.len(), 3);
+    }
+
+    #[test]
+    fn detect_index_path_ignores_directory_names() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("old-multistream.xml.bz2");
+        fs::create_dir(&nested).unwrap();
+        assert_eq!(
+            detect_index_path(nested.join("dump.xml.bz2").to_str().unwrap()),
+            None
+        );
+    }
+
     #[test]
     fn detect_index_path_returns_none_for_regular_dump() {
         let detected = detect_index_path("/tmp/enwiki-latest-pages-articles.xml.bz2");
@@ -3,11 +3,27 @@
 //! `PageParser<R>` implements `Iterator<Item = WikiPage>` over any `Read` source
 //! using a state machine over `quick-xml` events. `WikiReader` wraps it with BZ2
 //! decompression, probing PATH for `lbzip2`/`pbzip2` for parallel decompression.
-
-use crate::models::{PageType, WikiPage};
+//! With `track_offsets(true)`, each page records the byte offset of its `<page>`
+//! tag within the decompressed stream so it can later be re-read by seeking.
+//! A leading UTF-8 BOM is skipped (`SkipBom`), and an XML declaration, doctype,
+//! or comments before `<mediawiki>` are ignored. The `<siteinfo>` header
+//! (generator, database name, base URL and namespace names) is collected into a
+//! `DumpInfo`, available before iteration via `read_dump_info()`.
+//! Malformed pages (no parseable `<id>`, no `<title>`, or a title longer than
+//! `MAX_TITLE_BYTES`) are skipped with a warning rather than ending iteration.
+//!
+//! An `http://` or `https://` input (`is_url()`) is streamed with a blocking
+//! `reqwest` client and decoded in-process as it arrives, so nothing is written
+//! to disk. Every open is a new request: fine for single-pass readers such as
+//! `dedalus sample`, but the indexing and extraction passes would each download
+//! the whole dump, so `extract` and `pipeline` want a local file.
+
+use crate::config::{DEFAULT_LINK_NAMESPACES, LINK_NAMESPACE_ALIASES, MAX_TITLE_BYTES};
+use crate::models::{DumpInfo, PageType, WikiPage};
 use anyhow::{Context, Result};
 use bzip2::read::MultiBzDecoder;
-use quick_xml::events::Event;
+use flate2::read::MultiGzDecoder;
+use quick_xml::events::{BytesStart, Event};
 use quick_xml::reader::Reader;
 use std::fs::File;
 use std::io::{BufReader, Read};
@@ -16,15 +32,109 @@ use std::str;
 use tracing::{info, warn};
 
 #[cfg(test)]
-use bzip2::Compression;
+use bzip2::Compression as BzLevel;
 #[cfg(test)]
 use bzip2::write::BzEncoder;
 #[cfg(test)]
 use std::io::Write;
 
-enum DecompressSource {
+/// Compression codec of a dump file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// `.bz2`, single or multistream.
+    Bzip2,
+    /// `.gz`, one or more gzip members.
+    Gzip,
+    /// `.zst`, one or more zstd frames.
+    Zstd,
+    /// Uncompressed `.xml`.
+    None,
+}
+
+impl Compression {
+    /// Codec for the dump at `path`: from its extension (`.bz2`, `.gz`,
+    /// `.zst`, `.xml`), or else from the file's magic bytes. A file matching
+    /// neither is read as uncompressed XML.
+    pub fn detect(path: &str) -> Result<Self> {
+        // For a URL, the extension is read from the path without query or fragment.
+        let name = if is_url(path) {
+            path.split(['?', '#']).next().unwrap_or(path)
+        } else {
+            path
+        };
+        let extension = std::path::Path::new(name)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("bz2") => return Ok(Self::Bzip2),
+            Some("gz") => return Ok(Self::Gzip),
+            Some("zst" | "zstd") => return Ok(Self::Zstd),
+            Some("xml") => return Ok(Self::None),
+            _ => {}
+        }
+
+        let mut magic = Vec::with_capacity(4);
+        open_raw(path)?
+            .take(4)
+            .read_to_end(&mut magic)
+            .with_context(|| format!("Could not read {}", path))?;
+        Ok(match magic.as_slice() {
+            [b'B', b'Z', b'h', ..] => Self::Bzip2,
+            [0x1f, 0x8b, ..] => Self::Gzip,
+            [0x28, 0xb5, 0x2f, 0xfd] => Self::Zstd,
+            _ => Self::None,
+        })
+    }
+}
+
+/// `true` if `path` is an `http://` or `https://` URL rather than a local file.
+#[must_use]
+pub fn is_url(path: &str) -> bool {
+    let lower = path.get(..8).unwrap_or(path).to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Compressed bytes of a dump: a local file or the body of an HTTP response.
+pub(crate) enum RawSource {
+    File(File),
+    Http(Box<dyn Read + Send>),
+}
+
+impl Read for RawSource {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Self::File(r) => r.read(buf),
+            Self::Http(r) => r.read(buf),
+        }
+    }
+}
+
+/// Opens `path`, or starts a GET request for a URL. The client has no overall
+/// timeout, since a full dump takes hours to stream.
+fn open_raw(path: &str) -> Result<RawSource> {
+    if !is_url(path) {
+        let file = File::open(path).with_context(|| format!("Could not open file: {}", path))?;
+        return Ok(RawSource::File(file));
+    }
+    let response = reqwest::blocking::Client::builder()
+        .timeout(None)
+        .build()
+        .context("Failed to start HTTP client")?
+        .get(path)
+        .send()
+        .and_then(reqwest::blocking::Response::error_for_status)
+        .with_context(|| format!("Could not fetch {}", path))?;
+    Ok(RawSource::Http(Box::new(response)))
+}
+
+pub(crate) enum DecompressSource {
     External(ChildStdout),
-    InProcess(MultiBzDecoder<File>),
+    InProcess(MultiBzDecoder<RawSource>),
+    Gzip(MultiGzDecoder<RawSource>),
+    Zstd(zstd::Decoder<'static, BufReader<RawSource>>),
+    /// Uncompressed `.xml` dump.
+    Plain(RawSource),
 }
 
 impl Read for DecompressSource {
@@ -32,22 +142,142 @@ impl Read for DecompressSource {
         match self {
             Self::External(r) => r.read(buf),
             Self::InProcess(r) => r.read(buf),
+            Self::Gzip(r) => r.read(buf),
+            Self::Zstd(r) => r.read(buf),
+            Self::Plain(r) => r.read(buf),
+        }
+    }
+}
+
+/// Opens a dump for in-process decoding with `compression`. Bzip2 goes through
+/// `MultiBzDecoder` so multistream dumps read to the end.
+fn open_source(path: &str, compression: Compression) -> Result<DecompressSource> {
+    let file = open_raw(path)?;
+    Ok(match compression {
+        Compression::Bzip2 => DecompressSource::InProcess(MultiBzDecoder::new(file)),
+        Compression::Gzip => DecompressSource::Gzip(MultiGzDecoder::new(file)),
+        Compression::Zstd => DecompressSource::Zstd(
+            zstd::Decoder::new(file)
+                .with_context(|| format!("Failed to start zstd decoder for {}", path))?,
+        ),
+        Compression::None => DecompressSource::Plain(file),
+    })
+}
+
+/// Opens a dump for in-process reading with its codec from `Compression::detect`.
+pub(crate) fn open_in_process(path: &str) -> Result<DecompressSource> {
+    open_source(path, Compression::detect(path)?)
+}
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// Drops a leading UTF-8 BOM from a byte stream, remembering whether it did so
+/// that page offsets still index the original stream.
+struct SkipBom<R: Read> {
+    inner: R,
+    head: [u8; 3],
+    head_len: usize,
+    head_pos: usize,
+    checked: bool,
+    bom_len: u64,
+}
+
+impl<R: Read> SkipBom<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            head: [0; 3],
+            head_len: 0,
+            head_pos: 0,
+            checked: false,
+            bom_len: 0,
         }
     }
 }
 
+impl<R: Read> Read for SkipBom<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if !self.checked {
+            // Short reads are possible on pipes, so fill all three bytes first.
+            while self.head_len < UTF8_BOM.len() {
+                match self.inner.read(&mut self.head[self.head_len..])? {
+                    0 => break,
+                    n => self.head_len += n,
+                }
+            }
+            if self.head[..self.head_len] == UTF8_BOM {
+                self.head_pos = self.head_len;
+                self.bom_len = UTF8_BOM.len() as u64;
+            }
+            self.checked = true;
+        }
+        if self.head_pos < self.head_len {
+            let n = (self.head_len - self.head_pos).min(buf.len());
+            buf[..n].copy_from_slice(&self.head[self.head_pos..self.head_pos + n]);
+            self.head_pos += n;
+            return Ok(n);
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// `<siteinfo>` elements whose text is copied into `DumpInfo`.
+#[derive(Debug, Clone, Copy)]
+enum HeaderField {
+    Generator,
+    Dbname,
+    Base,
+}
+
+/// The `bytes` and `sha1` attributes of a `<text>` element, where present.
+fn text_attributes(e: &BytesStart) -> (Option<u64>, Option<String>) {
+    let attr = |name: &str| {
+        e.try_get_attribute(name)
+            .ok()
+            .flatten()
+            .map(|attr| String::from_utf8_lossy(&attr.value).into_owned())
+    };
+    (
+        attr("bytes").and_then(|bytes| bytes.trim().parse().ok()),
+        attr("sha1"),
+    )
+}
+
+/// Whether `title` starts with a namespace prefix, for pages without `<ns>`.
+/// Checks the `<siteinfo>` names (localized, e.g. `Kategorie`) when the dump
+/// declared any, otherwise `DEFAULT_LINK_NAMESPACES`; aliases always count.
+fn has_namespace_prefix(title: &str, namespaces: &[String]) -> bool {
+    let Some((prefix, _)) = title.split_once(':') else {
+        return false;
+    };
+    if LINK_NAMESPACE_ALIASES.contains(&prefix) {
+        return true;
+    }
+    if namespaces.is_empty() {
+        DEFAULT_LINK_NAMESPACES.contains(&prefix)
+    } else {
+        namespaces.iter().any(|ns| ns == prefix)
+    }
+}
+
 /// Generic XML page parser that works with any `Read` source.
 /// Extracts `WikiPage` items from a MediaWiki XML stream.
 pub struct PageParser<R: Read> {
-    reader: Reader<BufReader<R>>,
+    reader: Reader<BufReader<SkipBom<R>>>,
     buf: Vec<u8>,
     skip_text: bool,
     pub(crate) skip_timestamp: bool,
+    pub(crate) track_offsets: bool,
+    info: DumpInfo,
+    /// Set once `</siteinfo>` or the first `<page>` has been read.
+    header_done: bool,
+    /// Offset of a `<page>` tag consumed by `read_dump_info()`, for the next page.
+    pending_page_offset: Option<u64>,
 }
 
 impl<R: Read> PageParser<R> {
     pub fn new(source: R, skip_text: bool) -> Self {
-        let buf_reader = BufReader::with_capacity(256 * 1024, source);
+        let buf_reader = BufReader::with_capacity(256 * 1024, SkipBom::new(source));
         let mut xml_reader = Reader::from_reader(buf_reader);
         xml_reader.check_end_names(false);
         xml_reader.trim_text(true);
@@ -56,6 +286,10 @@ impl<R: Read> PageParser<R> {
             buf: Vec::with_capacity(crate::config::BUFREADER_CAPACITY),
             skip_text,
             skip_timestamp: false,
+            track_offsets: false,
+            info: DumpInfo::default(),
+            header_done: false,
+            pending_page_offset: None,
         }
     }
 
@@ -63,43 +297,130 @@ impl<R: Read> PageParser<R> {
         self.skip_timestamp = val;
         self
     }
-}
 
-impl<R: Read> Iterator for PageParser<R> {
-    type Item = WikiPage;
+    /// Records `WikiPage::offset` (bytes consumed from the source before `<page>`).
+    pub fn track_offsets(mut self, val: bool) -> Self {
+        self.track_offsets = val;
+        self
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Non-main namespace names from `<siteinfo><namespaces>` (e.g. `Category`,
+    /// `User talk`). Populated once parsing has passed the dump header.
+    pub fn namespaces(&self) -> &[String] {
+        &self.info.namespaces
+    }
+
+    /// The `<siteinfo>` header seen so far. Complete once the first page has
+    /// been read; call `read_dump_info()` to get it before iterating.
+    pub fn dump_info(&self) -> &DumpInfo {
+        &self.info
+    }
+
+    /// Reads up to the end of `<siteinfo>` (or the first `<page>`) and returns
+    /// the dump metadata. Iteration afterwards still yields every page.
+    pub fn read_dump_info(&mut self) -> &DumpInfo {
+        if !self.header_done {
+            let page = self.parse(true);
+            debug_assert!(page.is_none());
+        }
+        &self.info
+    }
+
+    /// Runs the event loop until the next complete page. With `header_only`,
+    /// stops instead at the end of the dump header without yielding a page.
+    fn parse(&mut self, header_only: bool) -> Option<WikiPage> {
         let mut current_id = None;
+        // Set at the first `</id>` so revision and contributor ids are never
+        // taken as the page id, even when the page id failed to parse.
+        let mut id_seen = false;
         let mut current_title: Option<String> = None;
         let mut current_text: Option<String> = None;
         let mut redirect_target = None;
         let mut current_ns: Option<i32> = None;
         let mut current_timestamp: Option<String> = None;
+        let mut page_offset: Option<u64> = self.pending_page_offset.take();
+        let mut revisions = 0u32;
+        let mut text_bytes: Option<u64> = None;
+        let mut text_sha1: Option<String> = None;
 
         let mut in_title = false;
         let mut in_id = false;
         let mut in_text = false;
         let mut in_ns = false;
         let mut in_timestamp = false;
+        let mut header_field: Option<HeaderField> = None;
+        let mut in_namespace = false;
 
         loop {
             match self.reader.read_event_into(&mut self.buf) {
                 Ok(Event::Start(e)) => match e.name().as_ref() {
-                    b"page" => {}
+                    b"page" => {
+                        // Every page starts from a clean slate, even if the
+                        // previous one never reached its `</page>`.
+                        if id_seen || current_title.is_some() {
+                            warn!(
+                                id = ?current_id,
+                                title = ?current_title,
+                                "Skipping page without a closing </page>"
+                            );
+                        }
+                        current_id = None;
+                        id_seen = false;
+                        current_title = None;
+                        current_text = None;
+                        redirect_target = None;
+                        current_ns = None;
+                        current_timestamp = None;
+                        page_offset = None;
+                        revisions = 0;
+                        text_bytes = None;
+                        text_sha1 = None;
+                        in_title = false;
+                        in_id = false;
+                        in_text = false;
+                        in_ns = false;
+                        in_timestamp = false;
+                        if self.track_offsets {
+                            // buffer_position() is just past `<page ...>`; back up over the tag.
+                            let tag_len = e.len() + 2;
+                            let bom_len = self.reader.get_ref().get_ref().bom_len;
+                            page_offset =
+                                Some((self.reader.buffer_position() - tag_len) as u64 + bom_len);
+                        }
+                        self.header_done = true;
+                        if header_only {
+                            self.pending_page_offset = page_offset;
+                            self.buf.clear();
+                            return None;
+                        }
+                    }
 
                     b"title" => in_title = true,
-                    b"id" if current_id.is_none() => in_id = true,
+                    // History dumps list revisions oldest first; each one
+                    // replaces the last, so the latest revision wins.
+                    b"revision" => {
+                        revisions += 1;
+                        current_text = None;
+                        current_timestamp = None;
+                        text_bytes = None;
+                        text_sha1 = None;
+                    }
+                    b"id" if !id_seen => in_id = true,
                     b"ns" => in_ns = true,
-                    b"timestamp" => {
-                        if !self.skip_timestamp {
-                            in_timestamp = true;
-                        }
+                    b"timestamp" if !self.skip_timestamp => in_timestamp = true,
+                    b"text" if !self.skip_text => {
+                        // `<text ...></text>` yields no Text event; start from
+                        // an empty body so the page still has one.
+                        in_text = true;
+                        current_text = Some(String::new());
+                        (text_bytes, text_sha1) = text_attributes(&e);
                     }
-                    b"text" => {
-                        if !self.skip_text {
-                            in_text = true;
-                        }
+                    b"namespace" if !self.header_done => in_namespace = true,
+                    b"generator" if !self.header_done => {
+                        header_field = Some(HeaderField::Generator)
                     }
+                    b"dbname" if !self.header_done => header_field = Some(HeaderField::Dbname),
+                    b"base" if !self.header_done => header_field = Some(HeaderField::Base),
                     b"redirect" => {
                         if let Ok(Some(attr)) = e.try_get_attribute("title") {
                             redirect_target =
@@ -109,13 +430,21 @@ impl<R: Read> Iterator for PageParser<R> {
                     _ => (),
                 },
 
-                Ok(Event::Empty(e)) => {
-                    if e.name().as_ref() == b"redirect"
-                        && let Ok(Some(attr)) = e.try_get_attribute("title")
-                    {
-                        redirect_target = Some(String::from_utf8_lossy(&attr.value).to_string());
+                Ok(Event::Empty(e)) => match e.name().as_ref() {
+                    b"redirect" => {
+                        if let Ok(Some(attr)) = e.try_get_attribute("title") {
+                            redirect_target =
+                                Some(String::from_utf8_lossy(&attr.value).to_string());
+                        }
                     }
-                }
+                    // `<text bytes="0" />` or `<text deleted="deleted" />`:
+                    // the revision has no body, so the page gets an empty one.
+                    b"text" if !self.skip_text => {
+                        current_text = Some(String::new());
+                        (text_bytes, text_sha1) = text_attributes(&e);
+                    }
+                    _ => (),
+                },
 
                 Ok(Event::Text(e)) => {
                     if in_title && let Ok(s) = e.unescape() {
@@ -123,7 +452,7 @@ impl<R: Read> Iterator for PageParser<R> {
                     } else if in_id {
                         current_id = str::from_utf8(&e)
                             .ok()
-                            .and_then(|s| s.trim().parse::<u32>().ok());
+                            .and_then(|s| s.trim().parse::<u64>().ok());
                     } else if in_ns {
                         current_ns = str::from_utf8(&e)
                             .ok()
@@ -132,53 +461,101 @@ impl<R: Read> Iterator for PageParser<R> {
                         current_timestamp = str::from_utf8(&e).ok().map(|s| s.to_string());
                     } else if in_text && let Ok(s) = e.unescape() {
                         current_text = Some(s.into_owned());
+                    } else if in_namespace && let Ok(s) = e.unescape() {
+                        self.info.namespaces.push(s.into_owned());
+                    } else if let Some(field) = header_field
+                        && let Ok(s) = e.unescape()
+                    {
+                        let value = Some(s.into_owned());
+                        match field {
+                            HeaderField::Generator => self.info.generator = value,
+                            HeaderField::Dbname => self.info.dbname = value,
+                            HeaderField::Base => self.info.base = value,
+                        }
                     }
                 }
 
                 Ok(Event::End(e)) => match e.name().as_ref() {
                     b"title" => in_title = false,
-                    b"id" => in_id = false,
+                    b"id" => {
+                        id_seen |= in_id;
+                        in_id = false;
+                    }
                     b"ns" => in_ns = false,
                     b"timestamp" => in_timestamp = false,
                     b"text" => in_text = false,
+                    b"namespace" => in_namespace = false,
+                    b"generator" | b"dbname" | b"base" => header_field = None,
+                    b"siteinfo" => {
+                        self.header_done = true;
+                        if header_only {
+                            self.buf.clear();
+                            return None;
+                        }
+                    }
                     b"page" => {
-                        if let (Some(id), Some(title)) = (current_id, current_title.take()) {
-                            let page_type = if let Some(target) = redirect_target.take() {
-                                PageType::Redirect(target)
-                            } else if let Some(ns) = current_ns {
-                                if ns == 0 {
-                                    PageType::Article
-                                } else {
+                        match (current_id, current_title.take()) {
+                            (Some(id), Some(title)) if title.len() <= MAX_TITLE_BYTES => {
+                                let page_type = if let Some(target) = redirect_target.take() {
+                                    PageType::Redirect(target)
+                                } else if let Some(ns) = current_ns {
+                                    if ns == 0 {
+                                        PageType::Article
+                                    } else {
+                                        PageType::Special
+                                    }
+                                } else if has_namespace_prefix(&title, &self.info.namespaces) {
                                     PageType::Special
-                                }
-                            } else if title.starts_with("File:")
-                                || title.starts_with("Category:")
-                                || title.starts_with("Template:")
-                            {
-                                PageType::Special
-                            } else {
-                                PageType::Article
-                            };
-
-                            return Some(WikiPage {
+                                } else {
+                                    PageType::Article
+                                };
+
+                                return Some(WikiPage {
+                                    id,
+                                    title,
+                                    page_type,
+                                    text: current_text.take(),
+                                    ns: current_ns,
+                                    timestamp: current_timestamp.take(),
+                                    offset: page_offset.take(),
+                                    revisions,
+                                    text_bytes: text_bytes.take(),
+                                    text_sha1: text_sha1.take(),
+                                });
+                            }
+                            (Some(id), Some(title)) => warn!(
                                 id,
-                                title,
-                                page_type,
-                                text: current_text.take(),
-                                ns: current_ns,
-                                timestamp: current_timestamp.take(),
-                            });
+                                bytes = title.len(),
+                                "Skipping page with a title longer than MAX_TITLE_BYTES"
+                            ),
+                            (Some(id), None) => warn!(id, "Skipping page without a <title>"),
+                            (None, title) => warn!(?title, "Skipping page without a valid <id>"),
                         }
+                        // Nothing from a skipped page may leak into the next one.
+                        current_id = None;
+                        id_seen = false;
+                        current_text = None;
+                        redirect_target = None;
+                        current_ns = None;
+                        current_timestamp = None;
+                        page_offset = None;
+                        revisions = 0;
+                        text_bytes = None;
+                        text_sha1 = None;
                     }
                     _ => (),
                 },
-                Ok(Event::Eof) => return None,
+                Ok(Event::Eof) => {
+                    self.header_done = true;
+                    return None;
+                }
                 Err(e) => {
                     warn!(
                         position = self.reader.buffer_position(),
                         error = ?e,
                         "XML parse error"
                     );
+                    self.header_done = true;
                     return None;
                 }
                 _ => (),
@@ -188,10 +565,21 @@ impl<R: Read> Iterator for PageParser<R> {
     }
 }
 
-/// High-level Wikipedia dump reader with automatic BZ2 decompression.
+impl<R: Read> Iterator for PageParser<R> {
+    type Item = WikiPage;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.parse(false)
+    }
+}
+
+/// High-level Wikipedia dump reader with automatic decompression.
 ///
-/// Probes PATH for `lbzip2`/`pbzip2` for parallel decompression; falls back
-/// to in-process `MultiBzDecoder`. Implements `Iterator<Item = WikiPage>`.
+/// The codec comes from `Compression::detect` unless given to
+/// `with_compression`. For bzip2 dumps (single or multistream) it probes PATH
+/// for `lbzip2`/`pbzip2` for parallel decompression and falls back to
+/// in-process `MultiBzDecoder`; gzip and zstd are decoded in-process and
+/// uncompressed `.xml` is read directly. Implements `Iterator<Item = WikiPage>`.
 pub struct WikiReader {
     parser: PageParser<DecompressSource>,
     _child: Option<Child>,
@@ -220,13 +608,31 @@ fn spawn_decompressor(cmd: &str, path: &str) -> Result<Child> {
 
 impl WikiReader {
     pub fn new(path: &str, skip_text: bool) -> Result<Self> {
-        if !std::path::Path::new(path).exists() {
+        Self::with_compression(path, skip_text, None)
+    }
+
+    /// Like `new`, but `compression` overrides the codec detected from the
+    /// file name and magic bytes.
+    pub fn with_compression(
+        path: &str,
+        skip_text: bool,
+        compression: Option<Compression>,
+    ) -> Result<Self> {
+        if !is_url(path) && !std::path::Path::new(path).exists() {
             return Err(anyhow::anyhow!("Could not open file: {}", path));
         }
+        let compression = match compression {
+            Some(compression) => compression,
+            None => Compression::detect(path)?,
+        };
 
-        let (source, child): (DecompressSource, Option<Child>) = if let Some(cmd) =
-            find_decompressor()
+        // External decompressors read a local file; a URL is decoded in-process.
+        let (source, child): (DecompressSource, Option<Child>) = if compression
+            != Compression::Bzip2
+            || is_url(path)
         {
+            (open_source(path, compression)?, None)
+        } else if let Some(cmd) = find_decompressor() {
             match spawn_decompressor(cmd, path) {
                 Ok(mut child) => {
                     let stdout = child
@@ -238,15 +644,11 @@ impl WikiReader {
                 }
                 Err(e) => {
                     warn!(error = %e, "External decompressor failed, falling back to in-process");
-                    let file = File::open(path)
-                        .with_context(|| format!("Could not open file: {}", path))?;
-                    (DecompressSource::InProcess(MultiBzDecoder::new(file)), None)
+                    (open_source(path, compression)?, None)
                 }
             }
         } else {
-            let file =
-                File::open(path).with_context(|| format!("Could not open file: {}", path))?;
-            (DecompressSource::InProcess(MultiBzDecoder::new(file)), None)
+            (open_source(path, compression)?, None)
         };
 
         let parser = PageParser::new(source, skip_text);
@@ -262,11 +664,26 @@ impl WikiReader {
         self
     }
 
+    /// Opt in to recording each page's offset within the decompressed dump.
+    pub fn track_offsets(mut self, val: bool) -> Self {
+        self.parser.track_offsets = val;
+        self
+    }
+
+    /// Namespace names from the dump's `<siteinfo>` header; see `PageParser::namespaces`.
+    pub fn namespaces(&self) -> &[String] {
+        self.parser.namespaces()
+    }
+
+    /// The dump's `<siteinfo>` metadata; see `PageParser::read_dump_info`.
+    pub fn read_dump_info(&mut self) -> &DumpInfo {
+        self.parser.read_dump_info()
+    }
+
     /// Constructor that forces in-process decompression, bypassing external tool detection.
     #[cfg(test)]
     fn new_inprocess(path: &str, skip_text: bool) -> Result<Self> {
-        let file = File::open(path).with_context(|| format!("Could not open file: {}", path))?;
-        let source = DecompressSource::InProcess(MultiBzDecoder::new(file));
+        let source = open_in_process(path)?;
         let parser = PageParser::new(source, skip_text);
 
         Ok(Self {
@@ -276,6 +693,13 @@ impl WikiReader {
     }
 }
 
+/// Reads only the `<siteinfo>` header of a dump. Decompresses in-process, since
+/// the header sits in the first few kilobytes.
+pub fn read_dump_info(path: &str) -> Result<DumpInfo> {
+    let mut parser = PageParser::new(open_in_process(path)?, true);
+    Ok(parser.read_dump_info().clone())
+}
+
 impl Drop for WikiReader {
     fn drop(&mut self) {
         if let Some(ref mut child) = self._child {
@@ -299,7 +723,7 @@ mod tests {
     use tempfile::NamedTempFile;
 
     fn create_bz2_xml(xml: &str) -> NamedTempFile {
-        let mut encoder = BzEncoder::new(Vec::new(), Compression::fast());
+        let mut encoder = BzEncoder::new(Vec::new(), BzLevel::fast());
         encoder.write_all(xml.as_bytes()).unwrap();
         let compressed = encoder.finish().unwrap();
 
@@ -336,6 +760,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn empty_text_element_gives_empty_body_and_attributes() {
+        let xml = r#"<mediawiki>
+            <page>
+                <title>Blank</title>
+                <ns>0</ns>
+                <id>1</id>
+                <revision>
+                    <id>10</id>
+                    <text bytes="0" sha1="phoiac9h4m842xq45sp7s6u21eteeq1" />
+                </revision>
+            </page>
+            <page>
+                <title>Suppressed</title>
+                <ns>0</ns>
+                <id>2</id>
+                <revision>
+                    <id>20</id>
+                    <text deleted="deleted" />
+                </revision>
+            </page>
+            <page>
+                <title>Open and closed</title>
+                <ns>0</ns>
+                <id>3</id>
+                <revision>
+                    <id>30</id>
+                    <text bytes="0"></text>
+                </revision>
+            </page>
+        </mediawiki>"#;
+
+        let pages: Vec<_> = PageParser::new(xml.as_bytes(), false).collect();
+        assert_eq!(pages.len(), 3);
+        assert!(
+            pages
+                .iter()
+                .all(|p| matches!(p.page_type, PageType::Article))
+        );
+        assert!(pages.iter().all(|p| p.text.as_deref() == Some("")));
+        assert_eq!(pages[0].text_bytes, Some(0));
+        assert_eq!(
+            pages[0].text_sha1.as_deref(),
+            Some("phoiac9h4m842xq45sp7s6u21eteeq1")
+        );
+        assert_eq!(
+            (pages[1].text_bytes, pages[1].text_sha1.as_deref()),
+            (None, None)
+        );
+        assert_eq!(pages[2].text_bytes, Some(0));
+
+        // The index pass still skips text, even when the element is empty.
+        let pages: Vec<_> = PageParser::new(xml.as_bytes(), true).collect();
+        assert!(pages.iter().all(|p| p.text.is_none()));
+    }
+
     #[test]
     fn parse_skip_text_mode() {
         let xml = r#"<mediawiki>
@@ -418,6 +898,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn classify_by_ns_then_siteinfo_prefix() {
+        let xml = r#"<mediawiki>
+            <siteinfo>
+                <namespaces>
+                    <namespace key="0" />
+                    <namespace key="6">Datei</namespace>
+                    <namespace key="14">Kategorie</namespace>
+                    <namespace key="100">Portal</namespace>
+                </namespaces>
+            </siteinfo>
+            <page><title>Category: A Study</title><ns>0</ns><id>1</id></page>
+            <page><title>Datei:Beispiel.jpg</title><ns>6</ns><id>2</id></page>
+            <page><title>Kategorie:Sprachen</title><ns>14</ns><id>3</id></page>
+            <page><title>Portal:Informatik</title><id>4</id></page>
+            <page><title>Kategorie:Ohne ns</title><id>5</id></page>
+            <page><title>Category:Not declared</title><id>6</id></page>
+        </mediawiki>"#;
+
+        let tmp = create_bz2_xml(xml);
+        let reader = WikiReader::new(tmp.path().to_str().unwrap(), true).unwrap();
+        let pages: Vec<_> = reader.collect();
+
+        let special: Vec<_> = pages
+            .iter()
+            .map(|p| matches!(p.page_type, PageType::Special))
+            .collect();
+        assert_eq!(special, [false, true, true, true, true, false]);
+        assert_eq!(pages[2].ns, Some(14));
+        assert_eq!(pages[3].ns, None);
+    }
+
     #[test]
     fn parse_multiple_pages() {
         let xml = r#"<mediawiki>
@@ -471,6 +983,42 @@ mod tests {
         assert_eq!(pages[0].id, 42);
     }
 
+    #[test]
+    fn ids_above_u32_max_are_kept() {
+        let xml = b"<mediawiki>
+            <page><title>Big</title><id>4294967296</id>
+                <revision><id>99999999999</id><text>Hi</text></revision></page>
+        </mediawiki>";
+
+        let pages: Vec<_> = PageParser::new(&xml[..], false).collect();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].id, u64::from(u32::MAX) + 1);
+    }
+
+    #[test]
+    fn malformed_pages_are_skipped_without_ending_iteration() {
+        let long_title = "T".repeat(MAX_TITLE_BYTES + 1);
+        let xml = format!(
+            "<mediawiki>
+            <page><title>Bad Id</title><id>12x</id><ns>4</ns>
+                <revision><id>7</id><text>Bad.</text></revision></page>
+            <page><title>{long_title}</title><id>2</id>
+                <revision><id>8</id><text>Long.</text></revision></page>
+            <page><id>3</id><revision><id>9</id><text>Untitled.</text></revision></page>
+            <page><title>Good</title><id>4</id>
+                <revision><id>10</id><text>Fine.</text></revision></page>
+        </mediawiki>"
+        );
+
+        let pages: Vec<_> = PageParser::new(xml.as_bytes(), false).collect();
+        assert_eq!(pages.len(), 1);
+        // Neither the revision id nor the skipped page's <ns> is picked up.
+        assert_eq!(pages[0].id, 4);
+        assert_eq!(pages[0].title, "Good");
+        assert_eq!(pages[0].ns, None);
+        assert_eq!(pages[0].text.as_deref(), Some("Fine."));
+    }
+
     #[test]
     fn parse_empty_dump() {
         let xml = r#"<mediawiki></mediawiki>"#;
@@ -574,6 +1122,48 @@ mod tests {
         assert_eq!(pages[0].text.as_deref(), Some("Content here."));
     }
 
+    #[test]
+    fn offsets_are_not_tracked_by_default() {
+        let xml = b"<mediawiki><page><title>A</title><id>1</id></page></mediawiki>";
+        let pages: Vec<_> = PageParser::new(&xml[..], true).collect();
+        assert_eq!(pages[0].offset, None);
+    }
+
+    #[test]
+    fn tracked_offsets_point_at_page_start() {
+        let xml = "<mediawiki>
+            <page>
+                <title>First</title>
+                <id>1</id>
+                <revision><id>100</id><text>One.</text></revision>
+            </page>
+            <page>
+                <title>Second</title>
+                <id>2</id>
+                <revision><id>200</id><text>Two.</text></revision>
+            </page>
+        </mediawiki>";
+
+        let tmp = create_bz2_xml(xml);
+        let reader = WikiReader::new(tmp.path().to_str().unwrap(), false)
+            .unwrap()
+            .track_offsets(true);
+        let pages: Vec<_> = reader.collect();
+
+        let first = pages[0].offset.unwrap();
+        let second = pages[1].offset.unwrap();
+        assert!(second > first);
+        assert!(xml[first as usize..].starts_with("<page>"));
+        assert!(xml[second as usize..].starts_with("<page>"));
+
+        // Re-parsing from a recorded offset yields that page
+        let reparsed = PageParser::new(&xml.as_bytes()[second as usize..], false)
+            .next()
+            .unwrap();
+        assert_eq!(reparsed.title, "Second");
+        assert_eq!(reparsed.text.as_deref(), Some("Two."));
+    }
+
     #[test]
     fn page_parser_from_raw_xml() {
         let xml = b"<mediawiki>
@@ -592,4 +1182,170 @@ mod tests {
         assert_eq!(pages[0].title, "Direct");
         assert_eq!(pages[0].text.as_deref(), Some("Hello"));
     }
+
+    #[test]
+    fn bom_and_prolog_before_mediawiki_are_skipped() {
+        let body = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>
+<!DOCTYPE mediawiki>
+<!-- re-saved by an external tool -->
+<mediawiki xmlns=\"http://www.mediawiki.org/xml/export-0.10/\" xml:lang=\"en\">
+    <page>
+        <title>Bom Page</title>
+        <ns>0</ns>
+        <id>7</id>
+        <revision><id>70</id><text>Still parses.</text></revision>
+    </page>
+</mediawiki>";
+        let xml = format!("\u{feff}{body}");
+
+        let tmp = create_bz2_xml(&xml);
+        let pages: Vec<_> = WikiReader::new_inprocess(tmp.path().to_str().unwrap(), false)
+            .unwrap()
+            .track_offsets(true)
+            .collect();
+
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].id, 7);
+        assert_eq!(pages[0].title, "Bom Page");
+        assert_eq!(pages[0].text.as_deref(), Some("Still parses."));
+        // Offsets count the BOM, so they still index the raw decompressed bytes.
+        let offset = pages[0].offset.unwrap() as usize;
+        assert!(xml[offset..].starts_with("<page>"));
+    }
+
+    #[test]
+    fn leading_whitespace_before_declaration_is_tolerated() {
+        for prefix in ["\u{feff}", "\n  ", "\u{feff}\r\n"] {
+            let xml = format!(
+                "{prefix}<?xml version=\"1.0\"?><mediawiki><page><title>A</title><id>1</id></page></mediawiki>"
+            );
+            let pages: Vec<_> = PageParser::new(xml.as_bytes(), true).collect();
+            assert_eq!(pages.len(), 1, "prefix {prefix:?}");
+            assert_eq!(pages[0].title, "A");
+        }
+    }
+
+    #[test]
+    fn siteinfo_namespaces_are_collected() {
+        let xml = b"<mediawiki>
+            <siteinfo>
+                <sitename>Wikipedia</sitename>
+                <namespaces>
+                    <namespace key=\"-1\" case=\"first-letter\">Special</namespace>
+                    <namespace key=\"0\" case=\"first-letter\" />
+                    <namespace key=\"14\" case=\"first-letter\">Category</namespace>
+                    <namespace key=\"3\" case=\"first-letter\">User talk</namespace>
+                </namespaces>
+            </siteinfo>
+            <page><title>A</title><ns>0</ns><id>1</id></page>
+        </mediawiki>";
+
+        let mut parser = PageParser::new(&xml[..], true);
+        assert!(parser.namespaces().is_empty());
+        assert_eq!(parser.next().unwrap().title, "A");
+        assert_eq!(parser.namespaces(), ["Special", "Category", "User talk"]);
+    }
+
+    const SITEINFO_XML: &str = "<mediawiki>
+        <siteinfo>
+            <sitename>Wikipedia</sitename>
+            <dbname>enwiki</dbname>
+            <base>https://en.wikipedia.org/wiki/Main_Page</base>
+            <generator>MediaWiki 1.42.0-wmf.5</generator>
+            <case>first-letter</case>
+            <namespaces>
+                <namespace key=\"0\" case=\"first-letter\" />
+                <namespace key=\"14\" case=\"first-letter\">Category</namespace>
+            </namespaces>
+        </siteinfo>
+        <page><title>A</title><ns>0</ns><id>1</id></page>
+        <page><title>B</title><ns>0</ns><id>2</id></page>
+    </mediawiki>";
+
+    #[test]
+    fn history_page_keeps_latest_revision() {
+        let xml = b"<mediawiki>
+            <page><title>A</title><ns>0</ns><id>7</id>
+                <revision><id>100</id><timestamp>2001-01-01T00:00:00Z</timestamp>
+                    <text>Old text</text></revision>
+                <revision><id>200</id><timestamp>2024-01-01T00:00:00Z</timestamp>
+                    <text>New text</text></revision>
+            </page>
+            <page><title>B</title><ns>0</ns><id>8</id>
+                <revision><id>300</id><text>Only text</text></revision>
+                <revision><id>400</id><text deleted=\"deleted\" /></revision>
+            </page>
+        </mediawiki>";
+
+        let pages: Vec<_> = PageParser::new(&xml[..], false).collect();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].id, 7);
+        assert_eq!(pages[0].revisions, 2);
+        assert_eq!(pages[0].text.as_deref(), Some("New text"));
+        assert_eq!(pages[0].timestamp.as_deref(), Some("2024-01-01T00:00:00Z"));
+        // The latest revision's text was deleted: an empty body, not the older text.
+        assert_eq!(pages[1].text.as_deref(), Some(""));
+    }
+
+    #[test]
+    fn each_page_starts_from_a_clean_slate() {
+        let xml = b"<mediawiki>
+            <page><title>A</title><ns>0</ns><id>1</id>
+                <revision><id>10</id><text>Alpha text</text></revision></page>
+            <page><title>B</title><ns>0</ns><id>2</id>
+                <revision><id>20</id></revision></page>
+            <page><title>C</title><ns>0</ns><id>3</id>
+                <revision><id>30</id><text>Cut off
+            <page><title>D</title><ns>0</ns><id>4</id></page>
+        </mediawiki>";
+
+        let pages: Vec<_> = PageParser::new(&xml[..], false).collect();
+        let summary: Vec<_> = pages
+            .iter()
+            .map(|p| (p.id, p.title.as_str(), p.text.as_deref()))
+            .collect();
+        // B has no text of its own, and D inherits nothing from the unclosed C.
+        assert_eq!(
+            summary,
+            [(1, "A", Some("Alpha text")), (2, "B", None), (4, "D", None)]
+        );
+    }
+
+    #[test]
+    fn dump_info_is_read_before_iteration() {
+        let mut parser = PageParser::new(SITEINFO_XML.as_bytes(), true).track_offsets(true);
+        let info = parser.read_dump_info().clone();
+        assert_eq!(
+            info,
+            DumpInfo {
+                generator: Some("MediaWiki 1.42.0-wmf.5".into()),
+                dbname: Some("enwiki".into()),
+                base: Some("https://en.wikipedia.org/wiki/Main_Page".into()),
+                namespaces: vec!["Category".into()],
+            }
+        );
+        // Reading the header again is a no-op, and no page is lost.
+        assert_eq!(parser.read_dump_info(), &info);
+        let pages: Vec<_> = parser.collect();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(
+            pages[0].offset,
+            SITEINFO_XML.find("<page>").map(|o| o as u64)
+        );
+
+        // Without a siteinfo block, reading stops at (and keeps) the first page.
+        let xml = b"<mediawiki><page><title>A</title><id>1</id></page></mediawiki>";
+        let mut parser = PageParser::new(&xml[..], true).track_offsets(true);
+        assert_eq!(parser.read_dump_info(), &DumpInfo::default());
+        let page = parser.next().unwrap();
+        assert_eq!((page.title.as_str(), page.offset), ("A", Some(11)));
+    }
+
+    #[test]
+    fn read_dump_info_extracts_generator_from_dump() {
+        let tmp = create_bz2_xml(SITEINFO_XML);
+        let info = read_dump_info(tmp.path().to_str().unwrap()).unwrap();
+        assert_eq!(info.generator.as_deref(), Some("MediaWiki 1.42.0-wmf.5"));
+        assert_eq!(info.dbname.as_deref(), Some("enwiki"));
+    }
 }
@@ -0,0 +1,192 @@
+//! Small sub-dumps for fixtures and experiments (`dedalus sample`).
+//!
+//! `write_sample()` copies the input's `<mediawiki>` root tag and `<siteinfo>`
+//! header verbatim, then streams pages through `WikiReader` and writes the first
+//! `pages` of them (or, with a rate, only those whose hashed ID falls under it)
+//! to a new bz2 dump. Pages are rebuilt from `WikiPage`, so they keep what
+//! dedalus reads: title, `<ns>`, ID, redirect target and the latest revision's
+//! timestamp and text. Reading stops as soon as the sample is full.
+
+use crate::models::{PageType, WikiPage};
+use crate::parser::WikiReader;
+use anyhow::{Context, Result, bail};
+use bzip2::Compression;
+use bzip2::write::BzEncoder;
+use memchr::memmem;
+use quick_xml::escape::escape;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use tracing::info;
+
+/// Give up looking for the end of the dump header after this many bytes.
+const MAX_HEADER_BYTES: usize = 16 * 1024 * 1024;
+
+/// Counts from a [`write_sample`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SampleStats {
+    /// Pages read from the input.
+    pub pages_read: u64,
+    /// Pages written to the sample.
+    pub pages_written: u64,
+}
+
+/// Whether `--rate` keeps this page: its hashed ID, scaled to `[0, 1)`, is
+/// below `rate`. Deterministic, so the same input always gives the same sample.
+fn page_sampled(page_id: u64, rate: f64) -> bool {
+    // Salted differently from `blob_sampled` so the two samples are independent.
+    let h = crate::shard::fmix64(page_id ^ 0x5851_f42d_4c95_7f2d);
+    ((h >> 11) as f64 / (1u64 << 53) as f64) < rate
+}
+
+/// Decompressed bytes from the start of `input` through `</siteinfo>` (or up
+/// to the first `<page>` if the dump has no header), without a UTF-8 BOM.
+fn read_header(input: &str) -> Result<Vec<u8>> {
+    let mut decoder = crate::parser::open_in_process(input)?;
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 64 * 1024];
+    loop {
+        if let Some(end) = memmem::find(&head, b"</siteinfo>") {
+            head.truncate(end + b"</siteinfo>".len());
+            break;
+        }
+        if let Some(start) = memmem::find(&head, b"<page>") {
+            head.truncate(start);
+            break;
+        }
+        if head.len() > MAX_HEADER_BYTES {
+            bail!("No <siteinfo> or <page> in the first {MAX_HEADER_BYTES} bytes of {input}");
+        }
+        let n = decoder
+            .read(&mut chunk)
+            .with_context(|| format!("Failed to decompress {input}"))?;
+        if n == 0 {
+            // A dump without pages: keep everything up to the closing root tag.
+            if let Some(end) = memmem::find(&head, b"</mediawiki>") {
+                head.truncate(end);
+            }
+            break;
+        }
+        head.extend_from_slice(&chunk[..n]);
+    }
+    if head.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        head.drain(..3);
+    }
+    while head.last().is_some_and(u8::is_ascii_whitespace) {
+        head.pop();
+    }
+    Ok(head)
+}
+
+/// Writes one page as MediaWiki export XML.
+fn write_page(out: &mut impl Write, page: &WikiPage) -> std::io::Result<()> {
+    writeln!(out, "  <page>")?;
+    writeln!(out, "    <title>{}</title>", escape(&page.title))?;
+    if let Some(ns) = page.ns {
+        writeln!(out, "    <ns>{ns}</ns>")?;
+    }
+    writeln!(out, "    <id>{}</id>", page.id)?;
+    if let PageType::Redirect(target) = &page.page_type {
+        writeln!(out, "    <redirect title=\"{}\" />", escape(target))?;
+    }
+    writeln!(out, "    <revision>")?;
+    if let Some(timestamp) = &page.timestamp {
+        writeln!(out, "      <timestamp>{}</timestamp>", escape(timestamp))?;
+    }
+    match &page.text {
+        Some(text) => writeln!(
+            out,
+            "      <text xml:space=\"preserve\">{}</text>",
+            escape(text)
+        )?,
+        None => writeln!(out, "      <text />")?,
+    }
+    writeln!(out, "    </revision>")?;
+    writeln!(out, "  </page>")
+}
+
+/// Writes up to `pages` pages of `input` to `output` as a bz2 dump with the
+/// input's `<siteinfo>` header. Without `rate` these are the first `pages`
+/// pages; with it, pages are kept by `page_sampled` until `pages` are written.
+pub fn write_sample(
+    input: &str,
+    output: &Path,
+    pages: u64,
+    rate: Option<f64>,
+) -> Result<SampleStats> {
+    if pages == 0 {
+        bail!("--pages must be at least 1");
+    }
+    if let Some(rate) = rate
+        && !(0.0..=1.0).contains(&rate)
+    {
+        bail!("--rate must be between 0 and 1, got {rate}");
+    }
+    if Path::new(input) == output {
+        bail!("Sample output {output:?} is the input dump");
+    }
+
+    let header = read_header(input)?;
+    let reader = WikiReader::new(input, false)?;
+
+    let file = File::create(output).with_context(|| format!("Failed to create {output:?}"))?;
+    let mut out = BzEncoder::new(BufWriter::new(file), Compression::default());
+    out.write_all(&header)?;
+    out.write_all(b"\n")?;
+
+    let mut stats = SampleStats::default();
+    for page in reader {
+        stats.pages_read += 1;
+        if rate.is_some_and(|rate| !page_sampled(page.id, rate)) {
+            continue;
+        }
+        write_page(&mut out, &page).with_context(|| format!("Failed to write {output:?}"))?;
+        stats.pages_written += 1;
+        if stats.pages_written == pages {
+            break;
+        }
+    }
+    out.write_all(b"</mediawiki>\n")?;
+    out.finish()
+        .and_then(|mut writer| writer.flush())
+        .with_context(|| format!("Failed to write {output:?}"))?;
+
+    info!(
+        read = stats.pages_read,
+        written = stats.pages_written,
+        path = ?output,
+        "Wrote sample dump"
+    );
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_written_as_escaped_export_xml() {
+        let page = WikiPage {
+            id: 7,
+            title: "AT&T".to_string(),
+            page_type: PageType::Redirect("AT&T Inc.".to_string()),
+            text: Some("#REDIRECT [[AT&T Inc.]] <br>".to_string()),
+            ns: Some(0),
+            timestamp: Some("2024-01-01T00:00:00Z".to_string()),
+            offset: None,
+            revisions: 2,
+            text_bytes: None,
+            text_sha1: None,
+        };
+        let mut out = Vec::new();
+        write_page(&mut out, &page).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "  <page>\n    <title>AT&amp;T</title>\n    <ns>0</ns>\n    <id>7</id>\n    \
+             <redirect title=\"AT&amp;T Inc.\" />\n    <revision>\n      \
+             <timestamp>2024-01-01T00:00:00Z</timestamp>\n      \
+             <text xml:space=\"preserve\">#REDIRECT [[AT&amp;T Inc.]] &lt;br&gt;</text>\n    \
+             </revision>\n  </page>\n"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! Relationship type and node label names for graph output.
+//!
+//! `RelationshipNames` holds the `:TYPE` strings written to the edges and
+//! article-entity CSVs (`LINKS_TO`, `SEE_ALSO`, `HAS_CATEGORY`, `HAS_IMAGE`,
+//! `HAS_LINK` by default) and derives the SurrealDB relation table that
+//! `surrealdb_writer` loads edges into. Each name can be overridden with an
+//! `OLD=NEW` pair (`--relationship-name LINKS_TO=CITES`) so output matches
+//! an existing graph schema. Article nodes are labelled `DEFAULT_PAGE_LABEL`
+//! unless `--page-label` picks another name, checked by `validate_label()`.
+
+use crate::models::EdgeType;
+use anyhow::{Result, bail};
+
+/// `:LABEL` written for article nodes unless `--page-label` overrides it.
+pub const DEFAULT_PAGE_LABEL: &str = "Page";
+
+/// Whether `name` is a letter or underscore followed by ASCII alphanumerics or
+/// underscores, so it needs no quoting as a label or type name.
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Checks a node label (e.g. `--page-label WikiArticle`) with the same rules
+/// as relationship names.
+pub fn validate_label(label: &str) -> Result<()> {
+    if !is_identifier(label) {
+        bail!("Invalid node label {label:?}: use letters, digits, and underscores");
+    }
+    Ok(())
+}
+
+/// Relationship type names used by extraction and the SurrealDB loader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationshipNames {
+    pub links_to: String,
+    pub see_also: String,
+    pub has_category: String,
+    pub has_image: String,
+    pub has_link: String,
+}
+
+impl Default for RelationshipNames {
+    fn default() -> Self {
+        Self {
+            links_to: EdgeType::LinksTo.as_str().to_string(),
+            see_also: EdgeType::SeeAlso.as_str().to_string(),
+            has_category: "HAS_CATEGORY".to_string(),
+            has_image: "HAS_IMAGE".to_string(),
+            has_link: "HAS_LINK".to_string(),
+        }
+    }
+}
+
+impl RelationshipNames {
+    /// Default names with each `OLD=NEW` override applied in order.
+    pub fn from_overrides(overrides: &[String]) -> Result<Self> {
+        let mut names = Self::default();
+        for spec in overrides {
+            names.apply_override(spec)?;
+        }
+        Ok(names)
+    }
+
+    /// Renames one relationship type. `OLD` is the default name (case-insensitive);
+    /// `NEW` must be a letter or underscore followed by ASCII alphanumerics or underscores.
+    pub fn apply_override(&mut self, spec: &str) -> Result<()> {
+        let Some((old, new)) = spec.split_once('=') else {
+            bail!("Invalid relationship name override {spec:?}: expected OLD=NEW");
+        };
+        let new = new.trim();
+        if !is_identifier(new) {
+            bail!("Invalid relationship name {new:?}: use letters, digits, and underscores");
+        }
+        let slot = match old.trim().to_ascii_uppercase().as_str() {
+            "LINKS_TO" => &mut self.links_to,
+            "SEE_ALSO" => &mut self.see_also,
+            "HAS_CATEGORY" => &mut self.has_category,
+            "HAS_IMAGE" => &mut self.has_image,
+            "HAS_LINK" => &mut self.has_link,
+            other => bail!(
+                "Unknown relationship type {other:?}: expected one of \
+                 LINKS_TO, SEE_ALSO, HAS_CATEGORY, HAS_IMAGE, HAS_LINK"
+            ),
+        };
+        *slot = new.to_string();
+        Ok(())
+    }
+
+    /// `:TYPE` value for an article-to-article edge.
+    #[must_use]
+    pub fn edge(&self, edge_type: EdgeType) -> &str {
+        match edge_type {
+            EdgeType::LinksTo => &self.links_to,
+            EdgeType::SeeAlso => &self.see_also,
+        }
+    }
+
+    /// SurrealDB relation table for article edges: the `LINKS_TO` name, lowercased.
+    #[must_use]
+    pub fn relation_table(&self) -> String {
+        self.links_to.to_ascii_lowercase()
+    }
+
+    /// `DEFINE TABLE` statement for the edge relation table.
+    #[must_use]
+    pub fn define_relation_statement(&self) -> String {
+        format!(
+            "DEFINE TABLE {} SCHEMAFULL TYPE RELATION FROM article TO article;",
+            self.relation_table()
+        )
+    }
+
+    /// `RELATE` statement loading one edge row into the relation table.
+    #[must_use]
+    pub fn relate_statement(&self, start_id: &str, end_id: &str) -> String {
+        format!(
+            "RELATE article:{start_id}->{}->article:{end_id};\n",
+            self.relation_table()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_edge_type_names() {
+        let names = RelationshipNames::default();
+        assert_eq!(names.edge(EdgeType::LinksTo), "LINKS_TO");
+        assert_eq!(names.edge(EdgeType::SeeAlso), "SEE_ALSO");
+        assert_eq!(
+            names.relate_statement("1", "2"),
+            "RELATE article:1->links_to->article:2;\n"
+        );
+    }
+
+    #[test]
+    fn page_labels_must_be_identifiers() {
+        assert!(validate_label(DEFAULT_PAGE_LABEL).is_ok());
+        assert!(validate_label("WikiArticle").is_ok());
+        assert!(validate_label("_Page2").is_ok());
+        for bad in ["", "Wiki Article", "2Page", "Page:Article", "Pagé"] {
+            assert!(validate_label(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn overrides_rename_and_validate() {
+        let names = RelationshipNames::from_overrides(&[
+            "links_to=CITES".to_string(),
+            "HAS_IMAGE = DEPICTS".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(names.edge(EdgeType::LinksTo), "CITES");
+        assert_eq!(names.has_image, "DEPICTS");
+        assert_eq!(names.see_also, "SEE_ALSO");
+        assert_eq!(
+            names.define_relation_statement(),
+            "DEFINE TABLE cites SCHEMAFULL TYPE RELATION FROM article TO article;"
+        );
+
+        for bad in [
+            "LINKS_TO",
+            "LINKS_TO=",
+            "LINKS_TO=A-B",
+            "LINKS_TO=1X",
+            "LINKS_FROM=X",
+        ] {
+            assert!(
+                RelationshipNames::from_overrides(&[bad.to_string()]).is_err(),
+                "{bad}"
+            );
+        }
+    }
+}
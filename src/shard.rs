@@ -0,0 +1,119 @@
+//! Page-ID sharding shared by CSV shards, blob directories and checkpoints.
+//!
+//! `page_shard()` maps a page ID to a shard with the configured
+//! `ShardStrategy`. `Modulo` (`page_id % shards`) is the default and matches
+//! every output written before hashing existed; `Hash` is opt-in for dumps
+//! whose IDs cluster in a few ranges.
+
+use serde::{Deserialize, Serialize};
+
+/// How page IDs map to CSV shards and blob directories (see `page_shard`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum ShardStrategy {
+    /// `page_id % shards`; the layout written before hashing was introduced.
+    #[default]
+    Modulo,
+    /// Hash of the page ID; balanced regardless of how IDs are distributed.
+    Hash,
+}
+
+impl std::fmt::Display for ShardStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShardStrategy::Modulo => f.write_str("modulo"),
+            ShardStrategy::Hash => f.write_str("hash"),
+        }
+    }
+}
+
+/// Shard index for a page ID, used for both CSV shards and blob directories.
+///
+/// `Hash` runs the ID through a 64-bit finalizer (MurmurHash3 `fmix64`) before
+/// the modulo, so shards stay balanced even when IDs cluster in a few ranges.
+/// Stable across runs and platforms.
+#[must_use]
+pub fn page_shard(page_id: u64, shards: u32, strategy: ShardStrategy) -> u32 {
+    match strategy {
+        ShardStrategy::Modulo => (page_id % u64::from(shards)) as u32,
+        ShardStrategy::Hash => (fmix64(page_id) % u64::from(shards)) as u32,
+    }
+}
+
+/// MurmurHash3 64-bit finalizer.
+pub(crate) fn fmix64(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SHARD_COUNT;
+
+    #[test]
+    fn default_strategy_is_modulo() {
+        assert_eq!(ShardStrategy::default(), ShardStrategy::Modulo);
+        assert_eq!(
+            page_shard(123_456, SHARD_COUNT, ShardStrategy::default()),
+            456
+        );
+    }
+
+    #[test]
+    fn shard_calculation() {
+        let modulo = |id| page_shard(id, SHARD_COUNT, ShardStrategy::Modulo);
+        assert_eq!(modulo(0), 0);
+        assert_eq!(modulo(1), 1);
+        assert_eq!(modulo(999), 999);
+        assert_eq!(modulo(1000), 0);
+        assert_eq!(modulo(1001), 1);
+        assert_eq!(modulo(123456), 456);
+    }
+
+    #[test]
+    fn shard_stays_within_bounds() {
+        for strategy in [ShardStrategy::Hash, ShardStrategy::Modulo] {
+            for id in [
+                0u64,
+                1,
+                500,
+                999,
+                1000,
+                99999,
+                u64::from(u32::MAX) + 1,
+                u64::MAX,
+            ] {
+                assert!(page_shard(id, SHARD_COUNT, strategy) < SHARD_COUNT);
+            }
+        }
+    }
+
+    #[test]
+    fn hash_shard_balances_clustered_ids() {
+        // IDs that are all multiples of the shard count: modulo puts every one
+        // in shard 0, hashing should spread them evenly.
+        let shards = 8u32;
+        let ids: Vec<u64> = (0..80_000u64)
+            .map(|i| 10_000_000 + i * u64::from(shards))
+            .collect();
+        let mut counts = vec![0u32; shards as usize];
+        for &id in &ids {
+            counts[page_shard(id, shards, ShardStrategy::Hash) as usize] += 1;
+        }
+        let expected = ids.len() as u32 / shards;
+        for (shard, &count) in counts.iter().enumerate() {
+            assert!(
+                count.abs_diff(expected) < expected / 10,
+                "shard {shard} has {count} ids, expected ~{expected}"
+            );
+        }
+        assert!(
+            ids.iter()
+                .all(|&id| page_shard(id, shards, ShardStrategy::Modulo) == 0)
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Sorting node and edge CSVs by article ID (`--sort-nodes`, `--sort-edges`).
+//!
+//! Parallel extraction writes rows in whatever order the workers finish, which
+//! slows `neo4j-admin import` and makes two runs hard to diff. `sort_csvs()`
+//! rewrites `nodes` by `id:ID` and `edges` by `:START_ID`. The parts of a
+//! single-file layout are sorted as one sequence and written back with each part
+//! keeping its row count, so their concatenation is sorted; each shard of a
+//! sharded layout is sorted on its own, so rows stay in the shard
+//! `shard::page_shard` put them in.
+//!
+//! The sort is external and stable. Rows are read in runs of `SORT_RUN_ROWS`,
+//! each run is sorted in memory and spilled to `{name}.run{n}.tmp`, and the
+//! runs are k-way merged through a `BinaryHeap` keyed by `(id, run)`. Rows with
+//! the same ID keep their file order, so an article's edges stay in the order it
+//! listed them. Output is staged as `{name}.tmp` and renamed into place once
+//! the whole group is written; a group that fits in one run is never spilled.
+
+use crate::config::{MERGE_BUF_SIZE, SORT_RUN_ROWS};
+use crate::csv_util::{self, CsvLayout, CsvType};
+use anyhow::{Context, Result, bail};
+use csv::ByteRecord;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Counts from a [`sort_csvs`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SortStats {
+    /// CSV files rewritten.
+    pub files: u64,
+    /// Data rows sorted across all files.
+    pub rows: u64,
+    /// Sorted runs spilled to disk (0 when every group fit in memory).
+    pub spilled_runs: u64,
+}
+
+fn open_reader(path: &Path, has_headers: bool) -> Result<csv::Reader<File>> {
+    csv::ReaderBuilder::new()
+        .buffer_capacity(MERGE_BUF_SIZE)
+        .has_headers(has_headers)
+        .from_path(path)
+        .with_context(|| format!("Failed to open {path:?}"))
+}
+
+fn create_writer(path: &Path) -> Result<csv::Writer<File>> {
+    csv::WriterBuilder::new()
+        .buffer_capacity(MERGE_BUF_SIZE)
+        .from_path(path)
+        .with_context(|| format!("Failed to create {path:?}"))
+}
+
+/// Sorts `run` by key (stably) and writes it, without a header, to `path`.
+fn spill(run: &mut Vec<(u64, ByteRecord)>, path: &Path) -> Result<()> {
+    run.sort_by_key(|(key, _)| *key);
+    let mut writer = create_writer(path)?;
+    for (_, record) in run.drain(..) {
+        writer.write_byte_record(&record)?;
+    }
+    writer
+        .flush()
+        .with_context(|| format!("Failed to write {path:?}"))
+}
+
+/// Sorted output for one group: the `{file}.tmp` of each input file, filled in
+/// order up to that file's original row count (the last one takes any excess).
+struct GroupWriter {
+    writers: Vec<(csv::Writer<File>, u64)>,
+    current: usize,
+    written: u64,
+}
+
+impl GroupWriter {
+    fn create(tmp_paths: &[PathBuf], row_counts: &[u64], header: &ByteRecord) -> Result<Self> {
+        let mut writers = Vec::with_capacity(tmp_paths.len());
+        for (path, &rows) in tmp_paths.iter().zip(row_counts) {
+            let mut writer = create_writer(path)?;
+            if !header.is_empty() {
+                writer.write_byte_record(header)?;
+            }
+            writers.push((writer, rows));
+        }
+        Ok(Self {
+            writers,
+            current: 0,
+            written: 0,
+        })
+    }
+
+    fn write(&mut self, record: &ByteRecord) -> Result<()> {
+        while self.written == self.writers[self.current].1 && self.current + 1 < self.writers.len()
+        {
+            self.current += 1;
+            self.written = 0;
+        }
+        self.writers[self.current].0.write_byte_record(record)?;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        for (mut writer, _) in self.writers {
+            writer.flush().context("Failed to write sorted CSV")?;
+        }
+        Ok(())
+    }
+}
+
+/// Streams the sorted runs at `runs` into `out`, smallest key first and, among
+/// equal keys, in run order.
+fn merge_runs(runs: &[PathBuf], out: &mut GroupWriter) -> Result<()> {
+    let mut readers = runs
+        .iter()
+        .map(|path| open_reader(path, false))
+        .collect::<Result<Vec<_>>>()?;
+    let mut heads = vec![ByteRecord::new(); runs.len()];
+    let mut heap = BinaryHeap::with_capacity(runs.len());
+    for (run, reader) in readers.iter_mut().enumerate() {
+        if reader.read_byte_record(&mut heads[run])? {
+            heap.push(Reverse((
+                csv_util::parse_id(&heads[run], 0, &runs[run])?,
+                run,
+            )));
+        }
+    }
+    while let Some(Reverse((_, run))) = heap.pop() {
+        out.write(&heads[run])?;
+        if readers[run]
+            .read_byte_record(&mut heads[run])
+            .with_context(|| format!("Failed to read {:?}", runs[run]))?
+        {
+            heap.push(Reverse((
+                csv_util::parse_id(&heads[run], 0, &runs[run])?,
+                run,
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Sorts the rows of `paths` as one sequence by their first column and writes
+/// them back across the same files, keeping each file's row count.
+fn sort_group(paths: &[PathBuf], run_rows: usize, stats: &mut SortStats) -> Result<()> {
+    let run_path = |n: usize| paths[0].with_extension(format!("run{n}.tmp"));
+    let mut header = None;
+    let mut row_counts = Vec::with_capacity(paths.len());
+    let mut run: Vec<(u64, ByteRecord)> = Vec::new();
+    let mut runs = Vec::new();
+
+    for path in paths {
+        let mut reader = open_reader(path, true)?;
+        if header.is_none() {
+            header = Some(
+                reader
+                    .byte_headers()
+                    .with_context(|| format!("Failed to read header of {path:?}"))?
+                    .clone(),
+            );
+        }
+        let mut record = ByteRecord::new();
+        let mut rows = 0u64;
+        while reader
+            .read_byte_record(&mut record)
+            .with_context(|| format!("Failed to read {path:?}"))?
+        {
+            run.push((csv_util::parse_id(&record, 0, path)?, record.clone()));
+            rows += 1;
+            if run.len() >= run_rows {
+                let spilled = run_path(runs.len());
+                spill(&mut run, &spilled)?;
+                runs.push(spilled);
+            }
+        }
+        row_counts.push(rows);
+    }
+
+    let tmp_paths: Vec<PathBuf> = paths
+        .iter()
+        .map(|path| path.with_extension("csv.tmp"))
+        .collect();
+    let mut out = GroupWriter::create(&tmp_paths, &row_counts, &header.unwrap_or_default())?;
+    if runs.is_empty() {
+        run.sort_by_key(|(key, _)| *key);
+        for (_, record) in &run {
+            out.write(record)?;
+        }
+    } else {
+        if !run.is_empty() {
+            let spilled = run_path(runs.len());
+            spill(&mut run, &spilled)?;
+            runs.push(spilled);
+        }
+        merge_runs(&runs, &mut out)?;
+        for spilled in &runs {
+            fs::remove_file(spilled).with_context(|| format!("Failed to remove {spilled:?}"))?;
+        }
+    }
+    out.finish()?;
+
+    for (tmp_path, path) in tmp_paths.iter().zip(paths) {
+        fs::rename(tmp_path, path)
+            .with_context(|| format!("Failed to rename {tmp_path:?} to {path:?}"))?;
+    }
+    stats.files += paths.len() as u64;
+    stats.rows += row_counts.iter().sum::<u64>();
+    stats.spilled_runs += runs.len() as u64;
+    Ok(())
+}
+
+fn sort_csvs_in_runs(
+    output_dir: &str,
+    csv_types: &[CsvType],
+    run_rows: usize,
+) -> Result<SortStats> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    csv_util::validate_csv_files(output_dir, &layout)?;
+
+    let mut stats = SortStats::default();
+    for &csv_type in csv_types {
+        if !matches!(csv_type, CsvType::Nodes | CsvType::Edges) {
+            bail!(
+                "Only nodes and edges can be sorted, not {}",
+                csv_type.base_name()
+            );
+        }
+        let files: Vec<PathBuf> = csv_util::layout_files(output_dir, &layout, csv_type)
+            .into_iter()
+            .map(|file| Path::new(output_dir).join(file))
+            .collect();
+        match layout {
+            CsvLayout::Single => sort_group(&files, run_rows, &mut stats)?,
+            CsvLayout::Sharded { .. } => {
+                for file in files {
+                    sort_group(std::slice::from_ref(&file), run_rows, &mut stats)?;
+                }
+            }
+        }
+    }
+    info!(
+        files = stats.files,
+        rows = stats.rows,
+        spilled_runs = stats.spilled_runs,
+        "Sorted CSVs by article ID"
+    );
+    Ok(stats)
+}
+
+/// Sorts each of `csv_types` (`Nodes` and/or `Edges`) in `output_dir` by its
+/// first column, holding at most `SORT_RUN_ROWS` rows in memory at a time.
+/// Works on single-file and sharded layouts.
+pub fn sort_csvs(output_dir: &str, csv_types: &[CsvType]) -> Result<SortStats> {
+    sort_csvs_in_runs(output_dir, csv_types, SORT_RUN_ROWS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn external_sort_is_stable_and_keeps_part_sizes() {
+        let dir = TempDir::new().unwrap();
+        csv_util::write_csv_fixture(
+            dir.path(),
+            &[
+                (
+                    "nodes.csv",
+                    "id:ID,title,:LABEL\n30,C,Page\n4,\"A, b\",Page\n",
+                ),
+                ("nodes_part001.csv", "id:ID,title,:LABEL\n100,D,Page\n"),
+                ("nodes_part002.csv", "id:ID,title,:LABEL\n12,B,Page\n"),
+                (
+                    "edges.csv",
+                    ":START_ID,:END_ID,:TYPE\n30,9,LINKS_TO\n4,2,LINKS_TO\n30,1,LINKS_TO\n4,7,LINKS_TO\n30,5,LINKS_TO\n",
+                ),
+            ],
+        );
+
+        let stats = sort_csvs_in_runs(
+            dir.path().to_str().unwrap(),
+            &[CsvType::Nodes, CsvType::Edges],
+            2,
+        )
+        .unwrap();
+        assert_eq!(
+            stats,
+            SortStats {
+                files: 4,
+                rows: 9,
+                spilled_runs: 5
+            }
+        );
+
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(
+            read("nodes.csv"),
+            "id:ID,title,:LABEL\n4,\"A, b\",Page\n12,B,Page\n"
+        );
+        assert_eq!(read("nodes_part001.csv"), "id:ID,title,:LABEL\n30,C,Page\n");
+        assert_eq!(
+            read("nodes_part002.csv"),
+            "id:ID,title,:LABEL\n100,D,Page\n"
+        );
+        // Edges from one article keep their original order across runs.
+        assert_eq!(
+            read("edges.csv"),
+            ":START_ID,:END_ID,:TYPE\n4,2,LINKS_TO\n4,7,LINKS_TO\n30,9,LINKS_TO\n30,1,LINKS_TO\n30,5,LINKS_TO\n"
+        );
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.unwrap().file_name().into_string().ok())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+    }
+}
@@ -0,0 +1,465 @@
+//! SQLite extraction output -- writes every row into a single `graph.db` file.
+//!
+//! An alternative to sharded CSVs for a self-contained, queryable artifact. One
+//! table per CSV type mirrors the CSV columns (`nodes` becomes `pages`). Rayon
+//! workers send rows over a bounded channel to a single writer thread, which
+//! inserts them in batched transactions with WAL journaling and builds the
+//! indexes once at the end. `sync` waits for every queued row to be committed,
+//! so checkpoints never run ahead of the database. Node tables use `INSERT OR IGNORE`, so duplicate
+//! node rows (e.g. from fingerprint dedup) collapse onto their primary key.
+
+use crate::config::{SQLITE_BATCH_SIZE, SQLITE_CHANNEL_CAPACITY};
+use crate::csv_util::CsvType;
+use anyhow::{Context, Result, anyhow};
+use rusqlite::{Connection, params_from_iter};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::sync::{Mutex, OnceLock};
+use std::thread::JoinHandle;
+use tracing::{debug, info};
+
+/// Indexes created after all rows are inserted (cheaper than maintaining them per insert).
+const INDEXES: &[&str] = &[
+    "CREATE INDEX IF NOT EXISTS idx_pages_title ON pages(title)",
+    "CREATE INDEX IF NOT EXISTS idx_edges_start ON edges(start_id)",
+    "CREATE INDEX IF NOT EXISTS idx_edges_end ON edges(end_id)",
+    "CREATE INDEX IF NOT EXISTS idx_article_categories_start ON article_categories(start_id)",
+    "CREATE INDEX IF NOT EXISTS idx_article_categories_end ON article_categories(end_id)",
+    "CREATE INDEX IF NOT EXISTS idx_article_images_start ON article_images(start_id)",
+    "CREATE INDEX IF NOT EXISTS idx_article_images_end ON article_images(end_id)",
+    "CREATE INDEX IF NOT EXISTS idx_article_external_links_start ON article_external_links(start_id)",
+    "CREATE INDEX IF NOT EXISTS idx_article_external_links_end ON article_external_links(end_id)",
+];
+
+/// SQLite table name for a CSV type.
+#[must_use]
+pub fn table_name(csv_type: CsvType) -> &'static str {
+    match csv_type {
+        CsvType::Nodes => "pages",
+        other => other.base_name(),
+    }
+}
+
+fn create_table_sql(csv_type: CsvType) -> &'static str {
+    match csv_type {
+        CsvType::Nodes => {
+            "CREATE TABLE IF NOT EXISTS pages (id INTEGER PRIMARY KEY, title TEXT NOT NULL, label TEXT NOT NULL)"
+        }
+        CsvType::Edges => {
+            "CREATE TABLE IF NOT EXISTS edges (start_id INTEGER NOT NULL, end_id INTEGER NOT NULL, type TEXT NOT NULL, section TEXT)"
+        }
+        CsvType::Categories => {
+            "CREATE TABLE IF NOT EXISTS categories (id TEXT PRIMARY KEY, name TEXT NOT NULL, label TEXT NOT NULL)"
+        }
+        CsvType::ArticleCategories => {
+            "CREATE TABLE IF NOT EXISTS article_categories (start_id INTEGER NOT NULL, end_id TEXT NOT NULL, type TEXT NOT NULL)"
+        }
+        CsvType::ImageNodes => {
+            "CREATE TABLE IF NOT EXISTS image_nodes (id TEXT PRIMARY KEY, filename TEXT NOT NULL, label TEXT NOT NULL)"
+        }
+        CsvType::ArticleImages => {
+            "CREATE TABLE IF NOT EXISTS article_images (start_id INTEGER NOT NULL, end_id TEXT NOT NULL, type TEXT NOT NULL)"
+        }
+        CsvType::ExternalLinkNodes => {
+            "CREATE TABLE IF NOT EXISTS external_link_nodes (id TEXT PRIMARY KEY, url TEXT NOT NULL, label TEXT NOT NULL)"
+        }
+        CsvType::ArticleExternalLinks => {
+            "CREATE TABLE IF NOT EXISTS article_external_links (start_id INTEGER NOT NULL, end_id TEXT NOT NULL, type TEXT NOT NULL)"
+        }
+    }
+}
+
+fn insert_sql(csv_type: CsvType) -> &'static str {
+    match csv_type {
+        CsvType::Nodes => "INSERT OR IGNORE INTO pages VALUES (?1, ?2, ?3)",
+        CsvType::Edges => "INSERT INTO edges VALUES (?1, ?2, ?3, ?4)",
+        CsvType::Categories => "INSERT OR IGNORE INTO categories VALUES (?1, ?2, ?3)",
+        CsvType::ArticleCategories => "INSERT INTO article_categories VALUES (?1, ?2, ?3)",
+        CsvType::ImageNodes => "INSERT OR IGNORE INTO image_nodes VALUES (?1, ?2, ?3)",
+        CsvType::ArticleImages => "INSERT INTO article_images VALUES (?1, ?2, ?3)",
+        CsvType::ExternalLinkNodes => {
+            "INSERT OR IGNORE INTO external_link_nodes VALUES (?1, ?2, ?3)"
+        }
+        CsvType::ArticleExternalLinks => "INSERT INTO article_external_links VALUES (?1, ?2, ?3)",
+    }
+}
+
+struct Row {
+    table: CsvType,
+    values: Vec<String>,
+}
+
+enum Message {
+    Row(Row),
+    /// Commit the rows queued so far, then reply.
+    Flush(SyncSender<()>),
+}
+
+/// Handle to the background SQLite writer thread.
+///
+/// `insert` and `sync` are safe to call from many threads. If the writer
+/// thread fails, both return its error. Call `finish` to flush pending rows,
+/// build indexes, and surface any write error; dropping without `finish`
+/// still flushes but discards the result.
+pub struct SqliteWriter {
+    sender: Option<SyncSender<Message>>,
+    handle: Mutex<Option<JoinHandle<Result<u64>>>>,
+    /// The writer thread's error, once it has been joined after failing.
+    failure: OnceLock<String>,
+}
+
+impl std::fmt::Debug for SqliteWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteWriter")
+            .field("open", &self.sender.is_some())
+            .finish()
+    }
+}
+
+impl SqliteWriter {
+    /// Opens (or creates) the database at `path`, creates any missing tables,
+    /// and starts the writer thread. With `fresh`, any existing database (and
+    /// its WAL files) is removed first; otherwise rows are appended, as when
+    /// resuming an extraction.
+    pub fn open(path: &Path, fresh: bool) -> Result<Self> {
+        if fresh {
+            for suffix in ["", "-wal", "-shm"] {
+                let mut file = path.as_os_str().to_owned();
+                file.push(suffix);
+                match std::fs::remove_file(&file) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(e)
+                            .with_context(|| format!("Failed to remove old database: {:?}", file));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database: {:?}", path))?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .context("Failed to enable WAL journal mode")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")
+            .context("Failed to set synchronous mode")?;
+        for csv_type in CsvType::ALL {
+            conn.execute(create_table_sql(*csv_type), [])
+                .with_context(|| format!("Failed to create table {}", table_name(*csv_type)))?;
+        }
+
+        let (sender, receiver) = sync_channel(SQLITE_CHANNEL_CAPACITY);
+        let handle = std::thread::Builder::new()
+            .name("dedalus-sqlite".to_string())
+            .spawn(move || write_loop(conn, receiver))
+            .context("Failed to spawn SQLite writer thread")?;
+
+        info!(path = ?path, "Writing extraction output to SQLite");
+        Ok(Self {
+            sender: Some(sender),
+            handle: Mutex::new(Some(handle)),
+            failure: OnceLock::new(),
+        })
+    }
+
+    /// Queues one row for `table`. Blocks while the channel is full.
+    ///
+    /// Trailing optional columns (the edge `section`) may be omitted and are
+    /// stored as NULL. Fails with the writer thread's error if it has stopped.
+    pub fn insert(&self, table: CsvType, values: &[&str]) -> Result<()> {
+        self.send(Message::Row(Row {
+            table,
+            values: values.iter().map(|v| (*v).to_owned()).collect(),
+        }))
+    }
+
+    /// Blocks until every row queued so far is committed.
+    pub fn sync(&self) -> Result<()> {
+        let (done, committed) = sync_channel(1);
+        self.send(Message::Flush(done))?;
+        committed.recv().map_err(|_| self.failure())
+    }
+
+    fn send(&self, message: Message) -> Result<()> {
+        match &self.sender {
+            Some(sender) => sender.send(message).map_err(|_| self.failure()),
+            None => Err(anyhow!("SQLite writer is closed")),
+        }
+    }
+
+    /// The error that stopped the writer thread. The first caller joins the
+    /// thread; later ones get the recorded message.
+    fn failure(&self) -> anyhow::Error {
+        let handle = self.handle.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(handle) = handle {
+            let message = match handle.join() {
+                Ok(Ok(_)) => "SQLite writer thread stopped".to_string(),
+                Ok(Err(e)) => format!("{e:#}"),
+                Err(_) => "SQLite writer thread panicked".to_string(),
+            };
+            let _ = self.failure.set(message);
+        }
+        match self.failure.get() {
+            Some(message) => anyhow!("SQLite writer failed: {message}"),
+            None => anyhow!("SQLite writer failed"),
+        }
+    }
+
+    /// Flushes all queued rows, creates indexes, and returns the number of rows inserted.
+    pub fn finish(mut self) -> Result<u64> {
+        self.join()
+    }
+
+    fn join(&mut self) -> Result<u64> {
+        drop(self.sender.take());
+        let handle = self
+            .handle
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        match handle {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| anyhow!("SQLite writer thread panicked"))?,
+            None => match self.failure.get() {
+                Some(message) => Err(anyhow!("SQLite writer failed: {message}")),
+                None => Ok(0),
+            },
+        }
+    }
+}
+
+impl Drop for SqliteWriter {
+    fn drop(&mut self) {
+        let _ = self.join();
+    }
+}
+
+fn write_loop(mut conn: Connection, receiver: Receiver<Message>) -> Result<u64> {
+    let mut batch: Vec<Row> = Vec::with_capacity(SQLITE_BATCH_SIZE);
+    let mut inserted = 0u64;
+
+    // Block for the first message, then drain whatever is already queued so
+    // each transaction covers up to SQLITE_BATCH_SIZE rows. A flush request
+    // ends the batch early and is answered once it is committed.
+    while let Ok(message) = receiver.recv() {
+        let mut flush = None;
+        let mut next = Some(message);
+        while let Some(message) = next.take() {
+            match message {
+                Message::Row(row) => batch.push(row),
+                Message::Flush(done) => {
+                    flush = Some(done);
+                    break;
+                }
+            }
+            if batch.len() < SQLITE_BATCH_SIZE {
+                next = receiver.try_recv().ok();
+            }
+        }
+        if !batch.is_empty() {
+            inserted += insert_batch(&mut conn, &mut batch)?;
+        }
+        if let Some(done) = flush {
+            let _ = done.send(());
+        }
+    }
+
+    for sql in INDEXES {
+        conn.execute(sql, [])
+            .with_context(|| format!("Failed to create index: {}", sql))?;
+    }
+    info!(rows = inserted, "SQLite output complete");
+    Ok(inserted)
+}
+
+fn insert_batch(conn: &mut Connection, batch: &mut Vec<Row>) -> Result<u64> {
+    let tx = conn
+        .transaction()
+        .context("Failed to begin SQLite transaction")?;
+    let mut inserted = 0u64;
+    for row in batch.drain(..) {
+        let mut stmt = tx
+            .prepare_cached(insert_sql(row.table))
+            .context("Failed to prepare SQLite insert")?;
+        let columns = stmt.parameter_count();
+        let values = row.values.iter().map(Some).chain(std::iter::repeat(None));
+        inserted += stmt
+            .execute(params_from_iter(values.take(columns)))
+            .with_context(|| format!("Failed to insert into {}", table_name(row.table)))?
+            as u64;
+    }
+    tx.commit().context("Failed to commit SQLite batch")?;
+    debug!(rows = inserted, "Committed SQLite batch");
+    Ok(inserted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |r| r.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn writes_rows_and_indexes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("graph.db");
+
+        let writer = SqliteWriter::open(&path, true).unwrap();
+        writer
+            .insert(CsvType::Nodes, &["1", "Rust", "Page"])
+            .unwrap();
+        writer
+            .insert(CsvType::Nodes, &["2", "Python", "Page"])
+            .unwrap();
+        writer
+            .insert(CsvType::Edges, &["1", "2", "LINKS_TO"])
+            .unwrap();
+        writer
+            .insert(CsvType::Edges, &["2", "1", "LINKS_TO", "History"])
+            .unwrap();
+        assert_eq!(writer.finish().unwrap(), 4);
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(count(&conn, "pages"), 2);
+        let title: String = conn
+            .query_row("SELECT title FROM pages WHERE id = 2", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(title, "Python");
+        let sections: Vec<Option<String>> = conn
+            .prepare("SELECT section FROM edges ORDER BY start_id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(sections, vec![None, Some("History".to_string())]);
+        let index_count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name LIKE 'idx_%'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(index_count, INDEXES.len() as i64);
+    }
+
+    #[test]
+    fn duplicate_node_rows_are_ignored() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("graph.db");
+
+        let writer = SqliteWriter::open(&path, true).unwrap();
+        writer
+            .insert(CsvType::Categories, &["Science", "Science", "Category"])
+            .unwrap();
+        writer
+            .insert(CsvType::Categories, &["Science", "Science", "Category"])
+            .unwrap();
+        writer
+            .insert(
+                CsvType::ArticleCategories,
+                &["1", "Science", "HAS_CATEGORY"],
+            )
+            .unwrap();
+        writer
+            .insert(
+                CsvType::ArticleCategories,
+                &["2", "Science", "HAS_CATEGORY"],
+            )
+            .unwrap();
+        assert_eq!(writer.finish().unwrap(), 3);
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(count(&conn, "categories"), 1);
+        assert_eq!(count(&conn, "article_categories"), 2);
+    }
+
+    #[test]
+    fn concurrent_inserts_span_multiple_batches() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("graph.db");
+        let rows = SQLITE_BATCH_SIZE as u32 + 100;
+
+        let writer = SqliteWriter::open(&path, true).unwrap();
+        std::thread::scope(|s| {
+            for t in 0..4u32 {
+                let writer = &writer;
+                s.spawn(move || {
+                    for id in (t..rows).step_by(4) {
+                        writer
+                            .insert(CsvType::Nodes, &[&id.to_string(), "T", "Page"])
+                            .unwrap();
+                    }
+                });
+            }
+        });
+        assert_eq!(writer.finish().unwrap(), u64::from(rows));
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(count(&conn, "pages"), i64::from(rows));
+    }
+
+    #[test]
+    fn reopening_appends_to_existing_tables() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("graph.db");
+
+        let writer = SqliteWriter::open(&path, true).unwrap();
+        writer
+            .insert(CsvType::Nodes, &["1", "Rust", "Page"])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let writer = SqliteWriter::open(&path, false).unwrap();
+        writer
+            .insert(CsvType::Nodes, &["2", "Python", "Page"])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(count(&conn, "pages"), 2);
+    }
+
+    #[test]
+    fn sync_commits_queued_rows() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("graph.db");
+
+        let writer = SqliteWriter::open(&path, true).unwrap();
+        writer
+            .insert(CsvType::Nodes, &["1", "Rust", "Page"])
+            .unwrap();
+        writer
+            .insert(CsvType::Nodes, &["2", "Python", "Page"])
+            .unwrap();
+        writer.sync().unwrap();
+
+        // Visible to another connection while the writer is still open.
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(count(&conn, "pages"), 2);
+        writer.insert(CsvType::Nodes, &["3", "Go", "Page"]).unwrap();
+        assert_eq!(writer.finish().unwrap(), 3);
+    }
+
+    #[test]
+    fn writer_failure_is_returned_to_callers() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("graph.db");
+
+        let writer = SqliteWriter::open(&path, true).unwrap();
+        // `pages.id` is an INTEGER PRIMARY KEY, so a text ID fails the batch.
+        writer
+            .insert(CsvType::Nodes, &["x", "Rust", "Page"])
+            .unwrap();
+        let err = writer.sync().unwrap_err();
+        assert!(
+            format!("{err:#}").contains("Failed to insert into pages"),
+            "{err:#}"
+        );
+        assert!(writer.insert(CsvType::Nodes, &["1", "Go", "Page"]).is_err());
+        assert!(writer.finish().is_err());
+    }
+}
@@ -2,9 +2,86 @@
 //!
 //! `ExtractionStats` uses `AtomicU64` for lock-free updates at high frequency
 //! (per-article). Supports checkpoint serialization for resumable processing.
+//! `LengthHistogram` buckets article wikitext lengths by powers of two for
+//! `--length-histogram`.
 
 use crate::checkpoint::CheckpointStats;
-use std::sync::atomic::{AtomicU64, Ordering};
+use crate::config::{
+    LENGTH_HISTOGRAM_FILENAME, LOW_RESOLUTION_MIN_LINKS, LOW_RESOLUTION_WARN_RATIO,
+};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Buckets in a [`LengthHistogram`]: empty, then `[2^(b-1), 2^b)` bytes for
+/// `b` in `1..=22`, then everything from 4 MiB up.
+pub const LENGTH_BUCKETS: usize = 24;
+
+/// Histogram bucket for a wikitext length of `len` bytes (see [`LENGTH_BUCKETS`]).
+#[must_use]
+pub fn length_bucket(len: usize) -> usize {
+    let bits = (usize::BITS - len.leading_zeros()) as usize;
+    bits.min(LENGTH_BUCKETS - 1)
+}
+
+/// Byte range `(min, max)` covered by `bucket`, both inclusive; the last
+/// bucket has no upper bound.
+#[must_use]
+pub fn length_bucket_range(bucket: usize) -> (u64, Option<u64>) {
+    match bucket {
+        0 => (0, Some(0)),
+        b if b >= LENGTH_BUCKETS - 1 => (1 << (LENGTH_BUCKETS - 2), None),
+        b => (1 << (b - 1), Some((1 << b) - 1)),
+    }
+}
+
+/// Article counts by wikitext length on a log scale, updated lock-free.
+#[derive(Debug, Default)]
+pub struct LengthHistogram {
+    buckets: [AtomicU64; LENGTH_BUCKETS],
+}
+
+impl LengthHistogram {
+    pub fn record(&self, len: usize) {
+        self.buckets[length_bucket(len)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current count of each bucket.
+    #[must_use]
+    pub fn counts(&self) -> [u64; LENGTH_BUCKETS] {
+        std::array::from_fn(|b| self.buckets[b].load(Ordering::Relaxed))
+    }
+
+    /// Writes `length_histogram.csv` (`min_bytes,max_bytes,articles`, one row
+    /// per bucket) to `output_dir` and returns its path.
+    pub fn write_csv(&self, output_dir: &str) -> Result<PathBuf> {
+        let path = Path::new(output_dir).join(LENGTH_HISTOGRAM_FILENAME);
+        let mut writer = csv::Writer::from_path(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        writer.write_record(["min_bytes", "max_bytes", "articles"])?;
+        for (bucket, count) in self.counts().into_iter().enumerate() {
+            let (min, max) = length_bucket_range(bucket);
+            writer.write_record([
+                min.to_string(),
+                max.map_or_else(String::new, |max| max.to_string()),
+                count.to_string(),
+            ])?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+impl Clone for LengthHistogram {
+    fn clone(&self) -> Self {
+        let counts = self.counts();
+        Self {
+            buckets: std::array::from_fn(|b| AtomicU64::new(counts[b])),
+        }
+    }
+}
 
 /// Thread-safe atomic counters for extraction metrics.
 #[derive(Default)]
@@ -13,12 +90,28 @@ pub struct ExtractionStats {
     pub edges_extracted: AtomicU64,
     pub blobs_written: AtomicU64,
     pub invalid_links: AtomicU64,
+    /// The subset of `invalid_links` shaped like article titles (`extract::is_red_link`).
+    pub red_links: AtomicU64,
     pub categories_found: AtomicU64,
     pub category_edges: AtomicU64,
     pub see_also_edges: AtomicU64,
     pub infoboxes_extracted: AtomicU64,
     pub images_found: AtomicU64,
     pub external_links_found: AtomicU64,
+    /// Bytes written to CSV files, blobs, and SQLite rows (see `--max-output-bytes`).
+    pub output_bytes: AtomicU64,
+    /// Articles whose blob was left out by `--blob-sample-rate`.
+    pub blobs_sampled_out: AtomicU64,
+    /// Articles skipped because of an `--exclude-category` category.
+    pub articles_excluded: AtomicU64,
+    /// Links from an article to itself, skipped unless `--keep-self-links`.
+    pub self_links: AtomicU64,
+    /// Set when `--max-runtime` stopped this run (not carried in checkpoints).
+    pub time_budget_exhausted: AtomicBool,
+    /// Set when Ctrl-C stopped this run (not carried in checkpoints).
+    pub interrupted: AtomicBool,
+    /// Wikitext lengths, recorded with `--length-histogram` (not carried in checkpoints).
+    pub article_lengths: LengthHistogram,
 }
 
 impl ExtractionStats {
@@ -42,6 +135,10 @@ impl ExtractionStats {
         self.invalid_links.fetch_add(count, Ordering::Relaxed);
     }
 
+    pub fn add_red_links(&self, count: u64) {
+        self.red_links.fetch_add(count, Ordering::Relaxed);
+    }
+
     pub fn inc_categories(&self) {
         self.categories_found.fetch_add(1, Ordering::Relaxed);
     }
@@ -71,6 +168,22 @@ impl ExtractionStats {
             .fetch_add(count, Ordering::Relaxed);
     }
 
+    pub fn add_output_bytes(&self, count: u64) {
+        self.output_bytes.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn inc_blobs_sampled_out(&self) {
+        self.blobs_sampled_out.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_articles_excluded(&self) {
+        self.articles_excluded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_self_links(&self, count: u64) {
+        self.self_links.fetch_add(count, Ordering::Relaxed);
+    }
+
     #[must_use]
     pub fn articles(&self) -> u64 {
         self.articles_processed.load(Ordering::Relaxed)
@@ -91,6 +204,12 @@ impl ExtractionStats {
         self.invalid_links.load(Ordering::Relaxed)
     }
 
+    /// Unresolved links to titles that look valid, i.e. missing articles.
+    #[must_use]
+    pub fn red_links(&self) -> u64 {
+        self.red_links.load(Ordering::Relaxed)
+    }
+
     #[must_use]
     pub fn categories(&self) -> u64 {
         self.categories_found.load(Ordering::Relaxed)
@@ -121,18 +240,81 @@ impl ExtractionStats {
         self.external_links_found.load(Ordering::Relaxed)
     }
 
+    #[must_use]
+    pub fn output_bytes(&self) -> u64 {
+        self.output_bytes.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn blobs_sampled_out(&self) -> u64 {
+        self.blobs_sampled_out.load(Ordering::Relaxed)
+    }
+
+    pub fn articles_excluded(&self) -> u64 {
+        self.articles_excluded.load(Ordering::Relaxed)
+    }
+
+    #[must_use]
+    pub fn self_links(&self) -> u64 {
+        self.self_links.load(Ordering::Relaxed)
+    }
+
+    pub fn set_time_budget_exhausted(&self) {
+        self.time_budget_exhausted.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the run stopped early because `--max-runtime` elapsed.
+    #[must_use]
+    pub fn time_budget_exhausted(&self) -> bool {
+        self.time_budget_exhausted.load(Ordering::Relaxed)
+    }
+
+    pub fn set_interrupted(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the run stopped early on SIGINT (`ExtractionConfig::handle_interrupts`).
+    #[must_use]
+    pub fn interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of wiki links that did not resolve: `invalid / (edges + invalid)`.
+    #[must_use]
+    pub fn invalid_link_ratio(&self) -> f64 {
+        invalid_link_ratio(self.edges(), self.invalid())
+    }
+
+    /// Returns the unresolved-link ratio if it exceeds `LOW_RESOLUTION_WARN_RATIO`
+    /// over at least `LOW_RESOLUTION_MIN_LINKS` links.
+    #[must_use]
+    pub fn low_resolution_rate(&self) -> Option<f64> {
+        let ratio = self.invalid_link_ratio();
+        (self.edges() + self.invalid() >= LOW_RESOLUTION_MIN_LINKS
+            && ratio > LOW_RESOLUTION_WARN_RATIO)
+            .then_some(ratio)
+    }
+
     pub fn from_checkpoint(cp: &CheckpointStats) -> Self {
         Self {
             articles_processed: AtomicU64::new(cp.articles_processed),
             edges_extracted: AtomicU64::new(cp.edges_extracted),
             blobs_written: AtomicU64::new(cp.blobs_written),
             invalid_links: AtomicU64::new(cp.invalid_links),
+            red_links: AtomicU64::new(cp.red_links),
             categories_found: AtomicU64::new(cp.categories_found),
             category_edges: AtomicU64::new(cp.category_edges),
             see_also_edges: AtomicU64::new(cp.see_also_edges),
             infoboxes_extracted: AtomicU64::new(cp.infoboxes_extracted),
             images_found: AtomicU64::new(cp.images_found),
             external_links_found: AtomicU64::new(cp.external_links_found),
+            output_bytes: AtomicU64::new(cp.output_bytes),
+            blobs_sampled_out: AtomicU64::new(cp.blobs_sampled_out),
+            articles_excluded: AtomicU64::new(cp.articles_excluded),
+            self_links: AtomicU64::new(cp.self_links),
+            time_budget_exhausted: AtomicBool::new(false),
+            interrupted: AtomicBool::new(false),
+            article_lengths: LengthHistogram::default(),
         }
     }
 
@@ -142,20 +324,101 @@ impl ExtractionStats {
             edges_extracted: self.edges(),
             blobs_written: self.blobs(),
             invalid_links: self.invalid(),
+            red_links: self.red_links(),
             categories_found: self.categories(),
             category_edges: self.category_edges(),
             see_also_edges: self.see_also_edges(),
             infoboxes_extracted: self.infoboxes(),
             images_found: self.images(),
             external_links_found: self.external_links(),
+            output_bytes: self.output_bytes(),
+            blobs_sampled_out: self.blobs_sampled_out(),
+            articles_excluded: self.articles_excluded(),
+            self_links: self.self_links(),
         }
     }
 }
 
+/// `invalid / (edges + invalid)`, or 0.0 when no links were seen.
+#[must_use]
+pub fn invalid_link_ratio(edges: u64, invalid: u64) -> f64 {
+    let total = edges + invalid;
+    if total == 0 {
+        0.0
+    } else {
+        invalid as f64 / total as f64
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn invalid_link_ratio_calculation() {
+        assert_eq!(invalid_link_ratio(0, 0), 0.0);
+        assert_eq!(invalid_link_ratio(10, 0), 0.0);
+        assert_eq!(invalid_link_ratio(0, 10), 1.0);
+        assert_eq!(invalid_link_ratio(60, 40), 0.4);
+        assert_eq!(invalid_link_ratio(1, 3), 0.75);
+    }
+
+    #[test]
+    fn length_buckets_double_in_width() {
+        assert_eq!(length_bucket(0), 0);
+        assert_eq!(length_bucket(1), 1);
+        assert_eq!(length_bucket(2), 2);
+        assert_eq!(length_bucket(3), 2);
+        assert_eq!(length_bucket(1023), 10);
+        assert_eq!(length_bucket(1024), 11);
+        assert_eq!(length_bucket(usize::MAX), LENGTH_BUCKETS - 1);
+        // Each length falls inside the range reported for its bucket.
+        for len in [
+            0usize,
+            1,
+            5,
+            64,
+            1000,
+            4096,
+            (1 << 22) - 1,
+            1 << 22,
+            50 << 20,
+        ] {
+            let (min, max) = length_bucket_range(length_bucket(len));
+            assert!(
+                min <= len as u64 && max.is_none_or(|max| len as u64 <= max),
+                "{len}"
+            );
+        }
+        assert_eq!(length_bucket_range(LENGTH_BUCKETS - 1), (1 << 22, None));
+
+        let lengths = LengthHistogram::default();
+        for len in [0, 10, 12, 15, 300] {
+            lengths.record(len);
+        }
+        let counts = lengths.counts();
+        assert_eq!((counts[0], counts[4], counts[9]), (1, 3, 1));
+        assert_eq!(lengths.clone().counts(), counts);
+    }
+
+    #[test]
+    fn low_resolution_rate_needs_threshold_and_sample() {
+        let stats = ExtractionStats::new();
+        stats.add_edges(10);
+        stats.add_invalid_links(90);
+        // Too few links to judge
+        assert_eq!(stats.low_resolution_rate(), None);
+
+        stats.add_edges(590);
+        stats.add_invalid_links(310);
+        // 400 / 1000 is at the threshold, not above it
+        assert_eq!(stats.low_resolution_rate(), None);
+
+        stats.add_invalid_links(100);
+        let ratio = stats.low_resolution_rate().unwrap();
+        assert!((ratio - 500.0 / 1100.0).abs() < 1e-9);
+    }
+
     #[test]
     fn from_checkpoint_initializes_correctly() {
         let cp = CheckpointStats {
@@ -163,12 +426,17 @@ mod tests {
             edges_extracted: 500,
             blobs_written: 90,
             invalid_links: 10,
+            red_links: 6,
             categories_found: 5,
             category_edges: 20,
             see_also_edges: 3,
             infoboxes_extracted: 8,
             images_found: 15,
             external_links_found: 12,
+            output_bytes: 4096,
+            blobs_sampled_out: 9,
+            articles_excluded: 2,
+            self_links: 4,
         };
 
         let stats = ExtractionStats::from_checkpoint(&cp);
@@ -176,12 +444,17 @@ mod tests {
         assert_eq!(stats.edges(), 500);
         assert_eq!(stats.blobs(), 90);
         assert_eq!(stats.invalid(), 10);
+        assert_eq!(stats.red_links(), 6);
         assert_eq!(stats.categories(), 5);
         assert_eq!(stats.category_edges(), 20);
         assert_eq!(stats.see_also_edges(), 3);
         assert_eq!(stats.infoboxes(), 8);
         assert_eq!(stats.images(), 15);
         assert_eq!(stats.external_links(), 12);
+        assert_eq!(stats.output_bytes(), 4096);
+        assert_eq!(stats.blobs_sampled_out(), 9);
+        assert_eq!(stats.articles_excluded(), 2);
+        assert_eq!(stats.self_links(), 4);
     }
 
     #[test]
@@ -192,24 +465,34 @@ mod tests {
         stats.add_edges(25);
         stats.inc_blobs();
         stats.add_invalid_links(5);
+        stats.add_red_links(3);
         stats.inc_categories();
         stats.add_category_edges(10);
         stats.add_see_also_edges(2);
         stats.add_infoboxes(3);
         stats.add_images(7);
         stats.add_external_links(4);
+        stats.add_output_bytes(512);
+        stats.inc_blobs_sampled_out();
+        stats.inc_articles_excluded();
+        stats.add_self_links(2);
 
         let cp = stats.to_checkpoint();
         assert_eq!(cp.articles_processed, 2);
         assert_eq!(cp.edges_extracted, 25);
         assert_eq!(cp.blobs_written, 1);
         assert_eq!(cp.invalid_links, 5);
+        assert_eq!(cp.red_links, 3);
         assert_eq!(cp.categories_found, 1);
         assert_eq!(cp.category_edges, 10);
         assert_eq!(cp.see_also_edges, 2);
         assert_eq!(cp.infoboxes_extracted, 3);
         assert_eq!(cp.images_found, 7);
         assert_eq!(cp.external_links_found, 4);
+        assert_eq!(cp.output_bytes, 512);
+        assert_eq!(cp.blobs_sampled_out, 1);
+        assert_eq!(cp.articles_excluded, 1);
+        assert_eq!(cp.self_links, 2);
     }
 
     #[test]
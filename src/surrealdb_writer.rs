@@ -2,31 +2,99 @@
 //!
 //! Replaces the former Neo4j import module. Reads merged CSV files (nodes + edges)
 //! and writes them to an embedded SurrealDB instance backed by RocksDB. Only articles
-//! and links_to edges are loaded; categories, images, and external links remain as
-//! CSV-only output. Uses concurrent batch inserts for throughput.
+//! and edges are loaded, the latter into a relation table named after the `LINKS_TO`
+//! type (`links_to` unless renamed via `schema::RelationshipNames`); categories,
+//! images, and external links remain as CSV-only output. Uses concurrent batch
+//! inserts for throughput.
+//!
+//! When the output directory has a `manifest.json`, the load counts the article
+//! and edge records it added to the database and compares them with the
+//! extraction's counters (`count_mismatches`), less repeated page IDs, edges
+//! `merge-csvs --dedup-edges` dropped and files `resume` skipped
+//! ([`GraphCounts::expected`]). A difference beyond `LOAD_COUNT_TOLERANCE` is
+//! logged and returned in `LoadStats`, or fails the load with `strict_counts`.
+//!
+//! A batch whose query fails with a transient error (a transaction conflict or
+//! timeout) is retried with exponential backoff according to the config's
+//! `RetryPolicy`; any other error fails the load at once.
+//!
+//! Each CSV file is recorded in `load_progress.json` ([`LoadProgress`]) with
+//! its size and modification time once all of its batches are in. With
+//! `resume`, files recorded for the same database are skipped unless they have
+//! changed since, so a load that died partway does not insert the
+//! finished files twice (`RELATE` would duplicate their edges). Progress is
+//! per file: the file that was loading when the load died is loaded again.
+//! A load without `resume` starts a new progress file.
 
+use crate::checkpoint::CheckpointStats;
 use crate::config;
-use crate::csv_util::{self, CsvLayout};
+use crate::csv_util::{self, CsvLayout, CsvType};
+use crate::manifest::Manifest;
+use crate::schema::RelationshipNames;
 use anyhow::{Context, Result};
 use futures::stream::{FuturesUnordered, StreamExt};
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::future::IntoFuture;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use surrealdb::Surreal;
 use surrealdb::engine::local::RocksDb;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Maximum number of concurrent batch insert tasks.
 const MAX_CONCURRENT_BATCHES: usize = 8;
 
+/// Lowercased fragments of error messages worth retrying: transaction
+/// conflicts and timeouts, which a later attempt can get past.
+const TRANSIENT_ERROR_MARKERS: &[&str] = &[
+    "conflict",
+    "can be retried",
+    "deadlock",
+    "timed out",
+    "timeout",
+    "resource busy",
+];
+
+/// How a batch insert that fails with a transient error is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 disables retrying.
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled before each later one.
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: config::LOAD_MAX_RETRIES,
+            initial_backoff: Duration::from_millis(config::LOAD_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
 /// Configuration for the SurrealDB load step.
 #[derive(Debug, Clone)]
 pub struct SurrealWriterConfig {
     pub output_dir: String,
     pub db_path: String,
+    /// SurrealDB namespace and database to write into.
+    pub namespace: String,
+    pub database: String,
     pub batch_size: usize,
     pub clean: bool,
+    /// Names the edge relation table (the `LINKS_TO` name, lowercased).
+    pub relationship_names: RelationshipNames,
+    /// Fail the load when the loaded counts do not match `manifest.json`
+    /// instead of only reporting the mismatch.
+    pub strict_counts: bool,
+    /// Retrying of batches that fail with a transient error.
+    pub retry: RetryPolicy,
+    /// Skip CSV files that `load_progress.json` records as already loaded
+    /// into the same database.
+    pub resume: bool,
 }
 
 impl Default for SurrealWriterConfig {
@@ -34,8 +102,14 @@ impl Default for SurrealWriterConfig {
         Self {
             output_dir: String::new(),
             db_path: config::DEFAULT_DB_PATH.to_string(),
+            namespace: config::SURREAL_NAMESPACE.to_string(),
+            database: config::SURREAL_DATABASE.to_string(),
             batch_size: config::SURREAL_BATCH_SIZE,
             clean: false,
+            relationship_names: RelationshipNames::default(),
+            strict_counts: false,
+            retry: RetryPolicy::default(),
+            resume: false,
         }
     }
 }
@@ -46,32 +120,399 @@ pub struct LoadStats {
     pub articles_loaded: u64,
     pub edges_loaded: u64,
     pub elapsed_secs: f64,
+    /// CSV files skipped because an earlier load finished them (`resume`).
+    pub files_skipped: u64,
+    /// Differences between the database and `manifest.json`, from
+    /// [`count_mismatches`]. Empty if they agree or there is no manifest.
+    pub count_mismatches: Vec<String>,
 }
 
-/// Loads extracted CSV data into an embedded SurrealDB instance.
+/// CSV files a load has finished, saved as `load_progress.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadProgress {
+    /// Resolved database path the files were loaded into.
+    pub db_path: String,
+    /// The finished CSV files.
+    pub files: Vec<LoadedFile>,
+}
+
+/// A CSV file a load finished, with the size and modification time it had
+/// then, so a file rewritten since (a new extraction) is not skipped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadedFile {
+    /// File name, relative to the output directory.
+    pub name: String,
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub modified_ns: u64,
+}
+
+impl LoadedFile {
+    /// Describes `name` in `output_dir` as it is on disk now.
+    pub fn stat(output_dir: &str, name: &str) -> Result<Self> {
+        let path = Path::new(output_dir).join(name);
+        let metadata =
+            std::fs::metadata(&path).with_context(|| format!("Failed to stat {path:?}"))?;
+        let modified_ns = metadata
+            .modified()
+            .with_context(|| format!("Failed to read the modification time of {path:?}"))?
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        Ok(Self {
+            name: name.to_string(),
+            size: metadata.len(),
+            modified_ns,
+        })
+    }
+}
+
+impl LoadProgress {
+    fn path(output_dir: &str) -> PathBuf {
+        Path::new(output_dir).join(config::LOAD_PROGRESS_FILENAME)
+    }
+
+    /// Reads the progress file in `output_dir`. `None` if there is none.
+    pub fn load(output_dir: &str) -> Result<Option<Self>> {
+        let path = Self::path(output_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file =
+            std::fs::File::open(&path).with_context(|| format!("Failed to open {path:?}"))?;
+        let progress = serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Failed to parse {path:?}"))?;
+        Ok(Some(progress))
+    }
+
+    /// Writes the progress file to `output_dir`, replacing it atomically.
+    pub fn save(&self, output_dir: &str) -> Result<()> {
+        let path = Self::path(output_dir);
+        let tmp_path = path.with_extension("json.tmp");
+        let json = serde_json::to_vec_pretty(self)?;
+        std::fs::write(&tmp_path, json).with_context(|| format!("Failed to write {tmp_path:?}"))?;
+        std::fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to rename {tmp_path:?} to {path:?}"))
+    }
+
+    /// Whether `file` was loaded as it is now. An entry for the same name
+    /// with another size or mtime is stale and does not count.
+    fn contains(&self, file: &LoadedFile) -> bool {
+        let Some(done) = self.files.iter().find(|done| done.name == file.name) else {
+            return false;
+        };
+        if done != file {
+            warn!(
+                file = file.name,
+                "File changed since it was loaded; loading it again"
+            );
+        }
+        done == file
+    }
+
+    /// Records `file` as loaded, replacing any stale entry for it.
+    fn record(&mut self, file: LoadedFile) {
+        self.files.retain(|done| done.name != file.name);
+        self.files.push(file);
+    }
+}
+
+/// Progress a load starts from: the saved progress when resuming into the
+/// same database, otherwise none. `clean` has removed the database, so it
+/// always starts fresh.
+fn starting_progress(config: &SurrealWriterConfig, db_path: &str) -> Result<LoadProgress> {
+    let fresh = LoadProgress {
+        db_path: db_path.to_string(),
+        files: Vec::new(),
+    };
+    if !config.resume || config.clean {
+        return Ok(fresh);
+    }
+    match LoadProgress::load(&config.output_dir)? {
+        Some(progress) if progress.db_path == db_path => {
+            info!(
+                files = progress.files.len(),
+                "Resuming load; skipping files already loaded"
+            );
+            Ok(progress)
+        }
+        Some(progress) => {
+            warn!(
+                recorded = progress.db_path,
+                db_path, "Load progress is for another database; loading every file"
+            );
+            Ok(fresh)
+        }
+        None => {
+            info!("No {}; loading every file", config::LOAD_PROGRESS_FILENAME);
+            Ok(fresh)
+        }
+    }
+}
+
+/// Article and edge counts on one side of a `load --plan` comparison.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraphCounts {
+    pub articles: u64,
+    pub edges: u64,
+}
+
+impl GraphCounts {
+    /// Articles and edges an extraction wrote, from its counters. Both edge
+    /// types go into the relation table.
+    #[must_use]
+    pub fn extracted(stats: &CheckpointStats) -> Self {
+        Self {
+            articles: stats.articles_processed,
+            edges: stats.edges_extracted + stats.see_also_edges,
+        }
+    }
+
+    /// Records a load of the extraction described by `manifest` should add:
+    /// its articles less the repeated page IDs, which share a record, and its
+    /// edges less the `dropped_edges` that `merge-csvs --dedup-edges` removed.
+    #[must_use]
+    pub fn expected(manifest: &Manifest, dropped_edges: u64) -> Self {
+        let extracted = Self::extracted(&manifest.stats);
+        Self {
+            articles: extracted.articles.saturating_sub(manifest.duplicate_ids),
+            edges: extracted.edges.saturating_sub(dropped_edges),
+        }
+    }
+
+    /// `self - other` per count, floored at 0.
+    #[must_use]
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self {
+            articles: self.articles.saturating_sub(other.articles),
+            edges: self.edges.saturating_sub(other.edges),
+        }
+    }
+}
+
+/// Compares the records a load added to the database (`found`) with the
+/// `expected` counts from the extraction, allowing a relative difference of
+/// `tolerance`. Returns one message per count that is off.
+#[must_use]
+pub fn count_mismatches(
+    expected: GraphCounts,
+    found: GraphCounts,
+    tolerance: f64,
+    relation_table: &str,
+) -> Vec<String> {
+    [
+        ("article records", expected.articles, found.articles),
+        (relation_table, expected.edges, found.edges),
+    ]
+    .into_iter()
+    .filter(|&(_, expected, found)| expected.abs_diff(found) as f64 > expected as f64 * tolerance)
+    .map(|(what, expected, found)| {
+        format!("load added {found} {what}, but the extraction manifest expects {expected}")
+    })
+    .collect()
+}
+
+/// What a load would do, from `plan_surreal_load`. Nothing is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadPlan {
+    /// Rows in the nodes and edges CSVs.
+    pub to_load: GraphCounts,
+    /// Records already in the database; `None` if it does not exist yet.
+    pub existing: Option<GraphCounts>,
+    /// Relation table the edges would go into.
+    pub relation_table: String,
+    /// Problems the load would run into; empty if it is safe to run.
+    pub conflicts: Vec<String>,
+}
+
+impl LoadPlan {
+    /// `true` if the load would run without failing or duplicating data.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Reconciles the CSV row counts with what the database already holds.
 ///
-/// Reads `nodes.csv` and `edges.csv` from `output_dir`, creates the schema,
-/// and batch-inserts records using concurrent tasks. The database is stored at
-/// `db_path` (relative to `output_dir` if not absolute).
-pub async fn run_surreal_load(config: SurrealWriterConfig) -> Result<LoadStats> {
-    let start = Instant::now();
+/// Articles are written with `CREATE`, which fails on an existing record ID,
+/// and edges with `RELATE`, which adds a second edge between the same pair, so
+/// any existing data conflicts unless `clean` removes the database first.
+#[must_use]
+pub fn plan_load(
+    to_load: GraphCounts,
+    existing: Option<GraphCounts>,
+    layout: &CsvLayout,
+    clean: bool,
+    relation_table: &str,
+) -> LoadPlan {
+    let mut conflicts = Vec::new();
+    if let CsvLayout::Sharded { count } = layout {
+        conflicts.push(format!(
+            "CSVs are sharded ({count} shards); load requires merged files (run merge-csvs)"
+        ));
+    }
+    if to_load.articles == 0 {
+        conflicts.push("nodes CSV has no rows; there is nothing to load".to_string());
+    }
+    if let Some(existing) = existing
+        && !clean
+    {
+        if existing.articles > 0 {
+            conflicts.push(format!(
+                "database already has {} article records; CREATE would fail on the IDs it \
+                 shares with the {} in nodes.csv (use --clean to replace it)",
+                existing.articles, to_load.articles
+            ));
+        }
+        if existing.edges > 0 {
+            conflicts.push(format!(
+                "database already has {} {relation_table} edges; RELATE would duplicate them",
+                existing.edges
+            ));
+        }
+    }
+    LoadPlan {
+        to_load,
+        existing,
+        relation_table: relation_table.to_string(),
+        conflicts,
+    }
+}
 
-    let db_path = if Path::new(&config.db_path).is_absolute() {
-        config.db_path.clone()
+/// Dry run of `run_surreal_load`: counts the CSV rows and, if the database
+/// exists, its article and edge records, then reports conflicts via `plan_load`.
+/// Only reads from an existing database and never creates one.
+pub async fn plan_surreal_load(config: &SurrealWriterConfig) -> Result<LoadPlan> {
+    let layout = csv_util::detect_csv_layout(&config.output_dir)?;
+    let mut to_load = GraphCounts::default();
+    csv_util::for_each_record(&config.output_dir, &layout, CsvType::Nodes, |_| {
+        to_load.articles += 1;
+        Ok(())
+    })?;
+    csv_util::for_each_record(&config.output_dir, &layout, CsvType::Edges, |_| {
+        to_load.edges += 1;
+        Ok(())
+    })?;
+
+    let relation_table = config.relationship_names.relation_table();
+    let db_path = resolve_db_path(config);
+    let existing = if Path::new(&db_path).exists() {
+        info!("Inspecting existing SurrealDB at {}", db_path);
+        let db = Surreal::new::<RocksDb>(&db_path)
+            .await
+            .with_context(|| format!("Failed to open SurrealDB at {}", db_path))?;
+        db.use_ns(&config.namespace)
+            .use_db(&config.database)
+            .await
+            .context("Failed to select namespace/database")?;
+        Some(count_records(&db, &relation_table).await?)
     } else {
-        Path::new(&config.output_dir)
-            .join(&config.db_path)
-            .to_string_lossy()
-            .to_string()
+        None
     };
 
-    if config.clean && Path::new(&db_path).exists() {
-        info!("Cleaning existing database: {}", db_path);
-        std::fs::remove_dir_all(&db_path)
-            .with_context(|| format!("Failed to remove existing DB: {}", db_path))?;
+    Ok(plan_load(
+        to_load,
+        existing,
+        &layout,
+        config.clean,
+        &relation_table,
+    ))
+}
+
+/// Data rows in the CSV file at `path`.
+fn count_rows(path: &Path) -> Result<u64> {
+    let mut reader =
+        csv::Reader::from_path(path).with_context(|| format!("Failed to open {:?}", path))?;
+    let mut rows = 0;
+    for result in reader.byte_records() {
+        result.with_context(|| format!("Failed to read {:?}", path))?;
+        rows += 1;
     }
+    Ok(rows)
+}
 
-    // Detect CSV layout and validate nodes + edges exist
+/// Article and `relation_table` edge records in the database.
+async fn count_records(
+    db: &Surreal<surrealdb::engine::local::Db>,
+    relation_table: &str,
+) -> Result<GraphCounts> {
+    let mut response = db
+        .query(format!(
+            "SELECT count() AS count FROM article GROUP ALL;
+             SELECT count() AS count FROM {relation_table} GROUP ALL;"
+        ))
+        .await
+        .context("Failed to count records")?
+        .check()
+        .context("Counting records returned errors")?;
+    let articles: Option<i64> = response.take((0, "count"))?;
+    let edges: Option<i64> = response.take((1, "count"))?;
+    Ok(GraphCounts {
+        articles: articles.unwrap_or(0).max(0) as u64,
+        edges: edges.unwrap_or(0).max(0) as u64,
+    })
+}
+
+/// One CSV file of a [`LoadScript`] and the statement each of its rows becomes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadStep {
+    pub file: PathBuf,
+    /// Statement template, with `<column>` placeholders for the row's values.
+    pub statement: String,
+}
+
+/// What `run_surreal_load` would execute, from [`dry_run_load`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadScript {
+    /// Database directory, resolved against `output_dir`.
+    pub db_path: String,
+    /// `true` if `clean` would remove an existing database first.
+    pub removes_db: bool,
+    /// Schema statements run before any rows are inserted.
+    pub schema: Vec<String>,
+    /// CSV files in load order: every nodes part, then every edges part.
+    pub steps: Vec<LoadStep>,
+}
+
+/// Runs the load's checks and file discovery ([`preflight_load`]) and returns
+/// the statements the load would execute, without opening, creating or
+/// removing the database.
+pub fn dry_run_load(config: &SurrealWriterConfig) -> Result<LoadScript> {
+    preflight_load(config)?;
+    let db_path = resolve_db_path(config);
+    let part_steps = |base_name: &str, statement: String| {
+        csv_util::single_file_parts(&config.output_dir, base_name)
+            .into_iter()
+            .map(move |part| LoadStep {
+                file: Path::new(&config.output_dir).join(part),
+                statement: statement.clone(),
+            })
+    };
+    let steps = part_steps("nodes", article_statement("<id:ID>", "<title>"))
+        .chain(part_steps(
+            "edges",
+            config
+                .relationship_names
+                .relate_statement("<:START_ID>", "<:END_ID>"),
+        ))
+        .map(|mut step| {
+            step.statement.truncate(step.statement.trim_end().len());
+            step
+        })
+        .collect();
+    Ok(LoadScript {
+        removes_db: config.clean && Path::new(&db_path).exists(),
+        db_path,
+        schema: schema_statements(&config.relationship_names),
+        steps,
+    })
+}
+
+/// Checks everything a load needs before anything is written or removed:
+/// merged (non-sharded) CSVs with `nodes.csv` and `edges.csv` present, at
+/// least one article row in `nodes.csv`, and a database path that is a
+/// directory if it exists. Returns the CSV layout.
+pub fn preflight_load(config: &SurrealWriterConfig) -> Result<CsvLayout> {
     let layout = csv_util::detect_csv_layout(&config.output_dir)?;
     if !matches!(layout, CsvLayout::Single) {
         anyhow::bail!(
@@ -80,29 +521,166 @@ pub async fn run_surreal_load(config: SurrealWriterConfig) -> Result<LoadStats>
             config.output_dir
         );
     }
+    crate::manifest::check_output(&config.output_dir, 1)?;
+    for csv_type in [CsvType::Nodes, CsvType::Edges] {
+        let path =
+            Path::new(&config.output_dir).join(csv_util::part_filename(csv_type.base_name(), 0));
+        if !path.is_file() {
+            anyhow::bail!(
+                "Missing CSV file: {path:?}\n\
+                 Run 'dedalus extract' first."
+            );
+        }
+    }
+    let nodes_path =
+        Path::new(&config.output_dir).join(csv_util::part_filename(CsvType::Nodes.base_name(), 0));
+    let has_articles = csv::Reader::from_path(&nodes_path)
+        .with_context(|| format!("Failed to open {nodes_path:?}"))?
+        .records()
+        .next()
+        .is_some();
+    if !has_articles {
+        anyhow::bail!(
+            "{nodes_path:?} has no article rows, so there is nothing to load.\n\
+             The dump may contain only redirects or pages outside --namespaces."
+        );
+    }
+    let db_path = resolve_db_path(config);
+    if Path::new(&db_path).exists() && !Path::new(&db_path).is_dir() {
+        anyhow::bail!(
+            "Database path {db_path} is a file, not a SurrealDB directory; \
+             choose another --db-path"
+        );
+    }
+    Ok(layout)
+}
+
+/// `db_path`, resolved against `output_dir` unless it is absolute.
+fn resolve_db_path(config: &SurrealWriterConfig) -> String {
+    if Path::new(&config.db_path).is_absolute() {
+        config.db_path.clone()
+    } else {
+        Path::new(&config.output_dir)
+            .join(&config.db_path)
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Loads extracted CSV data into an embedded SurrealDB instance.
+///
+/// Reads `nodes.csv` and `edges.csv` (plus any `_partNNN` rollover parts) from
+/// `output_dir`, creates the schema, and batch-inserts records using concurrent
+/// tasks. [`preflight_load`] runs first, so `clean` only removes the database
+/// once the load is known to be able to start. The database is stored at
+/// `db_path` (relative to `output_dir` if not absolute). Afterwards the records
+/// the load added are checked against `manifest.json`, if present; with
+/// `strict_counts` a mismatch is an error. With `resume`, files that
+/// `load_progress.json` records as loaded are skipped.
+pub async fn run_surreal_load(config: SurrealWriterConfig) -> Result<LoadStats> {
+    let start = Instant::now();
+
+    let db_path = resolve_db_path(&config);
+
+    // Validate before --clean so a load that cannot run leaves the database intact.
+    let layout = preflight_load(&config)?;
     info!("Detected {} CSV layout", layout);
 
+    if config.clean && Path::new(&db_path).exists() {
+        info!("Cleaning existing database: {}", db_path);
+        std::fs::remove_dir_all(&db_path)
+            .with_context(|| format!("Failed to remove existing DB: {}", db_path))?;
+    }
+
     // Connect to embedded SurrealDB with RocksDB backend
     info!("Opening SurrealDB at {}", db_path);
     let db = Surreal::new::<RocksDb>(&db_path)
         .await
         .with_context(|| format!("Failed to open SurrealDB at {}", db_path))?;
 
-    db.use_ns(config::SURREAL_NAMESPACE)
-        .use_db(config::SURREAL_DATABASE)
+    db.use_ns(&config.namespace)
+        .use_db(&config.database)
         .await
         .context("Failed to select namespace/database")?;
 
     // Create schema
-    create_schema(&db).await?;
+    create_schema(&db, &config.relationship_names).await?;
+
+    // Records already in the database, kept without --clean, are not this load's.
+    let manifest = crate::manifest::load(&config.output_dir)?;
+    let relation_table = config.relationship_names.relation_table();
+    let before = match manifest {
+        Some(_) => count_records(&db, &relation_table).await?,
+        None => GraphCounts::default(),
+    };
 
-    // Load articles from nodes.csv
-    let nodes_path = Path::new(&config.output_dir).join("nodes.csv");
-    let articles_loaded = load_articles(&db, &nodes_path, config.batch_size).await?;
+    let mut progress = starting_progress(&config, &db_path)?;
+    progress.save(&config.output_dir)?;
+    let mut files_skipped = 0;
+    // Rows of skipped files, which an earlier load put in `before`.
+    let mut skipped_rows = GraphCounts::default();
 
-    // Load edges from edges.csv
-    let edges_path = Path::new(&config.output_dir).join("edges.csv");
-    let edges_loaded = load_edges(&db, &edges_path, config.batch_size).await?;
+    // Load articles from nodes.csv (and any --max-shard-bytes parts)
+    let mut articles_loaded = 0;
+    for part in csv_util::single_file_parts(&config.output_dir, "nodes") {
+        let file = LoadedFile::stat(&config.output_dir, &part)?;
+        let nodes_path = Path::new(&config.output_dir).join(&part);
+        if progress.contains(&file) {
+            files_skipped += 1;
+            skipped_rows.articles += count_rows(&nodes_path)?;
+            continue;
+        }
+        articles_loaded += load_articles(&db, &nodes_path, config.batch_size, config.retry).await?;
+        progress.record(file);
+        progress.save(&config.output_dir)?;
+    }
+
+    // Load edges from edges.csv (and any --max-shard-bytes parts)
+    let mut edges_loaded = 0;
+    for part in csv_util::single_file_parts(&config.output_dir, "edges") {
+        let file = LoadedFile::stat(&config.output_dir, &part)?;
+        let edges_path = Path::new(&config.output_dir).join(&part);
+        if progress.contains(&file) {
+            files_skipped += 1;
+            skipped_rows.edges += count_rows(&edges_path)?;
+            continue;
+        }
+        edges_loaded += load_edges(
+            &db,
+            &edges_path,
+            config.batch_size,
+            &config.relationship_names,
+            config.retry,
+        )
+        .await?;
+        progress.record(file);
+        progress.save(&config.output_dir)?;
+    }
+
+    let count_mismatches = match manifest {
+        Some(manifest) => {
+            let dropped_edges = crate::merge::DroppedRows::load(&config.output_dir)?.get("edges");
+            let expected = GraphCounts::expected(&manifest, dropped_edges);
+            let found = count_records(&db, &relation_table).await?;
+            count_mismatches(
+                expected.saturating_sub(skipped_rows),
+                found.saturating_sub(before),
+                config::LOAD_COUNT_TOLERANCE,
+                &relation_table,
+            )
+        }
+        None => Vec::new(),
+    };
+    for mismatch in &count_mismatches {
+        warn!("{mismatch}");
+    }
+    if config.strict_counts && !count_mismatches.is_empty() {
+        anyhow::bail!(
+            "Loaded database does not match {}: {}",
+            config::MANIFEST_FILENAME,
+            count_mismatches.join("; ")
+        );
+    }
 
     let elapsed = start.elapsed();
     info!(
@@ -116,42 +694,120 @@ pub async fn run_surreal_load(config: SurrealWriterConfig) -> Result<LoadStats>
         articles_loaded,
         edges_loaded,
         elapsed_secs: elapsed.as_secs_f64(),
+        files_skipped,
+        count_mismatches,
     })
 }
 
-async fn create_schema(db: &Surreal<surrealdb::engine::local::Db>) -> Result<()> {
+/// Statements defining the article table and the edge relation table.
+fn schema_statements(names: &RelationshipNames) -> Vec<String> {
+    [
+        "DEFINE TABLE article SCHEMAFULL;",
+        "DEFINE FIELD title ON article TYPE string;",
+        "DEFINE FIELD pagerank ON article TYPE option<float> DEFAULT NONE;",
+        "DEFINE FIELD community ON article TYPE option<int> DEFAULT NONE;",
+        "DEFINE FIELD degree ON article TYPE option<int> DEFAULT NONE;",
+        "DEFINE INDEX idx_article_title ON article FIELDS title;",
+    ]
+    .into_iter()
+    .map(String::from)
+    .chain([names.define_relation_statement()])
+    .collect()
+}
+
+async fn create_schema(
+    db: &Surreal<surrealdb::engine::local::Db>,
+    names: &RelationshipNames,
+) -> Result<()> {
     info!("Creating SurrealDB schema");
 
-    db.query(
-        "
-        DEFINE TABLE article SCHEMAFULL;
-        DEFINE FIELD title ON article TYPE string;
-        DEFINE FIELD pagerank ON article TYPE option<float> DEFAULT NONE;
-        DEFINE FIELD community ON article TYPE option<int> DEFAULT NONE;
-        DEFINE FIELD degree ON article TYPE option<int> DEFAULT NONE;
-        DEFINE INDEX idx_article_title ON article FIELDS title;
-
-        DEFINE TABLE links_to SCHEMAFULL TYPE RELATION FROM article TO article;
-        ",
-    )
-    .await
-    .context("Failed to create schema")?
-    .check()
-    .context("Schema creation returned errors")?;
+    db.query(schema_statements(names).join("\n"))
+        .await
+        .context("Failed to create schema")?
+        .check()
+        .context("Schema creation returned errors")?;
 
     info!("Schema created");
     Ok(())
 }
 
+/// Whether an error message describes a failure a later attempt can get past.
+fn is_transient(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    TRANSIENT_ERROR_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Runs `attempt` until it succeeds, retrying transient failures up to
+/// `policy.max_retries` times with exponential backoff. `what` names the
+/// batch in logs and in the error returned once it fails for good.
+async fn with_retries<T, E, F, Fut>(policy: RetryPolicy, what: &str, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: IntoFuture<Output = std::result::Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let mut backoff = policy.initial_backoff;
+    let mut retries = 0;
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(err) => {
+                let message = err.to_string();
+                if retries >= policy.max_retries || !is_transient(&message) {
+                    anyhow::bail!(
+                        "Failed to insert {what} (attempt {}): {message}",
+                        retries + 1
+                    );
+                }
+                retries += 1;
+                warn!(
+                    retries,
+                    backoff_ms = backoff.as_millis() as u64,
+                    "Retrying {what} after transient error: {message}"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
+}
+
+/// Wraps a batch of statements in one transaction, so a failed attempt is
+/// rolled back whole and a retry never meets its half-applied rows.
+fn transaction(statements: &str) -> String {
+    format!("BEGIN TRANSACTION;\n{statements}COMMIT TRANSACTION;\n")
+}
+
+/// Runs one batch and checks every statement's result: `query` itself
+/// succeeds even when statements inside it fail.
+async fn execute_batch(
+    db: &Surreal<surrealdb::engine::local::Db>,
+    query: &str,
+) -> std::result::Result<(), surrealdb::Error> {
+    db.query(query).await?.check()?;
+    Ok(())
+}
+
 /// Escapes a string for use in a SurQL single-quoted string literal.
 fn escape_surql(s: &str) -> String {
     s.replace('\\', "\\\\").replace('\'', "\\'")
 }
 
+/// `CREATE` statement loading one nodes row into the article table.
+fn article_statement(id: &str, title: &str) -> String {
+    format!(
+        "CREATE article:{id} SET title = '{}';\n",
+        escape_surql(title)
+    )
+}
+
 async fn load_articles(
     db: &Surreal<surrealdb::engine::local::Db>,
     csv_path: &Path,
     batch_size: usize,
+    retry: RetryPolicy,
 ) -> Result<u64> {
     info!(
         concurrency = MAX_CONCURRENT_BATCHES,
@@ -171,10 +827,7 @@ async fn load_articles(
         let id = record.get(0).unwrap_or("");
         let title = record.get(1).unwrap_or("");
 
-        let escaped_title = escape_surql(title);
-        batch.push_str(&format!(
-            "CREATE article:{id} SET title = '{escaped_title}';\n"
-        ));
+        batch.push_str(&article_statement(id, title));
         batch_count += 1;
 
         if batch_count >= batch_size {
@@ -189,15 +842,16 @@ async fn load_articles(
                 }
             }
 
-            let query = std::mem::replace(&mut batch, String::with_capacity(batch_size * 80));
+            let query = transaction(&std::mem::replace(
+                &mut batch,
+                String::with_capacity(batch_size * 80),
+            ));
             let db = db.clone();
             let counter = Arc::clone(&counter);
             let n = batch_count as u64;
 
             in_flight.push(tokio::spawn(async move {
-                db.query(&query)
-                    .await
-                    .context("Failed to insert article batch")?;
+                with_retries(retry, "article batch", || execute_batch(&db, &query)).await?;
                 let total = counter.fetch_add(n, Ordering::Relaxed) + n;
                 if total.is_multiple_of(100_000) {
                     info!(count = total, "Articles loaded");
@@ -219,14 +873,12 @@ async fn load_articles(
                 Err(e) => anyhow::bail!("Task join error: {e}"),
             }
         }
-        let query = batch;
+        let query = transaction(&batch);
         let db = db.clone();
         let counter = Arc::clone(&counter);
         let n = batch_count as u64;
         in_flight.push(tokio::spawn(async move {
-            db.query(&query)
-                .await
-                .context("Failed to insert final article batch")?;
+            with_retries(retry, "final article batch", || execute_batch(&db, &query)).await?;
             counter.fetch_add(n, Ordering::Relaxed);
             Ok::<(), anyhow::Error>(())
         }));
@@ -249,6 +901,8 @@ async fn load_edges(
     db: &Surreal<surrealdb::engine::local::Db>,
     csv_path: &Path,
     batch_size: usize,
+    names: &RelationshipNames,
+    retry: RetryPolicy,
 ) -> Result<u64> {
     info!(
         concurrency = MAX_CONCURRENT_BATCHES,
@@ -268,9 +922,7 @@ async fn load_edges(
         let start_id = record.get(0).unwrap_or("");
         let end_id = record.get(1).unwrap_or("");
 
-        batch.push_str(&format!(
-            "RELATE article:{start_id}->links_to->article:{end_id};\n"
-        ));
+        batch.push_str(&names.relate_statement(start_id, end_id));
         batch_count += 1;
 
         if batch_count >= batch_size {
@@ -284,15 +936,16 @@ async fn load_edges(
                 }
             }
 
-            let query = std::mem::replace(&mut batch, String::with_capacity(batch_size * 60));
+            let query = transaction(&std::mem::replace(
+                &mut batch,
+                String::with_capacity(batch_size * 60),
+            ));
             let db = db.clone();
             let counter = Arc::clone(&counter);
             let n = batch_count as u64;
 
             in_flight.push(tokio::spawn(async move {
-                db.query(&query)
-                    .await
-                    .context("Failed to insert edge batch")?;
+                with_retries(retry, "edge batch", || execute_batch(&db, &query)).await?;
                 let total = counter.fetch_add(n, Ordering::Relaxed) + n;
                 if total.is_multiple_of(100_000) {
                     info!(count = total, "Edges loaded");
@@ -314,14 +967,12 @@ async fn load_edges(
                 Err(e) => anyhow::bail!("Task join error: {e}"),
             }
         }
-        let query = batch;
+        let query = transaction(&batch);
         let db = db.clone();
         let counter = Arc::clone(&counter);
         let n = batch_count as u64;
         in_flight.push(tokio::spawn(async move {
-            db.query(&query)
-                .await
-                .context("Failed to insert final edge batch")?;
+            with_retries(retry, "final edge batch", || execute_batch(&db, &query)).await?;
             counter.fetch_add(n, Ordering::Relaxed);
             Ok::<(), anyhow::Error>(())
         }));
@@ -380,6 +1031,7 @@ mod tests {
             db_path: "test.db".to_string(),
             batch_size: 100,
             clean: true,
+            ..Default::default()
         };
 
         let stats = run_surreal_load(config).await.unwrap();
@@ -387,6 +1039,80 @@ mod tests {
         assert_eq!(stats.edges_loaded, 1);
     }
 
+    #[tokio::test]
+    async fn resume_skips_files_recorded_as_loaded() {
+        let dir = TempDir::new().unwrap();
+        write_test_csvs(dir.path());
+        let output_dir = dir.path().to_str().unwrap().to_string();
+        let stat = |name: &str| LoadedFile::stat(&output_dir, name).unwrap();
+        // Each scenario loads into its own database, so no record is inserted twice.
+        let config_for = |db: &str, resume: bool| SurrealWriterConfig {
+            output_dir: output_dir.clone(),
+            db_path: db.to_string(),
+            resume,
+            ..Default::default()
+        };
+
+        // A load that died after finishing nodes.csv.
+        let config = config_for("resumed.db", true);
+        let db_path = resolve_db_path(&config);
+        LoadProgress {
+            db_path: db_path.clone(),
+            files: vec![stat("nodes.csv")],
+        }
+        .save(&output_dir)
+        .unwrap();
+        let stats = run_surreal_load(config).await.unwrap();
+        assert_eq!(stats.files_skipped, 1);
+        assert_eq!((stats.articles_loaded, stats.edges_loaded), (0, 1));
+        let progress = LoadProgress::load(&output_dir).unwrap().unwrap();
+        assert_eq!(progress.files, [stat("nodes.csv"), stat("edges.csv")]);
+
+        // Progress recorded for another database is ignored.
+        LoadProgress {
+            db_path: "elsewhere.db".to_string(),
+            files: vec![stat("nodes.csv"), stat("edges.csv")],
+        }
+        .save(&output_dir)
+        .unwrap();
+        let stats = run_surreal_load(config_for("other.db", true))
+            .await
+            .unwrap();
+        assert_eq!(stats.files_skipped, 0);
+        assert_eq!((stats.articles_loaded, stats.edges_loaded), (2, 1));
+
+        // An entry whose file has changed since is stale and the file reloads.
+        let config = config_for("stale.db", true);
+        let stale = LoadedFile {
+            size: stat("nodes.csv").size + 1,
+            ..stat("nodes.csv")
+        };
+        LoadProgress {
+            db_path: resolve_db_path(&config),
+            files: vec![stale, stat("edges.csv")],
+        }
+        .save(&output_dir)
+        .unwrap();
+        let stats = run_surreal_load(config).await.unwrap();
+        assert_eq!(stats.files_skipped, 1);
+        assert_eq!((stats.articles_loaded, stats.edges_loaded), (2, 0));
+        let progress = LoadProgress::load(&output_dir).unwrap().unwrap();
+        assert_eq!(progress.files, [stat("edges.csv"), stat("nodes.csv")]);
+
+        // Without resume every file loads and the progress starts over.
+        let config = config_for("fresh.db", false);
+        let db_path = resolve_db_path(&config);
+        let stats = run_surreal_load(config).await.unwrap();
+        assert_eq!((stats.articles_loaded, stats.edges_loaded), (2, 1));
+        assert_eq!(
+            LoadProgress::load(&output_dir).unwrap().unwrap(),
+            LoadProgress {
+                db_path,
+                files: vec![stat("nodes.csv"), stat("edges.csv")],
+            }
+        );
+    }
+
     #[tokio::test]
     async fn test_surreal_load_clean() {
         let dir = TempDir::new().unwrap();
@@ -402,12 +1128,237 @@ mod tests {
             db_path: "test.db".to_string(),
             batch_size: 100,
             clean: true,
+            ..Default::default()
         };
 
         let stats = run_surreal_load(config).await.unwrap();
         assert_eq!(stats.articles_loaded, 2);
     }
 
+    #[tokio::test]
+    async fn failed_preflight_keeps_database_when_cleaning() {
+        let dir = TempDir::new().unwrap();
+        write_test_csvs(dir.path());
+        let db_path = dir.path().join("test.db");
+        std::fs::create_dir_all(&db_path).unwrap();
+        std::fs::write(db_path.join("data"), "existing").unwrap();
+        let config = SurrealWriterConfig {
+            output_dir: dir.path().to_str().unwrap().to_string(),
+            db_path: "test.db".to_string(),
+            clean: true,
+            ..Default::default()
+        };
+        assert!(preflight_load(&config).is_ok());
+
+        // Sharded CSVs fail before --clean touches the database.
+        std::fs::rename(
+            dir.path().join("nodes.csv"),
+            dir.path().join("nodes_000.csv"),
+        )
+        .unwrap();
+        let err = run_surreal_load(config.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("merge-csvs"), "{err}");
+        assert!(db_path.join("data").exists());
+
+        // So does a missing edges file.
+        std::fs::rename(
+            dir.path().join("nodes_000.csv"),
+            dir.path().join("nodes.csv"),
+        )
+        .unwrap();
+        std::fs::remove_file(dir.path().join("edges.csv")).unwrap();
+        let err = run_surreal_load(config.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("edges.csv"), "{err}");
+        assert!(db_path.join("data").exists());
+
+        // And a nodes.csv with only its header.
+        write_test_csvs(dir.path());
+        std::fs::write(dir.path().join("nodes.csv"), "id:ID,title,:LABEL\n").unwrap();
+        let err = run_surreal_load(config.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("no article rows"), "{err}");
+        assert!(db_path.join("data").exists());
+
+        // A database path that is a file is never a valid target.
+        let file_config = SurrealWriterConfig {
+            db_path: "nodes.csv".to_string(),
+            ..config
+        };
+        assert!(preflight_load(&file_config).is_err());
+    }
+
+    #[test]
+    fn plan_flags_existing_records_unless_clean() {
+        let to_load = GraphCounts {
+            articles: 2,
+            edges: 1,
+        };
+        let empty_db = Some(GraphCounts::default());
+        let populated_db = Some(GraphCounts {
+            articles: 5_000_000,
+            edges: 90_000_000,
+        });
+
+        assert!(plan_load(to_load, None, &CsvLayout::Single, false, "links_to").is_clean());
+        assert!(plan_load(to_load, empty_db, &CsvLayout::Single, false, "links_to").is_clean());
+        assert!(plan_load(to_load, populated_db, &CsvLayout::Single, true, "links_to").is_clean());
+
+        let plan = plan_load(to_load, populated_db, &CsvLayout::Single, false, "cites");
+        assert_eq!(plan.conflicts.len(), 2);
+        assert!(plan.conflicts[0].contains("5000000 article records"));
+        assert!(plan.conflicts[1].contains("90000000 cites edges"));
+
+        let sharded = plan_load(
+            to_load,
+            None,
+            &CsvLayout::Sharded { count: 4 },
+            false,
+            "links_to",
+        );
+        assert!(sharded.conflicts[0].contains("sharded (4 shards)"));
+        let empty_csvs = plan_load(GraphCounts::default(), None, &CsvLayout::Single, false, "x");
+        assert!(!empty_csvs.is_clean());
+    }
+
+    #[test]
+    fn loaded_counts_are_checked_against_the_manifest() {
+        let stats = CheckpointStats {
+            articles_processed: 10_000,
+            edges_extracted: 90_000,
+            see_also_edges: 10_000,
+            ..Default::default()
+        };
+        let expected = GraphCounts::extracted(&stats);
+        assert_eq!(expected.edges, 100_000);
+        assert!(count_mismatches(expected, expected, 0.001, "links_to").is_empty());
+
+        // Within tolerance: 10 of 10,000 articles and 100 of 100,000 edges.
+        let close = GraphCounts {
+            articles: 9_990,
+            edges: 100_100,
+        };
+        assert!(count_mismatches(expected, close, 0.001, "links_to").is_empty());
+
+        // A shard of edges that never loaded.
+        let missing_shard = GraphCounts {
+            articles: 10_000,
+            edges: 75_000,
+        };
+        let mismatches = count_mismatches(expected, missing_shard, 0.001, "links_to");
+        assert_eq!(
+            mismatches,
+            vec![
+                "load added 75000 links_to, but the extraction manifest expects 100000".to_string()
+            ]
+        );
+        assert_eq!(
+            count_mismatches(expected, GraphCounts::default(), 0.0, "links_to").len(),
+            2
+        );
+    }
+
+    #[test]
+    fn expected_counts_allow_for_duplicate_ids_dropped_edges_and_skipped_files() {
+        let mut manifest = Manifest::for_test(
+            1,
+            CheckpointStats {
+                articles_processed: 1_000,
+                edges_extracted: 4_000,
+                see_also_edges: 1_000,
+                ..Default::default()
+            },
+        );
+        manifest.duplicate_ids = 10;
+        let expected = GraphCounts::expected(&manifest, 500);
+        assert_eq!(
+            expected,
+            GraphCounts {
+                articles: 990,
+                edges: 4_500
+            }
+        );
+
+        // A resumed load adds only the files it did not skip; an existing
+        // database's records are left out by counting before the load.
+        let skipped = GraphCounts {
+            articles: 600,
+            edges: 2_000,
+        };
+        let before = GraphCounts {
+            articles: 700,
+            edges: 2_100,
+        };
+        let after = GraphCounts {
+            articles: 1_090,
+            edges: 4_600,
+        };
+        assert!(
+            count_mismatches(
+                expected.saturating_sub(skipped),
+                after.saturating_sub(before),
+                0.0,
+                "links_to"
+            )
+            .is_empty()
+        );
+        assert_eq!(
+            GraphCounts::default().saturating_sub(skipped),
+            GraphCounts::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn strict_counts_fails_a_load_that_does_not_match_the_manifest() {
+        let dir = TempDir::new().unwrap();
+        write_test_csvs(dir.path());
+        let output_dir = dir.path().to_str().unwrap().to_string();
+        let stats = CheckpointStats {
+            articles_processed: 1_000,
+            edges_extracted: 5_000,
+            ..Default::default()
+        };
+        Manifest::for_test(1, stats).write(&output_dir).unwrap();
+
+        let config = SurrealWriterConfig {
+            output_dir,
+            db_path: "test.db".to_string(),
+            ..Default::default()
+        };
+        let stats = run_surreal_load(config.clone()).await.unwrap();
+        assert_eq!(stats.count_mismatches.len(), 2);
+
+        let strict = SurrealWriterConfig {
+            clean: true,
+            strict_counts: true,
+            ..config
+        };
+        let err = run_surreal_load(strict).await.unwrap_err();
+        assert!(err.to_string().contains("does not match"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn plan_counts_csv_rows_without_creating_db() {
+        let dir = TempDir::new().unwrap();
+        write_test_csvs(dir.path());
+
+        let config = SurrealWriterConfig {
+            output_dir: dir.path().to_str().unwrap().to_string(),
+            db_path: "test.db".to_string(),
+            ..Default::default()
+        };
+        let plan = plan_surreal_load(&config).await.unwrap();
+        assert_eq!(
+            plan.to_load,
+            GraphCounts {
+                articles: 2,
+                edges: 1
+            }
+        );
+        assert_eq!(plan.existing, None);
+        assert_eq!(plan.relation_table, "links_to");
+        assert!(plan.is_clean());
+        assert!(!dir.path().join("test.db").exists());
+    }
+
     #[test]
     fn test_escape_surql() {
         assert_eq!(escape_surql("simple"), "simple");
@@ -415,4 +1366,62 @@ mod tests {
         assert_eq!(escape_surql("TBWA\\Chiat\\Day"), "TBWA\\\\Chiat\\\\Day");
         assert_eq!(escape_surql("a'b\\c"), "a\\'b\\\\c");
     }
+
+    fn fast_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn batches_run_in_one_transaction() {
+        let query = transaction(&article_statement("1", "Rust"));
+        assert_eq!(
+            query,
+            "BEGIN TRANSACTION;\nCREATE article:1 SET title = 'Rust';\nCOMMIT TRANSACTION;\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn transient_batch_failures_are_retried() {
+        // Fails twice with a transaction conflict, then succeeds.
+        let mut attempts = 0;
+        let loaded = with_retries(fast_retries(3), "article batch", || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt <= 2 {
+                    Err("Transaction conflict: Resource busy. This transaction can be retried")
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(loaded, 3);
+
+        // A syntax error is permanent and is not retried.
+        let mut attempts = 0;
+        let err = with_retries(fast_retries(3), "edge batch", || {
+            attempts += 1;
+            async { Err::<(), _>("Parse error: Unexpected token `->`") }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(attempts, 1);
+        assert!(err.to_string().contains("edge batch (attempt 1)"), "{err}");
+
+        // Transient errors give up after max_retries.
+        let mut attempts = 0;
+        let err = with_retries(fast_retries(2), "edge batch", || {
+            attempts += 1;
+            async { Err::<(), _>("Query timed out") }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(attempts, 3);
+        assert!(err.to_string().contains("attempt 3"), "{err}");
+    }
 }
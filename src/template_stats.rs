@@ -0,0 +1,116 @@
+//! Global template usage counts for `--template-stats`.
+//!
+//! `TemplateStats` wraps a `DashMap<String, u64>` that extraction workers update
+//! concurrently with each article's `content::extract_template_names()`. At the
+//! end of the run `write_csv()` emits `template_stats.csv` (`template_name,count`),
+//! most-used first, showing which templates dominate the dump.
+
+use crate::config::TEMPLATE_STATS_FILENAME;
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// Thread-safe count of template transclusions across all articles.
+#[derive(Debug, Default)]
+pub struct TemplateStats {
+    counts: DashMap<String, u64>,
+}
+
+impl TemplateStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts one use of each name (a template used twice in an article counts twice).
+    pub fn record(&self, names: &[Cow<'_, str>]) {
+        for name in names {
+            // Look up by reference first so known names don't allocate a key.
+            if let Some(mut count) = self.counts.get_mut(name.as_ref()) {
+                *count += 1;
+            } else {
+                *self.counts.entry(name.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Number of distinct template names seen.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// `(name, count)` pairs by descending count, then name.
+    pub fn sorted(&self) -> Vec<(String, u64)> {
+        let mut rows: Vec<(String, u64)> = self
+            .counts
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect();
+        rows.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        rows
+    }
+
+    /// Writes `template_stats.csv` to `output_dir` and returns its path.
+    pub fn write_csv(&self, output_dir: &str) -> Result<std::path::PathBuf> {
+        let path = Path::new(output_dir).join(TEMPLATE_STATS_FILENAME);
+        let mut writer = csv::Writer::from_path(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        writer.write_record(["template_name", "count"])?;
+        let mut itoa_buf = itoa::Buffer::new();
+        for (name, count) in self.sorted() {
+            writer.write_record([name.as_str(), itoa_buf.format(count)])?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::extract_template_names;
+    use rayon::prelude::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn concurrent_records_are_summed_and_sorted() {
+        let stats = TemplateStats::new();
+        let articles = [
+            "{{Reflist}} {{Stub}}",
+            "{{reflist}}",
+            "{{Cite web}} {{Reflist}}",
+        ];
+        (0..100).into_par_iter().for_each(|i| {
+            stats.record(&extract_template_names(articles[i % articles.len()]));
+        });
+
+        // 34 + 33 + 33 articles of each kind.
+        assert_eq!(
+            stats.sorted(),
+            vec![
+                ("Reflist".to_string(), 100),
+                ("Stub".to_string(), 34),
+                ("Cite web".to_string(), 33),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_csv_has_header_and_rows() {
+        let dir = TempDir::new().unwrap();
+        let stats = TemplateStats::new();
+        stats.record(&extract_template_names("{{B}} {{A}} {{B}}"));
+
+        let path = stats.write_csv(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "template_name,count\nB,2\nA,1\n"
+        );
+    }
+}
@@ -117,10 +117,8 @@ fn handle_config_key(app: &mut App, code: KeyCode) {
             app.field_index = 0;
             app.error_message = None;
         }
-        KeyCode::Up => {
-            if app.field_index > 0 {
-                app.field_index -= 1;
-            }
+        KeyCode::Up if app.field_index > 0 => {
+            app.field_index -= 1;
         }
         KeyCode::Down => {
             let max = app.field_count().saturating_sub(1);
@@ -7,6 +7,7 @@
 use crate::cache;
 use crate::checkpoint::{self, CheckpointManager};
 use crate::index::WikiIndex;
+use crate::shard::ShardStrategy;
 use crate::stats::ExtractionStats;
 use anyhow::{Context, Result};
 use std::collections::VecDeque;
@@ -66,6 +67,9 @@ fn run_extract_inner(
         Some(config.limit.parse().context("Invalid limit")?)
     };
     let checkpoint_interval: u32 = config.checkpoint.parse().context("Invalid checkpoint")?;
+    // The TUI has no shard strategy field; use the CLI default so a TUI run and
+    // a plain `dedalus extract` write (and resume) the same layout.
+    let shard_strategy = ShardStrategy::default();
 
     if config.clean {
         let output_path = std::path::Path::new(output_dir);
@@ -79,13 +83,21 @@ fn run_extract_inner(
     std::fs::create_dir_all(output_dir)
         .with_context(|| format!("Failed to create output directory: {}", output_dir))?;
 
+    let multistream_ranges = crate::multistream::resolve_ranges(input, None)?;
+    let build_index = || match multistream_ranges {
+        Some(ref ranges) => {
+            WikiIndex::build_multistream(input, ranges, crate::config::DEFAULT_CONTENT_NAMESPACES)
+        }
+        None => WikiIndex::build(input),
+    };
+
     // Indexing
     let start_indexing = Instant::now();
     let cache_path = cache::cache_path(output_dir);
 
     let index = if config.no_cache {
         info!("Cache disabled, building fresh index");
-        let idx = WikiIndex::build(input)?;
+        let idx = build_index()?;
         if !config.dry_run
             && let Err(e) = cache::save_index(&idx, input, output_dir)
         {
@@ -97,7 +109,7 @@ fn run_extract_inner(
         idx
     } else {
         info!("Building index (cache miss or invalid)");
-        let idx = WikiIndex::build(input)?;
+        let idx = build_index()?;
         if !config.dry_run
             && let Err(e) = cache::save_index(&idx, input, output_dir)
         {
@@ -122,6 +134,7 @@ fn run_extract_inner(
             output_dir,
             shard_count,
             csv_shards,
+            shard_strategy,
             checkpoint_interval,
         )?)
     } else {
@@ -129,7 +142,8 @@ fn run_extract_inner(
     };
 
     let checkpoint = if config.resume && !config.clean {
-        match checkpoint::load_if_valid(input, output_dir, shard_count, csv_shards)? {
+        match checkpoint::load_if_valid(input, output_dir, shard_count, csv_shards, shard_strategy)?
+        {
             Some(cp) => {
                 info!(
                     last_id = cp.last_processed_id,
@@ -158,11 +172,45 @@ fn run_extract_inner(
         index: &index,
         shard_count,
         csv_shards,
+        shard_strategy,
         limit,
+        expected_articles: None,
+        max_output_bytes: None,
+        max_runtime: None,
+        handle_interrupts: false,
+        metrics_addr: None,
+        max_shard_bytes: None,
+        max_invalid_ratio: None,
+        blob_sample_rate: None,
         dry_run: config.dry_run,
+        single_thread: false,
+        no_blobs: false,
+        blobs_only: false,
+        blob_links: false,
+        blob_format: crate::extract::BlobFormat::Files,
+        edge_section_context: false,
+        edge_redirect_flag: false,
+        weighted_edges: false,
+        keep_self_links: false,
+        normalize_urls: false,
+        single_revision: false,
+        template_stats: false,
+        length_histogram: false,
+        category_counts: false,
+        lead_templates: Vec::new(),
+        emit_redirect_map: false,
+        interwiki_links: false,
+        coordinates: false,
+        relationship_names: Default::default(),
+        page_label: crate::schema::DEFAULT_PAGE_LABEL.to_string(),
+        link_namespaces: Vec::new(),
+        exclude_categories: Vec::new(),
+        dedup_strategy: crate::dedup::DedupStrategy::Exact,
+        output_format: crate::extract::OutputFormat::Csv,
         resume_from: checkpoint.as_ref(),
         checkpoint_mgr: checkpoint_mgr.as_ref(),
-        multistream_ranges: None,
+        multistream_ranges: multistream_ranges.as_deref(),
+        enrich: None,
     };
     crate::extract::run_extraction_with_stats(
         &extraction_config,
@@ -200,6 +248,7 @@ pub fn spawn_surreal_load(
                 .parse()
                 .unwrap_or(crate::config::SURREAL_BATCH_SIZE),
             clean: config.clean,
+            ..Default::default()
         };
 
         let rt = match tokio::runtime::Builder::new_multi_thread()
@@ -282,8 +331,8 @@ pub fn spawn_analytics(
 /// Spawns the CSV merge worker thread.
 pub fn spawn_merge(config: MergeConfig, done: Arc<AtomicBool>, error: Arc<Mutex<Option<String>>>) {
     std::thread::spawn(move || {
-        match crate::merge::merge_csv_shards(&config.output) {
-            Ok(()) => {}
+        match crate::merge::merge_csv_shards(&config.output, false) {
+            Ok(_) => {}
             Err(e) => {
                 if let Ok(mut lock) = error.lock() {
                     *lock = Some(format!("{:#}", e));
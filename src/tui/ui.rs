@@ -476,7 +476,11 @@ fn draw_done(f: &mut Frame, app: &App) {
                 format_num(s.see_also_edges())
             )),
             Line::from(format!("  Blobs written:      {}", format_num(s.blobs()))),
-            Line::from(format!("  Invalid links:      {}", format_num(s.invalid()))),
+            Line::from(format!(
+                "  Invalid links:      {} ({} red)",
+                format_num(s.invalid()),
+                format_num(s.red_links())
+            )),
             Line::from(format!(
                 "  Categories found:   {}",
                 format_num(s.categories())
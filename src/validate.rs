@@ -0,0 +1,623 @@
+//! Consistency checks over extraction CSV output (`dedalus validate`).
+//!
+//! `validate_output()` reads every node ID into a set, then checks each
+//! relationship row's article endpoints against it: edges (`:START_ID` and
+//! `:END_ID`) and the article-category/image/external-link rows (`:START_ID`).
+//! Rows that do not parse, duplicate node IDs and relationships from articles
+//! without a node row are problems; edge targets without a node row are only
+//! counted, since links past `--limit` are expected.
+//!
+//! With `partial`, the directory may belong to a run that is still going (or
+//! was stopped with a checkpoint). Each writer flushes on its own, so bytes after
+//! a file's last newline are treated as an unflushed tail and skipped, a bad
+//! final row is a warning, missing files are warnings, and relationships whose
+//! article node has not been flushed yet are counted without failing.
+//!
+//! `verify_blobs()` (`dedalus verify-blobs`) is the same kind of check for the
+//! JSON output: every per-article blob file and every line of a JSON Lines blob
+//! shard must parse as an `ArticleBlob`, which catches files truncated by a
+//! crash or damaged on disk.
+
+use crate::checkpoint;
+use crate::csv_util::{self, CsvType};
+use crate::models::ArticleBlob;
+use anyhow::{Context, Result};
+use csv::StringRecord;
+use indicatif::ProgressBar;
+use rayon::prelude::*;
+use rustc_hash::FxHashSet;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Bytes read back from the end of a file when looking for its last newline.
+const TAIL_PROBE_BYTES: u64 = 64 * 1024;
+
+/// Findings from a [`validate_output`] run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub partial: bool,
+    /// Node rows read (including duplicates).
+    pub nodes: u64,
+    pub duplicate_nodes: u64,
+    /// Edge rows read.
+    pub edges: u64,
+    /// Article-category, article-image and article-external-link rows read.
+    pub article_relationships: u64,
+    /// Edge and article relationship rows whose `:START_ID` has no node row.
+    pub unknown_starts: u64,
+    /// Edges whose `:END_ID` has no node row (normal past `--limit` or mid-run).
+    pub unknown_ends: u64,
+    /// Rows that could not be parsed, excluding tolerated tails.
+    pub malformed_rows: u64,
+    /// Files ending in an incomplete or unparseable row (tolerated with `partial`).
+    pub truncated_tails: u64,
+    /// `(last_processed_id, articles_processed)` from `checkpoint.bin`, if any.
+    pub checkpoint: Option<(u64, u64)>,
+    /// Failures; validation passes when this is empty.
+    pub problems: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl ValidationReport {
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Adds the counts and findings of one file's report.
+    fn absorb(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.duplicate_nodes += other.duplicate_nodes;
+        self.edges += other.edges;
+        self.article_relationships += other.article_relationships;
+        self.unknown_starts += other.unknown_starts;
+        self.unknown_ends += other.unknown_ends;
+        self.malformed_rows += other.malformed_rows;
+        self.truncated_tails += other.truncated_tails;
+        self.problems.extend(other.problems);
+        self.warnings.extend(other.warnings);
+    }
+
+    /// Records a finding that fails full validation but is expected mid-run.
+    fn flag(&mut self, message: String) {
+        if self.partial {
+            self.warnings.push(message);
+        } else {
+            self.problems.push(message);
+        }
+    }
+}
+
+/// Byte length of `path` up to and including its last newline.
+fn complete_len(path: &Path) -> Result<u64> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let len = file.metadata()?.len();
+    let mut end = len;
+    let mut chunk = Vec::new();
+    while end > 0 {
+        let start = end.saturating_sub(TAIL_PROBE_BYTES);
+        file.seek(SeekFrom::Start(start))?;
+        chunk.clear();
+        (&mut file).take(end - start).read_to_end(&mut chunk)?;
+        if let Some(pos) = memchr::memrchr(b'\n', &chunk) {
+            return Ok(start + pos as u64 + 1);
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+/// Calls `check` on each data row of one file, reporting rows it rejects (or
+/// the csv reader cannot parse) as malformed. With `report.partial`, an
+/// incomplete last line is skipped and a bad last row counts as a tail.
+fn check_file(
+    path: &Path,
+    report: &mut ValidationReport,
+    mut check: impl FnMut(&StringRecord, &mut ValidationReport) -> Result<()>,
+) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if !path.exists() {
+        report.flag(format!("{name}: missing"));
+        return Ok(());
+    }
+    let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let total = file.metadata()?.len();
+    let readable = if report.partial {
+        complete_len(path)?
+    } else {
+        total
+    };
+    let mut tail = (readable < total).then(|| "incomplete last line".to_string());
+
+    let mut reader = csv::Reader::from_reader(file.take(readable));
+    let mut record = StringRecord::new();
+    let mut pending: Option<String> = None;
+    let mut malformed = 0u64;
+    let mut first_error = None;
+    loop {
+        let row = match reader.read_record(&mut record) {
+            Ok(false) => break,
+            Ok(true) => check(&record, report).map_err(|error| {
+                let line = record.position().map_or(0, |p| p.line());
+                format!("line {line}: {error}")
+            }),
+            Err(e) if e.is_io_error() => {
+                return Err(e).with_context(|| format!("Failed to read {path:?}"));
+            }
+            // The message carries the position.
+            Err(e) => Err(e.to_string()),
+        };
+        if let Some(error) = pending.take() {
+            malformed += 1;
+            first_error.get_or_insert(error);
+        }
+        pending = row.err();
+    }
+    if let Some(error) = pending {
+        if report.partial && tail.is_none() {
+            tail = Some(error);
+        } else {
+            malformed += 1;
+            first_error.get_or_insert(error);
+        }
+    }
+
+    if malformed > 0 {
+        report.malformed_rows += malformed;
+        report.problems.push(format!(
+            "{name}: {malformed} malformed row(s), first at {}",
+            first_error.unwrap_or_default()
+        ));
+    }
+    if let Some(reason) = tail {
+        report.truncated_tails += 1;
+        report
+            .warnings
+            .push(format!("{name}: skipped unflushed tail ({reason})"));
+    }
+    Ok(())
+}
+
+/// Progress bar over `len` files for the `validate` and `verify-blobs`
+/// commands (hidden when stderr is not a terminal).
+fn file_progress(len: usize, what: &str) -> ProgressBar {
+    let pb = ProgressBar::new(len as u64);
+    pb.set_style(
+        indicatif::ProgressStyle::default_bar()
+            .template("{spinner:.cyan} [{bar:30.cyan/blue}] {pos}/{len} {msg}")
+            .expect("valid progress template")
+            .progress_chars("=> "),
+    );
+    pb.set_message(what.to_string());
+    pb
+}
+
+/// Runs `check` on every path, with rayon unless `parallel` is false.
+/// Results keep the order of `paths`, so reports do not depend on scheduling.
+fn check_all<T: Send>(
+    paths: &[PathBuf],
+    parallel: bool,
+    pb: &ProgressBar,
+    check: impl Fn(&Path) -> Result<T> + Sync + Send,
+) -> Result<Vec<T>> {
+    let check = |path: &PathBuf| {
+        let result = check(path);
+        pb.inc(1);
+        result
+    };
+    if parallel {
+        paths.par_iter().map(check).collect()
+    } else {
+        paths.iter().map(check).collect()
+    }
+}
+
+/// Validates the CSV output in `output_dir`; see the module docs for what is
+/// checked and what `partial` tolerates. Files are checked in parallel: node
+/// files first (their IDs are needed for the rest), then every other file.
+/// Returns the report whether or not problems were found; errors are reserved
+/// for unreadable output.
+pub fn validate_output(output_dir: &str, partial: bool) -> Result<ValidationReport> {
+    validate_output_with(output_dir, partial, true)
+}
+
+fn validate_output_with(
+    output_dir: &str,
+    partial: bool,
+    parallel: bool,
+) -> Result<ValidationReport> {
+    let layout = csv_util::detect_csv_layout(output_dir)?;
+    let mut report = ValidationReport {
+        partial,
+        checkpoint: checkpoint::load(output_dir)?
+            .map(|cp| (cp.last_processed_id, cp.stats.articles_processed)),
+        ..Default::default()
+    };
+    if report.checkpoint.is_some() && !partial {
+        report
+            .warnings
+            .push("checkpoint.bin present: the extraction has not finished (see --partial)".into());
+    }
+    let dir = Path::new(output_dir);
+    let files = |csv_types: &[CsvType]| -> Vec<PathBuf> {
+        csv_types
+            .iter()
+            .flat_map(|&csv_type| csv_util::layout_files(output_dir, &layout, csv_type))
+            .map(|file| dir.join(file))
+            .collect()
+    };
+    let node_files = files(&[CsvType::Nodes]);
+    let edge_files = files(&[CsvType::Edges]);
+    let relationship_files = files(&[
+        CsvType::ArticleCategories,
+        CsvType::ArticleImages,
+        CsvType::ArticleExternalLinks,
+    ]);
+    let entity_files = files(&[
+        CsvType::Categories,
+        CsvType::ImageNodes,
+        CsvType::ExternalLinkNodes,
+    ]);
+    let pb = file_progress(
+        node_files.len() + edge_files.len() + relationship_files.len() + entity_files.len(),
+        "Validating CSVs",
+    );
+    // Each file gets its own report, merged in file order afterwards.
+    let file_report = || ValidationReport {
+        partial,
+        ..Default::default()
+    };
+
+    let node_results = check_all(&node_files, parallel, &pb, |path| {
+        let mut report = file_report();
+        let mut ids: FxHashSet<u64> = FxHashSet::default();
+        check_file(path, &mut report, |record, report| {
+            let id = csv_util::parse_id(record.as_byte_record(), 0, path)?;
+            report.nodes += 1;
+            if !ids.insert(id) {
+                report.duplicate_nodes += 1;
+            }
+            Ok(())
+        })?;
+        Ok((report, ids))
+    })?;
+    let mut node_ids: FxHashSet<u64> = FxHashSet::default();
+    for (file_report, ids) in node_results {
+        report.absorb(file_report);
+        if node_ids.is_empty() {
+            node_ids = ids;
+            continue;
+        }
+        for id in ids {
+            if !node_ids.insert(id) {
+                report.duplicate_nodes += 1;
+            }
+        }
+    }
+    if report.duplicate_nodes > 0 {
+        report
+            .problems
+            .push(format!("{} duplicate node ID(s)", report.duplicate_nodes));
+    }
+
+    let node_ids = &node_ids;
+    let edge_reports = check_all(&edge_files, parallel, &pb, |path| {
+        let mut report = file_report();
+        check_file(path, &mut report, |record, report| {
+            let start = csv_util::parse_id(record.as_byte_record(), 0, path)?;
+            let end = csv_util::parse_id(record.as_byte_record(), 1, path)?;
+            report.edges += 1;
+            report.unknown_starts += u64::from(!node_ids.contains(&start));
+            report.unknown_ends += u64::from(!node_ids.contains(&end));
+            Ok(())
+        })?;
+        Ok(report)
+    })?;
+    let relationship_reports = check_all(&relationship_files, parallel, &pb, |path| {
+        let mut report = file_report();
+        check_file(path, &mut report, |record, report| {
+            let start = csv_util::parse_id(record.as_byte_record(), 0, path)?;
+            report.article_relationships += 1;
+            report.unknown_starts += u64::from(!node_ids.contains(&start));
+            Ok(())
+        })?;
+        Ok(report)
+    })?;
+    let entity_reports = check_all(&entity_files, parallel, &pb, |path| {
+        let mut report = file_report();
+        check_file(path, &mut report, |_, _| Ok(()))?;
+        Ok(report)
+    })?;
+    pb.finish_and_clear();
+    for file_report in edge_reports
+        .into_iter()
+        .chain(relationship_reports)
+        .chain(entity_reports)
+    {
+        report.absorb(file_report);
+    }
+
+    if report.unknown_starts > 0 {
+        report.flag(format!(
+            "{} relationship row(s) start at an article with no node row",
+            report.unknown_starts
+        ));
+    }
+    info!(
+        nodes = report.nodes,
+        edges = report.edges,
+        problems = report.problems.len(),
+        warnings = report.warnings.len(),
+        partial,
+        "Validated output"
+    );
+    Ok(report)
+}
+
+/// Findings from a [`verify_blobs`] run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlobReport {
+    /// Per-article `.json` files and `.jsonl` shards read.
+    pub files: u64,
+    /// Blobs that parsed as an `ArticleBlob`.
+    pub valid: u64,
+    /// Files or lines that did not parse, as `path[:line]: error`, in shard
+    /// order and then by article ID (or line) within a shard.
+    pub corrupt: Vec<String>,
+}
+
+impl BlobReport {
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.corrupt.is_empty()
+    }
+
+    fn absorb(&mut self, other: Self) {
+        self.files += other.files;
+        self.valid += other.valid;
+        self.corrupt.extend(other.corrupt);
+    }
+}
+
+/// Parses every blob under `{output_dir}/blobs` as an [`ArticleBlob`]: each
+/// `NNN/{id}.json` file (whose `id` must match the file name) and each line of
+/// every `blobs_NNN.jsonl` shard. Shards are checked in parallel. Returns the
+/// report whether or not corrupt blobs were found; errors are reserved for a
+/// missing or unreadable `blobs/` directory.
+pub fn verify_blobs(output_dir: &str) -> Result<BlobReport> {
+    verify_blobs_with(output_dir, true)
+}
+
+fn verify_blobs_with(output_dir: &str, parallel: bool) -> Result<BlobReport> {
+    let blobs_dir = Path::new(output_dir).join("blobs");
+    let mut entries: Vec<PathBuf> = fs::read_dir(&blobs_dir)
+        .with_context(|| format!("Failed to read {blobs_dir:?}"))?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()
+        .with_context(|| format!("Failed to read {blobs_dir:?}"))?;
+    entries.sort();
+
+    let pb = file_progress(entries.len(), "Verifying blob shards");
+    let shard_reports = check_all(&entries, parallel, &pb, |path| {
+        if path.is_dir() {
+            verify_blob_dir(path)
+        } else if path.extension().is_some_and(|ext| ext == "jsonl") {
+            verify_jsonl_shard(path)
+        } else {
+            Ok(BlobReport::default())
+        }
+    })?;
+    pb.finish_and_clear();
+    let mut report = BlobReport::default();
+    for shard_report in shard_reports {
+        report.absorb(shard_report);
+    }
+    info!(
+        files = report.files,
+        valid = report.valid,
+        corrupt = report.corrupt.len(),
+        "Verified blobs"
+    );
+    Ok(report)
+}
+
+/// Checks the `{id}.json` files in one blob shard directory.
+fn verify_blob_dir(dir: &Path) -> Result<BlobReport> {
+    let id_of = |path: &Path| {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok())
+    };
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {dir:?}"))?
+        .map(|entry| entry.map(|e| e.path()))
+        .filter(|path| {
+            path.as_ref().map_or(true, |path| {
+                path.extension().is_some_and(|ext| ext == "json")
+            })
+        })
+        .collect::<std::io::Result<_>>()
+        .with_context(|| format!("Failed to read {dir:?}"))?;
+    // Numeric order, so `2.json` is reported before `10.json`.
+    paths.sort_by_cached_key(|path| (id_of(path), path.clone()));
+
+    let mut report = BlobReport::default();
+    for path in paths {
+        report.files += 1;
+        let bytes = fs::read(&path).with_context(|| format!("Failed to read {path:?}"))?;
+        let expected_id = id_of(&path);
+        match serde_json::from_slice::<ArticleBlob>(&bytes) {
+            Ok(blob) if expected_id.is_some_and(|id| id != blob.id) => report
+                .corrupt
+                .push(format!("{}: holds article {}", path.display(), blob.id)),
+            Ok(_) => report.valid += 1,
+            Err(e) => report.corrupt.push(format!("{}: {e}", path.display())),
+        }
+    }
+    Ok(report)
+}
+
+/// Checks each line of one JSON Lines blob shard.
+fn verify_jsonl_shard(path: &Path) -> Result<BlobReport> {
+    let mut report = BlobReport {
+        files: 1,
+        ..Default::default()
+    };
+    let file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut number = 0u64;
+    loop {
+        line.clear();
+        if reader
+            .read_until(b'\n', &mut line)
+            .with_context(|| format!("Failed to read {path:?}"))?
+            == 0
+        {
+            break;
+        }
+        number += 1;
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        match serde_json::from_slice::<ArticleBlob>(&line) {
+            Ok(_) => report.valid += 1,
+            Err(e) => report
+                .corrupt
+                .push(format!("{}:{number}: {e}", path.display())),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn partial_tolerates_only_the_last_row() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("edges.csv");
+        let check = |_: &StringRecord, _: &mut ValidationReport| Ok(());
+
+        fs::write(&path, ":START_ID,:END_ID,:TYPE\n1,2,LINKS_TO\n3,4\n").unwrap();
+        let mut report = ValidationReport {
+            partial: true,
+            ..Default::default()
+        };
+        check_file(&path, &mut report, check).unwrap();
+        assert_eq!((report.malformed_rows, report.truncated_tails), (0, 1));
+
+        fs::write(&path, ":START_ID,:END_ID,:TYPE\n1,2\n3,4,LINKS_TO\n5,6,LIN").unwrap();
+        let mut report = ValidationReport {
+            partial: true,
+            ..Default::default()
+        };
+        check_file(&path, &mut report, check).unwrap();
+        assert_eq!((report.malformed_rows, report.truncated_tails), (1, 1));
+        assert!(
+            report.problems[0].contains("2 fields"),
+            "{:?}",
+            report.problems
+        );
+
+        // A full validation reads the unterminated row and fails on the short one.
+        let mut report = ValidationReport::default();
+        check_file(&path, &mut report, check).unwrap();
+        assert_eq!((report.malformed_rows, report.truncated_tails), (1, 0));
+    }
+
+    #[test]
+    fn verify_blobs_reports_truncated_files_and_lines() {
+        let dir = TempDir::new().unwrap();
+        let blobs = dir.path().join("blobs");
+        fs::create_dir_all(blobs.join("000")).unwrap();
+        fs::create_dir_all(blobs.join("001")).unwrap();
+        let blob = |id: u64| format!(r#"{{"id":{id},"title":"T{id}","abstract_text":"A"}}"#);
+        fs::write(blobs.join("000/1.json"), blob(1)).unwrap();
+        fs::write(blobs.join("001/2.json"), &blob(2)[..20]).unwrap();
+        fs::write(blobs.join("001/3.json"), blob(4)).unwrap();
+        fs::write(
+            blobs.join("blobs_000.jsonl"),
+            format!("{}\n{}\n{}", blob(5), blob(6), &blob(7)[..10]),
+        )
+        .unwrap();
+
+        let report = verify_blobs(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!((report.files, report.valid), (4, 3));
+        assert!(!report.is_ok());
+        assert_eq!(report.corrupt.len(), 3, "{:?}", report.corrupt);
+        assert!(report.corrupt[0].contains("2.json"), "{:?}", report.corrupt);
+        assert!(report.corrupt[1].ends_with("3.json: holds article 4"));
+        assert!(report.corrupt[2].contains("blobs_000.jsonl:3: "));
+    }
+
+    #[test]
+    fn parallel_and_serial_runs_report_the_same_findings() {
+        let dir = TempDir::new().unwrap();
+        let write =
+            |name: String, content: String| fs::write(dir.path().join(name), content).unwrap();
+        for shard in 0..8u64 {
+            for &csv_type in CsvType::ALL {
+                let mut content = if csv_type == CsvType::Nodes {
+                    "id:ID,title,:LABEL\n".to_string()
+                } else {
+                    ":START_ID,:END_ID,:TYPE\n".to_string()
+                };
+                match csv_type {
+                    // Node 1 appears in every shard: seven duplicates.
+                    CsvType::Nodes => content.push_str(&format!("{shard}0,N,Page\n1,One,Page\n")),
+                    CsvType::Edges if shard % 3 == 0 => {
+                        content.push_str(&format!(
+                            "{shard}0,1,LINKS_TO\nbad,1,LINKS_TO\n9{shard},1,LINKS_TO\n"
+                        ));
+                    }
+                    CsvType::ArticleCategories if shard % 2 == 1 => {
+                        content.push_str(&format!("{shard}0,C,HAS_CATEGORY\n1,C\n"));
+                    }
+                    _ => {}
+                }
+                write(format!("{}_{shard:03}.csv", csv_type.base_name()), content);
+            }
+        }
+        let blobs = dir.path().join("blobs");
+        for shard in 0..8u64 {
+            let shard_dir = blobs.join(format!("{shard:03}"));
+            fs::create_dir_all(&shard_dir).unwrap();
+            for id in [shard, shard + 10, shard + 100] {
+                let blob = format!(r#"{{"id":{id},"title":"T","abstract_text":"A"}}"#);
+                let truncated = shard % 2 == 0 && id != shard + 10;
+                let blob = if truncated { &blob[..12] } else { &blob[..] };
+                fs::write(shard_dir.join(format!("{id}.json")), blob).unwrap();
+            }
+        }
+        let out = dir.path().to_str().unwrap();
+
+        let parallel = validate_output_with(out, false, true).unwrap();
+        let serial = validate_output_with(out, false, false).unwrap();
+        assert_eq!(parallel, serial);
+        assert_eq!((parallel.nodes, parallel.duplicate_nodes), (16, 7));
+        assert_eq!(parallel.malformed_rows, 3 + 4);
+        assert_eq!(parallel.unknown_starts, 3);
+        assert!(parallel.problems.len() > 3, "{:?}", parallel.problems);
+
+        let parallel = verify_blobs_with(out, true).unwrap();
+        let serial = verify_blobs_with(out, false).unwrap();
+        assert_eq!(parallel, serial);
+        assert_eq!((parallel.files, parallel.valid), (24, 16));
+        // Within a shard, blobs are reported by ID rather than by file name.
+        assert!(
+            parallel.corrupt[2].contains("002/2.json"),
+            "{:?}",
+            parallel.corrupt
+        );
+        assert!(
+            parallel.corrupt[3].contains("002/102.json"),
+            "{:?}",
+            parallel.corrupt
+        );
+    }
+}
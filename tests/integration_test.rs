@@ -8,6 +8,7 @@
 //! - **Extraction Tests** -- CSV generation, edge creation, JSON blob output
 //! - **Feature Tests** -- Categories, images, external links, infoboxes, see-also sections
 //! - **Sharding Tests** -- CSV shard distribution for parallel import
+//! - **Synthetic Dump Test** -- 50K generated pages through 8-shard parallel extraction
 //!
 //! # Test Strategy
 //!
@@ -33,11 +34,25 @@
 
 use bzip2::Compression;
 use bzip2::write::BzEncoder;
-use dedalus::extract::{ExtractionConfig, run_extraction};
+use dedalus::checkpoint::{self, CheckpointManager};
+use dedalus::csv_util::CsvType;
+use dedalus::dedup::DedupStrategy;
+use dedalus::extract::{
+    BlobFormat, ExtractionConfig, OutputFormat, jsonl_blob_path, run_extraction,
+    run_extraction_with_stats,
+};
 use dedalus::index::WikiIndex;
 use dedalus::models::{ArticleBlob, PageType};
-use dedalus::parser::WikiReader;
+use dedalus::parser::{self, WikiReader};
+use dedalus::schema::{DEFAULT_PAGE_LABEL, RelationshipNames};
+use dedalus::shard::{ShardStrategy, page_shard};
+use dedalus::stats::ExtractionStats;
+use dedalus::surrealdb_writer::{SurrealWriterConfig, dry_run_load, preflight_load};
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use tempfile::{NamedTempFile, TempDir};
 
 /// Helper: create a BZ2-compressed XML file from a string and return the temp file handle.
@@ -137,6 +152,12 @@ Some refs.
     </mediawiki>"#
 }
 
+/// Path of an article's blob under the default 1000-shard layout.
+fn blob_path(output_dir: &Path, page_id: u64) -> PathBuf {
+    let shard = page_shard(page_id, 1000, ShardStrategy::default());
+    output_dir.join(format!("blobs/{:03}/{}.json", shard, page_id))
+}
+
 /// Helper to build an ExtractionConfig with common defaults.
 fn make_config<'a>(
     input_path: &'a str,
@@ -152,11 +173,45 @@ fn make_config<'a>(
         index,
         shard_count: 1000,
         csv_shards,
+        shard_strategy: ShardStrategy::default(),
         limit,
+        expected_articles: None,
+        max_output_bytes: None,
+        max_runtime: None,
+        handle_interrupts: false,
+        metrics_addr: None,
+        max_shard_bytes: None,
+        max_invalid_ratio: None,
+        blob_sample_rate: None,
         dry_run,
+        single_thread: false,
+        no_blobs: false,
+        blobs_only: false,
+        blob_links: false,
+        blob_format: BlobFormat::Files,
+        edge_section_context: false,
+        edge_redirect_flag: false,
+        weighted_edges: false,
+        keep_self_links: false,
+        normalize_urls: false,
+        single_revision: false,
+        template_stats: false,
+        length_histogram: false,
+        category_counts: false,
+        lead_templates: Vec::new(),
+        emit_redirect_map: false,
+        interwiki_links: false,
+        coordinates: false,
+        relationship_names: RelationshipNames::default(),
+        page_label: DEFAULT_PAGE_LABEL.to_string(),
+        link_namespaces: Vec::new(),
+        exclude_categories: Vec::new(),
+        dedup_strategy: DedupStrategy::Exact,
+        output_format: OutputFormat::Csv,
         resume_from: None,
         checkpoint_mgr: None,
         multistream_ranges: None,
+        enrich: None,
     }
 }
 
@@ -222,6 +277,224 @@ fn parser_reads_timestamp() {
     assert_eq!(pages[1].timestamp.as_deref(), Some("2024-02-20T14:00:00Z"));
 }
 
+#[test]
+fn index_counts_repeated_page_ids() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>1</id><text>A</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>2</id><text>B</text></revision></page>
+        <page><title>Alpha (copy)</title><ns>0</ns><id>1</id><revision><id>3</id><text>A</text></revision></page>
+        <page><title>Gamma</title><ns>4</ns><id>2</id><revision><id>4</id><text>G</text></revision></page>
+        <page><title>Delta</title><ns>0</ns><id>3</id><revision><id>5</id><text>D</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let input = tmp.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+    assert_eq!(index.duplicate_ids(), 2);
+    let clean = create_bz2_xml(sample_xml());
+    let clean_index = WikiIndex::build(clean.path().to_str().unwrap()).unwrap();
+    assert_eq!(clean_index.duplicate_ids(), 0);
+
+    // The count survives the index cache, so --strict-ids also fails on a cache hit.
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    dedalus::cache::save_index(&index, input, out).unwrap();
+    let cached = dedalus::cache::load_index(&dedalus::cache::cache_path(out)).unwrap();
+    assert_eq!(cached.duplicate_ids(), 2);
+}
+
+#[test]
+fn parser_reads_plain_and_multistream_dumps_like_bz2() {
+    let xml = sample_xml();
+    let bz2 = create_bz2_xml(xml);
+    let mut plain = NamedTempFile::new().unwrap();
+    plain.write_all(xml.as_bytes()).unwrap();
+    plain.flush().unwrap();
+
+    // Multistream dumps are independent bz2 streams back to back, split between pages.
+    let split = xml.match_indices("<page>").nth(1).unwrap().0;
+    let mut multistream = NamedTempFile::new().unwrap();
+    for part in [&xml[..split], &xml[split..]] {
+        let mut encoder = BzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(part.as_bytes()).unwrap();
+        multistream.write_all(&encoder.finish().unwrap()).unwrap();
+    }
+    multistream.flush().unwrap();
+
+    let read = |tmp: &NamedTempFile| -> Vec<String> {
+        WikiReader::new(tmp.path().to_str().unwrap(), false)
+            .unwrap()
+            .map(|page| format!("{page:?}"))
+            .collect()
+    };
+    let expected = read(&bz2);
+    assert_eq!(expected.len(), 5);
+    assert_eq!(read(&plain), expected);
+    assert_eq!(read(&multistream), expected);
+}
+
+#[test]
+fn parser_output_is_identical_across_codecs() {
+    let xml = sample_xml().as_bytes();
+    let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    gzip.write_all(xml).unwrap();
+    let bz2 = create_bz2_xml(sample_xml());
+    let encoded = [
+        (".xml.gz", gzip.finish().unwrap()),
+        (".xml.zst", zstd::encode_all(xml, 0).unwrap()),
+        (".xml.bz2", std::fs::read(bz2.path()).unwrap()),
+    ];
+
+    let read =
+        |reader: WikiReader| -> Vec<String> { reader.map(|page| format!("{page:?}")).collect() };
+    let expected = read(WikiReader::new(bz2.path().to_str().unwrap(), false).unwrap());
+    assert_eq!(expected.len(), 5);
+    for (suffix, bytes) in encoded {
+        let mut tmp = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        tmp.write_all(&bytes).unwrap();
+        tmp.flush().unwrap();
+        let path = tmp.path().to_str().unwrap();
+        assert_eq!(
+            read(WikiReader::new(path, false).unwrap()),
+            expected,
+            "{suffix}"
+        );
+    }
+
+    // An explicit codec wins over a misleading extension.
+    let mut mislabeled = tempfile::Builder::new().suffix(".xml").tempfile().unwrap();
+    mislabeled
+        .write_all(&zstd::encode_all(xml, 0).unwrap())
+        .unwrap();
+    mislabeled.flush().unwrap();
+    let path = mislabeled.path().to_str().unwrap();
+    assert_eq!(
+        parser::Compression::detect(path).unwrap(),
+        parser::Compression::None
+    );
+    let reader =
+        WikiReader::with_compression(path, false, Some(parser::Compression::Zstd)).unwrap();
+    assert_eq!(read(reader), expected);
+}
+
+#[test]
+fn sample_dump_parses_back_with_siteinfo() {
+    let xml = sample_xml().replacen(
+        "<mediawiki>",
+        "<mediawiki>\n<siteinfo><dbname>testwiki</dbname>\
+         <namespaces><namespace key=\"14\">Category</namespace></namespaces></siteinfo>",
+        1,
+    );
+    let tmp = create_bz2_xml(&xml);
+    let input = tmp.path().to_str().unwrap();
+    let originals: Vec<_> = WikiReader::new(input, false).unwrap().collect();
+    let dir = TempDir::new().unwrap();
+
+    let first = dir.path().join("first.xml.bz2");
+    let stats = dedalus::sample::write_sample(input, &first, 3, None).unwrap();
+    assert_eq!((stats.pages_read, stats.pages_written), (3, 3));
+    let mut reader = WikiReader::new(first.to_str().unwrap(), false).unwrap();
+    assert_eq!(reader.read_dump_info().dbname.as_deref(), Some("testwiki"));
+    assert_eq!(reader.namespaces(), ["Category"]);
+    let pages: Vec<_> = reader.collect();
+    assert_eq!(pages.len(), 3);
+    for (page, original) in pages.iter().zip(&originals) {
+        assert_eq!(page.id, original.id);
+        assert_eq!(page.title, original.title);
+        assert_eq!(
+            format!("{:?}", page.page_type),
+            format!("{:?}", original.page_type)
+        );
+        assert_eq!(page.ns, original.ns);
+        assert_eq!(page.timestamp, original.timestamp);
+        assert_eq!(page.text, original.text);
+    }
+
+    let all = dir.path().join("all.xml.bz2");
+    let stats = dedalus::sample::write_sample(input, &all, 100, Some(1.0)).unwrap();
+    assert_eq!(stats.pages_written as usize, originals.len());
+    let none = dir.path().join("none.xml.bz2");
+    let stats = dedalus::sample::write_sample(input, &none, 100, Some(0.0)).unwrap();
+    assert_eq!(stats.pages_written, 0);
+    assert_eq!(
+        WikiReader::new(none.to_str().unwrap(), false)
+            .unwrap()
+            .count(),
+        0
+    );
+}
+
+/// Serves `body` to every GET on a local port, like a dump mirror would.
+/// Returns the base URL; the server thread lives until the test process exits.
+fn serve_bytes(body: Vec<u8>) -> String {
+    use std::io::{BufRead, BufReader};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            let mut request = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 && !line.trim_end().is_empty() {
+                request.push_str(&line);
+                line.clear();
+            }
+            let response = if request.starts_with("GET /missing") {
+                b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_vec()
+            } else {
+                let mut response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .into_bytes();
+                response.extend_from_slice(&body);
+                response
+            };
+            // The client may hang up early (e.g. after reading the header).
+            let _ = stream.write_all(&response);
+        }
+    });
+    format!("http://{addr}")
+}
+
+#[test]
+fn sample_streams_a_dump_over_http() {
+    let tmp = create_bz2_xml(sample_xml());
+    let local = tmp.path().to_str().unwrap();
+    let base = serve_bytes(std::fs::read(tmp.path()).unwrap());
+    let url = format!("{base}/enwiki-latest-pages-articles.xml.bz2");
+    assert!(parser::is_url(&url));
+
+    let local_pages: Vec<_> = WikiReader::new(local, false).unwrap().collect();
+    let remote_pages: Vec<_> = WikiReader::new(&url, false).unwrap().collect();
+    assert_eq!(remote_pages.len(), local_pages.len());
+    for (remote, local) in remote_pages.iter().zip(&local_pages) {
+        assert_eq!((remote.id, &remote.title), (local.id, &local.title));
+        assert_eq!(remote.text, local.text);
+    }
+    // Without an extension the codec comes from the first bytes of the body.
+    let sniffed: Vec<_> = WikiReader::new(&format!("{base}/dump?download=1"), false)
+        .unwrap()
+        .collect();
+    assert_eq!(sniffed.len(), local_pages.len());
+
+    let dir = TempDir::new().unwrap();
+    let out = dir.path().join("sample.xml.bz2");
+    let stats = dedalus::sample::write_sample(&url, &out, 2, None).unwrap();
+    assert_eq!((stats.pages_read, stats.pages_written), (2, 2));
+    assert_eq!(
+        WikiReader::new(out.to_str().unwrap(), false)
+            .unwrap()
+            .count(),
+        2
+    );
+
+    let err = WikiReader::new(&format!("{base}/missing.xml.bz2"), false)
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("404"), "{err:#}");
+}
+
 // ---------------------------------------------------------------------------
 // Index integration tests
 // ---------------------------------------------------------------------------
@@ -236,6 +509,16 @@ fn index_builds_from_dump() {
     assert_eq!(index.resolve_id("Python (programming language)"), Some(2));
 }
 
+#[test]
+fn index_reports_page_count_for_fixture() {
+    let tmp = create_bz2_xml(sample_xml());
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    // Every <page> counts, including the redirect and the category page.
+    assert_eq!(index.page_count(), 5);
+    assert!(index.page_count() > index.stats().0 as u64);
+}
+
 #[test]
 fn index_resolves_redirects() {
     let tmp = create_bz2_xml(sample_xml());
@@ -263,6 +546,51 @@ fn index_returns_none_for_unknown() {
     assert_eq!(index.resolve_id("Nonexistent Article"), None);
 }
 
+#[test]
+fn index_applies_delta_dump() {
+    let base = create_bz2_xml(sample_xml());
+    let mut index = WikiIndex::build(base.path().to_str().unwrap()).unwrap();
+
+    // Python is moved, "Rust" is retargeted, and a new article appears
+    let delta = create_bz2_xml(
+        r#"<mediawiki>
+        <page>
+            <title>Python (language)</title>
+            <ns>0</ns>
+            <id>2</id>
+            <revision><id>201</id><text>Moved.</text></revision>
+        </page>
+        <page>
+            <title>Python (programming language)</title>
+            <ns>0</ns>
+            <id>7</id>
+            <redirect title="Python (language)" />
+            <revision><id>700</id><text>#REDIRECT [[Python (language)]]</text></revision>
+        </page>
+        <page>
+            <title>Rust</title>
+            <ns>0</ns>
+            <id>3</id>
+            <redirect title="Rust (game)" />
+            <revision><id>301</id><text>#REDIRECT [[Rust (game)]]</text></revision>
+        </page>
+        <page>
+            <title>Rust (game)</title>
+            <ns>0</ns>
+            <id>6</id>
+            <revision><id>600</id><text>A game.</text></revision>
+        </page>
+    </mediawiki>"#,
+    );
+    index.update_from(delta.path().to_str().unwrap()).unwrap();
+
+    assert_eq!(index.resolve_id("Python (language)"), Some(2));
+    assert_eq!(index.resolve_id("Python (programming language)"), Some(2));
+    assert_eq!(index.resolve_id("Rust"), Some(6));
+    assert_eq!(index.resolve_id("Rust (programming language)"), Some(1));
+    assert_eq!(index.stats(), (3, 2));
+}
+
 // ---------------------------------------------------------------------------
 // End-to-end extraction tests
 // ---------------------------------------------------------------------------
@@ -349,8 +677,7 @@ fn extraction_writes_json_blobs() {
     assert!(stats.blobs() >= 1);
 
     // Check that a blob file exists and is valid JSON
-    let shard = 1 % 1000; // page id 1
-    let blob_path = output_dir.path().join(format!("blobs/{:03}/1.json", shard));
+    let blob_path = blob_path(output_dir.path(), 1);
     assert!(
         blob_path.exists(),
         "Blob file should exist at {:?}",
@@ -365,6 +692,51 @@ fn extraction_writes_json_blobs() {
     assert!(blob.abstract_text.contains("systems programming language"));
 }
 
+#[test]
+fn extraction_handles_ids_above_u32_max() {
+    const BIG: u64 = u32::MAX as u64 + 7;
+    let xml = format!(
+        r#"<mediawiki>
+        <page><title>Huge</title><ns>0</ns><id>{BIG}</id>
+            <revision><id>1</id><text>Huge links to [[Small]].</text></revision></page>
+        <page><title>Small</title><ns>0</ns><id>5</id>
+            <revision><id>2</id><text>Small links to [[Huge]].</text></revision></page>
+    </mediawiki>"#
+    );
+    let tmp = create_bz2_xml(&xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    assert_eq!(index.resolve_id("Huge"), Some(BIG));
+
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!(stats.articles(), 2);
+    assert_eq!(stats.edges(), 2);
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("edges.csv")).unwrap();
+    let mut edges: Vec<(u64, u64)> = rdr
+        .records()
+        .map(|r| {
+            let r = r.unwrap();
+            (r[0].parse().unwrap(), r[1].parse().unwrap())
+        })
+        .collect();
+    edges.sort_unstable();
+    assert_eq!(edges, vec![(5, BIG), (BIG, 5)]);
+
+    let blob: ArticleBlob =
+        serde_json::from_str(&std::fs::read_to_string(blob_path(output_dir.path(), BIG)).unwrap())
+            .unwrap();
+    assert_eq!(blob.id, BIG);
+}
+
 #[test]
 fn extraction_dry_run_writes_no_files() {
     let tmp = create_bz2_xml(sample_xml());
@@ -384,31 +756,1412 @@ fn extraction_dry_run_writes_no_files() {
     // Stats should still be collected
     assert_eq!(stats.articles(), 2);
 
-    // But no blob files should be written
-    assert_eq!(stats.blobs(), 0);
-    let blobs_dir = output_dir.path().join("blobs");
-    assert!(!blobs_dir.exists());
+    // But no blob files should be written
+    assert_eq!(stats.blobs(), 0);
+    let blobs_dir = output_dir.path().join("blobs");
+    assert!(!blobs_dir.exists());
+}
+
+#[test]
+fn exclude_category_skips_matching_articles() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        out.to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    // Prefix, case and underscores are normalized away.
+    config.exclude_categories = vec![
+        "category:systems_programming_languages".to_string(),
+        "Unused".to_string(),
+    ];
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!((stats.articles(), stats.articles_excluded()), (1, 1));
+
+    let first_column = |name: &str| -> HashSet<String> {
+        csv::Reader::from_path(out.join(name))
+            .unwrap()
+            .records()
+            .map(|r| r.unwrap()[0].to_string())
+            .collect()
+    };
+    let python = HashSet::from(["2".to_string()]);
+    assert_eq!(first_column("nodes.csv"), python);
+    assert_eq!(first_column("edges.csv"), python);
+    assert_eq!(first_column("article_categories.csv"), python);
+    assert!(first_column("image_nodes.csv").is_empty());
+    assert!(!blob_path(out, 1).exists());
+    assert!(blob_path(out, 2).exists());
+
+    // Known limitation: Python's link to the excluded Rust article is kept,
+    // because it is written before Rust's page is read.
+    let targets: HashSet<String> = csv::Reader::from_path(out.join("edges.csv"))
+        .unwrap()
+        .records()
+        .map(|r| r.unwrap()[1].to_string())
+        .collect();
+    assert!(targets.contains("1"));
+}
+
+#[test]
+fn link_namespaces_filter_localized_prefixes() {
+    // No <siteinfo>, so only the English defaults are known.
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>Siehe [[Beta]] und [[Datei:Bild.png]]. [[Kategorie:Dinge]]</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let path = tmp.path().to_str().unwrap();
+    let index = WikiIndex::build(path).unwrap();
+
+    let extract = |link_namespaces: &[&str]| {
+        let output_dir = TempDir::new().unwrap();
+        let mut config = make_config(
+            path,
+            output_dir.path().to_str().unwrap(),
+            &index,
+            1,
+            None,
+            false,
+        );
+        config.link_namespaces = link_namespaces.iter().map(|ns| ns.to_string()).collect();
+        let stats = run_extraction(&config).unwrap();
+        let edges: Vec<Vec<String>> = csv::Reader::from_path(output_dir.path().join("edges.csv"))
+            .unwrap()
+            .records()
+            .map(|r| r.unwrap().iter().map(str::to_string).collect())
+            .collect();
+        (stats, edges)
+    };
+
+    let (stats, edges) = extract(&[]);
+    assert_eq!(edges, vec![vec!["1", "2", "LINKS_TO"]]);
+    assert_eq!(stats.invalid(), 2);
+
+    let (stats, edges) = extract(&["Kategorie", "Datei"]);
+    assert_eq!(edges, vec![vec!["1", "2", "LINKS_TO"]]);
+    assert_eq!(stats.invalid(), 0);
+}
+
+#[test]
+fn redirects_only_dump_extracts_nothing_and_refuses_load() {
+    let xml = r#"<mediawiki>
+        <page><title>Rustlang</title><ns>0</ns><id>1</id><redirect title="Rust" />
+            <revision><id>10</id><text>#REDIRECT [[Rust]]</text></revision></page>
+        <page><title>Main Page</title><ns>4</ns><id>2</id>
+            <revision><id>20</id><text>Welcome.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!((stats.articles(), stats.blobs()), (0, 0));
+    assert_eq!(
+        std::fs::read_to_string(output_dir.path().join("nodes.csv")).unwrap(),
+        "id:ID,title,:LABEL\n"
+    );
+
+    let load = SurrealWriterConfig {
+        output_dir: output_dir.path().to_str().unwrap().to_string(),
+        ..Default::default()
+    };
+    let err = preflight_load(&load).unwrap_err();
+    assert!(err.to_string().contains("no article rows"), "{err}");
+}
+
+#[test]
+fn load_dry_run_lists_statements_without_touching_the_database() {
+    let tmp = create_bz2_xml(sample_xml());
+    let input = tmp.path().to_str().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+    run_extraction(&make_config(input, out, &index, 1, None, false)).unwrap();
+
+    let load = SurrealWriterConfig {
+        output_dir: out.to_string(),
+        clean: true,
+        ..Default::default()
+    };
+    let script = dry_run_load(&load).unwrap();
+    let files: Vec<_> = script
+        .steps
+        .iter()
+        .map(|step| step.file.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(files, ["nodes.csv", "edges.csv"]);
+    assert_eq!(
+        script.steps[1].statement,
+        "RELATE article:<:START_ID>->links_to->article:<:END_ID>;"
+    );
+    assert!(script.schema[0].starts_with("DEFINE TABLE article"));
+    assert!(!script.removes_db);
+    assert!(!output_dir.path().join(&load.db_path).exists());
+
+    // With --clean an existing database would be removed, but a dry run keeps it.
+    let db_dir = output_dir.path().join(&load.db_path);
+    std::fs::create_dir(&db_dir).unwrap();
+    assert!(dry_run_load(&load).unwrap().removes_db);
+    assert!(db_dir.is_dir());
+
+    let sharded = TempDir::new().unwrap();
+    let sharded_out = sharded.path().to_str().unwrap();
+    run_extraction(&make_config(input, sharded_out, &index, 2, None, false)).unwrap();
+    let load = SurrealWriterConfig {
+        output_dir: sharded_out.to_string(),
+        ..Default::default()
+    };
+    assert!(dry_run_load(&load).is_err());
+}
+
+#[test]
+fn extraction_includes_selected_custom_namespace() {
+    let xml = r#"<mediawiki>
+        <siteinfo><namespaces>
+            <namespace key="0" case="first-letter" />
+            <namespace key="14" case="first-letter">Category</namespace>
+            <namespace key="100" case="first-letter">Portal</namespace>
+        </namespaces></siteinfo>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>See [[Portal:Science]] and [[Beta]]. [[Category:Things]]</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta.</text></revision></page>
+        <page><title>Portal:Science</title><ns>100</ns><id>3</id><revision><id>30</id>
+            <text>Featured: [[Alpha]]. [[Category:Portals]]</text></revision></page>
+        <page><title>Category:Things</title><ns>14</ns><id>4</id><revision><id>40</id>
+            <text>Things.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let path = tmp.path().to_str().unwrap();
+
+    let extract = |index: &WikiIndex| {
+        let output_dir = TempDir::new().unwrap();
+        let config = make_config(
+            path,
+            output_dir.path().to_str().unwrap(),
+            index,
+            1,
+            None,
+            false,
+        );
+        let stats = run_extraction(&config).unwrap();
+        let read_rows = |name: &str| -> Vec<Vec<String>> {
+            csv::Reader::from_path(output_dir.path().join(name))
+                .unwrap()
+                .records()
+                .map(|r| r.unwrap().iter().map(str::to_string).collect())
+                .collect()
+        };
+        (stats, read_rows("nodes.csv"), read_rows("edges.csv"))
+    };
+
+    // Default: only the main namespace; namespace links are skipped, not invalid.
+    let index = WikiIndex::build(path).unwrap();
+    let (stats, nodes, edges) = extract(&index);
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(edges, vec![vec!["1", "2", "LINKS_TO"]]);
+    assert_eq!(stats.invalid(), 0);
+
+    let index = WikiIndex::build_for_namespaces(path, &[0, 100]).unwrap();
+    assert_eq!(index.content_namespaces(), [0, 100]);
+    assert_eq!(index.resolve_id("Portal:Science"), Some(3));
+    assert_eq!(index.resolve_id("Category:Things"), None);
+    let (stats, nodes, mut edges) = extract(&index);
+    let titles: HashSet<&str> = nodes.iter().map(|n| n[1].as_str()).collect();
+    assert_eq!(titles, HashSet::from(["Alpha", "Beta", "Portal:Science"]));
+    edges.sort();
+    assert_eq!(
+        edges,
+        vec![
+            vec!["1", "2", "LINKS_TO"],
+            vec!["1", "3", "LINKS_TO"],
+            vec!["3", "1", "LINKS_TO"],
+        ]
+    );
+    // Category links from either namespace still only feed HAS_CATEGORY.
+    assert_eq!(stats.invalid(), 0);
+    assert_eq!(stats.category_edges(), 2);
+}
+
+#[test]
+fn max_invalid_ratio_fails_when_links_do_not_resolve() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>[[Beta]] [[Missing one]] [[Missing two]] [[Missing three]]</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>[[Gone]] [[Also gone]]</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let output_dir = TempDir::new().unwrap();
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.max_invalid_ratio = Some(0.5);
+    let err = run_extraction(&config).err().unwrap().to_string();
+    assert!(err.contains("83.3% of wiki links did not resolve"), "{err}");
+    // Output is still written for inspection.
+    assert!(output_dir.path().join("edges.csv").exists());
+
+    // A looser threshold passes.
+    config.max_invalid_ratio = Some(0.9);
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!((stats.edges(), stats.invalid()), (1, 5));
+
+    config.max_invalid_ratio = Some(1.5);
+    assert!(run_extraction(&config).is_err());
+}
+
+#[test]
+fn extraction_runs_twice_in_one_process() {
+    // Extraction uses the ambient rayon pool and never builds a global one,
+    // so repeated runs (and embedding callers' own pools) are unaffected.
+    let tmp = create_bz2_xml(sample_xml());
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .unwrap();
+
+    let mut outputs = Vec::new();
+    let mut counts = Vec::new();
+    for _ in 0..2 {
+        let output_dir = TempDir::new().unwrap();
+        let config = make_config(
+            tmp.path().to_str().unwrap(),
+            output_dir.path().to_str().unwrap(),
+            &index,
+            2,
+            None,
+            false,
+        );
+        let stats = pool.install(|| run_extraction(&config)).unwrap();
+        counts.push((stats.articles(), stats.edges(), stats.blobs()));
+        outputs.push(output_dir);
+    }
+    assert_eq!(counts[0], counts[1]);
+    assert_eq!(counts[0].0, 2);
+    for output_dir in &outputs {
+        assert!(output_dir.path().join("nodes_000.csv").exists());
+        assert!(blob_path(output_dir.path(), 1).exists());
+    }
+}
+
+#[test]
+fn extraction_no_blobs_writes_csvs_only() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.no_blobs = true;
+    let stats = run_extraction(&config).unwrap();
+
+    assert_eq!(stats.articles(), 2);
+    assert_eq!(stats.blobs(), 0);
+    assert!(output_dir.path().join("nodes.csv").exists());
+    assert!(output_dir.path().join("edges.csv").exists());
+    assert!(!output_dir.path().join("blobs").exists());
+}
+
+#[test]
+fn extraction_blobs_only_writes_no_csvs() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.blobs_only = true;
+    let stats = run_extraction(&config).unwrap();
+
+    // Graph stats are still counted even though no CSVs are written
+    assert_eq!(stats.articles(), 2);
+    assert!(stats.edges() >= 1);
+    assert_eq!(stats.blobs(), 2);
+    assert!(blob_path(output_dir.path(), 1).exists());
+    assert!(!output_dir.path().join("nodes.csv").exists());
+    assert!(!output_dir.path().join("edges.csv").exists());
+}
+
+#[test]
+fn extraction_blob_links_lists_outbound_edges() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let read_rust_blob = |blob_links: bool| {
+        let mut config = make_config(
+            tmp.path().to_str().unwrap(),
+            output_dir.path().to_str().unwrap(),
+            &index,
+            1,
+            None,
+            false,
+        );
+        config.blob_links = blob_links;
+        run_extraction(&config).unwrap();
+        let json = std::fs::read_to_string(blob_path(output_dir.path(), 1)).unwrap();
+        serde_json::from_str::<ArticleBlob>(&json).unwrap()
+    };
+
+    // Rust (id 1) links to Python (id 2) in the body and under "See also";
+    // unresolved targets (C++, Mozilla) are not listed.
+    assert_eq!(
+        read_rust_blob(true).links,
+        vec![(2, "LINKS_TO".to_string()), (2, "SEE_ALSO".to_string())]
+    );
+
+    assert!(read_rust_blob(false).links.is_empty());
+}
+
+#[test]
+fn jsonl_blob_format_matches_per_file_blobs() {
+    let tmp = create_bz2_xml(sample_xml());
+    let files_dir = TempDir::new().unwrap();
+    let jsonl_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let extract = |output_dir: &Path, blob_format: BlobFormat| {
+        let mut config = make_config(
+            tmp.path().to_str().unwrap(),
+            output_dir.to_str().unwrap(),
+            &index,
+            1,
+            None,
+            false,
+        );
+        config.shard_count = 4;
+        config.blob_format = blob_format;
+        run_extraction(&config).unwrap()
+    };
+    let files_stats = extract(files_dir.path(), BlobFormat::Files);
+    let jsonl_stats = extract(jsonl_dir.path(), BlobFormat::Jsonl);
+    assert_eq!(files_stats.blobs(), jsonl_stats.blobs());
+    assert!(!jsonl_dir.path().join("blobs/000").exists());
+
+    let mut lines = 0;
+    for shard in 0..4 {
+        let path = jsonl_blob_path(jsonl_dir.path().to_str().unwrap(), shard);
+        for line in std::fs::read_to_string(path).unwrap().lines() {
+            let blob: ArticleBlob = serde_json::from_str(line).unwrap();
+            assert_eq!(page_shard(blob.id, 4, ShardStrategy::default()), shard);
+            let file_path = files_dir
+                .path()
+                .join(format!("blobs/{:03}/{}.json", shard, blob.id));
+            assert_eq!(std::fs::read_to_string(file_path).unwrap(), line);
+            lines += 1;
+        }
+    }
+    assert_eq!(lines, jsonl_stats.blobs());
+}
+
+#[test]
+fn interwiki_links_written_to_single_csv() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>Alpha relates to [[Beta]] and [[File:Alpha.png]].
+
+[[de:Alpha (Begriff)]]
+[[zh-yue:Alpha]]</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta. [[fr:Bêta]]</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        4,
+        None,
+        false,
+    );
+    config.interwiki_links = true;
+    run_extraction(&config).unwrap();
+
+    // One file even with sharded CSVs, and no interwiki shards.
+    assert!(!output_dir.path().join("interwiki_000.csv").exists());
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("interwiki.csv")).unwrap();
+    assert_eq!(rdr.headers().unwrap(), vec![":START_ID", "lang", "title"]);
+    let mut rows: Vec<Vec<String>> = rdr
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect())
+        .collect();
+    rows.sort();
+    assert_eq!(
+        rows,
+        vec![
+            vec!["1", "de", "Alpha (Begriff)"],
+            vec!["1", "zh-yue", "Alpha"],
+            vec!["2", "fr", "Bêta"],
+        ]
+    );
+}
+
+#[test]
+fn coordinates_written_to_single_csv() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>{{Infobox settlement|name=Alpha}} Alpha is a town.
+{{Coord|33|52|S|151|12|E|display=title}}</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta has no position. {{Coord missing|Nowhere}}</text></revision></page>
+        <page><title>Gamma</title><ns>0</ns><id>3</id><revision><id>30</id>
+            <text>Gamma. {{coord|40.7128|-74.006}}</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        4,
+        None,
+        false,
+    );
+    config.coordinates = true;
+    run_extraction(&config).unwrap();
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("coordinates.csv")).unwrap();
+    assert_eq!(
+        rdr.headers().unwrap(),
+        vec![":START_ID", "latitude:double", "longitude:double"]
+    );
+    let mut rows: Vec<(u64, f64, f64)> = rdr
+        .records()
+        .map(|r| {
+            let r = r.unwrap();
+            (
+                r[0].parse().unwrap(),
+                r[1].parse().unwrap(),
+                r[2].parse().unwrap(),
+            )
+        })
+        .collect();
+    rows.sort_by_key(|row| row.0);
+    let expected = [(1, -(33.0 + 52.0 / 60.0), 151.2), (3, 40.7128, -74.006)];
+    assert_eq!(rows.len(), expected.len());
+    for (row, want) in rows.iter().zip(expected) {
+        assert_eq!(row.0, want.0);
+        assert!(
+            (row.1 - want.1).abs() < 1e-9 && (row.2 - want.2).abs() < 1e-9,
+            "{row:?} != {want:?}"
+        );
+    }
+}
+
+#[test]
+fn edge_section_context_records_enclosing_section() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>Alpha relates to [[Beta]].
+
+== History ==
+Alpha was founded near [[Gamma]], later [[Beta]] again.
+
+== See also ==
+* [[Gamma]]</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta.</text></revision></page>
+        <page><title>Gamma</title><ns>0</ns><id>3</id><revision><id>30</id>
+            <text>Gamma.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.edge_section_context = true;
+    config.blob_links = true;
+    run_extraction(&config).unwrap();
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("edges.csv")).unwrap();
+    assert_eq!(
+        rdr.headers().unwrap(),
+        vec![":START_ID", ":END_ID", ":TYPE", "section"]
+    );
+    let edges: Vec<Vec<String>> = rdr
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect())
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            vec!["1", "2", "LINKS_TO", "History"],
+            vec!["1", "2", "LINKS_TO", "lead"],
+            vec!["1", "3", "LINKS_TO", "History"],
+            vec!["1", "3", "SEE_ALSO", "See also"],
+        ]
+    );
+
+    // Blob links name each target and type once, whatever the sections.
+    let json = std::fs::read_to_string(blob_path(output_dir.path(), 1)).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ArticleBlob>(&json).unwrap().links,
+        vec![
+            (2, "LINKS_TO".to_string()),
+            (3, "LINKS_TO".to_string()),
+            (3, "SEE_ALSO".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn edge_redirect_flag_marks_links_through_redirects() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>Alpha links [[Rust]] and [[Python (programming language)]].</text></revision></page>
+        <page><title>Rust (programming language)</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Rust.</text></revision></page>
+        <page><title>Python (programming language)</title><ns>0</ns><id>3</id><revision><id>30</id>
+            <text>Python.</text></revision></page>
+        <page><title>Rust</title><ns>0</ns><id>4</id><redirect title="Rust (programming language)" />
+            <revision><id>40</id><text>#REDIRECT [[Rust (programming language)]]</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>5</id><revision><id>50</id>
+            <text>Beta links [[Rust]] and [[Rust (programming language)]].</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.edge_redirect_flag = true;
+    run_extraction(&config).unwrap();
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("edges.csv")).unwrap();
+    assert_eq!(
+        rdr.headers().unwrap(),
+        vec![":START_ID", ":END_ID", ":TYPE", "via_redirect"]
+    );
+    let mut edges: Vec<Vec<String>> = rdr
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect())
+        .collect();
+    edges.sort();
+    // Beta links the same target both ways; the direct edge wins.
+    assert_eq!(
+        edges,
+        vec![
+            vec!["1", "2", "LINKS_TO", "true"],
+            vec!["1", "3", "LINKS_TO", "false"],
+            vec!["5", "2", "LINKS_TO", "false"],
+        ]
+    );
+}
+
+#[test]
+fn weighted_edges_count_repeated_links() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>[[Beta]] is great. More on [[Beta|beta]]. See [[Beta]]. Also [[Gamma]].</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta links [[Alpha]].</text></revision></page>
+        <page><title>Gamma</title><ns>0</ns><id>3</id><revision><id>30</id>
+            <text>Gamma.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.weighted_edges = true;
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!(stats.edges(), 3);
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("edges.csv")).unwrap();
+    assert_eq!(
+        rdr.headers().unwrap(),
+        vec![":START_ID", ":END_ID", ":TYPE", "weight:int"]
+    );
+    let mut edges: Vec<Vec<String>> = rdr
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect())
+        .collect();
+    edges.sort();
+    assert_eq!(
+        edges,
+        vec![
+            vec!["1", "2", "LINKS_TO", "3"],
+            vec!["1", "3", "LINKS_TO", "1"],
+            vec!["2", "1", "LINKS_TO", "1"],
+        ]
+    );
+}
+
+#[test]
+fn self_links_are_skipped_unless_kept() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>[[Alpha]] links to itself, to [[alpha|itself again]] and to [[Beta]].</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let read_edges = |dir: &Path| -> Vec<(String, String)> {
+        let mut rdr = csv::Reader::from_path(dir.join("edges.csv")).unwrap();
+        let mut edges: Vec<(String, String)> = rdr
+            .records()
+            .map(|r| {
+                let r = r.unwrap();
+                (r[0].to_string(), r[1].to_string())
+            })
+            .collect();
+        edges.sort();
+        edges
+    };
+
+    let output_dir = TempDir::new().unwrap();
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!(stats.edges(), 1);
+    assert_eq!(stats.self_links(), 2);
+    assert_eq!(stats.invalid(), 0);
+    assert_eq!(
+        read_edges(output_dir.path()),
+        vec![("1".to_string(), "2".to_string())]
+    );
+
+    let output_dir = TempDir::new().unwrap();
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.keep_self_links = true;
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!(stats.edges(), 2);
+    assert_eq!(stats.self_links(), 0);
+    assert_eq!(
+        read_edges(output_dir.path()),
+        vec![
+            ("1".to_string(), "1".to_string()),
+            ("1".to_string(), "2".to_string())
+        ]
+    );
+}
+
+#[test]
+fn renamed_links_to_matches_csv_and_load_statement() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>Alpha cites [[Beta]].
+
+== See also ==
+* [[Beta]]</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Beta. [[Category:Letters]]</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.relationship_names = RelationshipNames::from_overrides(&[
+        "LINKS_TO=CITES".to_string(),
+        "HAS_CATEGORY=IN_CATEGORY".to_string(),
+    ])
+    .unwrap();
+    run_extraction(&config).unwrap();
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("edges.csv")).unwrap();
+    let edges: Vec<(String, String, String)> = rdr
+        .records()
+        .map(|r| {
+            let r = r.unwrap();
+            (r[0].to_string(), r[1].to_string(), r[2].to_string())
+        })
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            ("1".into(), "2".into(), "CITES".into()),
+            ("1".into(), "2".into(), "SEE_ALSO".into()),
+        ]
+    );
+
+    // The loader's statement for the renamed edge uses the same name.
+    let (start, end, rel_type) = &edges[0];
+    assert_eq!(
+        config.relationship_names.relate_statement(start, end),
+        format!(
+            "RELATE article:1->{}->article:2;\n",
+            rel_type.to_ascii_lowercase()
+        )
+    );
+
+    let categories =
+        std::fs::read_to_string(output_dir.path().join("article_categories.csv")).unwrap();
+    assert!(categories.contains("2,Letters,IN_CATEGORY"), "{categories}");
+}
+
+#[test]
+fn page_label_sets_article_node_label() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.page_label = "WikiArticle".to_string();
+    run_extraction(&config).unwrap();
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("nodes.csv")).unwrap();
+    let labels: HashSet<String> = rdr.records().map(|r| r.unwrap()[2].to_string()).collect();
+    assert_eq!(labels, HashSet::from(["WikiArticle".to_string()]));
+    // Entity nodes keep their own labels.
+    let categories = std::fs::read_to_string(output_dir.path().join("categories.csv")).unwrap();
+    assert!(categories.contains(",Category\n"), "{categories}");
+
+    config.page_label = "Wiki Article".to_string();
+    let err = run_extraction(&config).err().unwrap();
+    assert!(err.to_string().contains("label"), "{err}");
+}
+
+#[test]
+fn template_stats_counts_fixture_templates() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        4,
+        None,
+        false,
+    );
+    config.template_stats = true;
+    run_extraction(&config).unwrap();
+
+    let content = std::fs::read_to_string(output_dir.path().join("template_stats.csv")).unwrap();
+    assert_eq!(
+        content,
+        "template_name,count\nInfobox programming language,1\n"
+    );
+
+    // Off by default.
+    let plain_dir = TempDir::new().unwrap();
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        plain_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    run_extraction(&config).unwrap();
+    assert!(!plain_dir.path().join("template_stats.csv").exists());
+}
+
+#[test]
+fn redirect_map_lists_resolved_redirects() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.emit_redirect_map = true;
+    run_extraction(&config).unwrap();
+
+    let content = std::fs::read_to_string(output_dir.path().join("redirect_map.csv")).unwrap();
+    assert_eq!(
+        content,
+        "source_title,target_title,target_id\nRust,Rust (programming language),1\n"
+    );
+}
+
+#[test]
+fn category_counts_count_articles_per_category() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let mut config = make_config(tmp.path().to_str().unwrap(), out, &index, 4, None, false);
+    config.category_counts = true;
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!(stats.categories(), 2);
+
+    let mut rows = Vec::new();
+    for shard in 0..4 {
+        let path = output_dir.path().join(format!("categories_{shard:03}.csv"));
+        let mut reader = csv::Reader::from_path(path).unwrap();
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["id:ID(Category)", "name", ":LABEL", "count:int"]
+        );
+        rows.extend(reader.records().map(|r| r.unwrap()));
+    }
+    let mut counts: Vec<(String, String)> = rows
+        .iter()
+        .map(|r| (r[0].to_string(), r[3].to_string()))
+        .collect();
+    counts.sort();
+    assert_eq!(
+        counts,
+        vec![
+            ("Programming languages".to_string(), "2".to_string()),
+            ("Systems programming languages".to_string(), "1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn history_dump_extracts_latest_revision_or_fails_with_single_revision() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id>
+            <revision><id>10</id><text>Early draft links [[Gamma]].</text></revision>
+            <revision><id>11</id><text>Now links [[Beta]].</text></revision>
+        </page>
+        <page><title>Beta</title><ns>0</ns><id>2</id>
+            <revision><id>20</id><text>Beta.</text></revision></page>
+        <page><title>Gamma</title><ns>0</ns><id>3</id>
+            <revision><id>30</id><text>Gamma.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let path = tmp.path().to_str().unwrap();
+    let index = WikiIndex::build(path).unwrap();
+
+    let output_dir = TempDir::new().unwrap();
+    let mut config = make_config(
+        path,
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    run_extraction(&config).unwrap();
+    let edges = std::fs::read_to_string(output_dir.path().join("edges.csv")).unwrap();
+    assert_eq!(edges, ":START_ID,:END_ID,:TYPE\n1,2,LINKS_TO\n");
+
+    let strict_dir = TempDir::new().unwrap();
+    config.output_dir = strict_dir.path().to_str().unwrap();
+    config.single_revision = true;
+    let err = run_extraction(&config).err().unwrap().to_string();
+    assert!(err.contains("Page 1 has 2 revisions"), "{err}");
+}
+
+#[test]
+fn dump_info_records_siteinfo_header() {
+    let xml = r#"<mediawiki>
+        <siteinfo>
+            <dbname>testwiki</dbname>
+            <generator>MediaWiki 1.41.0</generator>
+            <namespaces><namespace key="14">Category</namespace></namespaces>
+        </siteinfo>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>Alpha.</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    run_extraction(&config).unwrap();
+
+    let content = std::fs::read_to_string(output_dir.path().join("dump_info.json")).unwrap();
+    let info: dedalus::models::DumpInfo = serde_json::from_str(&content).unwrap();
+    assert_eq!(info.generator.as_deref(), Some("MediaWiki 1.41.0"));
+    assert_eq!(info.dbname.as_deref(), Some("testwiki"));
+    assert_eq!(info.base, None);
+    assert_eq!(info.namespaces, ["Category"]);
+}
+
+#[test]
+fn manifest_summarizes_the_extraction_run() {
+    let tmp = create_bz2_xml(sample_xml());
+    let input = tmp.path().to_str().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+    let config = make_config(input, out, &index, 2, None, false);
+    let stats = run_extraction(&config).unwrap();
+
+    let content = std::fs::read_to_string(output_dir.path().join("manifest.json")).unwrap();
+    let manifest: dedalus::manifest::Manifest = serde_json::from_str(&content).unwrap();
+    assert_eq!(manifest.stats.articles_processed, stats.articles());
+    assert_eq!(manifest.stats.edges_extracted, stats.edges());
+    assert_eq!(manifest.input_path, input);
+    assert_eq!(manifest.input_size, std::fs::metadata(input).unwrap().len());
+    assert_eq!(manifest.csv_shards, 2);
+    assert_eq!(manifest.dedalus_version, env!("CARGO_PKG_VERSION"));
+    assert!(manifest.complete);
+    assert!(!manifest.interrupted);
+    assert_eq!(manifest.duplicate_ids, index.duplicate_ids());
+    assert!(manifest.started_at <= manifest.finished_at);
+
+    dedalus::merge::merge_csv_shards(out, false).unwrap();
+    // A stray shard from a run with more shards is caught before merging.
+    std::fs::copy(
+        output_dir.path().join("nodes_000.csv"),
+        output_dir.path().join("nodes_002.csv"),
+    )
+    .unwrap();
+    let err = dedalus::merge::merge_csv_shards(out, true).unwrap_err();
+    assert!(err.to_string().contains("--csv-shards 2"), "{err}");
+}
+
+#[test]
+fn graph_jsonl_export_roundtrips_sharded_extraction() {
+    use std::io::BufRead;
+
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(tmp.path().to_str().unwrap(), out, &index, 4, None, false);
+    let stats = run_extraction(&config).unwrap();
+
+    let dest = output_dir.path().join("graph.jsonl.gz");
+    let export = dedalus::export::export_graph_jsonl(out, &dest).unwrap();
+
+    let reader = std::io::BufReader::new(flate2::read::GzDecoder::new(
+        std::fs::File::open(&dest).unwrap(),
+    ));
+    let records: Vec<serde_json::Value> = reader
+        .lines()
+        .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+        .collect();
+    let of_type = |t: &str| records.iter().filter(|r| r["type"] == t).count() as u64;
+    assert_eq!(of_type("node"), export.nodes);
+    assert_eq!(of_type("edge"), export.edges);
+
+    let pages: Vec<&serde_json::Value> = records.iter().filter(|r| r["label"] == "Page").collect();
+    assert_eq!(pages.len() as u64, stats.articles());
+    assert!(records.contains(&serde_json::json!({
+        "type": "node", "id": 1, "title": "Rust (programming language)", "label": "Page"
+    })));
+    assert!(records.contains(&serde_json::json!({
+        "type": "edge", "start": 1, "end": 2, "rel": "LINKS_TO"
+    })));
+    assert!(records.contains(&serde_json::json!({
+        "type": "edge", "start": 1, "end": "Programming languages", "rel": "HAS_CATEGORY"
+    })));
+    let article_edges = records
+        .iter()
+        .filter(|r| r["rel"] == "LINKS_TO" || r["rel"] == "SEE_ALSO")
+        .count() as u64;
+    assert_eq!(article_edges, stats.edges() + stats.see_also_edges());
+}
+
+/// Edges as (start title, end title, type), resolved through the nodes CSVs.
+fn titled_edges(output_dir: &str) -> HashSet<(String, String, String)> {
+    use dedalus::csv_util::{CsvType, detect_csv_layout, for_each_record};
+    let layout = detect_csv_layout(output_dir).unwrap();
+    let mut titles = std::collections::HashMap::new();
+    for_each_record(output_dir, &layout, CsvType::Nodes, |r| {
+        titles.insert(r[0].to_string(), r[1].to_string());
+        Ok(())
+    })
+    .unwrap();
+    let mut edges = HashSet::new();
+    for_each_record(output_dir, &layout, CsvType::Edges, |r| {
+        edges.insert((
+            titles[&r[0]].clone(),
+            titles[&r[1]].clone(),
+            r[2].to_string(),
+        ));
+        Ok(())
+    })
+    .unwrap();
+    edges
+}
+
+#[test]
+fn in_degree_counts_links_to_each_article() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(tmp.path().to_str().unwrap(), out, &index, 2, None, false);
+    run_extraction(&config).unwrap();
+
+    let (path, stats) = dedalus::degree::compute_in_degree(out, "LINKS_TO").unwrap();
+    assert_eq!(stats.nodes, 2);
+    let degrees: HashMap<String, String> = csv::Reader::from_path(path)
+        .unwrap()
+        .records()
+        .map(|r| {
+            let r = r.unwrap();
+            (r[0].to_string(), r[1].to_string())
+        })
+        .collect();
+    // Rust -> Python in the body (the "See also" entry is SEE_ALSO, not counted),
+    // and Python -> Rust.
+    assert_eq!(degrees["2"], "1");
+    assert_eq!(degrees["1"], "1");
+}
+
+#[test]
+fn validate_accepts_in_progress_output_only_with_partial() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(tmp.path().to_str().unwrap(), out, &index, 2, None, false);
+    run_extraction(&config).unwrap();
+
+    let report = dedalus::validate::validate_output(out, false).unwrap();
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert_eq!(report.nodes, 2);
+    assert!(report.edges > 0);
+    assert_eq!((report.unknown_starts, report.truncated_tails), (0, 0));
+
+    // Mid-run: an edge row cut off mid-write, and a category row whose
+    // article's node row is still sitting in the nodes writer's buffer.
+    let append = |name: &str, text: &str| {
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(output_dir.path().join(name))
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    };
+    append("edges_001.csv", "1,2");
+    append("article_categories_000.csv", "999,Science,HAS_CATEGORY\n");
+
+    let full = dedalus::validate::validate_output(out, false).unwrap();
+    assert!(!full.is_ok());
+    assert_eq!(full.malformed_rows, 1);
+    let partial = dedalus::validate::validate_output(out, true).unwrap();
+    assert!(partial.is_ok(), "{:?}", partial.problems);
+    assert_eq!((partial.truncated_tails, partial.unknown_starts), (1, 1));
+    assert_eq!(partial.warnings.len(), 2);
+
+    // A bad row followed by good ones is not a tail, even mid-run.
+    append("edges_000.csv", "x,y,LINKS_TO\n1,2,LINKS_TO\n");
+    let partial = dedalus::validate::validate_output(out, true).unwrap();
+    assert!(!partial.is_ok());
+    assert_eq!(partial.malformed_rows, 1);
+}
+
+#[test]
+fn verify_blobs_flags_a_truncated_blob() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(tmp.path().to_str().unwrap(), out, &index, 2, None, false);
+    run_extraction(&config).unwrap();
+
+    let report = dedalus::validate::verify_blobs(out).unwrap();
+    assert!(report.is_ok(), "{:?}", report.corrupt);
+    assert_eq!((report.files, report.valid), (2, 2));
+
+    // As if the process died halfway through writing article 1.
+    let path = blob_path(output_dir.path(), 1);
+    let content = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &content[..content.len() / 2]).unwrap();
+    let report = dedalus::validate::verify_blobs(out).unwrap();
+    assert_eq!(report.valid, 1);
+    assert_eq!(report.corrupt.len(), 1);
+    assert!(
+        report.corrupt[0].contains(&path.display().to_string()),
+        "{:?}",
+        report.corrupt
+    );
+}
+
+#[test]
+fn compact_ids_keeps_edges_between_the_same_articles() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>900</id><revision><id>1</id>
+            <text>Alpha links [[Beta]] and [[Gamma]].</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>40</id><revision><id>2</id>
+            <text>Beta links [[Gamma]].
+
+[[Category:Letters]]</text></revision></page>
+        <page><title>Gamma</title><ns>0</ns><id>70000</id><revision><id>3</id>
+            <text>Gamma links [[Alpha]].</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(tmp.path().to_str().unwrap(), out, &index, 2, None, false);
+    run_extraction(&config).unwrap();
+
+    let before = titled_edges(out);
+    assert_eq!(before.len(), 4);
+    let stats = dedalus::compact::compact_ids(out).unwrap();
+    assert_eq!(stats.articles, 3);
+    assert_eq!(titled_edges(out), before);
+
+    let id_map = std::fs::read_to_string(output_dir.path().join("id_map.csv")).unwrap();
+    assert_eq!(id_map, "old_id,new_id\n40,0\n900,1\n70000,2\n");
+    let article_categories: String = (0..2)
+        .map(|s| {
+            std::fs::read_to_string(
+                output_dir
+                    .path()
+                    .join(format!("article_categories_{s:03}.csv")),
+            )
+            .unwrap()
+        })
+        .collect();
+    assert!(
+        article_categories.contains("0,Letters,HAS_CATEGORY"),
+        "{article_categories}"
+    );
+}
+
+#[test]
+fn sort_nodes_and_edges_is_ordered_and_lossless() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>900</id><revision><id>1</id>
+            <text>Alpha links [[Beta]] and [[Gamma]].</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>40</id><revision><id>2</id>
+            <text>Beta links [[Gamma]].</text></revision></page>
+        <page><title>Gamma</title><ns>0</ns><id>70000</id><revision><id>3</id>
+            <text>Gamma links [[Alpha]].</text></revision></page>
+        <page><title>Delta</title><ns>0</ns><id>5</id><revision><id>4</id>
+            <text>Delta links [[Alpha]] and [[Beta]].</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let config = make_config(tmp.path().to_str().unwrap(), out, &index, 1, None, false);
+    run_extraction(&config).unwrap();
+
+    let rows = |name: &str| -> Vec<String> {
+        std::fs::read_to_string(output_dir.path().join(name))
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    };
+    let (nodes_before, edges_before) = (rows("nodes.csv"), rows("edges.csv"));
+    let stats = dedalus::sort::sort_csvs(out, &[CsvType::Nodes, CsvType::Edges]).unwrap();
+    assert_eq!(stats.files, 2);
+    assert_eq!(
+        stats.rows as usize,
+        nodes_before.len() + edges_before.len() - 2
+    );
+
+    for (name, before) in [("nodes.csv", nodes_before), ("edges.csv", edges_before)] {
+        let after = rows(name);
+        assert_eq!(after[0], before[0], "{name} header");
+        let ids: Vec<u64> = after[1..]
+            .iter()
+            .map(|row| row.split(',').next().unwrap().parse().unwrap())
+            .collect();
+        assert!(ids.is_sorted(), "{name} not sorted: {ids:?}");
+        let (mut before, mut after) = (before, after);
+        before.sort();
+        after.sort();
+        assert_eq!(after, before, "{name} rows changed");
+    }
+}
+
+#[test]
+fn extraction_sqlite_output_writes_graph_db() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        4,
+        None,
+        false,
+    );
+    config.output_format = OutputFormat::Sqlite;
+    let stats = run_extraction(&config).unwrap();
+
+    assert!(!output_dir.path().join("nodes_000.csv").exists());
+    let conn = rusqlite::Connection::open(output_dir.path().join("graph.db")).unwrap();
+    let count = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, i64>(0)).unwrap();
+    assert_eq!(count("SELECT COUNT(*) FROM pages"), 2);
+    assert_eq!(
+        count("SELECT COUNT(*) FROM edges") as u64,
+        stats.edges() + stats.see_also_edges()
+    );
+    assert_eq!(
+        count("SELECT COUNT(*) FROM edges WHERE start_id = 2 AND end_id = 1"),
+        1,
+        "Python -> Rust edge should be queryable"
+    );
+    assert_eq!(
+        count("SELECT COUNT(*) FROM categories") as u64,
+        stats.categories()
+    );
+    assert_eq!(
+        count("SELECT COUNT(*) FROM article_categories") as u64,
+        stats.category_edges()
+    );
+}
+
+#[test]
+fn extraction_respects_limit() {
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        Some(1),
+        true,
+    );
+    let stats = run_extraction(&config).unwrap();
+
+    // The limit counts articles only, and slots are reserved atomically
+    assert_eq!(stats.articles(), 1);
+}
+
+#[test]
+fn single_thread_limit_keeps_first_n_articles() {
+    let (xml, _) = synthetic_dump_xml(2_000, 7);
+    let tmp = create_bz2_xml(&xml);
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let first_ids = || {
+        let output_dir = TempDir::new().unwrap();
+        let mut config = make_config(
+            tmp.path().to_str().unwrap(),
+            output_dir.path().to_str().unwrap(),
+            &index,
+            1,
+            Some(250),
+            false,
+        );
+        config.single_thread = true;
+        let stats = run_extraction(&config).unwrap();
+        assert_eq!(stats.articles(), 250);
+
+        let mut rdr = csv::Reader::from_path(output_dir.path().join("nodes.csv")).unwrap();
+        rdr.records()
+            .map(|r| r.unwrap()[0].parse::<u64>().unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let ids = first_ids();
+    assert_eq!(ids.len(), 250);
+    // Sequential processing keeps dump order, so runs are identical
+    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(ids, first_ids());
 }
 
 #[test]
-fn extraction_respects_limit() {
-    let tmp = create_bz2_xml(sample_xml());
-    let output_dir = TempDir::new().unwrap();
+fn parallel_limit_is_exact() {
+    let (xml, _) = synthetic_dump_xml(2_000, 11);
+    let tmp = create_bz2_xml(&xml);
     let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let output_dir = TempDir::new().unwrap();
 
     let config = make_config(
         tmp.path().to_str().unwrap(),
         output_dir.path().to_str().unwrap(),
         &index,
-        1,
-        Some(1),
+        4,
+        Some(300),
         true,
     );
-    let stats = run_extraction(&config).unwrap();
-
-    // With limit=1, at most 1 article should be processed
-    // (the limit applies to all page types seen, not just articles)
-    assert!(stats.articles() <= 1);
+    assert_eq!(run_extraction(&config).unwrap().articles(), 300);
 }
 
 #[test]
@@ -438,7 +2191,7 @@ fn nodes_csv_format_is_neo4j_compatible() {
     for record in rdr.records() {
         let record = record.unwrap();
         // id should be numeric
-        record.get(0).unwrap().parse::<u32>().unwrap();
+        record.get(0).unwrap().parse::<u64>().unwrap();
         // title should be non-empty
         assert!(!record.get(1).unwrap().is_empty());
         // label should be "Page"
@@ -473,8 +2226,8 @@ fn edges_csv_format_is_neo4j_compatible() {
     for record in rdr.records() {
         let record = record.unwrap();
         // start and end IDs should be numeric
-        record.get(0).unwrap().parse::<u32>().unwrap();
-        record.get(1).unwrap().parse::<u32>().unwrap();
+        record.get(0).unwrap().parse::<u64>().unwrap();
+        record.get(1).unwrap().parse::<u64>().unwrap();
         // type should be LINKS_TO or SEE_ALSO
         let edge_type = record.get(2).unwrap();
         assert!(
@@ -563,6 +2316,57 @@ fn extraction_produces_images_csv() {
     assert!(stats.images() >= 1); // Rust logo.svg
 }
 
+#[test]
+fn infobox_image_field_produces_image_edge() {
+    let xml = r#"<mediawiki>
+        <page>
+            <title>Freedonia</title>
+            <ns>0</ns>
+            <id>7</id>
+            <revision>
+                <id>700</id>
+                <text>{{Infobox country
+| name = Freedonia
+| image = Freedonia_skyline.jpg
+| flag = File:Flag of Freedonia.svg
+}}
+Freedonia is a country. [[File:Freedonia skyline.jpg|thumb]]</text>
+            </revision>
+        </page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    let stats = run_extraction(&config).unwrap();
+
+    // The skyline appears both as a file link and an infobox field: one node, one edge.
+    assert_eq!(stats.images(), 2);
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("article_images.csv")).unwrap();
+    let edges: Vec<(String, String)> = rdr
+        .records()
+        .map(|r| {
+            let r = r.unwrap();
+            (r[0].to_string(), r[1].to_string())
+        })
+        .collect();
+    assert_eq!(
+        edges,
+        vec![
+            ("7".to_string(), "Freedonia skyline.jpg".to_string()),
+            ("7".to_string(), "Flag of Freedonia.svg".to_string()),
+        ]
+    );
+}
+
 #[test]
 fn extraction_produces_external_links_csv() {
     let tmp = create_bz2_xml(sample_xml());
@@ -598,6 +2402,60 @@ fn extraction_produces_external_links_csv() {
     assert!(stats.external_links() >= 1); // rust-lang.org
 }
 
+#[test]
+fn normalize_urls_collapses_external_link_variants() {
+    let xml = r#"<mediawiki>
+        <page><title>Alpha</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>See [http://www.example.com/ Example] and [https://example.com#about about].</text></revision></page>
+        <page><title>Beta</title><ns>0</ns><id>2</id><revision><id>20</id>
+            <text>Also [https://example.com/?utm_source=wiki Example] and [https://example.com/a/ A].</text></revision></page>
+    </mediawiki>"#;
+    let tmp = create_bz2_xml(xml);
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let read_column = |dir: &Path, name: &str, column: usize| -> Vec<String> {
+        let mut rdr = csv::Reader::from_path(dir.join(name)).unwrap();
+        let mut values: Vec<String> = rdr
+            .records()
+            .map(|r| r.unwrap()[column].to_string())
+            .collect();
+        values.sort();
+        values
+    };
+
+    let output_dir = TempDir::new().unwrap();
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    assert_eq!(run_extraction(&config).unwrap().external_links(), 4);
+
+    let output_dir = TempDir::new().unwrap();
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.normalize_urls = true;
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!(stats.external_links(), 2);
+    assert_eq!(
+        read_column(output_dir.path(), "external_link_nodes.csv", 0),
+        vec!["https://example.com", "https://example.com/a"]
+    );
+    // Alpha's two variants become one relationship row.
+    assert_eq!(
+        read_column(output_dir.path(), "article_external_links.csv", 0),
+        vec!["1", "2", "2"]
+    );
+}
+
 #[test]
 fn blob_contains_enriched_data() {
     let tmp = create_bz2_xml(sample_xml());
@@ -614,8 +2472,7 @@ fn blob_contains_enriched_data() {
     );
     run_extraction(&config).unwrap();
 
-    let shard = 1 % 1000;
-    let blob_path = output_dir.path().join(format!("blobs/{:03}/1.json", shard));
+    let blob_path = blob_path(output_dir.path(), 1);
     let blob_content = std::fs::read_to_string(&blob_path).unwrap();
     let blob: ArticleBlob = serde_json::from_str(&blob_content).unwrap();
 
@@ -646,6 +2503,99 @@ fn blob_contains_enriched_data() {
     assert!(!blob.is_disambiguation);
 }
 
+#[test]
+fn metrics_endpoint_serves_live_counters_mid_run() {
+    use std::io::Read;
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Mutex;
+
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    // Scrape from inside the run: the hook fires once per article, after it is counted.
+    let scraped = Mutex::new(Vec::new());
+    let scrape = |_: &dedalus::models::WikiPage, _: &mut ArticleBlob| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let articles = response
+            .lines()
+            .find_map(|line| line.strip_prefix("dedalus_articles_processed_total "))
+            .map(|value| value.parse::<u64>().unwrap());
+        scraped.lock().unwrap().push(articles);
+    };
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.single_thread = true;
+    config.metrics_addr = Some(addr);
+    config.enrich = Some(&scrape);
+    let stats = run_extraction(&config).unwrap();
+
+    assert_eq!(*scraped.lock().unwrap(), vec![Some(1), Some(2)]);
+    assert_eq!(stats.articles(), 2);
+    // The server stops with the extraction.
+    assert!(TcpStream::connect(addr).is_err());
+}
+
+#[test]
+fn enrich_hook_adds_custom_blob_fields() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    let tmp = create_bz2_xml(sample_xml());
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    // Called from many threads at once: shared state must be Sync
+    let calls = AtomicU64::new(0);
+    let enrich = |page: &dedalus::models::WikiPage, blob: &mut ArticleBlob| {
+        calls.fetch_add(1, Ordering::Relaxed);
+        let words = page
+            .text
+            .as_deref()
+            .unwrap_or("")
+            .split_whitespace()
+            .count();
+        blob.extra.insert("word_count".to_string(), words.into());
+        blob.extra
+            .insert("tag".to_string(), format!("custom:{}", blob.title).into());
+    };
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.enrich = Some(&enrich);
+    run_extraction(&config).unwrap();
+
+    assert_eq!(calls.load(Ordering::Relaxed), 2);
+    let blob_content = std::fs::read_to_string(blob_path(output_dir.path(), 2)).unwrap();
+    let blob: ArticleBlob = serde_json::from_str(&blob_content).unwrap();
+    assert_eq!(blob.title, "Python (programming language)");
+    assert_eq!(blob.extra["tag"], "custom:Python (programming language)");
+    assert!(blob.extra["word_count"].as_u64().unwrap() > 0);
+    // Built-in fields are still populated
+    assert!(blob.timestamp.is_some());
+}
+
 #[test]
 fn extraction_finds_see_also_edges() {
     let tmp = create_bz2_xml(sample_xml());
@@ -797,3 +2747,667 @@ fn single_csv_shard_produces_original_filenames() {
     // Should NOT have numbered files
     assert!(!output_dir.path().join("nodes_000.csv").exists());
 }
+
+#[test]
+fn max_shard_bytes_rolls_single_csv_into_parts() {
+    use dedalus::csv_util::{self, CsvLayout, CsvType};
+
+    const PART_CAP: u64 = 4096;
+    let (xml, _) = synthetic_dump_xml(500, 0x5017_0ff5);
+    let tmp = create_bz2_xml(&xml);
+    let input = tmp.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+
+    let whole_dir = TempDir::new().unwrap();
+    let whole = whole_dir.path().to_str().unwrap();
+    let mut config = make_config(input, whole, &index, 1, None, false);
+    config.single_thread = true;
+    run_extraction(&config).unwrap();
+
+    let split_dir = TempDir::new().unwrap();
+    let split = split_dir.path().to_str().unwrap();
+    let mut config = make_config(input, split, &index, 1, None, false);
+    config.single_thread = true;
+    config.max_shard_bytes = Some(PART_CAP);
+    run_extraction(&config).unwrap();
+
+    let parts = csv_util::single_file_parts(split, "edges");
+    assert!(parts.len() > 1, "expected edges to roll over: {parts:?}");
+    let header = std::fs::read_to_string(whole_dir.path().join("edges.csv"))
+        .unwrap()
+        .lines()
+        .next()
+        .unwrap()
+        .to_string();
+    for part in &parts {
+        let content = std::fs::read_to_string(split_dir.path().join(part)).unwrap();
+        assert_eq!(content.lines().next().unwrap(), header, "{part} header");
+        // A part may exceed the cap only by its final row.
+        assert!((content.len() as u64) < PART_CAP + 256, "{part} too large");
+    }
+
+    // The parts read back as exactly the single-file rows, in order.
+    let rows = |dir: &str, csv_type| {
+        let mut rows = Vec::new();
+        csv_util::for_each_record(dir, &CsvLayout::Single, csv_type, |r| {
+            rows.push(r.iter().collect::<Vec<_>>().join(","));
+            Ok(())
+        })
+        .unwrap();
+        rows
+    };
+    for csv_type in [CsvType::Nodes, CsvType::Edges, CsvType::ArticleCategories] {
+        assert_eq!(rows(whole, csv_type), rows(split, csv_type), "{csv_type:?}");
+    }
+
+    // A fresh run into the same directory removes the parts of the last one.
+    let mut config = make_config(input, split, &index, 1, None, false);
+    config.single_thread = true;
+    run_extraction(&config).unwrap();
+    assert_eq!(csv_util::single_file_parts(split, "edges"), ["edges.csv"]);
+    assert_eq!(rows(whole, CsvType::Edges), rows(split, CsvType::Edges));
+
+    // Rollover only applies to single-file output.
+    let sharded_dir = TempDir::new().unwrap();
+    let mut config = make_config(
+        input,
+        sharded_dir.path().to_str().unwrap(),
+        &index,
+        4,
+        None,
+        false,
+    );
+    config.max_shard_bytes = Some(PART_CAP);
+    assert!(run_extraction(&config).is_err());
+}
+
+#[test]
+fn sharded_entity_nodes_are_deterministic_across_runs() {
+    let tmp = create_bz2_xml(sample_xml());
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let shard_contents = || {
+        let output_dir = TempDir::new().unwrap();
+        let config = make_config(
+            tmp.path().to_str().unwrap(),
+            output_dir.path().to_str().unwrap(),
+            &index,
+            4,
+            None,
+            false,
+        );
+        run_extraction(&config).unwrap();
+
+        let mut per_shard = Vec::new();
+        for base in ["categories", "image_nodes", "external_link_nodes"] {
+            for shard in 0..4 {
+                let path = output_dir.path().join(format!("{}_{:03}.csv", base, shard));
+                let content = std::fs::read_to_string(path).unwrap();
+                let mut rows: Vec<String> = content.lines().skip(1).map(String::from).collect();
+                rows.sort();
+                per_shard.push(rows);
+            }
+        }
+        per_shard
+    };
+
+    let first = shard_contents();
+    let second = shard_contents();
+    assert_eq!(first, second);
+
+    // Each shared category appears in exactly one shard
+    let category_rows: Vec<&String> = first[..4].iter().flatten().collect();
+    assert_eq!(
+        category_rows
+            .iter()
+            .filter(|r| r.starts_with("Programming languages,"))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn fingerprint_dedup_matches_exact_after_merge() {
+    let tmp = create_bz2_xml(sample_xml());
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let merged_nodes = |strategy: DedupStrategy| {
+        let output_dir = TempDir::new().unwrap();
+        let mut config = make_config(
+            tmp.path().to_str().unwrap(),
+            output_dir.path().to_str().unwrap(),
+            &index,
+            2,
+            None,
+            false,
+        );
+        config.dedup_strategy = strategy;
+        run_extraction(&config).unwrap();
+        dedalus::merge::merge_csv_shards(output_dir.path().to_str().unwrap(), false).unwrap();
+
+        let mut rows = Vec::new();
+        for base in ["categories", "image_nodes", "external_link_nodes"] {
+            let path = output_dir.path().join(format!("{}.csv", base));
+            let content = std::fs::read_to_string(path).unwrap();
+            rows.extend(content.lines().skip(1).map(String::from));
+        }
+        rows.sort();
+        rows
+    };
+
+    let exact = merged_nodes(DedupStrategy::Exact);
+    assert!(!exact.is_empty());
+    assert_eq!(exact, merged_nodes(DedupStrategy::Fingerprint));
+}
+
+// ---------------------------------------------------------------------------
+// Large synthetic dump
+// ---------------------------------------------------------------------------
+
+/// Deterministic xorshift64 generator so the synthetic dump is reproducible.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// Generates a dump of `page_count` pages: ~90% articles with random links,
+/// shared categories, images and external links, plus ~6% redirects and ~4%
+/// category pages. Returns the XML and the number of articles.
+fn synthetic_dump_xml(page_count: u32, seed: u64) -> (String, usize) {
+    let mut rng = XorShift(seed);
+    let mut xml = String::from("<mediawiki>\n");
+    let mut articles = 0;
+
+    for id in 1..=page_count {
+        let roll = rng.below(100);
+        if roll < 6 {
+            // Redirect to a random page, which may itself be a redirect or special
+            let target = rng.below(u64::from(page_count)) + 1;
+            xml.push_str(&format!(
+                "<page><title>Page {id}</title><ns>0</ns><id>{id}</id>\
+                 <redirect title=\"Page {target}\" /><revision><id>{id}</id>\
+                 <text>#REDIRECT [[Page {target}]]</text></revision></page>\n"
+            ));
+        } else if roll < 10 {
+            xml.push_str(&format!(
+                "<page><title>Category:Page {id}</title><ns>14</ns><id>{id}</id>\
+                 <revision><id>{id}</id><text>Category page</text></revision></page>\n"
+            ));
+        } else {
+            articles += 1;
+            let mut text = format!("Page {id} is a synthetic article.");
+            for _ in 0..rng.below(8) {
+                // Some targets are redirects, specials, or beyond the dump
+                let target = rng.below(u64::from(page_count) + 100) + 1;
+                text.push_str(&format!(" See [[Page {target}]]."));
+            }
+            for _ in 0..=rng.below(3) {
+                text.push_str(&format!("\n[[Category:Shared {}]]", rng.below(200)));
+            }
+            if rng.below(4) == 0 {
+                text.push_str(&format!("\n[[File:Image {}.png|thumb]]", rng.below(100)));
+            }
+            if rng.below(4) == 0 {
+                text.push_str(&format!(
+                    "\n[https://example.com/{} External]",
+                    rng.below(100)
+                ));
+            }
+            xml.push_str(&format!(
+                "<page><title>Page {id}</title><ns>0</ns><id>{id}</id>\
+                 <revision><id>{id}</id><text>{text}</text></revision></page>\n"
+            ));
+        }
+    }
+
+    xml.push_str("</mediawiki>\n");
+    (xml, articles)
+}
+
+/// Reads column `col` of every data row across all shards of `base`.
+fn shard_column(dir: &std::path::Path, base: &str, shards: u32, col: usize) -> Vec<String> {
+    let mut values = Vec::new();
+    for shard in 0..shards {
+        let path = dir.join(format!("{}_{:03}.csv", base, shard));
+        let mut rdr = csv::Reader::from_path(&path).unwrap();
+        for record in rdr.records() {
+            values.push(record.unwrap()[col].to_string());
+        }
+    }
+    values
+}
+
+#[test]
+fn synthetic_dump_invariants_hold_under_parallel_extraction() {
+    const PAGES: u32 = 50_000;
+    const SHARDS: u32 = 8;
+
+    let (xml, expected_articles) = synthetic_dump_xml(PAGES, 0x5eed_d3da);
+    let tmp = create_bz2_xml(&xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+    assert_eq!(index.stats().0, expected_articles);
+
+    let config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        SHARDS,
+        None,
+        false,
+    );
+    let stats = run_extraction(&config).unwrap();
+    assert_eq!(stats.articles(), expected_articles as u64);
+
+    let dir = output_dir.path();
+    let node_ids = shard_column(dir, "nodes", SHARDS, 0);
+    assert_eq!(node_ids.len(), expected_articles);
+    let known: HashSet<&str> = node_ids.iter().map(String::as_str).collect();
+    assert_eq!(known.len(), node_ids.len(), "duplicate article node rows");
+
+    // Exact dedup must hold across shards and threads
+    for base in ["categories", "image_nodes", "external_link_nodes"] {
+        let ids = shard_column(dir, base, SHARDS, 0);
+        assert!(!ids.is_empty(), "no {} rows", base);
+        let unique: HashSet<&String> = ids.iter().collect();
+        assert_eq!(unique.len(), ids.len(), "duplicate ids in {}", base);
+    }
+
+    let starts = shard_column(dir, "edges", SHARDS, 0);
+    let ends = shard_column(dir, "edges", SHARDS, 1);
+    assert_eq!(starts.len() as u64, stats.edges());
+    for (start, end) in starts.iter().zip(&ends) {
+        assert!(
+            known.contains(start.as_str()),
+            "unknown edge start {}",
+            start
+        );
+        assert!(known.contains(end.as_str()), "unknown edge end {}", end);
+    }
+
+    let categories: HashSet<String> = shard_column(dir, "categories", SHARDS, 0)
+        .into_iter()
+        .collect();
+    for end in shard_column(dir, "article_categories", SHARDS, 1) {
+        assert!(categories.contains(&end), "unknown category {}", end);
+    }
+
+    // Rows are spread over every shard
+    for shard in 0..SHARDS {
+        let path = dir.join(format!("nodes_{:03}.csv", shard));
+        let rows = csv::Reader::from_path(&path).unwrap().records().count();
+        assert!(rows > 0, "nodes shard {} is empty", shard);
+    }
+}
+
+#[test]
+fn blob_sample_rate_writes_blobs_for_a_fraction_of_articles() {
+    let (xml, expected_articles) = synthetic_dump_xml(20_000, 0xb10b);
+    let tmp = create_bz2_xml(&xml);
+    let output_dir = TempDir::new().unwrap();
+    let index = WikiIndex::build(tmp.path().to_str().unwrap()).unwrap();
+
+    let mut config = make_config(
+        tmp.path().to_str().unwrap(),
+        output_dir.path().to_str().unwrap(),
+        &index,
+        1,
+        None,
+        false,
+    );
+    config.blob_sample_rate = Some(0.05);
+    let stats = run_extraction(&config).unwrap();
+
+    // The full graph is still written.
+    assert_eq!(stats.articles(), expected_articles as u64);
+    let nodes = csv::Reader::from_path(output_dir.path().join("nodes.csv"))
+        .unwrap()
+        .records()
+        .count();
+    assert_eq!(nodes, expected_articles);
+
+    let expected = expected_articles as f64 * 0.05;
+    let blobs = stats.blobs() as f64;
+    assert!(
+        (blobs - expected).abs() < expected * 0.2,
+        "{blobs} blobs, expected ~{expected}"
+    );
+    assert_eq!(stats.blobs() + stats.blobs_sampled_out(), stats.articles());
+    let on_disk = file_count(&output_dir.path().join("blobs"));
+    assert_eq!(on_disk as u64, stats.blobs());
+}
+
+/// Total size of every file under `dir`, recursively.
+fn dir_size(dir: &std::path::Path) -> u64 {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let meta = entry.metadata().unwrap();
+            if meta.is_dir() {
+                dir_size(&entry.path())
+            } else {
+                meta.len()
+            }
+        })
+        .sum()
+}
+
+/// Number of files under `dir`, recursively.
+fn file_count(dir: &std::path::Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            if path.is_dir() { file_count(&path) } else { 1 }
+        })
+        .sum()
+}
+
+#[test]
+fn max_output_bytes_stops_and_resumes_from_checkpoint() {
+    max_output_bytes_stop_and_resume(true);
+}
+
+#[test]
+fn max_output_bytes_resume_in_parallel_misses_no_pages() {
+    max_output_bytes_stop_and_resume(false);
+}
+
+fn max_output_bytes_stop_and_resume(single_thread: bool) {
+    const CAP: u64 = 64 * 1024;
+
+    let (xml, expected_articles) = synthetic_dump_xml(2_000, 0xb17e_5ca9);
+    let tmp = create_bz2_xml(&xml);
+    let input = tmp.path().to_str().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+    let mgr = CheckpointManager::new(input, out, 1000, 2, ShardStrategy::Hash, 100_000).unwrap();
+
+    let mut config = make_config(input, out, &index, 2, None, false);
+    config.single_thread = single_thread;
+    config.max_output_bytes = Some(CAP);
+    config.checkpoint_mgr = Some(&mgr);
+    let capped = run_extraction(&config).unwrap();
+
+    assert!(capped.output_bytes() >= CAP);
+    assert!((capped.articles() as usize) < expected_articles);
+    let cp = checkpoint::load_if_valid(input, out, 1000, 2, ShardStrategy::Hash)
+        .unwrap()
+        .expect("checkpoint should be flushed at the size cap");
+    assert_eq!(cp.stats.articles_processed, capped.articles());
+    assert_eq!(cp.stats.output_bytes, capped.output_bytes());
+
+    // The counter tracks exactly what reached disk (the checkpoint, dump info
+    // and manifest are not output).
+    let checkpoint_size = std::fs::metadata(checkpoint::checkpoint_path(out))
+        .unwrap()
+        .len();
+    let file_size = |name: &str| {
+        std::fs::metadata(output_dir.path().join(name))
+            .unwrap()
+            .len()
+    };
+    assert_eq!(
+        dir_size(output_dir.path())
+            - checkpoint_size
+            - file_size("dump_info.json")
+            - file_size("manifest.json"),
+        capped.output_bytes()
+    );
+    let manifest = dedalus::manifest::load(out).unwrap().unwrap();
+    assert!(!manifest.complete);
+
+    // Resuming without a cap picks up exactly where the capped run stopped.
+    config.max_output_bytes = None;
+    config.resume_from = Some(&cp);
+    let resumed = run_extraction(&config).unwrap();
+    assert_eq!(resumed.articles() as usize, expected_articles);
+
+    let ids = shard_column(output_dir.path(), "nodes", 2, 0);
+    assert_eq!(ids.len(), expected_articles);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), expected_articles);
+}
+
+#[test]
+fn resume_from_explicit_id_appends_remaining_pages() {
+    let (xml, expected_articles) = synthetic_dump_xml(500, 0x2e5_0f20);
+    let tmp = create_bz2_xml(&xml);
+    let input = tmp.path().to_str().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+
+    let mut config = make_config(input, out, &index, 2, Some(100), false);
+    config.single_thread = true;
+    let first = run_extraction(&config).unwrap();
+    assert_eq!(first.articles(), 100);
+
+    // No checkpoint was written: the restart point comes from the output itself.
+    let last_id = shard_column(output_dir.path(), "nodes", 2, 0)
+        .iter()
+        .map(|id| id.parse::<u64>().unwrap())
+        .max()
+        .unwrap();
+    let cp = checkpoint::starting_after(input, out, 1000, 2, ShardStrategy::Hash, last_id).unwrap();
+    assert_eq!(cp.stats.articles_processed, 0);
+    config.limit = None;
+    config.resume_from = Some(&cp);
+    let resumed = run_extraction(&config).unwrap();
+    assert_eq!(resumed.articles() as usize, expected_articles - 100);
+
+    let ids = shard_column(output_dir.path(), "nodes", 2, 0);
+    assert_eq!(ids.len(), expected_articles);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), expected_articles);
+}
+
+#[test]
+fn max_runtime_stops_and_resumes_from_checkpoint() {
+    max_runtime_stop_and_resume(true);
+}
+
+#[test]
+fn max_runtime_resume_in_parallel_misses_no_pages() {
+    max_runtime_stop_and_resume(false);
+}
+
+fn max_runtime_stop_and_resume(single_thread: bool) {
+    let (xml, expected_articles) = synthetic_dump_xml(2_000, 0x7157_ab1e);
+    let tmp = create_bz2_xml(&xml);
+    let input = tmp.path().to_str().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+    let mgr = CheckpointManager::new(input, out, 1000, 2, ShardStrategy::Hash, 100_000).unwrap();
+
+    // A slow enrich hook keeps the run well past the tiny budget.
+    // Uneven delays make parallel workers finish pages out of order.
+    let slow = |page: &dedalus::models::WikiPage, _: &mut ArticleBlob| {
+        std::thread::sleep(std::time::Duration::from_millis(1 + page.id % 4));
+    };
+    let mut config = make_config(input, out, &index, 2, None, false);
+    config.single_thread = single_thread;
+    config.max_runtime = Some(std::time::Duration::from_millis(50));
+    config.enrich = Some(&slow);
+    config.checkpoint_mgr = Some(&mgr);
+    let stopped = run_extraction(&config).unwrap();
+
+    assert!(stopped.time_budget_exhausted());
+    assert!(stopped.articles() > 0);
+    assert!((stopped.articles() as usize) < expected_articles);
+    let cp = checkpoint::load_if_valid(input, out, 1000, 2, ShardStrategy::Hash)
+        .unwrap()
+        .expect("checkpoint should be flushed when the time budget runs out");
+    assert_eq!(cp.stats.articles_processed, stopped.articles());
+
+    config.max_runtime = None;
+    config.enrich = None;
+    config.resume_from = Some(&cp);
+    let resumed = run_extraction(&config).unwrap();
+    assert!(!resumed.time_budget_exhausted());
+    assert_eq!(resumed.articles() as usize, expected_articles);
+
+    let ids = shard_column(output_dir.path(), "nodes", 2, 0);
+    assert_eq!(ids.len(), expected_articles);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), expected_articles);
+}
+
+#[test]
+fn sigint_stops_and_flushes_a_resumable_checkpoint() {
+    sigint_stop_and_resume(true);
+}
+
+#[test]
+fn sigint_resume_in_parallel_misses_no_pages() {
+    sigint_stop_and_resume(false);
+}
+
+/// Held by tests that raise SIGINT: a second signal while another run's
+/// handler is installed would exit the test process.
+static SIGINT_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+fn sigint_stop_and_resume(single_thread: bool) {
+    let _serial = SIGINT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let (xml, expected_articles) = synthetic_dump_xml(2_000, 0x0051_6117);
+    let tmp = create_bz2_xml(&xml);
+    let input = tmp.path().to_str().unwrap();
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(input).unwrap();
+    let mgr = CheckpointManager::new(input, out, 1000, 2, ShardStrategy::Hash, 100_000).unwrap();
+
+    // Uneven delays make parallel workers finish pages out of order.
+    let slow = |page: &dedalus::models::WikiPage, _: &mut ArticleBlob| {
+        std::thread::sleep(std::time::Duration::from_millis(1 + page.id % 4));
+    };
+    let mut config = make_config(input, out, &index, 2, None, false);
+    config.single_thread = single_thread;
+    config.handle_interrupts = true;
+    config.enrich = Some(&slow);
+    config.checkpoint_mgr = Some(&mgr);
+
+    // Ctrl-C from another thread once the run is under way.
+    let stats = Arc::new(ExtractionStats::new());
+    let watched = Arc::clone(&stats);
+    let signaller = std::thread::spawn(move || {
+        while watched.articles() < 20 {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        signal_hook::low_level::raise(signal_hook::consts::SIGINT).unwrap();
+    });
+    let stopped = run_extraction_with_stats(
+        &config,
+        Arc::clone(&stats),
+        Arc::new(AtomicBool::new(false)),
+        true,
+    )
+    .unwrap();
+    signaller.join().unwrap();
+
+    assert!(stopped.interrupted());
+    assert!(!stopped.time_budget_exhausted());
+    assert!((stopped.articles() as usize) < expected_articles);
+    let cp = checkpoint::load_if_valid(input, out, 1000, 2, ShardStrategy::Hash)
+        .unwrap()
+        .expect("checkpoint should be flushed on SIGINT");
+    assert_eq!(cp.stats.articles_processed, stopped.articles());
+    let manifest = dedalus::manifest::load(out).unwrap().unwrap();
+    assert!(manifest.interrupted && !manifest.complete);
+
+    config.handle_interrupts = false;
+    config.enrich = None;
+    config.resume_from = Some(&cp);
+    let resumed = run_extraction(&config).unwrap();
+    assert!(!resumed.interrupted());
+    assert_eq!(resumed.articles() as usize, expected_articles);
+
+    let ids = shard_column(output_dir.path(), "nodes", 2, 0);
+    assert_eq!(ids.len(), expected_articles);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), expected_articles);
+}
+
+#[test]
+fn images_dump_adds_metadata_to_image_nodes() {
+    let articles = create_bz2_xml(
+        r#"<mediawiki>
+        <page><title>Cats</title><ns>0</ns><id>1</id><revision><id>10</id>
+            <text>[[File:Cat_photo.jpg|thumb|A cat]] Cats purr. [[File:Dog.png]]</text></revision></page>
+    </mediawiki>"#,
+    );
+    let images = create_bz2_xml(
+        r#"<mediawiki>
+        <page><title>File:Cat photo.jpg</title><ns>6</ns><id>500</id><revision><id>5000</id>
+            <text>{{Information
+|description={{en|1=A [[cat]] asleep}}
+|date=2020-01-02
+|author=Jane Doe
+}}</text></revision></page>
+        <page><title>Commons:Welcome</title><ns>4</ns><id>501</id><revision><id>5010</id>
+            <text>{{Information|description=Not a file}}</text></revision></page>
+        <page><title>File:Unused.svg</title><ns>6</ns><id>502</id><revision><id>5020</id>
+            <text>{{Information|description=Never linked}}</text></revision></page>
+    </mediawiki>"#,
+    );
+    let output_dir = TempDir::new().unwrap();
+    let out = output_dir.path().to_str().unwrap();
+    let index = WikiIndex::build(articles.path().to_str().unwrap()).unwrap();
+    let config = make_config(
+        articles.path().to_str().unwrap(),
+        out,
+        &index,
+        1,
+        None,
+        false,
+    );
+    run_extraction(&config).unwrap();
+
+    let stats = dedalus::commons::add_image_metadata(out, images.path().to_str().unwrap()).unwrap();
+    assert_eq!((stats.images, stats.file_pages, stats.matched), (2, 2, 1));
+
+    let mut rdr = csv::Reader::from_path(output_dir.path().join("image_nodes.csv")).unwrap();
+    assert_eq!(
+        rdr.headers().unwrap(),
+        vec![
+            "id:ID(Image)",
+            "filename",
+            ":LABEL",
+            "description",
+            "author",
+            "date"
+        ]
+    );
+    let mut rows: Vec<Vec<String>> = rdr
+        .records()
+        .map(|r| r.unwrap().iter().map(str::to_string).collect())
+        .collect();
+    rows.sort();
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                "Cat photo.jpg",
+                "Cat photo.jpg",
+                "Image",
+                "A cat asleep",
+                "Jane Doe",
+                "2020-01-02"
+            ],
+            vec!["Dog.png", "Dog.png", "Image", "", "", ""],
+        ]
+    );
+    assert!(!output_dir.path().join("image_nodes.csv.tmp").exists());
+
+    // The columns are added once.
+    assert!(dedalus::commons::add_image_metadata(out, images.path().to_str().unwrap()).is_err());
+}
@@ -202,7 +202,7 @@ fn test_merge_csvs_with_category_deduplication() -> Result<()> {
     )?;
 
     // Run merge
-    merge::merge_csv_shards(output_dir)?;
+    merge::merge_csv_shards(output_dir, false)?;
 
     // Verify merged files exist
     assert!(temp_dir.path().join("nodes.csv").exists());
@@ -398,7 +398,7 @@ fn test_merge_csvs_handles_many_shards() -> Result<()> {
     }
 
     // Run merge
-    merge::merge_csv_shards(output_dir)?;
+    merge::merge_csv_shards(output_dir, false)?;
 
     // Verify categories deduplication worked - "Common" should appear only once
     let categories_content = fs::read_to_string(temp_dir.path().join("categories.csv"))?;
@@ -425,3 +425,70 @@ fn test_merge_csvs_handles_many_shards() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_merge_skips_already_merged_types() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let output_dir = temp_dir.path().to_str().unwrap();
+
+    let types = [
+        ("nodes", "id:ID,title,:LABEL", "1,Article_One,Page"),
+        ("edges", ":START_ID,:END_ID,:TYPE", "1,2,LINKS_TO"),
+        (
+            "categories",
+            "id:ID(Category),name,:LABEL",
+            "Science,Science,Category",
+        ),
+        (
+            "article_categories",
+            ":START_ID,:END_ID(Category),:TYPE",
+            "1,Science,HAS_CATEGORY",
+        ),
+        (
+            "image_nodes",
+            "id:ID(Image),filename,:LABEL",
+            "a.jpg,a.jpg,Image",
+        ),
+        (
+            "article_images",
+            ":START_ID,:END_ID(Image),:TYPE",
+            "1,a.jpg,HAS_IMAGE",
+        ),
+        (
+            "external_link_nodes",
+            "id:ID(ExternalLink),url,:LABEL",
+            "https://example.com,https://example.com,ExternalLink",
+        ),
+        (
+            "article_external_links",
+            ":START_ID,:END_ID(ExternalLink),:TYPE",
+            "1,https://example.com,HAS_LINK",
+        ),
+    ];
+    for (base, header, row) in types {
+        create_test_shard(temp_dir.path(), base, 0, header, &[row])?;
+    }
+
+    merge::merge_csv_shards(output_dir, false)?;
+    assert!(!temp_dir.path().join("nodes.csv.tmp").exists());
+
+    // A merged file with the right header and row count is treated as done.
+    let nodes_path = temp_dir.path().join("nodes.csv");
+    fs::write(&nodes_path, "id:ID,title,:LABEL\n1,Marker,Page\n")?;
+    merge::merge_csv_shards(output_dir, false)?;
+    assert!(fs::read_to_string(&nodes_path)?.contains("Marker"));
+
+    // A truncated merged file is re-merged.
+    fs::write(&nodes_path, "id:ID,title,:LABEL\n")?;
+    merge::merge_csv_shards(output_dir, false)?;
+    assert!(fs::read_to_string(&nodes_path)?.contains("Article_One"));
+
+    // --force-merge re-merges even a valid file.
+    fs::write(&nodes_path, "id:ID,title,:LABEL\n1,Marker,Page\n")?;
+    merge::merge_csv_shards(output_dir, true)?;
+    let nodes_content = fs::read_to_string(&nodes_path)?;
+    assert!(nodes_content.contains("Article_One"));
+    assert!(!nodes_content.contains("Marker"));
+
+    Ok(())
+}